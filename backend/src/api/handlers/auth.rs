@@ -250,8 +250,11 @@ pub async fn get_current_user(
     http_req: HttpRequest,
 ) -> Result<HttpResponse> {
     // Extract user from request (set by auth middleware)
-    if let Some(claims) = http_req.extensions().get::<crate::database::models::Claims>() {
-        let user_id = match Uuid::parse_str(&claims.sub) {
+    let subject = http_req.extensions()
+        .get::<crate::database::models::Claims>()
+        .map(|claims| claims.sub.clone());
+    if let Some(subject) = subject {
+        let user_id = match Uuid::parse_str(&subject) {
             Ok(id) => id,
             Err(_) => {
                 return Ok(HttpResponse::BadRequest().json(
@@ -1,81 +1,309 @@
 //! Bot management handlers
 
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::database::models::{BotConfig, ApiResponse, CreateBotConfigRequest};
+use crate::api::handlers::kill_switch::halted_response;
+use crate::auth::middleware::authenticated_user_id;
+use crate::bots::config::BotSettings;
+use crate::bots::revisions;
+use crate::bots::{BotManager, BotState};
+use crate::database::models::{
+    BotConfig, BotConfigRevision, ApiResponse, CreateBotConfigRequest, RevisionDiffParams,
+    UpdateBotConfigRequest,
+};
 
 /// List user's bot configurations
 pub async fn list_bot_configs(
     pool: web::Data<PgPool>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    // TODO: Extract user ID from JWT token
-    let bots: Vec<BotConfig> = vec![];
-    Ok(HttpResponse::Ok().json(ApiResponse::success(bots)))
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let bots = sqlx::query_as::<_, BotConfig>(
+        "SELECT * FROM bot_configs WHERE user_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match bots {
+        Ok(bots) => Ok(HttpResponse::Ok().json(ApiResponse::success(bots))),
+        Err(e) => {
+            log::error!("Failed to fetch bot configurations: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch bot configurations".to_string())
+            ))
+        }
+    }
 }
 
 /// Create new bot configuration
 pub async fn create_bot_config(
     pool: web::Data<PgPool>,
     req: web::Json<CreateBotConfigRequest>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    // TODO: Implement bot configuration creation
-    Ok(HttpResponse::NotImplemented().json(
-        ApiResponse::<()>::error("Bot creation not implemented yet".to_string())
-    ))
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if req.name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Bot name is required".to_string())
+        ));
+    }
+
+    if let Err(response) = validate_settings(pool.get_ref(), user_id, &req.bot_type, &req.config).await {
+        return Ok(response);
+    }
+
+    let bot = revisions::create_config(
+        pool.get_ref(),
+        user_id,
+        &req.bot_type,
+        req.name.trim(),
+        &req.config,
+    )
+    .await;
+
+    match bot {
+        Ok(bot) => Ok(HttpResponse::Created().json(ApiResponse::success(bot))),
+        Err(e) => {
+            log::error!("Failed to create bot configuration: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to create bot configuration".to_string())
+            ))
+        }
+    }
 }
 
 /// Get bot configuration by ID
 pub async fn get_bot_config(
     pool: web::Data<PgPool>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let bot_id = path.into_inner();
+    match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => Ok(HttpResponse::Ok().json(ApiResponse::success(bot))),
+        Err(response) => Ok(response),
+    }
+}
 
-    let bot = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE id = $1")
-        .bind(bot_id)
-        .fetch_optional(pool.get_ref())
-        .await;
+/// Update bot configuration. A bot's config can't change while it is active,
+/// since the running task would keep trading on the old one; renaming can.
+pub async fn update_bot_config(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateBotConfigRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
 
-    match bot {
-        Ok(Some(bot)) => {
-            Ok(HttpResponse::Ok().json(ApiResponse::success(bot)))
+    if req.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Ok(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Bot name is required".to_string())
+        ));
+    }
+
+    if let Some(config) = &req.config {
+        if bot.is_active {
+            return Ok(running_bot_conflict());
+        }
+        if let Err(response) = validate_settings(pool.get_ref(), bot.user_id, &bot.bot_type, config).await {
+            return Ok(response);
         }
-        Ok(None) => {
-            Ok(HttpResponse::NotFound().json(
-                ApiResponse::<()>::error("Bot configuration not found".to_string())
+    }
+
+    let updated = revisions::update_config(
+        pool.get_ref(),
+        bot.id,
+        bot.user_id,
+        req.name.as_deref().map(str::trim),
+        req.config.as_ref(),
+        None,
+    )
+    .await;
+
+    match updated {
+        Ok(bot) => Ok(HttpResponse::Ok().json(ApiResponse::success(bot))),
+        Err(e) => {
+            log::error!("Failed to update bot configuration: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to update bot configuration".to_string())
             ))
         }
+    }
+}
+
+fn running_bot_conflict() -> HttpResponse {
+    HttpResponse::Conflict().json(
+        ApiResponse::<()>::error("Stop the bot before changing its configuration".to_string())
+    )
+}
+
+/// List a bot's configuration revisions, newest first
+pub async fn list_bot_revisions(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    match revisions::list_revisions(pool.get_ref(), bot.id).await {
+        Ok(revisions) => Ok(HttpResponse::Ok().json(ApiResponse::success(revisions))),
         Err(e) => {
-            log::error!("Database error: {}", e);
+            log::error!("Failed to fetch revisions of bot {}: {}", bot.id, e);
             Ok(HttpResponse::InternalServerError().json(
-                ApiResponse::<()>::error("Internal server error".to_string())
+                ApiResponse::<()>::error("Failed to fetch bot revisions".to_string())
             ))
         }
     }
 }
 
-/// Update bot configuration
-pub async fn update_bot_config(
+/// Get one configuration revision
+pub async fn get_bot_revision(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, i32)>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let (bot_id, revision) = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), bot_id, &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    match find_bot_revision(pool.get_ref(), bot.id, revision).await {
+        Ok(revision) => Ok(HttpResponse::Ok().json(ApiResponse::success(revision))),
+        Err(response) => Ok(response),
+    }
+}
+
+/// Compare two configuration revisions
+pub async fn diff_bot_revisions(
     pool: web::Data<PgPool>,
     path: web::Path<Uuid>,
-    req: web::Json<serde_json::Value>,
+    query: web::Query<RevisionDiffParams>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    Ok(HttpResponse::NotImplemented().json(
-        ApiResponse::<()>::error("Bot update not implemented yet".to_string())
-    ))
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+    let to_revision = query.to.unwrap_or(bot.revision);
+
+    let from = match find_bot_revision(pool.get_ref(), bot.id, query.from).await {
+        Ok(revision) => revision,
+        Err(response) => return Ok(response),
+    };
+    let to = match find_bot_revision(pool.get_ref(), bot.id, to_revision).await {
+        Ok(revision) => revision,
+        Err(response) => return Ok(response),
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "from": from.revision,
+        "to": to.revision,
+        "changes": revisions::diff(&from.config_json, &to.config_json),
+    }))))
+}
+
+/// Restore an earlier revision. The restored configuration is validated
+/// again and saved as a new revision; history is never rewritten. Like an
+/// edit, a rollback is refused while the bot is active.
+pub async fn rollback_bot_config(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, i32)>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let (bot_id, revision) = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), bot_id, &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    if bot.is_active {
+        return Ok(running_bot_conflict());
+    }
+
+    let target = match find_bot_revision(pool.get_ref(), bot.id, revision).await {
+        Ok(revision) => revision,
+        Err(response) => return Ok(response),
+    };
+
+    if let Err(response) = validate_settings(pool.get_ref(), bot.user_id, &bot.bot_type, &target.config_json).await {
+        return Ok(response);
+    }
+
+    let restored = revisions::update_config(
+        pool.get_ref(),
+        bot.id,
+        bot.user_id,
+        Some(&target.name),
+        Some(&target.config_json),
+        Some(target.revision),
+    )
+    .await;
+
+    match restored {
+        Ok(bot) => Ok(HttpResponse::Ok().json(ApiResponse::success(bot))),
+        Err(e) => {
+            log::error!("Failed to roll back bot {} to revision {}: {}", bot.id, target.revision, e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to roll back bot configuration".to_string())
+            ))
+        }
+    }
+}
+
+/// JSON Schema of a bot type's configuration
+pub async fn get_bot_schema(path: web::Path<String>) -> Result<HttpResponse> {
+    match BotSettings::schema(&path.into_inner()) {
+        Some(schema) => Ok(HttpResponse::Ok().json(ApiResponse::success(schema))),
+        None => Ok(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Unknown bot type".to_string())
+        )),
+    }
 }
 
 /// Delete bot configuration
 pub async fn delete_bot_config(
     pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let bot_id = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
 
-    let result = sqlx::query("DELETE FROM bot_configs WHERE id = $1")
-        .bind(bot_id)
+    if let Err(e) = manager.stop(bot.id).await {
+        log::warn!("Failed to stop bot {} before deletion: {}", bot.id, e);
+    }
+
+    let result = sqlx::query("DELETE FROM bot_configs WHERE id = $1 AND user_id = $2")
+        .bind(bot.id)
+        .bind(bot.user_id)
         .execute(pool.get_ref())
         .await;
 
@@ -103,29 +331,41 @@ pub async fn delete_bot_config(
 /// Start bot
 pub async fn start_bot(
     pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let bot_id = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    if manager.status(bot.id).await.is_some_and(|status| status.state.is_live()) {
+        return Ok(HttpResponse::Conflict().json(
+            ApiResponse::<()>::error("Bot is already running".to_string())
+        ));
+    }
+
+    if let Some(response) = halted_response(pool.get_ref(), bot.user_id).await {
+        return Ok(response);
+    }
+
+    if let Err(e) = manager.start(bot.clone()).await {
+        return Ok(HttpResponse::UnprocessableEntity().json(ApiResponse::<()>::error(e.to_string())));
+    }
 
     let result = sqlx::query("UPDATE bot_configs SET is_active = true WHERE id = $1")
-        .bind(bot_id)
+        .bind(bot.id)
         .execute(pool.get_ref())
         .await;
 
     match result {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                Ok(HttpResponse::Ok().json(
-                    ApiResponse::<()>::message("Bot started successfully".to_string())
-                ))
-            } else {
-                Ok(HttpResponse::NotFound().json(
-                    ApiResponse::<()>::error("Bot configuration not found".to_string())
-                ))
-            }
-        }
+        Ok(_) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Bot started successfully".to_string())
+        )),
         Err(e) => {
             log::error!("Failed to start bot: {}", e);
+            let _ = manager.stop(bot.id).await;
             Ok(HttpResponse::InternalServerError().json(
                 ApiResponse::<()>::error("Failed to start bot".to_string())
             ))
@@ -133,30 +373,35 @@ pub async fn start_bot(
     }
 }
 
-/// Stop bot
+/// Stop bot after its current step
 pub async fn stop_bot(
     pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let bot_id = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
 
+    // Deactivate first so a restart in the meantime doesn't restore the bot
     let result = sqlx::query("UPDATE bot_configs SET is_active = false WHERE id = $1")
-        .bind(bot_id)
+        .bind(bot.id)
         .execute(pool.get_ref())
         .await;
 
-    match result {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                Ok(HttpResponse::Ok().json(
-                    ApiResponse::<()>::message("Bot stopped successfully".to_string())
-                ))
-            } else {
-                Ok(HttpResponse::NotFound().json(
-                    ApiResponse::<()>::error("Bot configuration not found".to_string())
-                ))
-            }
-        }
+    if let Err(e) = result {
+        log::error!("Failed to stop bot: {}", e);
+        return Ok(HttpResponse::InternalServerError().json(
+            ApiResponse::<()>::error("Failed to stop bot".to_string())
+        ));
+    }
+
+    match manager.stop(bot.id).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Bot stopped successfully".to_string())
+        )),
         Err(e) => {
             log::error!("Failed to stop bot: {}", e);
             Ok(HttpResponse::InternalServerError().json(
@@ -166,39 +411,167 @@ pub async fn stop_bot(
     }
 }
 
-/// Get bot status
+/// Pause a running bot between steps
+pub async fn pause_bot(
+    pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    match manager.pause(bot.id).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Bot paused".to_string())
+        )),
+        Err(e) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Resume a paused bot
+pub async fn resume_bot(
+    pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    match manager.resume(bot.id).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Bot resumed".to_string())
+        )),
+        Err(e) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Get bot status as reported by the supervisor
 pub async fn get_bot_status(
     pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let bot_id = path.into_inner();
+    let bot = match find_user_bot(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(bot) => bot,
+        Err(response) => return Ok(response),
+    };
+
+    let runtime = match manager.status_or_recorded(bot.id).await {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    };
+    let state = runtime.as_ref().map(|r| r.state).unwrap_or(BotState::Stopped);
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "id": bot.id,
+        "name": bot.name,
+        "bot_type": bot.bot_type,
+        "is_active": bot.is_active,
+        "last_run": bot.last_run,
+        "status": state,
+        "started_at": runtime.as_ref().and_then(|r| r.started_at),
+        "last_heartbeat": runtime.as_ref().and_then(|r| r.last_heartbeat),
+        "last_error": runtime.as_ref().and_then(|r| r.last_error.clone()),
+        "consecutive_failures": runtime.as_ref().map(|r| r.consecutive_failures).unwrap_or(0),
+        "stop_reason": runtime.as_ref().and_then(|r| r.stop_reason.clone()),
+        "progress": runtime.and_then(|r| r.progress)
+    }))))
+}
+
+/// Validate a bot configuration and check that its wallets belong to the user
+async fn validate_settings(
+    pool: &PgPool,
+    user_id: Uuid,
+    bot_type: &str,
+    config: &serde_json::Value,
+) -> std::result::Result<BotSettings, HttpResponse> {
+    let settings = BotSettings::parse(bot_type, config).map_err(|e| {
+        HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string()))
+    })?;
+
+    let wallet_ids = settings.wallet_ids().to_vec();
+    let owned = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM wallets WHERE user_id = $1 AND id = ANY($2)"
+    )
+    .bind(user_id)
+    .bind(&wallet_ids)
+    .fetch_one(pool)
+    .await;
+
+    match owned {
+        Ok(count) if count == wallet_ids.len() as i64 => Ok(settings),
+        Ok(_) => Err(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Bot configuration references unknown wallets".to_string())
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
 
-    let bot = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE id = $1")
+/// Load one revision of a bot or the error response to return
+async fn find_bot_revision(
+    pool: &PgPool,
+    bot_id: Uuid,
+    revision: i32,
+) -> std::result::Result<BotConfigRevision, HttpResponse> {
+    match revisions::find_revision(pool, bot_id, revision).await {
+        Ok(Some(revision)) => Ok(revision),
+        Ok(None) => Err(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error(format!("Revision {} not found", revision))
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
+
+/// Resolve one of the caller's bots or the error response to return
+async fn find_user_bot(
+    pool: &PgPool,
+    bot_id: Uuid,
+    http_req: &HttpRequest,
+) -> std::result::Result<BotConfig, HttpResponse> {
+    let user_id = authenticated_user_id(http_req).ok_or_else(|| {
+        HttpResponse::Unauthorized().json(
+            ApiResponse::<()>::error("Authentication required".to_string())
+        )
+    })?;
+
+    let bot = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE id = $1 AND user_id = $2")
         .bind(bot_id)
-        .fetch_optional(pool.get_ref())
+        .bind(user_id)
+        .fetch_optional(pool)
         .await;
 
     match bot {
-        Ok(Some(bot)) => {
-            Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
-                "id": bot.id,
-                "name": bot.name,
-                "bot_type": bot.bot_type,
-                "is_active": bot.is_active,
-                "last_run": bot.last_run,
-                "status": if bot.is_active { "running" } else { "stopped" }
-            }))))
-        }
-        Ok(None) => {
-            Ok(HttpResponse::NotFound().json(
-                ApiResponse::<()>::error("Bot configuration not found".to_string())
-            ))
-        }
+        Ok(Some(bot)) => Ok(bot),
+        Ok(None) => Err(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Bot configuration not found".to_string())
+        )),
         Err(e) => {
             log::error!("Database error: {}", e);
-            Ok(HttpResponse::InternalServerError().json(
+            Err(HttpResponse::InternalServerError().json(
                 ApiResponse::<()>::error("Internal server error".to_string())
             ))
         }
     }
-}
\ No newline at end of file
+}
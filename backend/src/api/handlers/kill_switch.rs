@@ -0,0 +1,248 @@
+//! Kill switch handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::middleware::authenticated_user_id;
+use crate::bots::BotManager;
+use crate::config::Config;
+use crate::database::audit::{self, AuditEntry};
+use crate::database::models::{ApiResponse, KillSwitch, KillSwitchRequest};
+use crate::trading::kill_switch::{self, Cancellations, TradingHalted};
+
+/// Kill switches covering the current user
+#[derive(Debug, Serialize)]
+pub struct KillSwitchStatus {
+    pub halted: bool,
+    pub user: Option<KillSwitch>,
+    pub global: Option<KillSwitch>,
+}
+
+/// What engaging a kill switch stopped and cancelled
+#[derive(Debug, Serialize)]
+pub struct KillSwitchReport {
+    pub switch: KillSwitch,
+    /// False when the switch was already engaged; the sweep runs regardless
+    pub newly_engaged: bool,
+    pub bots_stopped: Vec<Uuid>,
+    pub cancelled: Cancellations,
+}
+
+/// 423 response when a kill switch halts the user's trading
+pub async fn halted_response(pool: &PgPool, user_id: Uuid) -> Option<HttpResponse> {
+    match kill_switch::halting(pool, user_id).await {
+        Ok(None) => None,
+        Ok(Some(switch)) => Some(HttpResponse::Locked().json(
+            ApiResponse::<()>::error(TradingHalted { switch }.to_string())
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Some(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
+
+/// Kill switches currently halting the user's trading
+pub async fn get_kill_switch(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let switches = futures::try_join!(
+        kill_switch::engaged(pool.get_ref(), Some(user_id)),
+        kill_switch::engaged(pool.get_ref(), None),
+    );
+
+    match switches {
+        Ok((user, global)) => Ok(HttpResponse::Ok().json(ApiResponse::success(KillSwitchStatus {
+            halted: user.is_some() || global.is_some(),
+            user,
+            global,
+        }))),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
+
+/// Halt trading for the current user, or globally for operators: stop
+/// their bots, cancel unsent trades and open orders, and refuse new trades
+/// until the switch is released
+pub async fn engage_kill_switch(
+    pool: web::Data<PgPool>,
+    manager: web::Data<BotManager>,
+    config: web::Data<Config>,
+    req: web::Json<KillSwitchRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let scope = match scope_for(&config, user_id, &req) {
+        Some(scope) => scope,
+        None => {
+            return Ok(HttpResponse::Forbidden().json(
+                ApiResponse::<()>::error("Only operators can use the global kill switch".to_string())
+            ));
+        }
+    };
+
+    let (switch, newly_engaged) = match kill_switch::engage(pool.get_ref(), scope, user_id, req.reason.as_deref()).await {
+        Ok(engaged) => engaged,
+        Err(e) => {
+            log::error!("Failed to engage kill switch: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to engage kill switch".to_string())
+            ));
+        }
+    };
+    log::warn!("Kill switch {} engaged by user {}", switch.id, user_id);
+
+    // New trades are refused from here on; now clear out what is already running
+    let bots_stopped = match stop_bots(pool.get_ref(), &manager, scope).await {
+        Ok(bots) => bots,
+        Err(e) => {
+            log::error!("Kill switch failed to stop bots: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Kill switch engaged, but stopping bots failed".to_string())
+            ));
+        }
+    };
+
+    let cancelled = match kill_switch::cancel_pending(pool.get_ref(), scope).await {
+        Ok(cancelled) => cancelled,
+        Err(e) => {
+            log::error!("Kill switch failed to cancel pending trades: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Kill switch engaged, but cancelling pending trades failed".to_string())
+            ));
+        }
+    };
+
+    let entry = AuditEntry::new(Some(user_id), "kill_switch.engaged")
+        .resource("kill_switch", switch.id)
+        .details(serde_json::json!({
+            "global": scope.is_none(),
+            "reason": switch.reason,
+            "newly_engaged": newly_engaged,
+            "bots_stopped": bots_stopped.len(),
+            "trades_cancelled": cancelled.trades.len(),
+            "orders_cancelled": cancelled.orders.len(),
+        }))
+        .client(&http_req);
+    if let Err(e) = audit::record(pool.get_ref(), &entry).await {
+        log::error!("Failed to audit kill switch {}: {}", switch.id, e);
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(KillSwitchReport {
+        switch,
+        newly_engaged,
+        bots_stopped,
+        cancelled,
+    })))
+}
+
+/// Re-arm trading by releasing the current user's or the global switch.
+/// Stopped bots stay stopped.
+pub async fn release_kill_switch(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    req: web::Json<KillSwitchRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let scope = match scope_for(&config, user_id, &req) {
+        Some(scope) => scope,
+        None => {
+            return Ok(HttpResponse::Forbidden().json(
+                ApiResponse::<()>::error("Only operators can use the global kill switch".to_string())
+            ));
+        }
+    };
+
+    let switch = match kill_switch::release(pool.get_ref(), scope, user_id).await {
+        Ok(Some(switch)) => switch,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Kill switch is not engaged".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Failed to release kill switch: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to release kill switch".to_string())
+            ));
+        }
+    };
+    log::warn!("Kill switch {} released by user {}", switch.id, user_id);
+
+    let entry = AuditEntry::new(Some(user_id), "kill_switch.released")
+        .resource("kill_switch", switch.id)
+        .details(serde_json::json!({
+            "global": scope.is_none(),
+            "reason": req.reason,
+            "engaged_at": switch.engaged_at,
+        }))
+        .client(&http_req);
+    if let Err(e) = audit::record(pool.get_ref(), &entry).await {
+        log::error!("Failed to audit kill switch {}: {}", switch.id, e);
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(switch)))
+}
+
+/// The scope a request acts on: the caller's own trading, or everyone's
+/// (`None`) for operators asking for the global switch. Nothing if the
+/// caller may not act on the global switch.
+fn scope_for(config: &Config, user_id: Uuid, req: &KillSwitchRequest) -> Option<Option<Uuid>> {
+    if !req.global {
+        Some(Some(user_id))
+    } else if config.is_operator(user_id) {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+/// Deactivate and stop every bot in the scope. Bots are deactivated first
+/// so a restart doesn't bring them back, then stopped together.
+async fn stop_bots(pool: &PgPool, manager: &BotManager, scope: Option<Uuid>) -> anyhow::Result<Vec<Uuid>> {
+    let bots = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE bot_configs SET is_active = false WHERE is_active AND ($1::uuid IS NULL OR user_id = $1) RETURNING id"
+    )
+    .bind(scope)
+    .fetch_all(pool)
+    .await?;
+
+    futures::future::try_join_all(bots.iter().map(|bot_id| manager.stop(*bot_id))).await?;
+    Ok(bots)
+}
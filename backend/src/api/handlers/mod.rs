@@ -4,4 +4,12 @@ pub mod auth;
 pub mod users;
 pub mod wallets;
 pub mod trades;
-pub mod bots;
\ No newline at end of file
+pub mod bots;
+pub mod paper;
+pub mod orders;
+pub mod positions;
+pub mod risk;
+pub mod tokens;
+pub mod kill_switch;
+pub mod transfers;
+pub mod withdrawals;
@@ -0,0 +1,257 @@
+//! Conditional order handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::handlers::kill_switch::halted_response;
+use crate::api::handlers::wallets::find_user_wallet;
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{
+    ApiResponse, AmendOrderRequest, CreateOrderRequest, Order, OrderListParams
+};
+use crate::orders::{insert_order, status, validate_amendment, validate_order_request};
+use crate::trading::TradeEngine;
+
+/// List the current user's orders
+pub async fn list_orders(
+    pool: web::Data<PgPool>,
+    query: web::Query<OrderListParams>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let orders = sqlx::query_as::<_, Order>(
+        r#"
+        SELECT * FROM orders
+        WHERE user_id = $1
+          AND ($2::text IS NULL OR status = $2)
+          AND ($3::text IS NULL OR token_address = $3)
+        ORDER BY created_at DESC
+        "#
+    )
+    .bind(user_id)
+    .bind(&query.status)
+    .bind(&query.token_address)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match orders {
+        Ok(orders) => Ok(HttpResponse::Ok().json(ApiResponse::success(orders))),
+        Err(e) => {
+            log::error!("Failed to fetch orders: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch orders".to_string())
+            ))
+        }
+    }
+}
+
+/// Place a new resting order
+pub async fn create_order(
+    pool: web::Data<PgPool>,
+    engine: web::Data<TradeEngine>,
+    req: web::Json<CreateOrderRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Some(response) = halted_response(pool.get_ref(), user_id).await {
+        return Ok(response);
+    }
+
+    let order_type = match validate_order_request(&req) {
+        Ok(order_type) => order_type,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    match find_user_wallet(pool.get_ref(), req.wallet_id, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Wallet not found".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    }
+
+    let paper = engine.user_paper_mode(user_id).await.unwrap_or(false);
+
+    let order = insert_order(pool.get_ref(), user_id, &req, order_type, paper).await;
+
+    match order {
+        Ok(order) => Ok(HttpResponse::Created().json(ApiResponse::success(order))),
+        Err(e) => {
+            log::error!("Failed to create order: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to create order".to_string())
+            ))
+        }
+    }
+}
+
+/// Get order by ID
+pub async fn get_order(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    match find_user_order(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse::success(order))),
+        Err(response) => Ok(response),
+    }
+}
+
+/// Amend the size, trigger or slippage of an open order
+pub async fn amend_order(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: web::Json<AmendOrderRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let order = match find_user_order(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(order) => order,
+        Err(response) => return Ok(response),
+    };
+
+    if let Err(e) = validate_amendment(&order, &req) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+    }
+
+    // Changing the trail restarts the high-water mark
+    let reset_peak = req.trail_percent.is_some();
+
+    let updated = sqlx::query_as::<_, Order>(
+        r#"
+        UPDATE orders SET
+            sol_amount = COALESCE($2, sol_amount),
+            trigger_price = COALESCE($3, trigger_price),
+            trail_percent = COALESCE($4, trail_percent),
+            slippage_tolerance = COALESCE($5, slippage_tolerance),
+            priority_fee = COALESCE($6, priority_fee),
+            peak_price = CASE WHEN $7 THEN NULL ELSE peak_price END,
+            updated_at = NOW()
+        WHERE id = $1 AND status = $8
+        RETURNING *
+        "#
+    )
+    .bind(order.id)
+    .bind(req.sol_amount)
+    .bind(req.trigger_price)
+    .bind(req.trail_percent)
+    .bind(req.slippage_tolerance)
+    .bind(req.priority_fee)
+    .bind(reset_peak)
+    .bind(status::OPEN)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match updated {
+        Ok(Some(order)) => Ok(HttpResponse::Ok().json(ApiResponse::success(order))),
+        Ok(None) => {
+            Ok(HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Order triggered before it could be amended".to_string())
+            ))
+        }
+        Err(e) => {
+            log::error!("Failed to amend order: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to amend order".to_string())
+            ))
+        }
+    }
+}
+
+/// Cancel an open order
+pub async fn cancel_order(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let order = match find_user_order(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(order) => order,
+        Err(response) => return Ok(response),
+    };
+
+    let result = sqlx::query(
+        "UPDATE orders SET status = $2, updated_at = NOW() WHERE id = $1 AND status = $3"
+    )
+    .bind(order.id)
+    .bind(status::CANCELLED)
+    .bind(status::OPEN)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                Ok(HttpResponse::Ok().json(
+                    ApiResponse::<()>::message("Order cancelled successfully".to_string())
+                ))
+            } else {
+                Ok(HttpResponse::Conflict().json(
+                    ApiResponse::<()>::error(format!("Order cannot be cancelled once {}", order.status))
+                ))
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to cancel order: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to cancel order".to_string())
+            ))
+        }
+    }
+}
+
+/// Resolve one of the caller's orders or the error response to return
+async fn find_user_order(
+    pool: &PgPool,
+    order_id: Uuid,
+    http_req: &HttpRequest,
+) -> std::result::Result<Order, HttpResponse> {
+    let user_id = authenticated_user_id(http_req).ok_or_else(|| {
+        HttpResponse::Unauthorized().json(
+            ApiResponse::<()>::error("Authentication required".to_string())
+        )
+    })?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 AND user_id = $2")
+        .bind(order_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await;
+
+    match order {
+        Ok(Some(order)) => Ok(order),
+        Ok(None) => Err(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Order not found".to_string())
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
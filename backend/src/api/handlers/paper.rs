@@ -0,0 +1,151 @@
+//! Paper-trading handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::handlers::wallets::find_user_wallet;
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{ApiResponse, FundPaperWalletRequest, PaperModeRequest, Wallet};
+use crate::trading::paper;
+
+/// Switch paper-trading mode on or off for the current user
+pub async fn set_paper_mode(
+    pool: web::Data<PgPool>,
+    req: web::Json<PaperModeRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let result = sqlx::query("UPDATE users SET paper_trading = $1 WHERE id = $2")
+        .bind(req.enabled)
+        .bind(user_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(_) => {
+            let mode = if req.enabled { "enabled" } else { "disabled" };
+            Ok(HttpResponse::Ok().json(
+                ApiResponse::<()>::message(format!("Paper trading {}", mode))
+            ))
+        }
+        Err(e) => {
+            log::error!("Failed to update paper mode: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to update paper mode".to_string())
+            ))
+        }
+    }
+}
+
+/// Get virtual balances of a wallet
+pub async fn get_paper_balances(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let wallet = match owned_wallet(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(wallet) => wallet,
+        Err(response) => return Ok(response),
+    };
+
+    match paper::balances(pool.get_ref(), wallet.id).await {
+        Ok(balances) => Ok(HttpResponse::Ok().json(ApiResponse::success(balances))),
+        Err(e) => {
+            log::error!("Failed to fetch paper balances: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch paper balances".to_string())
+            ))
+        }
+    }
+}
+
+/// Add virtual SOL to a wallet
+pub async fn fund_paper_wallet(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: web::Json<FundPaperWalletRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    if req.sol_amount <= Decimal::ZERO {
+        return Ok(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("SOL amount must be greater than zero".to_string())
+        ));
+    }
+
+    let wallet = match owned_wallet(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(wallet) => wallet,
+        Err(response) => return Ok(response),
+    };
+
+    match paper::fund(pool.get_ref(), wallet.user_id, wallet.id, req.sol_amount).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message(format!("Funded paper wallet with {} SOL", req.sol_amount))
+        )),
+        Err(e) => {
+            log::error!("Failed to fund paper wallet: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fund paper wallet".to_string())
+            ))
+        }
+    }
+}
+
+/// Clear all virtual balances of a wallet
+pub async fn reset_paper_wallet(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let wallet = match owned_wallet(pool.get_ref(), path.into_inner(), &http_req).await {
+        Ok(wallet) => wallet,
+        Err(response) => return Ok(response),
+    };
+
+    match paper::reset(pool.get_ref(), wallet.id).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Paper wallet reset".to_string())
+        )),
+        Err(e) => {
+            log::error!("Failed to reset paper wallet: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to reset paper wallet".to_string())
+            ))
+        }
+    }
+}
+
+/// Resolve the caller's wallet or the error response to return
+async fn owned_wallet(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    http_req: &HttpRequest,
+) -> std::result::Result<Wallet, HttpResponse> {
+    let user_id = authenticated_user_id(http_req).ok_or_else(|| {
+        HttpResponse::Unauthorized().json(
+            ApiResponse::<()>::error("Authentication required".to_string())
+        )
+    })?;
+
+    match find_user_wallet(pool, wallet_id, user_id).await {
+        Ok(Some(wallet)) => Ok(wallet),
+        Ok(None) => Err(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Wallet not found".to_string())
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
+}
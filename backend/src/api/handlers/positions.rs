@@ -0,0 +1,126 @@
+//! Position and PnL handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use sqlx::PgPool;
+
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{ApiResponse, PnlParams, PositionParams};
+use crate::market::PriceFeed;
+use crate::positions::{report, CostMethod, FillScope};
+use crate::trading::TradeEngine;
+
+/// List the current user's positions with unrealized PnL
+pub async fn list_positions(
+    pool: web::Data<PgPool>,
+    engine: web::Data<TradeEngine>,
+    prices: web::Data<dyn PriceFeed>,
+    query: web::Query<PositionParams>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let method = match query.method.as_deref().map(str::parse::<CostMethod>).transpose() {
+        Ok(method) => method.unwrap_or_default(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    let paper = match query.paper {
+        Some(paper) => paper,
+        None => engine.user_paper_mode(user_id).await.unwrap_or(false),
+    };
+    let scope = FillScope {
+        user_id,
+        paper,
+        wallet_id: query.wallet_id,
+        bot_config_id: None,
+        until: None,
+    };
+
+    match report::build_book(pool.get_ref(), &scope, method).await {
+        Ok(book) => {
+            let marks = report::mark_prices(prices.get_ref(), &book).await;
+            let views = report::position_views(&book, &marks, query.include_closed.unwrap_or(false));
+            Ok(HttpResponse::Ok().json(ApiResponse::success(views)))
+        }
+        Err(e) => {
+            log::error!("Failed to build positions: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch positions".to_string())
+            ))
+        }
+    }
+}
+
+/// Realized PnL over a window with per-bot and per-wallet breakdowns
+pub async fn get_pnl(
+    pool: web::Data<PgPool>,
+    engine: web::Data<TradeEngine>,
+    prices: web::Data<dyn PriceFeed>,
+    query: web::Query<PnlParams>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let method = match query.method.as_deref().map(str::parse::<CostMethod>).transpose() {
+        Ok(method) => method.unwrap_or_default(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Ok(HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("'from' must not be after 'to'".to_string())
+            ));
+        }
+    }
+
+    let paper = match query.paper {
+        Some(paper) => paper,
+        None => engine.user_paper_mode(user_id).await.unwrap_or(false),
+    };
+    // Sells in the window need every earlier buy for their cost basis, so
+    // history is replayed from the start and only cut off at `to`
+    let scope = FillScope {
+        user_id,
+        paper,
+        wallet_id: query.wallet_id,
+        bot_config_id: None,
+        until: query.to,
+    };
+
+    match report::build_book(pool.get_ref(), &scope, method).await {
+        Ok(book) => {
+            let marks = if query.to.is_none() {
+                report::mark_prices(prices.get_ref(), &book).await
+            } else {
+                Default::default()
+            };
+            let pnl = report::pnl_report(&book, &marks, query.from, query.to);
+            Ok(HttpResponse::Ok().json(ApiResponse::success(pnl)))
+        }
+        Err(e) => {
+            log::error!("Failed to build PnL report: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to compute PnL".to_string())
+            ))
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Risk limit handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::handlers::wallets::find_user_wallet;
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{ApiResponse, RiskLimit, SetRiskLimitRequest};
+use crate::trading::risk::{set_limit, validate_risk_limit};
+
+/// List the current user's risk limits
+pub async fn list_risk_limits(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let limits = sqlx::query_as::<_, RiskLimit>(
+        "SELECT * FROM risk_limits WHERE user_id = $1 ORDER BY created_at ASC"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match limits {
+        Ok(limits) => Ok(HttpResponse::Ok().json(ApiResponse::success(limits))),
+        Err(e) => {
+            log::error!("Failed to fetch risk limits: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch risk limits".to_string())
+            ))
+        }
+    }
+}
+
+/// Set the limits for the user, one of their wallets or one of their bots
+pub async fn set_risk_limit(
+    pool: web::Data<PgPool>,
+    req: web::Json<SetRiskLimitRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Err(e) = validate_risk_limit(&req) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+    }
+
+    let owned = if let Some(wallet_id) = req.wallet_id {
+        find_user_wallet(pool.get_ref(), wallet_id, user_id).await.map(|wallet| wallet.is_some())
+    } else if let Some(bot_id) = req.bot_config_id {
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM bot_configs WHERE id = $1 AND user_id = $2)")
+            .bind(bot_id)
+            .bind(user_id)
+            .fetch_one(pool.get_ref())
+            .await
+    } else {
+        Ok(true)
+    };
+
+    match owned {
+        Ok(true) => {}
+        Ok(false) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Wallet or bot not found".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    }
+
+    match set_limit(pool.get_ref(), user_id, &req).await {
+        Ok(limit) => Ok(HttpResponse::Ok().json(ApiResponse::success(limit))),
+        Err(e) => {
+            log::error!("Failed to set risk limit: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to set risk limit".to_string())
+            ))
+        }
+    }
+}
+
+/// Remove a set of risk limits
+pub async fn delete_risk_limit(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let result = sqlx::query("DELETE FROM risk_limits WHERE id = $1 AND user_id = $2")
+        .bind(path.into_inner())
+        .bind(user_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json(
+            ApiResponse::<()>::message("Risk limit deleted successfully".to_string())
+        )),
+        Ok(_) => Ok(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Risk limit not found".to_string())
+        )),
+        Err(e) => {
+            log::error!("Failed to delete risk limit: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to delete risk limit".to_string())
+            ))
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Token risk handlers
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use solana_sdk::pubkey::Pubkey;
+use sqlx::PgPool;
+use std::str::FromStr;
+
+use crate::auth::middleware::authenticated_user_id;
+use crate::bots::config::BotSettings;
+use crate::database::models::{ApiResponse, BotConfig, TokenRiskParams};
+use crate::market::{SecurityPolicy, TokenFilter};
+
+/// Full security report on a mint, under a bot's rules or the defaults
+pub async fn get_token_risk(
+    pool: web::Data<PgPool>,
+    filter: web::Data<dyn TokenFilter>,
+    path: web::Path<String>,
+    query: web::Query<TokenRiskParams>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let mint = path.into_inner();
+    if Pubkey::from_str(&mint).is_err() {
+        return Ok(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Invalid mint address".to_string())
+        ));
+    }
+
+    let policy = match query.bot_id {
+        None => SecurityPolicy::default(),
+        Some(bot_id) => {
+            let bot = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE id = $1 AND user_id = $2")
+                .bind(bot_id)
+                .bind(user_id)
+                .fetch_optional(pool.get_ref())
+                .await;
+
+            let settings = match bot {
+                Ok(Some(bot)) => BotSettings::parse(&bot.bot_type, &bot.config_json),
+                Ok(None) => {
+                    return Ok(HttpResponse::NotFound().json(
+                        ApiResponse::<()>::error("Bot configuration not found".to_string())
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json(
+                        ApiResponse::<()>::error("Internal server error".to_string())
+                    ));
+                }
+            };
+
+            match settings.map(|settings| settings.security_policy().cloned()) {
+                Ok(Some(policy)) => policy,
+                Ok(None) => {
+                    return Ok(HttpResponse::BadRequest().json(
+                        ApiResponse::<()>::error("This bot type has no security rules".to_string())
+                    ));
+                }
+                Err(e) => {
+                    return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+                }
+            }
+        }
+    };
+
+    match filter.assess(&mint, &policy).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse::success(report))),
+        Err(e) => {
+            log::error!("Failed to assess token {}: {}", mint, e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to assess token".to_string())
+            ))
+        }
+    }
+}
@@ -1,45 +1,353 @@
 //! Trading handlers
 
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::database::models::{Trade, ApiResponse, CreateTradeRequest, PaginationParams};
+use crate::api::handlers::wallets::find_user_wallet;
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{
+    Trade, ApiResponse, CreateTradeRequest, CreateTradeParams, PanicSellJob, PanicSellRequest,
+    TradeExportParams, TradeListParams
+};
+use crate::market::PriceFeed;
+use crate::positions::CostMethod;
+use crate::trading::export::{self, ExportFormat, ExportQuery};
+use crate::trading::history::{self, TradeQuery};
+use crate::trading::lifecycle::{self, TradeStatus, TransitionDetails};
+use crate::trading::liquidation;
+use crate::trading::{
+    validate_trade_request, PanicSellConfig, PanicSeller, TradeEngine, TradeOptions, TradingHalted,
+};
 
-/// List user's trades
+/// Look up a trade owned by the given user
+async fn find_user_trade(
+    pool: &PgPool,
+    trade_id: Uuid,
+    user_id: Uuid,
+) -> std::result::Result<Option<Trade>, sqlx::Error> {
+    sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE id = $1 AND user_id = $2")
+        .bind(trade_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// List user's trades with filters, sorting and pagination
 pub async fn list_trades(
     pool: web::Data<PgPool>,
-    query: web::Query<PaginationParams>,
+    query: web::Query<TradeListParams>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let query = match TradeQuery::from_params(user_id, &query) {
+        Ok(query) => query,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    match history::list_trades(pool.get_ref(), &query).await {
+        Ok(page) => Ok(HttpResponse::Ok().json(ApiResponse::success(page))),
+        Err(e) => {
+            log::error!("Failed to fetch trades: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch trades".to_string())
+            ))
+        }
+    }
+}
+
+/// Stream settled trades as CSV or JSON for accounting
+pub async fn export_trades(
+    pool: web::Data<PgPool>,
+    query: web::Query<TradeExportParams>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    // TODO: Extract user ID from JWT token and implement pagination
-    let trades: Vec<Trade> = vec![];
-    Ok(HttpResponse::Ok().json(ApiResponse::success(trades)))
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let format = match query.format.as_deref().map(str::parse::<ExportFormat>).transpose() {
+        Ok(format) => format.unwrap_or_default(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+    let method = match query.method.as_deref().map(str::parse::<CostMethod>).transpose() {
+        Ok(method) => method.unwrap_or_default(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Ok(HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("'from' must not be after 'to'".to_string())
+            ));
+        }
+    }
+
+    let export_query = ExportQuery {
+        user_id,
+        paper: query.paper.unwrap_or(false),
+        from: query.from,
+        to: query.to,
+        method,
+        format,
+    };
+    let body = export::stream_trades(pool.get_ref().clone(), export_query);
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"trades.{}\"", format.extension()),
+        ))
+        .streaming(body))
 }
 
-/// Create new trade
+/// Create new trade and submit it to the chain, or only simulate it with `?dry_run=true`
 pub async fn create_trade(
     pool: web::Data<PgPool>,
+    engine: web::Data<TradeEngine>,
+    params: web::Query<CreateTradeParams>,
     req: web::Json<CreateTradeRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Err(e) = validate_trade_request(&req) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+    }
+
+    // The wallet must belong to the caller
+    match find_user_wallet(pool.get_ref(), req.wallet_id, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Wallet not found".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    }
+
+    let paper = match engine.user_paper_mode(user_id).await {
+        Ok(paper) => paper,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    };
+    let options = TradeOptions {
+        paper,
+        ..Default::default()
+    };
+
+    if params.dry_run.unwrap_or(false) {
+        return match engine.dry_run(user_id, &req, &options).await {
+            Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse::success(report))),
+            Err(e) => {
+                log::warn!("Dry run failed: {}", e);
+                Ok(HttpResponse::UnprocessableEntity().json(
+                    ApiResponse::<()>::error(format!("Dry run failed: {}", e))
+                ))
+            }
+        };
+    }
+
+    let trade = match engine.create_trade(user_id, &req, &options).await {
+        Ok(trade) => trade,
+        Err(e) if e.is::<TradingHalted>() => {
+            return Ok(HttpResponse::Locked().json(ApiResponse::<()>::error(e.to_string())));
+        }
+        Err(e) => {
+            log::error!("Failed to create trade: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to create trade".to_string())
+            ));
+        }
+    };
+
+    match engine.execute(&trade).await {
+        Ok(trade) => Ok(HttpResponse::Created().json(ApiResponse::success(trade))),
+        Err(e) => {
+            log::error!("Failed to execute trade {}: {}", trade.id, e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to execute trade".to_string())
+            ))
+        }
+    }
+}
+
+/// Start selling every token held in the chosen wallets back to SOL, most
+/// valuable first. Goes ahead while trading is halted; the run happens in
+/// the background and its report is polled with `get_panic_sell`.
+pub async fn panic_sell(
+    pool: web::Data<PgPool>,
+    engine: web::Data<TradeEngine>,
+    prices: web::Data<dyn PriceFeed>,
+    req: web::Json<PanicSellRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let wallet_ids = if req.wallet_ids.is_empty() {
+        let wallets = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM wallets WHERE user_id = $1 AND is_active = true ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(pool.get_ref())
+        .await;
+
+        match wallets {
+            Ok(wallets) => wallets,
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::error("Internal server error".to_string())
+                ));
+            }
+        }
+    } else {
+        let mut wallet_ids = req.wallet_ids.clone();
+        wallet_ids.sort();
+        wallet_ids.dedup();
+
+        // Every wallet must belong to the caller
+        for wallet_id in &wallet_ids {
+            match find_user_wallet(pool.get_ref(), *wallet_id, user_id).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return Ok(HttpResponse::NotFound().json(
+                        ApiResponse::<()>::error(format!("Wallet {} not found", wallet_id))
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json(
+                        ApiResponse::<()>::error("Internal server error".to_string())
+                    ));
+                }
+            }
+        }
+        wallet_ids
+    };
+
+    if wallet_ids.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("No wallets to sell from".to_string())
+        ));
+    }
+
+    let job = match liquidation::start_job(pool.get_ref(), user_id, &wallet_ids).await {
+        Ok(Some(job)) => job,
+        Ok(None) => {
+            return Ok(HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("A panic-sell is already running".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Failed to start panic-sell: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to start panic-sell".to_string())
+            ));
+        }
+    };
+
+    let seller = PanicSeller::new(engine.get_ref().clone(), prices.into_inner(), PanicSellConfig::default());
+    seller.spawn(job.clone());
+    Ok(HttpResponse::Accepted().json(ApiResponse::success(job)))
+}
+
+/// Progress of a panic-sell; the report is present once it has completed
+pub async fn get_panic_sell(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    // TODO: Implement trade creation with Solana transaction
-    Ok(HttpResponse::NotImplemented().json(
-        ApiResponse::<()>::error("Trade creation not implemented yet".to_string())
-    ))
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let job = sqlx::query_as::<_, PanicSellJob>(
+        "SELECT * FROM panic_sell_jobs WHERE id = $1 AND user_id = $2"
+    )
+    .bind(path.into_inner())
+    .bind(user_id)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match job {
+        Ok(Some(job)) => Ok(HttpResponse::Ok().json(ApiResponse::success(job))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Panic-sell not found".to_string())
+        )),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ))
+        }
+    }
 }
 
 /// Get trade by ID
 pub async fn get_trade(
     pool: web::Data<PgPool>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let trade_id = path.into_inner();
-
-    let trade = sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE id = $1")
-        .bind(trade_id)
-        .fetch_optional(pool.get_ref())
-        .await;
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
 
-    match trade {
+    match find_user_trade(pool.get_ref(), path.into_inner(), user_id).await {
         Ok(Some(trade)) => {
             Ok(HttpResponse::Ok().json(ApiResponse::success(trade)))
         }
@@ -57,35 +365,113 @@ pub async fn get_trade(
     }
 }
 
-/// Cancel trade
+/// Get the status transition history of a trade
+pub async fn get_trade_events(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let trade = match find_user_trade(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(Some(trade)) => trade,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Trade not found".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    };
+
+    match lifecycle::history(pool.get_ref(), trade.id).await {
+        Ok(events) => Ok(HttpResponse::Ok().json(ApiResponse::success(events))),
+        Err(e) => {
+            log::error!("Failed to fetch trade events: {}", e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch trade events".to_string())
+            ))
+        }
+    }
+}
+
+/// Cancel trade (only possible before it has been submitted)
 pub async fn cancel_trade(
     pool: web::Data<PgPool>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
     let trade_id = path.into_inner();
 
-    let result = sqlx::query("UPDATE trades SET status = 'cancelled' WHERE id = $1 AND status = 'pending'")
-        .bind(trade_id)
-        .execute(pool.get_ref())
+    // The engine may move the trade along between the read and the cancel,
+    // so a lost race is retried from the status it moved to
+    loop {
+        let trade = match find_user_trade(pool.get_ref(), trade_id, user_id).await {
+            Ok(Some(trade)) => trade,
+            Ok(None) => {
+                return Ok(HttpResponse::NotFound().json(
+                    ApiResponse::<()>::error("Trade not found".to_string())
+                ));
+            }
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::error("Internal server error".to_string())
+                ));
+            }
+        };
+
+        let status = match trade.lifecycle_status() {
+            Ok(status) if status.is_cancellable() => status,
+            _ => {
+                return Ok(HttpResponse::Conflict().json(
+                    ApiResponse::<()>::error(format!("Trade cannot be cancelled once {}", trade.status))
+                ));
+            }
+        };
+
+        let result = lifecycle::transition(
+            pool.get_ref(),
+            trade.id,
+            status,
+            TradeStatus::Cancelled,
+            TransitionDetails::message("Cancelled by user"),
+        )
         .await;
 
-    match result {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                Ok(HttpResponse::Ok().json(
+        match result {
+            Ok(true) => {
+                return Ok(HttpResponse::Ok().json(
                     ApiResponse::<()>::message("Trade cancelled successfully".to_string())
-                ))
-            } else {
-                Ok(HttpResponse::NotFound().json(
-                    ApiResponse::<()>::error("Trade not found or cannot be cancelled".to_string())
-                ))
+                ));
+            }
+            Ok(false) => continue,
+            Err(e) => {
+                log::error!("Failed to cancel trade: {}", e);
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::error("Failed to cancel trade".to_string())
+                ));
             }
-        }
-        Err(e) => {
-            log::error!("Failed to cancel trade: {}", e);
-            Ok(HttpResponse::InternalServerError().json(
-                ApiResponse::<()>::error("Failed to cancel trade".to_string())
-            ))
         }
     }
 }
\ No newline at end of file
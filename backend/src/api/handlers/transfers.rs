@@ -0,0 +1,164 @@
+//! Handlers moving SOL between a user's wallets
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::auth::middleware::authenticated_user_id;
+use crate::database::models::{ApiResponse, DistributeRequest, SweepRequest, Wallet};
+use crate::wallet::transfers::{self, TransferPlan, WalletTransfers};
+
+/// Active wallets of the user, in the order asked for. The id of the first
+/// one that isn't theirs is returned instead if any is missing.
+async fn user_wallets(
+    pool: &PgPool,
+    wallet_ids: &[Uuid],
+    user_id: Uuid,
+) -> std::result::Result<std::result::Result<Vec<Wallet>, Uuid>, sqlx::Error> {
+    let wallets = sqlx::query_as::<_, Wallet>(
+        "SELECT * FROM wallets WHERE id = ANY($1) AND user_id = $2 AND is_active = true"
+    )
+    .bind(wallet_ids)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_id: HashMap<Uuid, Wallet> = wallets.into_iter().map(|w| (w.id, w)).collect();
+    Ok(wallet_ids
+        .iter()
+        .map(|id| by_id.remove(id).ok_or(*id))
+        .collect())
+}
+
+fn database_error(e: sqlx::Error) -> HttpResponse {
+    log::error!("Database error: {}", e);
+    HttpResponse::InternalServerError().json(
+        ApiResponse::<()>::error("Internal server error".to_string())
+    )
+}
+
+fn wallet_not_found(wallet_id: Uuid) -> HttpResponse {
+    HttpResponse::NotFound().json(
+        ApiResponse::<()>::error(format!("Wallet {} not found", wallet_id))
+    )
+}
+
+/// Send a planned set of transfers, or explain why it can't be made
+async fn execute_plan(
+    service: &WalletTransfers,
+    user_id: Uuid,
+    plan: anyhow::Result<TransferPlan>,
+) -> HttpResponse {
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(e) => {
+            return HttpResponse::UnprocessableEntity().json(
+                ApiResponse::<()>::error(e.to_string())
+            );
+        }
+    };
+
+    match service.execute(user_id, &plan).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => {
+            log::error!("Failed to execute {} transfers: {}", plan.kind, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to execute transfers".to_string())
+            )
+        }
+    }
+}
+
+/// Distribute SOL from a funding wallet to trading wallets
+pub async fn distribute(
+    pool: web::Data<PgPool>,
+    service: web::Data<WalletTransfers>,
+    req: web::Json<DistributeRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Err(e) = transfers::validate_distribution(&req) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+    }
+
+    let mut wallet_ids = vec![req.from_wallet_id];
+    wallet_ids.extend(req.targets.iter().map(|t| t.wallet_id));
+    let mut wallets = match user_wallets(pool.get_ref(), &wallet_ids, user_id).await {
+        Ok(Ok(wallets)) => wallets,
+        Ok(Err(wallet_id)) => return Ok(wallet_not_found(wallet_id)),
+        Err(e) => return Ok(database_error(e)),
+    };
+
+    let from = wallets.remove(0);
+    let targets = wallets
+        .into_iter()
+        .zip(&req.targets)
+        .map(|(wallet, target)| (wallet, target.weight.unwrap_or(Decimal::ONE)))
+        .collect();
+
+    let plan = service.plan_distribution(from, targets, req.total_sol).await;
+    Ok(execute_plan(&service, user_id, plan).await)
+}
+
+/// Sweep SOL from trading wallets back into one wallet
+pub async fn sweep(
+    pool: web::Data<PgPool>,
+    service: web::Data<WalletTransfers>,
+    req: web::Json<SweepRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Err(e) = transfers::validate_sweep(&req) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string())));
+    }
+
+    let mut from_wallet_ids = if req.from_wallet_ids.is_empty() {
+        let wallets = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM wallets WHERE user_id = $1 AND is_active = true AND id <> $2 ORDER BY created_at LIMIT $3"
+        )
+        .bind(user_id)
+        .bind(req.to_wallet_id)
+        .bind(transfers::MAX_WALLETS as i64)
+        .fetch_all(pool.get_ref())
+        .await;
+
+        match wallets {
+            Ok(wallets) => wallets,
+            Err(e) => return Ok(database_error(e)),
+        }
+    } else {
+        req.from_wallet_ids.clone()
+    };
+    from_wallet_ids.sort();
+    from_wallet_ids.dedup();
+
+    let mut wallet_ids = vec![req.to_wallet_id];
+    wallet_ids.extend(from_wallet_ids);
+    let mut wallets = match user_wallets(pool.get_ref(), &wallet_ids, user_id).await {
+        Ok(Ok(wallets)) => wallets,
+        Ok(Err(wallet_id)) => return Ok(wallet_not_found(wallet_id)),
+        Err(e) => return Ok(database_error(e)),
+    };
+
+    let to = wallets.remove(0);
+    let plan = service.plan_sweep(to, wallets, req.leave_sol.unwrap_or_default()).await;
+    Ok(execute_plan(&service, user_id, plan).await)
+}
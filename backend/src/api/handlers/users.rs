@@ -69,11 +69,11 @@ pub async fn get_user(
 
 /// Update user
 pub async fn update_user(
-    pool: web::Data<PgPool>,
+    _pool: web::Data<PgPool>,
     path: web::Path<Uuid>,
-    req: web::Json<serde_json::Value>,
+    _req: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse> {
-    let user_id = path.into_inner();
+    let _user_id = path.into_inner();
 
     // For now, just return not implemented
     Ok(HttpResponse::NotImplemented().json(
@@ -1,14 +1,31 @@
 //! Wallet management handlers
 
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::auth::middleware::authenticated_user_id;
 use crate::database::models::{Wallet, WalletResponse, ApiResponse, CreateWalletRequest};
+use crate::wallet::reclaim::Reclaimer;
+
+/// Look up an active wallet owned by the given user
+pub async fn find_user_wallet(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    user_id: Uuid,
+) -> std::result::Result<Option<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(
+        "SELECT * FROM wallets WHERE id = $1 AND user_id = $2 AND is_active = true"
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
 
 /// List user's wallets
 pub async fn list_wallets(
-    pool: web::Data<PgPool>,
+    _pool: web::Data<PgPool>,
 ) -> Result<HttpResponse> {
     // TODO: Extract user ID from JWT token
     // For now, return empty list
@@ -18,8 +35,8 @@ pub async fn list_wallets(
 
 /// Create new wallet
 pub async fn create_wallet(
-    pool: web::Data<PgPool>,
-    req: web::Json<CreateWalletRequest>,
+    _pool: web::Data<PgPool>,
+    _req: web::Json<CreateWalletRequest>,
 ) -> Result<HttpResponse> {
     // TODO: Implement wallet creation with Solana keypair generation
     Ok(HttpResponse::NotImplemented().json(
@@ -61,9 +78,9 @@ pub async fn get_wallet(
 
 /// Update wallet
 pub async fn update_wallet(
-    pool: web::Data<PgPool>,
-    path: web::Path<Uuid>,
-    req: web::Json<serde_json::Value>,
+    _pool: web::Data<PgPool>,
+    _path: web::Path<Uuid>,
+    _req: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse> {
     Ok(HttpResponse::NotImplemented().json(
         ApiResponse::<()>::error("Wallet update not implemented yet".to_string())
@@ -105,8 +122,8 @@ pub async fn delete_wallet(
 
 /// Get wallet balance
 pub async fn get_balance(
-    pool: web::Data<PgPool>,
-    path: web::Path<Uuid>,
+    _pool: web::Data<PgPool>,
+    _path: web::Path<Uuid>,
 ) -> Result<HttpResponse> {
     Ok(HttpResponse::NotImplemented().json(
         ApiResponse::<()>::error("Balance checking not implemented yet".to_string())
@@ -115,10 +132,52 @@ pub async fn get_balance(
 
 /// Get wallet token holdings
 pub async fn get_token_holdings(
-    pool: web::Data<PgPool>,
-    path: web::Path<Uuid>,
+    _pool: web::Data<PgPool>,
+    _path: web::Path<Uuid>,
 ) -> Result<HttpResponse> {
     Ok(HttpResponse::NotImplemented().json(
         ApiResponse::<()>::error("Token holdings not implemented yet".to_string())
     ))
-}
\ No newline at end of file
+}
+
+/// Close the wallet's empty token accounts and report the rent recovered
+pub async fn reclaim_rent(
+    pool: web::Data<PgPool>,
+    reclaimer: web::Data<Reclaimer>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let wallet = match find_user_wallet(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(Some(wallet)) => wallet,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Wallet not found".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Internal server error".to_string())
+            ));
+        }
+    };
+
+    match reclaimer.reclaim(&wallet).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse::success(report))),
+        Err(e) => {
+            log::error!("Failed to reclaim rent of wallet {}: {}", wallet.id, e);
+            Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to reclaim rent".to_string())
+            ))
+        }
+    }
+}
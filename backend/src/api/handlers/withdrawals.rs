@@ -0,0 +1,366 @@
+//! Withdrawal and withdrawal allowlist handlers
+//!
+//! Listing an address and withdrawing both need a step-up token from
+//! `POST /api/withdrawals/step-up`. Every change and every withdrawal,
+//! refused ones included, goes to the audit log.
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::handlers::wallets::find_user_wallet;
+use crate::auth::middleware::{authenticated_session_id, authenticated_user_id};
+use crate::auth::{generate_step_up_token, step_up_token, validate_step_up_token, verify_password};
+use crate::config::Config;
+use crate::database::audit::{self, AuditEntry};
+use crate::database::models::{
+    AddWithdrawalAddressRequest, ApiResponse, StepUpRequest, User, WithdrawRequest, Withdrawal,
+};
+use crate::wallet::withdrawals::{self, AddressNotAllowed, Withdrawals, STEP_UP_PURPOSE};
+
+/// Token granted by step-up auth
+#[derive(Debug, Serialize)]
+pub struct StepUpToken {
+    pub token: String,
+    pub expires_at: chrono::DateTime<Utc>,
+}
+
+async fn audit(pool: &PgPool, entry: AuditEntry) {
+    if let Err(e) = audit::record(pool, &entry).await {
+        log::error!("Failed to audit {}: {}", entry.action, e);
+    }
+}
+
+fn database_error(e: impl std::fmt::Display) -> HttpResponse {
+    log::error!("Database error: {}", e);
+    HttpResponse::InternalServerError().json(
+        ApiResponse::<()>::error("Internal server error".to_string())
+    )
+}
+
+/// Why the request's step-up token doesn't unlock withdrawals, if it doesn't
+fn step_up_refusal(http_req: &HttpRequest, user_id: Uuid, config: &Config) -> Option<String> {
+    let token = match step_up_token(http_req) {
+        Some(token) => token,
+        None => return Some("Step-up authentication required".to_string()),
+    };
+    let session_id = authenticated_session_id(http_req).unwrap_or_default();
+
+    validate_step_up_token(token, user_id, &session_id, STEP_UP_PURPOSE, &config.jwt_secret)
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Re-enter the password to get a short-lived token unlocking withdrawals
+pub async fn step_up(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    req: web::Json<StepUpRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let (user_id, session_id) = match (authenticated_user_id(&http_req), authenticated_session_id(&http_req)) {
+        (Some(user_id), Some(session_id)) => (user_id, session_id),
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND is_active = true")
+        .bind(user_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let user = match user {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+        Err(e) => return Ok(database_error(e)),
+    };
+
+    if user.locked_until.is_some_and(|locked_until| locked_until > Utc::now()) {
+        return Ok(HttpResponse::Unauthorized().json(
+            ApiResponse::<()>::error("Account is temporarily locked".to_string())
+        ));
+    }
+
+    match verify_password(&req.password, &user.password_hash) {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = sqlx::query(
+                "UPDATE users SET failed_login_attempts = failed_login_attempts + 1 WHERE id = $1"
+            )
+            .bind(user_id)
+            .execute(pool.get_ref())
+            .await;
+
+            audit(pool.get_ref(), AuditEntry::new(Some(user_id), "step_up.failed")
+                .details(serde_json::json!({ "purpose": STEP_UP_PURPOSE }))
+                .client(&http_req)).await;
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Invalid password".to_string())
+            ));
+        }
+        Err(e) => {
+            log::error!("Password verification error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Authentication error".to_string())
+            ));
+        }
+    }
+
+    let granted = generate_step_up_token(
+        user_id,
+        &session_id,
+        STEP_UP_PURPOSE,
+        &config.jwt_secret,
+        config.step_up_ttl_minutes,
+    );
+    let (token, expires_at) = match granted {
+        Ok(granted) => granted,
+        Err(e) => {
+            log::error!("Token generation error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to generate token".to_string())
+            ));
+        }
+    };
+
+    audit(pool.get_ref(), AuditEntry::new(Some(user_id), "step_up.granted")
+        .details(serde_json::json!({ "purpose": STEP_UP_PURPOSE, "expires_at": expires_at }))
+        .client(&http_req)).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(StepUpToken { token, expires_at })))
+}
+
+/// The user's withdrawal allowlist
+pub async fn list_addresses(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    match withdrawals::list_addresses(pool.get_ref(), user_id).await {
+        Ok(addresses) => Ok(HttpResponse::Ok().json(ApiResponse::success(addresses))),
+        Err(e) => Ok(database_error(e)),
+    }
+}
+
+/// Add an address to the allowlist; it becomes usable after the
+/// configured delay
+pub async fn add_address(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    req: web::Json<AddWithdrawalAddressRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    if let Some(refusal) = step_up_refusal(&http_req, user_id, &config) {
+        return Ok(HttpResponse::Forbidden().json(ApiResponse::<()>::error(refusal)));
+    }
+
+    let address = match withdrawals::validate_address(&req.address) {
+        Ok(address) => address.to_string(),
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string()))),
+    };
+
+    let delay = Duration::hours(config.withdrawal_address_delay_hours as i64);
+    let entry = withdrawals::add_address(pool.get_ref(), user_id, &address, req.label.as_deref(), delay).await;
+    match entry {
+        Ok(Some(entry)) => {
+            audit(pool.get_ref(), AuditEntry::new(Some(user_id), "withdrawal_address.added")
+                .resource("withdrawal_address", entry.id)
+                .details(serde_json::json!({
+                    "address": entry.address,
+                    "label": entry.label,
+                    "active_from": entry.active_from,
+                }))
+                .client(&http_req)).await;
+            Ok(HttpResponse::Created().json(ApiResponse::success(entry)))
+        }
+        Ok(None) => Ok(HttpResponse::Conflict().json(
+            ApiResponse::<()>::error("Address is already on the allowlist".to_string())
+        )),
+        Err(e) => Ok(database_error(e)),
+    }
+}
+
+/// Remove an address from the allowlist, effective immediately
+pub async fn remove_address(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    match withdrawals::remove_address(pool.get_ref(), user_id, path.into_inner()).await {
+        Ok(Some(entry)) => {
+            audit(pool.get_ref(), AuditEntry::new(Some(user_id), "withdrawal_address.removed")
+                .resource("withdrawal_address", entry.id)
+                .details(serde_json::json!({ "address": entry.address }))
+                .client(&http_req)).await;
+            Ok(HttpResponse::Ok().json(ApiResponse::success(entry)))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Address not found".to_string())
+        )),
+        Err(e) => Ok(database_error(e)),
+    }
+}
+
+/// The user's withdrawals, newest first
+pub async fn list_withdrawals(
+    pool: web::Data<PgPool>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let withdrawals = sqlx::query_as::<_, Withdrawal>(
+        "SELECT * FROM withdrawals WHERE user_id = $1 ORDER BY created_at DESC LIMIT 100"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match withdrawals {
+        Ok(withdrawals) => Ok(HttpResponse::Ok().json(ApiResponse::success(withdrawals))),
+        Err(e) => Ok(database_error(e)),
+    }
+}
+
+/// Withdraw SOL or a token to an allowlisted address
+pub async fn withdraw(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    service: web::Data<Withdrawals>,
+    req: web::Json<WithdrawRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse> {
+    let user_id = match authenticated_user_id(&http_req) {
+        Some(id) => id,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(
+                ApiResponse::<()>::error("Authentication required".to_string())
+            ));
+        }
+    };
+
+    let denied = |reason: &str| {
+        AuditEntry::new(Some(user_id), "withdrawal.denied")
+            .resource("wallet", req.wallet_id)
+            .details(serde_json::json!({
+                "to_address": req.to_address,
+                "token_address": req.token_address,
+                "amount": req.amount,
+                "reason": reason,
+            }))
+            .client(&http_req)
+    };
+
+    if let Some(refusal) = step_up_refusal(&http_req, user_id, &config) {
+        audit(pool.get_ref(), denied(&refusal)).await;
+        return Ok(HttpResponse::Forbidden().json(ApiResponse::<()>::error(refusal)));
+    }
+
+    let wallet = match find_user_wallet(pool.get_ref(), req.wallet_id, user_id).await {
+        Ok(Some(wallet)) => wallet,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Wallet not found".to_string())
+            ));
+        }
+        Err(e) => return Ok(database_error(e)),
+    };
+
+    let address = match withdrawals::usable_address(pool.get_ref(), user_id, req.to_address.trim()).await {
+        Ok(address) => address,
+        Err(e) if e.is::<AddressNotAllowed>() => {
+            audit(pool.get_ref(), denied(&e.to_string())).await;
+            return Ok(HttpResponse::Forbidden().json(ApiResponse::<()>::error(e.to_string())));
+        }
+        Err(e) => return Ok(database_error(e)),
+    };
+
+    let prepared = service.prepare(wallet, address, req.token_address.as_deref(), req.amount).await;
+    let prepared = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            return Ok(HttpResponse::UnprocessableEntity().json(ApiResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    let withdrawal = match service.execute(user_id, prepared).await {
+        Ok(withdrawal) => withdrawal,
+        Err(e) => {
+            log::error!("Failed to execute withdrawal: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to execute withdrawal".to_string())
+            ));
+        }
+    };
+
+    let action = match withdrawal.status.as_str() {
+        withdrawals::status::CONFIRMED => "withdrawal.sent",
+        withdrawals::status::SUBMITTED => "withdrawal.submitted",
+        _ => "withdrawal.failed",
+    };
+    audit(pool.get_ref(), AuditEntry::new(Some(user_id), action)
+        .resource("withdrawal", withdrawal.id)
+        .details(serde_json::json!({
+            "wallet_id": withdrawal.wallet_id,
+            "to_address": withdrawal.to_address,
+            "token_address": withdrawal.token_address,
+            "amount": withdrawal.amount,
+            "signature": withdrawal.signature,
+            "error": withdrawal.error_message,
+        }))
+        .client(&http_req)).await;
+
+    if withdrawal.status == withdrawals::status::CONFIRMED {
+        Ok(HttpResponse::Ok().json(ApiResponse::success(withdrawal)))
+    } else if withdrawal.status == withdrawals::status::SUBMITTED {
+        // Sent but unconfirmed; it settles from its signature
+        Ok(HttpResponse::Accepted().json(ApiResponse::success(withdrawal)))
+    } else {
+        Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(format!(
+            "Withdrawal {} failed: {}",
+            withdrawal.id,
+            withdrawal.error_message.unwrap_or_default()
+        ))))
+    }
+}
@@ -13,7 +13,14 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
             .configure(user_routes)
             .configure(wallet_routes)
             .configure(trade_routes)
+            .configure(order_routes)
+            .configure(position_routes)
             .configure(bot_routes)
+            .configure(paper_routes)
+            .configure(token_routes)
+            .configure(risk_routes)
+            .configure(kill_switch_routes)
+            .configure(withdrawal_routes)
     );
 }
 
@@ -46,11 +53,14 @@ fn wallet_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/wallets")
             .route("", web::get().to(handlers::wallets::list_wallets))
             .route("", web::post().to(handlers::wallets::create_wallet))
+            .route("/distribute", web::post().to(handlers::transfers::distribute))
+            .route("/sweep", web::post().to(handlers::transfers::sweep))
             .route("/{id}", web::get().to(handlers::wallets::get_wallet))
             .route("/{id}", web::put().to(handlers::wallets::update_wallet))
             .route("/{id}", web::delete().to(handlers::wallets::delete_wallet))
             .route("/{id}/balance", web::get().to(handlers::wallets::get_balance))
             .route("/{id}/tokens", web::get().to(handlers::wallets::get_token_holdings))
+            .route("/{id}/reclaim", web::post().to(handlers::wallets::reclaim_rent))
     );
 }
 
@@ -60,22 +70,100 @@ fn trade_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/trades")
             .route("", web::get().to(handlers::trades::list_trades))
             .route("", web::post().to(handlers::trades::create_trade))
+            .route("/export", web::get().to(handlers::trades::export_trades))
+            .route("/panic-sell", web::post().to(handlers::trades::panic_sell))
+            .route("/panic-sell/{id}", web::get().to(handlers::trades::get_panic_sell))
             .route("/{id}", web::get().to(handlers::trades::get_trade))
+            .route("/{id}/events", web::get().to(handlers::trades::get_trade_events))
             .route("/{id}/cancel", web::post().to(handlers::trades::cancel_trade))
     );
 }
 
+/// Configure conditional order routes
+fn order_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/orders")
+            .route("", web::get().to(handlers::orders::list_orders))
+            .route("", web::post().to(handlers::orders::create_order))
+            .route("/{id}", web::get().to(handlers::orders::get_order))
+            .route("/{id}", web::put().to(handlers::orders::amend_order))
+            .route("/{id}/cancel", web::post().to(handlers::orders::cancel_order))
+    );
+}
+
+/// Configure position and PnL routes
+fn position_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/positions", web::get().to(handlers::positions::list_positions))
+        .route("/pnl", web::get().to(handlers::positions::get_pnl));
+}
+
 /// Configure bot management routes
 fn bot_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/bots")
             .route("", web::get().to(handlers::bots::list_bot_configs))
             .route("", web::post().to(handlers::bots::create_bot_config))
+            .route("/schemas/{bot_type}", web::get().to(handlers::bots::get_bot_schema))
             .route("/{id}", web::get().to(handlers::bots::get_bot_config))
             .route("/{id}", web::put().to(handlers::bots::update_bot_config))
             .route("/{id}", web::delete().to(handlers::bots::delete_bot_config))
             .route("/{id}/start", web::post().to(handlers::bots::start_bot))
             .route("/{id}/stop", web::post().to(handlers::bots::stop_bot))
+            .route("/{id}/pause", web::post().to(handlers::bots::pause_bot))
+            .route("/{id}/resume", web::post().to(handlers::bots::resume_bot))
             .route("/{id}/status", web::get().to(handlers::bots::get_bot_status))
+            .route("/{id}/revisions", web::get().to(handlers::bots::list_bot_revisions))
+            .route("/{id}/revisions/diff", web::get().to(handlers::bots::diff_bot_revisions))
+            .route("/{id}/revisions/{revision}", web::get().to(handlers::bots::get_bot_revision))
+            .route("/{id}/revisions/{revision}/rollback", web::post().to(handlers::bots::rollback_bot_config))
     );
-}
\ No newline at end of file
+}
+
+/// Configure paper-trading routes
+fn paper_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/paper")
+            .route("/mode", web::put().to(handlers::paper::set_paper_mode))
+            .route("/wallets/{id}/balances", web::get().to(handlers::paper::get_paper_balances))
+            .route("/wallets/{id}/fund", web::post().to(handlers::paper::fund_paper_wallet))
+            .route("/wallets/{id}/reset", web::post().to(handlers::paper::reset_paper_wallet))
+    );
+}
+
+/// Configure token risk routes
+fn token_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/tokens/{mint}/risk", web::get().to(handlers::tokens::get_token_risk));
+}
+
+/// Configure risk limit routes
+fn risk_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/risk")
+            .route("/limits", web::get().to(handlers::risk::list_risk_limits))
+            .route("/limits", web::put().to(handlers::risk::set_risk_limit))
+            .route("/limits/{id}", web::delete().to(handlers::risk::delete_risk_limit))
+    );
+}
+
+/// Configure kill switch routes
+pub fn kill_switch_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/kill-switch")
+            .route("", web::get().to(handlers::kill_switch::get_kill_switch))
+            .route("", web::post().to(handlers::kill_switch::engage_kill_switch))
+            .route("/release", web::post().to(handlers::kill_switch::release_kill_switch))
+    );
+}
+
+/// Configure withdrawal and withdrawal allowlist routes
+fn withdrawal_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/withdrawals")
+            .route("", web::get().to(handlers::withdrawals::list_withdrawals))
+            .route("", web::post().to(handlers::withdrawals::withdraw))
+            .route("/step-up", web::post().to(handlers::withdrawals::step_up))
+            .route("/addresses", web::get().to(handlers::withdrawals::list_addresses))
+            .route("/addresses", web::post().to(handlers::withdrawals::add_address))
+            .route("/addresses/{id}", web::delete().to(handlers::withdrawals::remove_address))
+    );
+}
@@ -91,6 +91,7 @@ mod tests {
             is_verified: true,
             failed_login_attempts: 0,
             locked_until: None,
+            paper_trading: false,
         }
     }
 
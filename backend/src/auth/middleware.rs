@@ -2,7 +2,7 @@
 
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpMessage, HttpResponse,
+    Error, HttpMessage, HttpRequest,
 };
 use futures::future::{ok, Ready};
 use std::future::Future;
@@ -26,7 +26,7 @@ impl AuthMiddleware {
 
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + Clone + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -52,7 +52,7 @@ pub struct AuthMiddlewareService<S> {
 
 impl<S, B> Service<ServiceRequest> for AuthMiddlewareService<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + Clone + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -64,13 +64,11 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let jwt_secret = self.jwt_secret.clone();
-        let service = self.service.clone();
 
         // Skip authentication for public routes
         let path = req.path();
         if path == "/health" || path == "/" || path.starts_with("/api/auth/") {
-            let fut = service.call(req);
-            return Box::pin(async move { fut.await });
+            return Box::pin(self.service.call(req));
         }
 
         // Extract and validate JWT token
@@ -83,8 +81,7 @@ where
                         // Add user info to request extensions
                         req.extensions_mut().insert(claims);
                         
-                        let fut = service.call(req);
-                        return Box::pin(async move { fut.await });
+                        return Box::pin(self.service.call(req));
                     }
                 }
             }
@@ -99,12 +96,14 @@ where
 
 /// Extract authenticated user from request
 pub async fn get_authenticated_user(req: &ServiceRequest, pool: &PgPool) -> Result<User, Error> {
-    let extensions = req.extensions();
-    let claims = extensions.get::<crate::database::models::Claims>()
-        .ok_or_else(|| actix_web::error::ErrorUnauthorized("No authentication claims found"))?;
+    let user_id = {
+        let extensions = req.extensions();
+        let claims = extensions.get::<crate::database::models::Claims>()
+            .ok_or_else(|| actix_web::error::ErrorUnauthorized("No authentication claims found"))?;
 
-    let user_id = Uuid::parse_str(&claims.sub)
-        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid user ID in token"))?;
+        Uuid::parse_str(&claims.sub)
+            .map_err(|_| actix_web::error::ErrorBadRequest("Invalid user ID in token"))?
+    };
 
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND is_active = true")
         .bind(user_id)
@@ -114,4 +113,18 @@ pub async fn get_authenticated_user(req: &ServiceRequest, pool: &PgPool) -> Resu
         .ok_or_else(|| actix_web::error::ErrorUnauthorized("User not found or inactive"))?;
 
     Ok(user)
-}
\ No newline at end of file
+}
+
+/// Extract the authenticated user's ID from claims set by the middleware
+pub fn authenticated_user_id(req: &HttpRequest) -> Option<Uuid> {
+    req.extensions()
+        .get::<crate::database::models::Claims>()
+        .and_then(|claims| Uuid::parse_str(&claims.sub).ok())
+}
+
+/// Session the request was authenticated with
+pub fn authenticated_session_id(req: &HttpRequest) -> Option<String> {
+    req.extensions()
+        .get::<crate::database::models::Claims>()
+        .map(|claims| claims.session_id.clone())
+}
@@ -4,9 +4,11 @@
 pub mod jwt;
 pub mod password;
 pub mod middleware;
+pub mod step_up;
 
 pub use jwt::*;
 pub use password::*;
+pub use step_up::*;
 
 use crate::database::models::{User, Claims};
 use anyhow::Result;
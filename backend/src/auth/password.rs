@@ -5,7 +5,7 @@ use argon2::{
     Argon2
 };
 use anyhow::{Result, anyhow};
-use rand_core::OsRng;
+use rand::rngs::OsRng;
 
 /// Hash a password using Argon2id
 pub fn hash_password(password: &str) -> Result<String> {
@@ -0,0 +1,108 @@
+//! Step-up authentication for sensitive actions
+//!
+//! A signed-in user re-enters their password to get a step-up token: a JWT
+//! that lives for a few minutes, covers one purpose and is only accepted
+//! alongside the session it was issued in. Session and step-up tokens carry
+//! different claims, so neither passes for the other.
+
+use actix_web::HttpRequest;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use uuid::Uuid;
+
+use crate::database::models::StepUpClaims;
+
+/// Header carrying the step-up token
+pub const STEP_UP_HEADER: &str = "X-Step-Up-Token";
+
+/// Issue a step-up token for `purpose`, returning it with its expiry
+pub fn generate_step_up_token(
+    user_id: Uuid,
+    session_id: &str,
+    purpose: &str,
+    secret: &str,
+    ttl_minutes: u64,
+) -> Result<(String, DateTime<Utc>)> {
+    let now = Utc::now();
+    let expires_at = now + Duration::minutes(ttl_minutes as i64);
+
+    let claims = StepUpClaims {
+        sub: user_id.to_string(),
+        session_id: session_id.to_string(),
+        purpose: purpose.to_string(),
+        exp: expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref()))?;
+
+    Ok((token, expires_at))
+}
+
+/// Check a step-up token was issued to this user and session for `purpose`
+/// and hasn't expired
+pub fn validate_step_up_token(
+    token: &str,
+    user_id: Uuid,
+    session_id: &str,
+    purpose: &str,
+    secret: &str,
+) -> Result<()> {
+    let claims = decode::<StepUpClaims>(token, &DecodingKey::from_secret(secret.as_ref()), &Validation::default())
+        .map_err(|_| anyhow!("Invalid or expired step-up token"))?
+        .claims;
+
+    if claims.sub != user_id.to_string() || claims.session_id != session_id {
+        return Err(anyhow!("Step-up token belongs to another session"));
+    }
+    if claims.purpose != purpose {
+        return Err(anyhow!("Step-up token was not issued for {}", purpose));
+    }
+    Ok(())
+}
+
+/// The step-up token sent with a request, if any
+pub fn step_up_token(req: &HttpRequest) -> Option<&str> {
+    req.headers().get(STEP_UP_HEADER).and_then(|value| value.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::generate_jwt_token;
+    use crate::database::models::User;
+
+    #[test]
+    fn test_step_up_token_is_bound_to_session_and_purpose() {
+        let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4().to_string();
+        let secret = "test_secret";
+
+        let (token, expires_at) = generate_step_up_token(user_id, &session_id, "withdrawal", secret, 5).unwrap();
+        assert!(expires_at > Utc::now());
+        assert!(validate_step_up_token(&token, user_id, &session_id, "withdrawal", secret).is_ok());
+
+        assert!(validate_step_up_token(&token, user_id, "other-session", "withdrawal", secret).is_err());
+        assert!(validate_step_up_token(&token, Uuid::new_v4(), &session_id, "withdrawal", secret).is_err());
+        assert!(validate_step_up_token(&token, user_id, &session_id, "export", secret).is_err());
+        assert!(validate_step_up_token(&token, user_id, &session_id, "withdrawal", "other_secret").is_err());
+
+        // A session token is not a step-up token
+        let user = User {
+            id: user_id,
+            username: "testuser".to_string(),
+            email: "test@example.com".to_string(),
+            password_hash: "hash".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login: None,
+            is_active: true,
+            is_verified: true,
+            failed_login_attempts: 0,
+            locked_until: None,
+            paper_trading: false,
+        };
+        let session_token = generate_jwt_token(&user, Uuid::parse_str(&session_id).unwrap(), secret, 1).unwrap();
+        assert!(validate_step_up_token(&session_token, user_id, &session_id, "withdrawal", secret).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Cerberus Chain: Hydra - Kill switch CLI
+//! Engages, releases or shows the kill switch through the backend API
+//!
+//! Usage: kill_switch <engage|release|status> [--global] [--reason <text>]
+//!
+//! Reads the API address from CERBERUS_API_URL (default http://localhost:8080)
+//! and a bearer token from CERBERUS_API_TOKEN.
+
+use anyhow::{anyhow, Context, Result};
+use dotenv::dotenv;
+use std::env;
+
+const USAGE: &str = "Usage: kill_switch <engage|release|status> [--global] [--reason <text>]";
+
+struct Args {
+    command: String,
+    global: bool,
+    reason: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let command = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let mut global = false;
+    let mut reason = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--global" => global = true,
+            "--reason" => reason = Some(args.next().ok_or_else(|| anyhow!("--reason needs a value"))?),
+            _ => return Err(anyhow!("Unknown argument {}\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Args { command, global, reason })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+
+    let args = parse_args(env::args().skip(1))?;
+    let base_url = env::var("CERBERUS_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+    let token = env::var("CERBERUS_API_TOKEN").context("CERBERUS_API_TOKEN is not set")?;
+    let url = format!("{}/api/kill-switch", base_url.trim_end_matches('/'));
+    let body = serde_json::json!({ "global": args.global, "reason": args.reason });
+
+    let client = reqwest::Client::new();
+    let request = match args.command.as_str() {
+        "engage" => client.post(&url).json(&body),
+        "release" => client.post(format!("{}/release", url)).json(&body),
+        "status" => client.get(&url),
+        _ => return Err(anyhow!(USAGE)),
+    };
+
+    let response = request.bearer_auth(token).send().await?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await?;
+    println!("{}", serde_json::to_string_pretty(&body)?);
+
+    if !status.is_success() {
+        return Err(anyhow!("Request failed with status {}", status));
+    }
+    Ok(())
+}
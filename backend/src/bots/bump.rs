@@ -0,0 +1,300 @@
+//! Bump bot
+//!
+//! Keeps a token visible on trending lists with tiny round trips: a wallet
+//! buys a fixed amount, sells it back shortly after, and the next round trip
+//! starts from the next wallet after a jittered interval. Every round trip
+//! loses a little to fees and slippage; once those add up to the configured
+//! ceiling the bot closes any open position and stops.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::bots::config::{BotSettings, BumpConfig};
+use crate::bots::{Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+
+/// Pause between a buy and its sell, so the buy can settle first
+const SELL_DELAY: Duration = Duration::from_secs(5);
+
+/// Sells retried before the position is abandoned
+const MAX_SELL_ATTEMPTS: u32 = 3;
+
+/// A buy waiting to be sold back
+#[derive(Debug, Clone, PartialEq)]
+struct OpenBump {
+    wallet_id: Uuid,
+    sol_amount: Decimal,
+    token_amount: Option<Decimal>,
+    sell_attempts: u32,
+}
+
+/// Costs and counters reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct BumpStats {
+    fees_sol: Decimal,
+    slippage_sol: Decimal,
+    round_trips: u64,
+    failed: u64,
+    abandoned: u64,
+}
+
+impl BumpStats {
+    fn cost_sol(&self) -> Decimal {
+        self.fees_sol + self.slippage_sol
+    }
+}
+
+/// SOL lost between a buy and the sell that unwinds it. The sell is scaled
+/// to the tokens bought, so tokens left over from a partial sell aren't
+/// counted as lost.
+fn slippage_cost(buy_sol: Decimal, buy_tokens: Option<Decimal>, sell_sol: Decimal, sell_tokens: Option<Decimal>) -> Decimal {
+    let sold_value = match (buy_tokens, sell_tokens) {
+        (Some(bought), Some(sold)) if bought > Decimal::ZERO && sold > Decimal::ZERO => sell_sol * bought / sold,
+        _ => sell_sol,
+    };
+    (buy_sol - sold_value).max(Decimal::ZERO).round_dp(9)
+}
+
+pub struct BumpBot {
+    config: BumpConfig,
+    rng: StdRng,
+    next_wallet: usize,
+    open: Option<OpenBump>,
+    stats: BumpStats,
+}
+
+impl BumpBot {
+    pub fn new(config: BumpConfig, rng: StdRng) -> Self {
+        Self {
+            config,
+            rng,
+            next_wallet: 0,
+            open: None,
+            stats: BumpStats::default(),
+        }
+    }
+
+    pub fn from_config(bot: &BotConfig) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Bump(config) => Ok(Self::new(config, StdRng::from_entropy())),
+            _ => Err(anyhow!("Bot {} is not a bump bot", bot.id)),
+        }
+    }
+
+    fn over_budget(&self) -> bool {
+        self.stats.cost_sol() >= self.config.max_total_cost_sol
+    }
+
+    /// The configured interval, varied by up to `jitter_percent` either way
+    fn next_interval(&mut self) -> Duration {
+        let jitter = self.config.jitter_percent.to_f64().unwrap_or_default() / 100.0;
+        let factor = if jitter > 0.0 { 1.0 + self.rng.gen_range(-jitter..=jitter) } else { 1.0 };
+        Duration::from_secs_f64(self.config.interval_seconds as f64 * factor)
+    }
+
+    fn trade_request(&self, wallet_id: Uuid, trade_type: &str, sol_amount: Decimal) -> CreateTradeRequest {
+        CreateTradeRequest {
+            wallet_id,
+            token_address: self.config.token_address.clone(),
+            trade_type: trade_type.to_string(),
+            sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        }
+    }
+
+    async fn buy(&mut self, ctx: &BotContext) -> Result<Step> {
+        let wallet_id = self.config.wallet_ids[self.next_wallet % self.config.wallet_ids.len()];
+        self.next_wallet = (self.next_wallet + 1) % self.config.wallet_ids.len();
+
+        let trade = ctx.trade(&self.trade_request(wallet_id, "buy", self.config.sol_amount)).await?;
+        self.stats.fees_sol += trade.fee_sol();
+
+        if !trade.succeeded() {
+            log::warn!("Bump bot {} buy from {} failed", ctx.bot.id, wallet_id);
+            self.stats.failed += 1;
+            return Ok(Step::Wait(self.next_interval()));
+        }
+
+        self.open = Some(OpenBump {
+            wallet_id,
+            sol_amount: trade.sol_amount(),
+            token_amount: trade.token_amount(),
+            sell_attempts: 0,
+        });
+        Ok(Step::Wait(SELL_DELAY))
+    }
+
+    async fn sell(&mut self, ctx: &BotContext, mut open: OpenBump) -> Result<Step> {
+        // Sells receive an exact SOL amount; leaving room for slippage keeps
+        // them within the tokens the buy received
+        let slippage = self.config.slippage_tolerance.unwrap_or(Decimal::ONE) / Decimal::from(100);
+        let sol_amount = (open.sol_amount * (Decimal::ONE - slippage)).round_dp(9);
+
+        let trade = ctx.trade(&self.trade_request(open.wallet_id, "sell", sol_amount)).await?;
+        self.stats.fees_sol += trade.fee_sol();
+
+        if trade.succeeded() {
+            self.stats.slippage_sol += slippage_cost(open.sol_amount, open.token_amount, sol_amount, trade.token_amount());
+            self.stats.round_trips += 1;
+            return Ok(Step::Wait(self.next_interval()));
+        }
+
+        self.stats.failed += 1;
+        open.sell_attempts += 1;
+        if open.sell_attempts < MAX_SELL_ATTEMPTS {
+            self.open = Some(open);
+            return Ok(Step::Wait(SELL_DELAY));
+        }
+
+        log::warn!("Bump bot {} gave up selling from {}", ctx.bot.id, open.wallet_id);
+        self.stats.abandoned += 1;
+        Ok(Step::Wait(self.next_interval()))
+    }
+}
+
+#[async_trait]
+impl Bot for BumpBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        // An open position is always unwound, even past the cost ceiling
+        if let Some(open) = self.open.take() {
+            return self.sell(ctx, open).await;
+        }
+
+        if self.over_budget() {
+            return Ok(Step::Done(format!(
+                "Fees and slippage reached the {} SOL cost ceiling",
+                self.config.max_total_cost_sol
+            )));
+        }
+
+        self.buy(ctx).await
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        let mut progress = serde_json::to_value(&self.stats).ok()?;
+        progress["cost_sol"] = serde_json::json!(self.stats.cost_sol());
+        progress["remaining_cost_sol"] =
+            serde_json::json!((self.config.max_total_cost_sol - self.stats.cost_sol()).max(Decimal::ZERO));
+        progress["position_open"] = serde_json::json!(self.open.is_some());
+        Some(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::bots::{BotTrade, Trader};
+    use crate::database::models::{CreateOrderRequest, Order};
+    use crate::trading::SimulationReport;
+    use chrono::Utc;
+    use sqlx::postgres::PgPoolOptions;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    /// Buys at 0.001 SOL per token and sells at 0.00099, charging 0.0001 SOL a trade
+    #[derive(Default)]
+    struct LossyTrader {
+        trades: Mutex<Vec<CreateTradeRequest>>,
+    }
+
+    #[async_trait]
+    impl Trader for LossyTrader {
+        async fn trade(&self, _bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade> {
+            self.trades.lock().unwrap().push(req.clone());
+            let price = if req.trade_type == "buy" { dec("0.001") } else { dec("0.00099") };
+            Ok(BotTrade::Simulated(Box::new(SimulationReport {
+                success: true,
+                error: None,
+                input_mint: String::new(),
+                output_mint: String::new(),
+                sol_amount: req.sol_amount,
+                expected_token_amount: req.sol_amount / price,
+                worst_case_token_amount: req.sol_amount / price,
+                price_per_token: Some(price),
+                price_impact_pct: Decimal::ZERO,
+                network_fee_lamports: 100_000,
+                priority_fee_lamports: 0,
+                compute_units_consumed: None,
+                logs: vec![],
+            })))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("bump bots place no orders"))
+        }
+    }
+
+    #[test]
+    fn test_slippage_cost_is_scaled_to_tokens_bought() {
+        // 100 tokens bought for 0.1 SOL, 98 sold for 0.097: the 2 kept aren't a loss
+        assert_eq!(slippage_cost(dec("0.1"), Some(dec("100")), dec("0.097"), Some(dec("98"))), dec("0.001020408"));
+        assert_eq!(slippage_cost(dec("0.1"), None, dec("0.097"), None), dec("0.003"));
+        assert_eq!(slippage_cost(dec("0.1"), Some(dec("100")), dec("0.11"), Some(dec("100"))), Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_round_trips_rotate_wallets_until_cost_ceiling() {
+        let config = BumpConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_ids: vec![Uuid::new_v4(), Uuid::new_v4()],
+            sol_amount: dec("0.1"),
+            interval_seconds: 60,
+            jitter_percent: dec("20"),
+            max_total_cost_sol: dec("0.003"),
+            slippage_tolerance: Some(dec("2")),
+            priority_fee: None,
+            common: CommonSettings { dry_run: true, ..Default::default() },
+        };
+        let wallets = config.wallet_ids.clone();
+        let trader = Arc::new(LossyTrader::default());
+        let ctx = BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "bump".to_string(),
+                name: "bump".to_string(),
+                is_active: true,
+                config_json: serde_json::json!({}),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader: trader.clone(),
+        };
+        let mut bot = BumpBot::new(config, StdRng::seed_from_u64(3));
+
+        let mut steps = Vec::new();
+        loop {
+            match bot.step(&ctx).await.unwrap() {
+                Step::Done(_) => break,
+                Step::Wait(delay) => steps.push(delay),
+            }
+        }
+
+        // Each round trip costs 0.0002 SOL in fees and ~0.00099 in slippage
+        assert_eq!(bot.stats.round_trips, 3);
+        assert!(bot.stats.cost_sol() >= dec("0.003"));
+        assert!(steps.iter().step_by(2).all(|d| *d == SELL_DELAY));
+        assert!(steps.iter().skip(1).step_by(2).all(|d| d.as_secs() >= 48 && d.as_secs() <= 72));
+
+        let trades = trader.trades.lock().unwrap().clone();
+        let sides: Vec<_> = trades.iter().map(|t| (t.trade_type.as_str(), wallets.iter().position(|w| *w == t.wallet_id).unwrap())).collect();
+        assert_eq!(sides, [("buy", 0), ("sell", 0), ("buy", 1), ("sell", 1), ("buy", 0), ("sell", 0)]);
+        assert_eq!(trades[1].sol_amount, dec("0.098"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Bundle bot
+//!
+//! Buys a token from several wallets at once. The buys, plus a tip paid from
+//! the first wallet, go to a block engine as one atomic bundle, so either
+//! every wallet gets in within the same slot or none does. The bot polls the
+//! bundle until it lands, retrying with a fresh bundle when one fails.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bots::config::{BotSettings, BundleConfig};
+use crate::bots::{Bot, BotBundle, BotContext, Step};
+use crate::database::models::{BotConfig, Bundle, CreateTradeRequest};
+use crate::trading::bundle::status;
+use crate::trading::{BlockEngine, JitoBlockEngine};
+
+/// How often a submitted bundle is checked on
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Pause before a failed bundle is rebuilt and sent again
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Bundles sent before the bot gives up
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Counters reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct BundleStats {
+    attempts: u32,
+    bundle_id: Option<String>,
+    status: Option<String>,
+    landed_slot: Option<i64>,
+    last_error: Option<String>,
+}
+
+pub struct BundleBot {
+    config: BundleConfig,
+    engine: Arc<dyn BlockEngine>,
+    pending: Option<Bundle>,
+    stats: BundleStats,
+}
+
+impl BundleBot {
+    pub fn new(config: BundleConfig, engine: Arc<dyn BlockEngine>) -> Self {
+        Self {
+            config,
+            engine,
+            pending: None,
+            stats: BundleStats::default(),
+        }
+    }
+
+    /// A bot naming its own block engine uses it instead of the shared one
+    pub fn from_config(bot: &BotConfig, default_engine: Arc<dyn BlockEngine>) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Bundle(config) => {
+                let engine = match &config.block_engine_url {
+                    Some(url) => Arc::new(JitoBlockEngine::new(url)) as Arc<dyn BlockEngine>,
+                    None => default_engine,
+                };
+                Ok(Self::new(config, engine))
+            }
+            _ => Err(anyhow!("Bot {} is not a bundle bot", bot.id)),
+        }
+    }
+
+    fn requests(&self) -> Vec<CreateTradeRequest> {
+        self.config.wallet_ids.iter()
+            .map(|wallet_id| CreateTradeRequest {
+                wallet_id: *wallet_id,
+                token_address: self.config.token_address.clone(),
+                trade_type: "buy".to_string(),
+                sol_amount: self.config.sol_amount_per_wallet,
+                slippage_tolerance: self.config.slippage_tolerance,
+                priority_fee: None,
+            })
+            .collect()
+    }
+
+    async fn submit(&mut self, ctx: &BotContext) -> Result<Step> {
+        let tip_wallet_id = *self.config.wallet_ids
+            .first()
+            .ok_or_else(|| anyhow!("Bundle bot has no wallets"))?;
+
+        self.stats.attempts += 1;
+        let placed = ctx
+            .trade_bundle(&self.requests(), tip_wallet_id, self.config.tip_sol, self.engine.as_ref())
+            .await?;
+
+        match placed {
+            BotBundle::Submitted { bundle, .. } => {
+                log::info!("Bundle bot {} submitted bundle {}", ctx.bot.id, bundle.engine_bundle_id);
+                self.track(&bundle);
+                self.pending = Some(*bundle);
+                Ok(Step::Wait(POLL_INTERVAL))
+            }
+            BotBundle::Rejected(trades) => {
+                let reason = trades.iter()
+                    .find_map(|t| t.error_message.clone())
+                    .unwrap_or_else(|| "Bundle was not sent".to_string());
+                Ok(self.failed(reason))
+            }
+            BotBundle::Legs(legs) => {
+                if legs.iter().all(|leg| leg.succeeded()) {
+                    Ok(Step::Done(format!("Placed {} buys without a bundle", legs.len())))
+                } else {
+                    Ok(self.failed("Not every buy went through".to_string()))
+                }
+            }
+        }
+    }
+
+    fn track(&mut self, bundle: &Bundle) {
+        self.stats.bundle_id = Some(bundle.engine_bundle_id.clone());
+        self.stats.status = Some(bundle.status.clone());
+        self.stats.landed_slot = bundle.landed_slot;
+    }
+
+    /// Retry after a failed bundle, unless every attempt has been used
+    fn failed(&mut self, reason: String) -> Step {
+        log::warn!("Bundle attempt {} failed: {}", self.stats.attempts, reason);
+        self.stats.last_error = Some(reason.clone());
+
+        if self.stats.attempts >= MAX_ATTEMPTS {
+            Step::Done(format!("Bundle failed after {} attempts: {}", self.stats.attempts, reason))
+        } else {
+            Step::Wait(RETRY_DELAY)
+        }
+    }
+}
+
+#[async_trait]
+impl Bot for BundleBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        let pending = match self.pending.take() {
+            Some(bundle) => bundle,
+            None => return self.submit(ctx).await,
+        };
+
+        let bundle = ctx.refresh_bundle(&pending, self.engine.as_ref()).await?;
+        self.track(&bundle);
+
+        match bundle.status.as_str() {
+            status::LANDED => Ok(Step::Done(format!(
+                "Bundle landed in slot {}",
+                bundle.landed_slot.unwrap_or_default()
+            ))),
+            status::FAILED => {
+                let reason = bundle.error_message.unwrap_or_else(|| "Bundle failed".to_string());
+                Ok(self.failed(reason))
+            }
+            _ => {
+                self.pending = Some(bundle);
+                Ok(Step::Wait(POLL_INTERVAL))
+            }
+        }
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.stats).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::bots::{BotTrade, Trader};
+    use crate::database::models::{CreateOrderRequest, Order};
+    use crate::trading::{BundleStatus, LocalBlockEngine};
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use solana_sdk::transaction::VersionedTransaction;
+    use sqlx::postgres::PgPoolOptions;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    /// Sends one placeholder transaction per leg plus the tip to the engine
+    /// and mirrors what it reports
+    struct BundlingTrader;
+
+    #[async_trait]
+    impl Trader for BundlingTrader {
+        async fn trade(&self, _bot: &BotConfig, _req: &CreateTradeRequest) -> Result<BotTrade> {
+            Err(anyhow!("bundle bots only trade in bundles"))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("bundle bots place no orders"))
+        }
+
+        async fn trade_bundle(
+            &self,
+            bot: &BotConfig,
+            reqs: &[CreateTradeRequest],
+            tip_wallet_id: Uuid,
+            tip_sol: Decimal,
+            engine: &dyn BlockEngine,
+        ) -> Result<BotBundle> {
+            let transactions = vec![VersionedTransaction::default(); reqs.len() + 1];
+            let engine_bundle_id = engine.send_bundle(&transactions).await?;
+            Ok(BotBundle::Submitted {
+                bundle: Box::new(Bundle {
+                    id: Uuid::new_v4(),
+                    user_id: bot.user_id,
+                    bot_id: Some(bot.id),
+                    engine_bundle_id,
+                    status: status::PENDING.to_string(),
+                    tip_wallet_id: Some(tip_wallet_id),
+                    tip_sol,
+                    tip_signature: None,
+                    landed_slot: None,
+                    error_message: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                }),
+                trades: vec![],
+            })
+        }
+
+        async fn refresh_bundle(&self, bundle: &Bundle, engine: &dyn BlockEngine) -> Result<Bundle> {
+            let mut bundle = bundle.clone();
+            match engine.bundle_status(&bundle.engine_bundle_id).await? {
+                BundleStatus::Landed { slot } => {
+                    bundle.status = status::LANDED.to_string();
+                    bundle.landed_slot = Some(slot as i64);
+                }
+                BundleStatus::Failed(reason) => {
+                    bundle.status = status::FAILED.to_string();
+                    bundle.error_message = Some(reason);
+                }
+                BundleStatus::Pending | BundleStatus::Unknown => {}
+            }
+            Ok(bundle)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_bundle_until_it_lands() {
+        let config = BundleConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_ids: vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()],
+            sol_amount_per_wallet: Decimal::from_str("0.2").unwrap(),
+            tip_sol: Decimal::from_str("0.001").unwrap(),
+            slippage_tolerance: None,
+            block_engine_url: None,
+            common: CommonSettings::default(),
+        };
+        let ctx = BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "bundle".to_string(),
+                name: "bundle".to_string(),
+                is_active: true,
+                config_json: serde_json::to_value(&config).unwrap(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader: Arc::new(BundlingTrader),
+        };
+
+        let engine = Arc::new(LocalBlockEngine::new());
+        engine.reject_next("Bundle simulation failed");
+        let mut bot = BundleBot::new(config, engine.clone());
+
+        assert_eq!(bot.step(&ctx).await.unwrap(), Step::Wait(POLL_INTERVAL));
+        assert_eq!(bot.step(&ctx).await.unwrap(), Step::Wait(RETRY_DELAY));
+        assert_eq!(bot.stats.last_error.as_deref(), Some("Bundle simulation failed"));
+
+        assert_eq!(bot.step(&ctx).await.unwrap(), Step::Wait(POLL_INTERVAL));
+        assert_eq!(bot.step(&ctx).await.unwrap(), Step::Done("Bundle landed in slot 2".to_string()));
+        assert_eq!(bot.stats.attempts, 2);
+
+        let bundles = engine.bundles();
+        assert_eq!(bundles.len(), 2);
+        assert!(bundles.iter().all(|txs| txs.len() == 4));
+    }
+}
@@ -0,0 +1,578 @@
+//! Typed configuration for each bot type
+//!
+//! `bot_configs.config_json` is parsed into the struct for the bot's type.
+//! Unknown fields are rejected so a typo can't silently fall back to a
+//! default, and every config is range-checked before it is stored. Amounts
+//! are in SOL, percentages are 0-100.
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::market::security::{Limit, SecurityPolicy};
+
+/// Bot types accepted by `bot_configs.bot_type`
+pub const BOT_TYPES: [&str; 8] = ["volume", "bundle", "bump", "sniper", "human", "copy", "dca", "grid"];
+
+/// Settings every bot type takes, flattened into its config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CommonSettings {
+    /// Trade against simulated fills instead of the chain
+    #[serde(default)]
+    pub paper: bool,
+    /// Simulate each trade and record the report without sending it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Close the bot's empty token accounts when it stops
+    #[serde(default)]
+    pub reclaim_rent_on_stop: bool,
+}
+
+/// Volume bot: rotates wallets through randomized buys and sells
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VolumeConfig {
+    pub token_address: String,
+    /// Wallets traded from, in rotation
+    pub wallet_ids: Vec<Uuid>,
+    pub min_sol_amount: Decimal,
+    pub max_sol_amount: Decimal,
+    pub min_interval_seconds: u64,
+    pub max_interval_seconds: u64,
+    /// SOL the bot may spend over its lifetime, fees included
+    pub total_sol_budget: Decimal,
+    pub max_sol_per_hour: Option<Decimal>,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// Bundle bot: buys from several wallets in one atomic bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BundleConfig {
+    pub token_address: String,
+    pub wallet_ids: Vec<Uuid>,
+    pub sol_amount_per_wallet: Decimal,
+    /// Tip paid to the block engine, in SOL
+    pub tip_sol: Decimal,
+    pub slippage_tolerance: Option<Decimal>,
+    pub block_engine_url: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// Bump bot: tiny buy-then-sell round trips that keep a token on trending lists
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BumpConfig {
+    pub token_address: String,
+    pub wallet_ids: Vec<Uuid>,
+    pub sol_amount: Decimal,
+    pub interval_seconds: u64,
+    /// Random +/- variation applied to each interval
+    #[serde(default)]
+    pub jitter_percent: Decimal,
+    /// Bot halts once fees and slippage have cost this much SOL
+    pub max_total_cost_sol: Decimal,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// Sniper bot: buys new pools for watched mints or creators
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SniperConfig {
+    pub wallet_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub watch_mints: Vec<String>,
+    #[serde(default)]
+    pub watch_creators: Vec<String>,
+    pub sol_amount: Decimal,
+    pub max_slippage_percent: Decimal,
+    pub priority_fee: Option<Decimal>,
+    pub take_profit_percent: Option<Decimal>,
+    pub stop_loss_percent: Option<Decimal>,
+    /// Rules each new token is checked against before it is bought
+    #[serde(default)]
+    pub security: SecurityPolicy,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// Human bot: trades on irregular, human-looking schedules and sizes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HumanConfig {
+    pub token_address: String,
+    pub wallet_ids: Vec<Uuid>,
+    pub median_sol_amount: Decimal,
+    pub max_sol_amount: Decimal,
+    /// Average trades per hour at peak activity
+    pub trades_per_hour: Decimal,
+    /// Hour of the day (UTC) activity peaks; the quietest hour is twelve later
+    #[serde(default = "default_peak_hour")]
+    pub peak_hour_utc: u32,
+    pub total_sol_budget: Decimal,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    /// Fixed random seed, so a run can be reproduced
+    pub seed: Option<u64>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+fn default_peak_hour() -> u32 {
+    15
+}
+
+/// Copy-trading bot: mirrors the swaps of a watched wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CopyConfig {
+    /// Public key of the wallet to follow
+    pub target_wallet: String,
+    /// Our wallet the mirrored trades are placed from
+    pub wallet_id: Uuid,
+    /// Mirrored size as a multiple of the target's SOL amount
+    pub size_ratio: Decimal,
+    /// Most SOL held in any one token through mirrored buys
+    pub max_position_sol: Decimal,
+    /// Wait between seeing a swap and mirroring it
+    #[serde(default)]
+    pub delay_seconds: u64,
+    /// Mints that are never mirrored
+    #[serde(default)]
+    pub blacklist: Vec<String>,
+    /// Rules a token is checked against before its first mirrored buy
+    #[serde(default)]
+    pub security: SecurityPolicy,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// DCA bot: buys a fixed amount on a schedule until a budget or token target is met
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DcaConfig {
+    pub token_address: String,
+    pub wallet_id: Uuid,
+    /// SOL spent on each buy
+    pub sol_amount: Decimal,
+    pub interval_minutes: u64,
+    /// Stop once this much SOL has been spent, fees included
+    pub total_sol_budget: Option<Decimal>,
+    /// Stop once this many tokens have been bought
+    pub target_token_amount: Option<Decimal>,
+    /// Only buy while the price is below its average over this many minutes
+    pub moving_average_minutes: Option<u64>,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+/// What a grid bot does when the price leaves its range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GridExit {
+    /// Stop, keeping any inventory
+    #[default]
+    Stop,
+    /// Sell the inventory and re-center the grid on the current price
+    Rebalance,
+}
+
+/// Grid bot: buys and sells on a ladder of price levels in a range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GridConfig {
+    pub token_address: String,
+    pub wallet_id: Uuid,
+    /// Range of the grid, in SOL per token
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    /// Price levels, evenly spaced from lower to upper price inclusive
+    pub levels: u32,
+    /// SOL spent on each buy
+    pub sol_per_level: Decimal,
+    #[serde(default)]
+    pub on_exit: GridExit,
+    #[serde(default = "default_poll_seconds")]
+    pub poll_seconds: u64,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    #[serde(flatten)]
+    pub common: CommonSettings,
+}
+
+fn default_poll_seconds() -> u64 {
+    30
+}
+
+/// Parsed configuration of any bot type
+#[derive(Debug, Clone, PartialEq)]
+pub enum BotSettings {
+    Volume(VolumeConfig),
+    Bundle(BundleConfig),
+    Bump(BumpConfig),
+    Sniper(SniperConfig),
+    Human(HumanConfig),
+    Copy(CopyConfig),
+    Dca(DcaConfig),
+    Grid(GridConfig),
+}
+
+impl BotSettings {
+    /// Parse and validate `config_json` for a bot type
+    pub fn parse(bot_type: &str, config: &serde_json::Value) -> Result<Self> {
+        fn from<T: serde::de::DeserializeOwned>(config: &serde_json::Value) -> Result<T> {
+            serde_json::from_value(config.clone()).map_err(|e| anyhow!("Invalid bot configuration: {}", e))
+        }
+
+        let settings = match bot_type {
+            "volume" => BotSettings::Volume(from(config)?),
+            "bundle" => BotSettings::Bundle(from(config)?),
+            "bump" => BotSettings::Bump(from(config)?),
+            "sniper" => BotSettings::Sniper(from(config)?),
+            "human" => BotSettings::Human(from(config)?),
+            "copy" => BotSettings::Copy(from(config)?),
+            "dca" => BotSettings::Dca(from(config)?),
+            "grid" => BotSettings::Grid(from(config)?),
+            other => return Err(anyhow!("Unknown bot type: {}", other)),
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// JSON Schema of a bot type's configuration
+    pub fn schema(bot_type: &str) -> Option<RootSchema> {
+        match bot_type {
+            "volume" => Some(schema_for!(VolumeConfig)),
+            "bundle" => Some(schema_for!(BundleConfig)),
+            "bump" => Some(schema_for!(BumpConfig)),
+            "sniper" => Some(schema_for!(SniperConfig)),
+            "human" => Some(schema_for!(HumanConfig)),
+            "copy" => Some(schema_for!(CopyConfig)),
+            "dca" => Some(schema_for!(DcaConfig)),
+            "grid" => Some(schema_for!(GridConfig)),
+            _ => None,
+        }
+    }
+
+    pub fn wallet_ids(&self) -> &[Uuid] {
+        match self {
+            BotSettings::Volume(c) => &c.wallet_ids,
+            BotSettings::Bundle(c) => &c.wallet_ids,
+            BotSettings::Bump(c) => &c.wallet_ids,
+            BotSettings::Sniper(c) => &c.wallet_ids,
+            BotSettings::Human(c) => &c.wallet_ids,
+            BotSettings::Copy(c) => std::slice::from_ref(&c.wallet_id),
+            BotSettings::Dca(c) => std::slice::from_ref(&c.wallet_id),
+            BotSettings::Grid(c) => std::slice::from_ref(&c.wallet_id),
+        }
+    }
+
+    /// Security rules of bots that buy tokens the user didn't pick
+    pub fn security_policy(&self) -> Option<&SecurityPolicy> {
+        match self {
+            BotSettings::Sniper(c) => Some(&c.security),
+            BotSettings::Copy(c) => Some(&c.security),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        check_wallets(self.wallet_ids())?;
+
+        match self {
+            BotSettings::Volume(c) => {
+                check_mint("token_address", &c.token_address)?;
+                check_positive("min_sol_amount", c.min_sol_amount)?;
+                check_ordered("min_sol_amount", c.min_sol_amount, "max_sol_amount", c.max_sol_amount)?;
+                check_interval(c.min_interval_seconds)?;
+                check_ordered(
+                    "min_interval_seconds",
+                    c.min_interval_seconds,
+                    "max_interval_seconds",
+                    c.max_interval_seconds,
+                )?;
+                check_budget("total_sol_budget", c.total_sol_budget, c.max_sol_amount)?;
+                if let Some(hourly) = c.max_sol_per_hour {
+                    check_budget("max_sol_per_hour", hourly, c.max_sol_amount)?;
+                }
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+            BotSettings::Bundle(c) => {
+                check_mint("token_address", &c.token_address)?;
+                // Block engines cap bundles at five transactions, one of which is the tip
+                if c.wallet_ids.len() > 4 {
+                    return Err(anyhow!("Bundles support at most 4 wallets"));
+                }
+                check_positive("sol_amount_per_wallet", c.sol_amount_per_wallet)?;
+                check_positive("tip_sol", c.tip_sol)?;
+                check_trade_costs(c.slippage_tolerance, None)
+            }
+            BotSettings::Bump(c) => {
+                check_mint("token_address", &c.token_address)?;
+                check_positive("sol_amount", c.sol_amount)?;
+                check_interval(c.interval_seconds)?;
+                check_percent("jitter_percent", c.jitter_percent)?;
+                check_positive("max_total_cost_sol", c.max_total_cost_sol)?;
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+            BotSettings::Sniper(c) => {
+                if c.watch_mints.is_empty() && c.watch_creators.is_empty() {
+                    return Err(anyhow!("Sniper bots need at least one watched mint or creator"));
+                }
+                for mint in &c.watch_mints {
+                    check_mint("watch_mints", mint)?;
+                }
+                for creator in &c.watch_creators {
+                    check_mint("watch_creators", creator)?;
+                }
+                check_positive("sol_amount", c.sol_amount)?;
+                check_percent("max_slippage_percent", c.max_slippage_percent)?;
+                if let Some(take_profit) = c.take_profit_percent {
+                    check_positive("take_profit_percent", take_profit)?;
+                }
+                if let Some(stop_loss) = c.stop_loss_percent {
+                    check_positive("stop_loss_percent", stop_loss)?;
+                    check_percent("stop_loss_percent", stop_loss)?;
+                }
+                check_security(&c.security)?;
+                check_trade_costs(None, c.priority_fee)
+            }
+            BotSettings::Human(c) => {
+                check_mint("token_address", &c.token_address)?;
+                check_positive("median_sol_amount", c.median_sol_amount)?;
+                check_ordered("median_sol_amount", c.median_sol_amount, "max_sol_amount", c.max_sol_amount)?;
+                check_positive("trades_per_hour", c.trades_per_hour)?;
+                if c.peak_hour_utc > 23 {
+                    return Err(anyhow!("peak_hour_utc must be between 0 and 23"));
+                }
+                check_budget("total_sol_budget", c.total_sol_budget, c.max_sol_amount)?;
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+            BotSettings::Copy(c) => {
+                check_mint("target_wallet", &c.target_wallet)?;
+                for mint in &c.blacklist {
+                    check_mint("blacklist", mint)?;
+                }
+                check_positive("size_ratio", c.size_ratio)?;
+                check_positive("max_position_sol", c.max_position_sol)?;
+                check_security(&c.security)?;
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+            BotSettings::Dca(c) => {
+                check_mint("token_address", &c.token_address)?;
+                check_positive("sol_amount", c.sol_amount)?;
+                if c.interval_minutes == 0 {
+                    return Err(anyhow!("interval_minutes must be at least one"));
+                }
+                if c.total_sol_budget.is_none() && c.target_token_amount.is_none() {
+                    return Err(anyhow!("DCA bots need a total_sol_budget or a target_token_amount"));
+                }
+                if let Some(budget) = c.total_sol_budget {
+                    check_budget("total_sol_budget", budget, c.sol_amount)?;
+                }
+                if let Some(target) = c.target_token_amount {
+                    check_positive("target_token_amount", target)?;
+                }
+                if c.moving_average_minutes == Some(0) {
+                    return Err(anyhow!("moving_average_minutes must be at least one"));
+                }
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+            BotSettings::Grid(c) => {
+                check_mint("token_address", &c.token_address)?;
+                check_positive("lower_price", c.lower_price)?;
+                if c.lower_price >= c.upper_price {
+                    return Err(anyhow!("lower_price must be below upper_price"));
+                }
+                if !(2..=100).contains(&c.levels) {
+                    return Err(anyhow!("Grids need between 2 and 100 levels"));
+                }
+                check_positive("sol_per_level", c.sol_per_level)?;
+                check_interval(c.poll_seconds)?;
+                check_trade_costs(c.slippage_tolerance, c.priority_fee)
+            }
+        }
+    }
+}
+
+fn check_wallets(wallet_ids: &[Uuid]) -> Result<()> {
+    if wallet_ids.is_empty() {
+        return Err(anyhow!("At least one wallet is required"));
+    }
+
+    let mut seen = wallet_ids.to_vec();
+    seen.sort();
+    seen.dedup();
+    if seen.len() != wallet_ids.len() {
+        return Err(anyhow!("Wallets must not be listed twice"));
+    }
+
+    Ok(())
+}
+
+fn check_mint(field: &str, address: &str) -> Result<()> {
+    Pubkey::from_str(address)
+        .map(|_| ())
+        .map_err(|_| anyhow!("{} must be a valid Solana public key", field))
+}
+
+fn check_positive(field: &str, value: Decimal) -> Result<()> {
+    if value <= Decimal::ZERO {
+        return Err(anyhow!("{} must be greater than zero", field));
+    }
+    Ok(())
+}
+
+fn check_percent(field: &str, value: Decimal) -> Result<()> {
+    if value < Decimal::ZERO || value > Decimal::from(100) {
+        return Err(anyhow!("{} must be between 0 and 100", field));
+    }
+    Ok(())
+}
+
+/// Thresholds are percentages, and warnings must trip before blocks do
+fn check_security(policy: &SecurityPolicy) -> Result<()> {
+    let limits = [
+        ("top_holder_pct", &policy.top_holder_pct, false),
+        ("top10_holders_pct", &policy.top10_holders_pct, false),
+        ("lp_locked_pct", &policy.lp_locked_pct, true),
+        ("round_trip_loss_pct", &policy.round_trip_loss_pct, false),
+        ("transfer_fee_pct", &policy.transfer_fee_pct, false),
+    ];
+    for (field, Limit { warn, block }, minimum) in limits {
+        for value in [warn, block].into_iter().flatten() {
+            check_percent(&format!("security.{}", field), *value)?;
+        }
+        if let (Some(warn), Some(block)) = (warn, block) {
+            let (warn_field, block_field) = (format!("security.{}.warn", field), format!("security.{}.block", field));
+            if minimum {
+                check_ordered(&block_field, block, &warn_field, warn)?;
+            } else {
+                check_ordered(&warn_field, warn, &block_field, block)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_ordered<T: PartialOrd>(min_field: &str, min: T, max_field: &str, max: T) -> Result<()> {
+    if min > max {
+        return Err(anyhow!("{} must not exceed {}", min_field, max_field));
+    }
+    Ok(())
+}
+
+fn check_interval(seconds: u64) -> Result<()> {
+    if seconds == 0 {
+        return Err(anyhow!("Intervals must be at least one second"));
+    }
+    Ok(())
+}
+
+/// A budget has to cover at least one trade of the largest size
+fn check_budget(field: &str, budget: Decimal, max_trade: Decimal) -> Result<()> {
+    if budget < max_trade {
+        return Err(anyhow!("{} must cover at least one trade of the maximum size", field));
+    }
+    Ok(())
+}
+
+fn check_trade_costs(slippage_tolerance: Option<Decimal>, priority_fee: Option<Decimal>) -> Result<()> {
+    if let Some(slippage) = slippage_tolerance {
+        check_percent("slippage_tolerance", slippage)?;
+    }
+    if let Some(fee) = priority_fee {
+        if fee < Decimal::ZERO {
+            return Err(anyhow!("priority_fee cannot be negative"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MINT: &str = "So11111111111111111111111111111111111111112";
+
+    fn volume() -> serde_json::Value {
+        json!({
+            "token_address": MINT,
+            "wallet_ids": [Uuid::new_v4()],
+            "min_sol_amount": "0.01",
+            "max_sol_amount": "0.05",
+            "min_interval_seconds": 30,
+            "max_interval_seconds": 90,
+            "total_sol_budget": "2"
+        })
+    }
+
+    #[test]
+    fn test_valid_config_parses() {
+        let settings = BotSettings::parse("volume", &volume()).unwrap();
+        assert_eq!(settings.wallet_ids().len(), 1);
+        assert!(matches!(settings, BotSettings::Volume(VolumeConfig { common: CommonSettings { paper: false, .. }, .. })));
+    }
+
+    #[test]
+    fn test_common_settings_sit_beside_the_type_fields() {
+        let mut paper = volume();
+        paper["paper"] = json!(true);
+        paper["reclaim_rent_on_stop"] = json!(true);
+        let settings = BotSettings::parse("volume", &paper).unwrap();
+        let BotSettings::Volume(config) = settings else { panic!("not a volume config") };
+        assert_eq!(
+            config.common,
+            CommonSettings { paper: true, dry_run: false, reclaim_rent_on_stop: true }
+        );
+        assert_eq!(serde_json::to_value(&config).unwrap()["paper"], json!(true));
+
+        // The fields of the baseline volume seeds are rejected, not ignored
+        let mut legacy = volume();
+        legacy["enabled_hours"] = json!([9, 10]);
+        assert!(BotSettings::parse("volume", &legacy).is_err());
+    }
+
+    #[test]
+    fn test_unknown_fields_and_bad_ranges_are_rejected() {
+        let mut typo = volume();
+        typo["max_sol_amout"] = json!("0.05");
+        assert!(BotSettings::parse("volume", &typo).is_err());
+
+        let mut inverted = volume();
+        inverted["min_sol_amount"] = json!("0.1");
+        assert!(BotSettings::parse("volume", &inverted).is_err());
+
+        let mut no_wallets = volume();
+        no_wallets["wallet_ids"] = json!([]);
+        assert!(BotSettings::parse("volume", &no_wallets).is_err());
+
+        assert!(BotSettings::parse("volume", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_every_bot_type_has_a_schema() {
+        for bot_type in BOT_TYPES {
+            assert!(BotSettings::schema(bot_type).is_some(), "{}", bot_type);
+        }
+        assert!(BotSettings::schema("unknown").is_none());
+    }
+}
@@ -0,0 +1,400 @@
+//! Copy-trading bot
+//!
+//! Follows a target wallet's swaps and mirrors them from one of our wallets,
+//! scaled by a size ratio. Buys are capped per token so one token can't take
+//! over the wallet, sells only unwind what the bot itself bought, and
+//! blacklisted mints are never touched. A token is security-checked before
+//! its first mirrored buy. Every mirrored trade records the signature of the
+//! swap it copies.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::bots::config::{BotSettings, CopyConfig};
+use crate::bots::{Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+use crate::market::swaps::SwapEventStream;
+use crate::market::{SwapEventSource, TokenFilter, TokenVerdict, WalletSwap};
+use crate::trading::TradeStatus;
+
+/// How long one step waits for a swap before yielding to the supervisor
+const EVENT_WAIT: Duration = Duration::from_secs(5);
+
+/// Counters reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct CopyStats {
+    swaps_seen: u64,
+    mirrored: u64,
+    failed: u64,
+    skipped: u64,
+    last_skip: Option<String>,
+}
+
+pub struct CopyBot {
+    config: CopyConfig,
+    swap_events: Arc<dyn SwapEventSource>,
+    token_filter: Arc<dyn TokenFilter>,
+    events: Option<SwapEventStream>,
+    event_wait: Duration,
+    /// Swaps waiting out the configured delay, oldest first
+    queued: VecDeque<(Instant, WalletSwap)>,
+    /// SOL held per token through mirrored buys
+    positions: HashMap<String, Decimal>,
+    restored: bool,
+    /// Security verdicts of tokens already checked
+    verdicts: HashMap<String, TokenVerdict>,
+    stats: CopyStats,
+}
+
+impl CopyBot {
+    pub fn new(
+        config: CopyConfig,
+        swap_events: Arc<dyn SwapEventSource>,
+        token_filter: Arc<dyn TokenFilter>,
+    ) -> Self {
+        Self {
+            config,
+            swap_events,
+            token_filter,
+            events: None,
+            event_wait: EVENT_WAIT,
+            queued: VecDeque::new(),
+            positions: HashMap::new(),
+            restored: false,
+            verdicts: HashMap::new(),
+            stats: CopyStats::default(),
+        }
+    }
+
+    pub fn from_config(
+        bot: &BotConfig,
+        swap_events: Arc<dyn SwapEventSource>,
+        token_filter: Arc<dyn TokenFilter>,
+    ) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Copy(config) => Ok(Self::new(config, swap_events, token_filter)),
+            _ => Err(anyhow!("Bot {} is not a copy-trading bot", bot.id)),
+        }
+    }
+
+    /// Pick up the positions opened by earlier runs of this bot, so sells
+    /// still unwind them and buys stay within the per-token cap
+    async fn restore(&mut self, ctx: &BotContext) -> Result<()> {
+        let positions = sqlx::query_as::<_, (String, Decimal)>(
+            r#"
+            SELECT token_address,
+                   SUM(CASE WHEN trade_type = 'buy' THEN sol_amount ELSE -sol_amount END)
+            FROM trades
+            WHERE bot_config_id = $1 AND status NOT IN ($2, $3, $4)
+            GROUP BY token_address
+            HAVING SUM(CASE WHEN trade_type = 'buy' THEN sol_amount ELSE -sol_amount END) > 0
+            "#
+        )
+        .bind(ctx.bot.id)
+        .bind(TradeStatus::Failed.as_str())
+        .bind(TradeStatus::Expired.as_str())
+        .bind(TradeStatus::Cancelled.as_str())
+        .fetch_all(&ctx.pool)
+        .await?;
+
+        self.positions = positions.into_iter().collect();
+        Ok(())
+    }
+
+    fn skip(&mut self, swap: &WalletSwap, reason: &str) {
+        log::info!("Not mirroring {}: {}", swap.signature, reason);
+        self.stats.skipped += 1;
+        self.stats.last_skip = Some(format!("{}: {}", swap.signature, reason));
+    }
+
+    /// SOL amount to mirror a swap with, or why it isn't mirrored
+    fn mirror_amount(&self, swap: &WalletSwap) -> Result<Decimal, String> {
+        let held = self.positions.get(&swap.token_mint).copied().unwrap_or_default();
+        let scaled = (swap.sol_amount * self.config.size_ratio).round_dp(9);
+
+        let amount = match swap.trade_type.as_str() {
+            "buy" => scaled.min(self.config.max_position_sol - held),
+            "sell" if held.is_zero() => return Err("No position to sell".to_string()),
+            "sell" => scaled.min(held),
+            other => return Err(format!("Unknown trade type {}", other)),
+        };
+
+        if amount <= Decimal::ZERO {
+            return Err(format!("Position limit of {} SOL reached", self.config.max_position_sol));
+        }
+        Ok(amount)
+    }
+
+    /// Security verdict on a token, checked once per run. A token that
+    /// couldn't be checked is rechecked next time rather than remembered.
+    async fn verdict(&mut self, mint: &str) -> TokenVerdict {
+        if let Some(verdict) = self.verdicts.get(mint) {
+            return verdict.clone();
+        }
+        match self.token_filter.check(mint, &self.config.security).await {
+            Ok(verdict) => {
+                self.verdicts.insert(mint.to_string(), verdict.clone());
+                verdict
+            }
+            // Fail closed: a token we couldn't check is not bought
+            Err(e) => TokenVerdict::from_reasons(vec![format!("Security check failed: {}", e)]),
+        }
+    }
+
+    async fn mirror(&mut self, ctx: &BotContext, swap: WalletSwap) -> Result<()> {
+        let sol_amount = match self.mirror_amount(&swap) {
+            Ok(amount) => amount,
+            Err(reason) => {
+                self.skip(&swap, &reason);
+                return Ok(());
+            }
+        };
+
+        // Sells only unwind positions, so only buys are checked
+        if swap.trade_type == "buy" {
+            let verdict = self.verdict(&swap.token_mint).await;
+            if !verdict.passed {
+                self.skip(&swap, &verdict.reasons.join("; "));
+                return Ok(());
+            }
+        }
+
+        let req = CreateTradeRequest {
+            wallet_id: self.config.wallet_id,
+            token_address: swap.token_mint.clone(),
+            trade_type: swap.trade_type.clone(),
+            sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        };
+        let trade = ctx.mirror_trade(&req, &swap.signature).await?;
+
+        if !trade.succeeded() {
+            log::warn!("Copy bot {} failed to mirror {}", ctx.bot.id, swap.signature);
+            self.stats.failed += 1;
+            return Ok(());
+        }
+
+        let held = self.positions.entry(swap.token_mint.clone()).or_default();
+        if swap.trade_type == "buy" {
+            *held += sol_amount;
+        } else {
+            *held = (*held - sol_amount).max(Decimal::ZERO);
+        }
+        self.stats.mirrored += 1;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Bot for CopyBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        // Dry runs record nothing, so there is nothing to restore
+        if !self.restored && !self.config.common.dry_run {
+            self.restore(ctx).await?;
+        }
+        self.restored = true;
+
+        let now = Instant::now();
+        if self.queued.front().is_some_and(|(due, _)| *due <= now) {
+            if let Some((_, swap)) = self.queued.pop_front() {
+                self.mirror(ctx, swap).await?;
+            }
+            return Ok(Step::Wait(Duration::ZERO));
+        }
+
+        let mut events = match self.events.take() {
+            Some(events) => events,
+            None => self.swap_events.subscribe(&self.config.target_wallet).await?,
+        };
+
+        // Stop listening in time to mirror the next queued swap on schedule
+        let wait = self.queued.front().map_or(self.event_wait, |(due, _)| self.event_wait.min(*due - now));
+
+        // A failed or closed stream is dropped so the next step resubscribes
+        let swap = match tokio::time::timeout(wait, events.next()).await {
+            Err(_) => {
+                self.events = Some(events);
+                return Ok(Step::Wait(Duration::ZERO));
+            }
+            Ok(Some(Ok(swap))) => {
+                self.events = Some(events);
+                swap
+            }
+            Ok(Some(Err(e))) => return Err(e),
+            Ok(None) => return Err(anyhow!("Swap event stream closed")),
+        };
+
+        self.stats.swaps_seen += 1;
+        if self.config.blacklist.contains(&swap.token_mint) {
+            self.skip(&swap, "Token is blacklisted");
+        } else {
+            let due = Instant::now() + Duration::from_secs(self.config.delay_seconds);
+            self.queued.push_back((due, swap));
+        }
+
+        Ok(Step::Wait(Duration::ZERO))
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        let mut progress = serde_json::to_value(&self.stats).ok()?;
+        progress["queued"] = serde_json::json!(self.queued.len());
+        progress["positions"] = serde_json::json!(self.positions);
+        Some(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::bots::{BotTrade, Trader};
+    use crate::database::models::{CreateOrderRequest, Order};
+    use crate::market::security::{evaluate, TokenFacts};
+    use crate::market::{ChannelSwapEvents, RiskReport, SecurityPolicy};
+    use crate::trading::SimulationReport;
+    use chrono::Utc;
+    use sqlx::postgres::PgPoolOptions;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    const TARGET: &str = "Target1111111111111111111111111111111111111";
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    /// Records mirrored trades with their source signatures
+    #[derive(Default)]
+    struct MirrorTrader {
+        mirrored: Mutex<Vec<(CreateTradeRequest, String)>>,
+    }
+
+    #[async_trait]
+    impl Trader for MirrorTrader {
+        async fn trade(&self, _bot: &BotConfig, _req: &CreateTradeRequest) -> Result<BotTrade> {
+            Err(anyhow!("copy bots only place mirrored trades"))
+        }
+
+        async fn mirror_trade(&self, _bot: &BotConfig, req: &CreateTradeRequest, source_signature: &str) -> Result<BotTrade> {
+            self.mirrored.lock().unwrap().push((req.clone(), source_signature.to_string()));
+            Ok(BotTrade::Simulated(Box::new(SimulationReport {
+                success: true,
+                error: None,
+                input_mint: String::new(),
+                output_mint: String::new(),
+                sol_amount: req.sol_amount,
+                expected_token_amount: Decimal::ONE,
+                worst_case_token_amount: Decimal::ONE,
+                price_per_token: None,
+                price_impact_pct: Decimal::ZERO,
+                network_fee_lamports: 5_000,
+                priority_fee_lamports: 0,
+                compute_units_consumed: None,
+                logs: vec![],
+            })))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("copy bots place no orders"))
+        }
+    }
+
+    /// Flags every mint but GoodMint as still mintable
+    struct OnlyGoodMint;
+
+    #[async_trait]
+    impl TokenFilter for OnlyGoodMint {
+        async fn assess(&self, mint: &str, policy: &SecurityPolicy) -> Result<RiskReport> {
+            let facts = TokenFacts { mint_authority: mint != "GoodMint", ..TokenFacts::default() };
+            Ok(evaluate(mint, facts, policy))
+        }
+    }
+
+    fn swap(signature: &str, mint: &str, trade_type: &str, sol_amount: &str) -> WalletSwap {
+        WalletSwap {
+            wallet: TARGET.to_string(),
+            token_mint: mint.to_string(),
+            trade_type: trade_type.to_string(),
+            sol_amount: dec(sol_amount),
+            token_amount: dec("1000"),
+            signature: signature.to_string(),
+            slot: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mirrors_scaled_swaps_within_limits() {
+        let config = CopyConfig {
+            target_wallet: TARGET.to_string(),
+            wallet_id: Uuid::new_v4(),
+            size_ratio: dec("0.5"),
+            max_position_sol: dec("1"),
+            delay_seconds: 0,
+            blacklist: vec!["BadMint".to_string()],
+            security: SecurityPolicy::default(),
+            slippage_tolerance: Some(dec("5")),
+            priority_fee: None,
+            common: CommonSettings { dry_run: true, ..Default::default() },
+        };
+        let events = ChannelSwapEvents::new();
+        let trader = Arc::new(MirrorTrader::default());
+        let ctx = BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "copy".to_string(),
+                name: "copy".to_string(),
+                is_active: true,
+                config_json: serde_json::json!({}),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader: trader.clone(),
+        };
+        let mut bot = CopyBot::new(config, Arc::new(events.clone()), Arc::new(OnlyGoodMint));
+        bot.event_wait = Duration::from_millis(20);
+
+        // Subscribe first; only swaps made after that are seen
+        bot.step(&ctx).await.unwrap();
+        events.publish(swap("s1", "GoodMint", "buy", "1.2"));
+        events.publish(swap("s2", "BadMint", "buy", "1"));
+        events.publish(swap("s3", "GoodMint", "buy", "1"));
+        events.publish(swap("s4", "OtherMint", "sell", "1"));
+        events.publish(swap("s5", "GoodMint", "sell", "0.4"));
+        events.publish(swap("s6", "GoodMint", "buy", "2"));
+        events.publish(swap("s7", "RiskyMint", "buy", "1"));
+        for _ in 0..14 {
+            bot.step(&ctx).await.unwrap();
+        }
+
+        let mirrored = trader.mirrored.lock().unwrap().clone();
+        let summary: Vec<_> = mirrored.iter()
+            .map(|(req, sig)| (sig.as_str(), req.trade_type.as_str(), req.sol_amount))
+            .collect();
+        assert_eq!(summary, [
+            ("s1", "buy", dec("0.6")),
+            // Capped at the 1 SOL position limit
+            ("s3", "buy", dec("0.4")),
+            ("s5", "sell", dec("0.2")),
+            ("s6", "buy", dec("0.2")),
+        ]);
+        assert!(mirrored.iter().all(|(req, _)| req.slippage_tolerance == Some(dec("5"))));
+
+        assert_eq!(bot.stats.swaps_seen, 7);
+        assert_eq!(bot.stats.skipped, 3);
+        assert!(bot.stats.last_skip.as_deref().unwrap().ends_with("Mint authority has not been revoked"));
+        assert_eq!(bot.positions["GoodMint"], dec("1"));
+    }
+}
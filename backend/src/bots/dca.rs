@@ -0,0 +1,328 @@
+//! DCA bot
+//!
+//! Accumulates a token by buying a fixed SOL amount every interval until a
+//! SOL budget is spent or a token target is reached. With a moving-average
+//! filter a due buy is held back until the price dips below its recent
+//! average. Totals are rebuilt from the bot's recorded trades when it
+//! starts, so a restart doesn't reset the budget.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bots::config::{BotSettings, DcaConfig};
+use crate::bots::{Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+use crate::market::PriceFeed;
+use crate::trading::TradeStatus;
+
+/// How often the price is sampled for the moving average
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Totals reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct DcaStats {
+    /// SOL spent on buys and fees
+    spent_sol: Decimal,
+    tokens_bought: Decimal,
+    buys: u64,
+    failed: u64,
+    next_buy_at: Option<DateTime<Utc>>,
+    last_price: Option<Decimal>,
+    moving_average: Option<Decimal>,
+    /// A buy is due but held back until the price dips below its average
+    waiting_for_dip: bool,
+}
+
+pub struct DcaBot {
+    config: DcaConfig,
+    price_feed: Arc<dyn PriceFeed>,
+    /// Price samples inside the moving-average window, oldest first
+    prices: VecDeque<(DateTime<Utc>, Decimal)>,
+    restored: bool,
+    stats: DcaStats,
+}
+
+impl DcaBot {
+    pub fn new(config: DcaConfig, price_feed: Arc<dyn PriceFeed>) -> Self {
+        Self {
+            config,
+            price_feed,
+            prices: VecDeque::new(),
+            restored: false,
+            stats: DcaStats::default(),
+        }
+    }
+
+    pub fn from_config(bot: &BotConfig, price_feed: Arc<dyn PriceFeed>) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Dca(config) => Ok(Self::new(config, price_feed)),
+            _ => Err(anyhow!("Bot {} is not a DCA bot", bot.id)),
+        }
+    }
+
+    /// Pick up the totals of buys made by earlier runs of this bot
+    async fn restore(&mut self, ctx: &BotContext) -> Result<()> {
+        let (spent_sol, tokens_bought, buys) = sqlx::query_as::<_, (Decimal, Decimal, i64)>(
+            r#"
+            SELECT COALESCE(SUM(sol_amount + COALESCE(fee_sol, 0)), 0),
+                   COALESCE(SUM(token_amount), 0),
+                   COUNT(*)
+            FROM trades
+            WHERE bot_config_id = $1 AND trade_type = 'buy' AND status NOT IN ($2, $3, $4)
+            "#
+        )
+        .bind(ctx.bot.id)
+        .bind(TradeStatus::Failed.as_str())
+        .bind(TradeStatus::Expired.as_str())
+        .bind(TradeStatus::Cancelled.as_str())
+        .fetch_one(&ctx.pool)
+        .await?;
+
+        self.stats.spent_sol = spent_sol;
+        self.stats.tokens_bought = tokens_bought;
+        self.stats.buys = buys as u64;
+        Ok(())
+    }
+
+    /// Why the bot is finished, if it is
+    fn goal_reached(&self) -> Option<String> {
+        if let Some(budget) = self.config.total_sol_budget {
+            if budget - self.stats.spent_sol < self.config.sol_amount {
+                return Some(format!("Total SOL budget of {} spent", budget));
+            }
+        }
+        if let Some(target) = self.config.target_token_amount {
+            if self.stats.tokens_bought >= target {
+                return Some(format!("Bought the target of {} tokens", target));
+            }
+        }
+        None
+    }
+
+    /// Record a price sample; returns the average over the window once the
+    /// samples cover all of it
+    fn sample(&mut self, now: DateTime<Utc>, price: Decimal, window: chrono::Duration) -> Option<Decimal> {
+        let covered = self.prices.front().is_some_and(|(at, _)| *at <= now - window);
+        while self.prices.len() > 1 && self.prices[1].0 <= now - window {
+            self.prices.pop_front();
+        }
+        self.prices.push_back((now, price));
+
+        self.stats.last_price = Some(price);
+        self.stats.moving_average = covered.then(|| {
+            let total: Decimal = self.prices.iter().map(|(_, p)| *p).sum();
+            (total / Decimal::from(self.prices.len())).round_dp(12)
+        });
+        self.stats.moving_average
+    }
+
+    async fn step_at(&mut self, ctx: &BotContext, now: DateTime<Utc>) -> Result<Step> {
+        // Dry runs record nothing, so there is nothing to restore
+        if !self.restored && !self.config.common.dry_run {
+            self.restore(ctx).await?;
+        }
+        self.restored = true;
+
+        if let Some(reason) = self.goal_reached() {
+            return Ok(Step::Done(reason));
+        }
+
+        let next_buy = self.stats.next_buy_at.unwrap_or(now);
+        let until_buy = (next_buy - now).to_std().unwrap_or_default();
+
+        if let Some(minutes) = self.config.moving_average_minutes {
+            let price = self.price_feed
+                .price_in_sol(&self.config.token_address)
+                .await?
+                .ok_or_else(|| anyhow!("No price available for {}", self.config.token_address))?;
+            let average = self.sample(now, price, chrono::Duration::minutes(minutes as i64));
+
+            if !until_buy.is_zero() {
+                return Ok(Step::Wait(until_buy.min(SAMPLE_INTERVAL)));
+            }
+            // Still filling the window, or not cheap enough yet
+            self.stats.waiting_for_dip = average.is_none_or(|average| price >= average);
+            if self.stats.waiting_for_dip {
+                return Ok(Step::Wait(SAMPLE_INTERVAL));
+            }
+        } else if !until_buy.is_zero() {
+            return Ok(Step::Wait(until_buy));
+        }
+
+        let req = CreateTradeRequest {
+            wallet_id: self.config.wallet_id,
+            token_address: self.config.token_address.clone(),
+            trade_type: "buy".to_string(),
+            sol_amount: self.config.sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        };
+        let trade = ctx.trade(&req).await?;
+
+        self.stats.spent_sol += trade.fee_sol();
+        if trade.succeeded() {
+            self.stats.spent_sol += trade.sol_amount();
+            self.stats.tokens_bought += trade.token_amount().unwrap_or_default();
+            self.stats.buys += 1;
+        } else {
+            log::warn!("DCA bot {} buy of {} SOL failed", ctx.bot.id, req.sol_amount);
+            self.stats.failed += 1;
+        }
+
+        // A failed buy waits for the next slot rather than retrying at once
+        let interval = chrono::Duration::minutes(self.config.interval_minutes as i64);
+        self.stats.next_buy_at = Some(now + interval);
+        Ok(Step::Wait(Duration::ZERO))
+    }
+}
+
+#[async_trait]
+impl Bot for DcaBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        self.step_at(ctx, Utc::now()).await
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        let mut progress = serde_json::to_value(&self.stats).ok()?;
+        if let Some(budget) = self.config.total_sol_budget {
+            progress["remaining_sol"] = serde_json::json!((budget - self.stats.spent_sol).max(Decimal::ZERO));
+        }
+        if let Some(target) = self.config.target_token_amount {
+            progress["target_token_amount"] = serde_json::json!(target);
+            progress["percent_complete"] =
+                serde_json::json!((self.stats.tokens_bought / target * Decimal::from(100)).min(Decimal::from(100)).round_dp(2));
+        }
+        Some(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::bots::{BotTrade, Trader};
+    use crate::database::models::{CreateOrderRequest, Order};
+    use crate::trading::SimulationReport;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    /// Price set by the test; buys fill at it
+    #[derive(Default)]
+    struct Market {
+        price: Mutex<Decimal>,
+        buys: Mutex<Vec<Decimal>>,
+    }
+
+    impl Market {
+        fn set(&self, price: &str) {
+            *self.price.lock().unwrap() = dec(price);
+        }
+    }
+
+    #[async_trait]
+    impl PriceFeed for Market {
+        async fn prices_in_sol(&self, mints: &[String]) -> Result<HashMap<String, Decimal>> {
+            let price = *self.price.lock().unwrap();
+            Ok(mints.iter().map(|mint| (mint.clone(), price)).collect())
+        }
+    }
+
+    #[async_trait]
+    impl Trader for Market {
+        async fn trade(&self, _bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade> {
+            let price = *self.price.lock().unwrap();
+            self.buys.lock().unwrap().push(price);
+            Ok(BotTrade::Simulated(Box::new(SimulationReport {
+                success: true,
+                error: None,
+                input_mint: String::new(),
+                output_mint: String::new(),
+                sol_amount: req.sol_amount,
+                expected_token_amount: req.sol_amount / price,
+                worst_case_token_amount: req.sol_amount / price,
+                price_per_token: Some(price),
+                price_impact_pct: Decimal::ZERO,
+                network_fee_lamports: 0,
+                priority_fee_lamports: 0,
+                compute_units_consumed: None,
+                logs: vec![],
+            })))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("DCA bots place no orders"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buys_below_average_until_target() {
+        let config = DcaConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_id: Uuid::new_v4(),
+            sol_amount: dec("1"),
+            interval_minutes: 60,
+            total_sol_budget: None,
+            target_token_amount: Some(dec("250")),
+            moving_average_minutes: Some(10),
+            slippage_tolerance: None,
+            priority_fee: None,
+            common: CommonSettings { dry_run: true, ..Default::default() },
+        };
+        let market = Arc::new(Market::default());
+        let ctx = BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "dca".to_string(),
+                name: "dca".to_string(),
+                is_active: true,
+                config_json: serde_json::to_value(&config).unwrap(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader: market.clone(),
+        };
+        let mut bot = DcaBot::new(config, market.clone());
+        let start = Utc::now();
+        let minute = |m: i64| start + chrono::Duration::minutes(m);
+
+        // Nothing is bought while the average window fills, even on a dip
+        market.set("0.01");
+        for m in 0..10 {
+            assert_eq!(bot.step_at(&ctx, minute(m)).await.unwrap(), Step::Wait(SAMPLE_INTERVAL));
+        }
+        market.set("0.008");
+        bot.step_at(&ctx, minute(10)).await.unwrap();
+        assert_eq!(market.buys.lock().unwrap().len(), 1);
+
+        // The next slot comes due above the average and waits for a dip
+        market.set("0.012");
+        assert_eq!(bot.step_at(&ctx, minute(30)).await.unwrap(), Step::Wait(SAMPLE_INTERVAL));
+        bot.step_at(&ctx, minute(70)).await.unwrap();
+        assert!(bot.stats.waiting_for_dip);
+        market.set("0.005");
+        bot.step_at(&ctx, minute(71)).await.unwrap();
+        assert_eq!(*market.buys.lock().unwrap(), [dec("0.008"), dec("0.005")]);
+
+        // 125 + 200 tokens passes the 250 target
+        assert_eq!(bot.stats.tokens_bought, dec("325"));
+        assert!(matches!(bot.step_at(&ctx, minute(200)).await.unwrap(), Step::Done(_)));
+        assert_eq!(bot.progress().unwrap()["percent_complete"], serde_json::json!(dec("100")));
+    }
+}
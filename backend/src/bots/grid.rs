@@ -0,0 +1,383 @@
+//! Grid bot
+//!
+//! Splits a price range into evenly spaced levels. Each cell between two
+//! neighbouring levels buys when the price falls through its lower level and
+//! sells what it bought when the price rises through its upper level, then
+//! re-arms the buy. Completed buy-sell cycles are counted as grid profit;
+//! tokens still held are marked to market separately as inventory PnL. When
+//! the price leaves the range the bot either stops or sells its inventory and
+//! re-centers the grid on the current price.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bots::config::{BotSettings, GridConfig, GridExit};
+use crate::bots::{Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+use crate::market::PriceFeed;
+
+/// Tokens bought by a cell, waiting for the price to reach its upper level
+#[derive(Debug, Clone, PartialEq)]
+struct Holding {
+    tokens: Decimal,
+    /// SOL paid, fees included
+    cost_sol: Decimal,
+}
+
+/// A fill the grid wants, for the cell at `cell`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fill {
+    Buy(usize),
+    Sell(usize),
+}
+
+/// Price levels and what each cell between them holds
+#[derive(Debug, Clone)]
+struct Grid {
+    levels: Vec<Decimal>,
+    cells: Vec<Option<Holding>>,
+}
+
+impl Grid {
+    fn new(lower: Decimal, upper: Decimal, levels: u32) -> Self {
+        let step = (upper - lower) / Decimal::from(levels - 1);
+        let levels: Vec<Decimal> = (0..levels)
+            .map(|i| (lower + step * Decimal::from(i)).round_dp(12))
+            .collect();
+        Self { cells: vec![None; levels.len() - 1], levels }
+    }
+
+    fn lower(&self) -> Decimal {
+        self.levels[0]
+    }
+
+    fn upper(&self) -> Decimal {
+        self.levels[self.levels.len() - 1]
+    }
+
+    fn contains(&self, price: Decimal) -> bool {
+        price >= self.lower() && price <= self.upper()
+    }
+
+    /// Fills triggered by the price moving from `previous` to `price`: buys
+    /// for empty cells whose lower level was crossed going down, nearest
+    /// first, and sells for held cells whose upper level was crossed going up
+    fn crossings(&self, previous: Decimal, price: Decimal) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        for (cell, holding) in self.cells.iter().enumerate() {
+            let (lower, upper) = (self.levels[cell], self.levels[cell + 1]);
+            match holding {
+                None if previous > lower && price <= lower => fills.push(Fill::Buy(cell)),
+                Some(_) if previous < upper && price >= upper => fills.push(Fill::Sell(cell)),
+                _ => {}
+            }
+        }
+        if price < previous {
+            fills.reverse();
+        }
+        fills
+    }
+
+    fn inventory(&self) -> (Decimal, Decimal) {
+        self.cells.iter().flatten().fold((Decimal::ZERO, Decimal::ZERO), |(tokens, cost), h| {
+            (tokens + h.tokens, cost + h.cost_sol)
+        })
+    }
+}
+
+/// Profit and counters reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct GridStats {
+    /// Realized by completed buy-sell cycles, fees included
+    grid_profit_sol: Decimal,
+    /// Realized by selling inventory when the grid was re-centered
+    realized_inventory_pnl_sol: Decimal,
+    buys: u64,
+    sells: u64,
+    failed: u64,
+    rebalances: u64,
+    last_price: Option<Decimal>,
+}
+
+pub struct GridBot {
+    config: GridConfig,
+    price_feed: Arc<dyn PriceFeed>,
+    grid: Grid,
+    stats: GridStats,
+}
+
+impl GridBot {
+    pub fn new(config: GridConfig, price_feed: Arc<dyn PriceFeed>) -> Self {
+        let grid = Grid::new(config.lower_price, config.upper_price, config.levels);
+        Self {
+            config,
+            price_feed,
+            grid,
+            stats: GridStats::default(),
+        }
+    }
+
+    pub fn from_config(bot: &BotConfig, price_feed: Arc<dyn PriceFeed>) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Grid(config) => Ok(Self::new(config, price_feed)),
+            _ => Err(anyhow!("Bot {} is not a grid bot", bot.id)),
+        }
+    }
+
+    fn trade_request(&self, trade_type: &str, sol_amount: Decimal) -> CreateTradeRequest {
+        CreateTradeRequest {
+            wallet_id: self.config.wallet_id,
+            token_address: self.config.token_address.clone(),
+            trade_type: trade_type.to_string(),
+            sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        }
+    }
+
+    /// SOL to ask for when selling tokens at a price. Sells receive an exact
+    /// SOL amount; leaving room for slippage keeps them within the tokens held.
+    fn sell_amount(&self, tokens: Decimal, price: Decimal) -> Decimal {
+        let slippage = self.config.slippage_tolerance.unwrap_or(Decimal::ONE) / Decimal::from(100);
+        (tokens * price * (Decimal::ONE - slippage)).round_dp(9)
+    }
+
+    async fn fill(&mut self, ctx: &BotContext, fill: Fill) -> Result<()> {
+        match fill {
+            Fill::Buy(cell) => {
+                let price = self.grid.levels[cell];
+                let trade = ctx.trade(&self.trade_request("buy", self.config.sol_per_level)).await?;
+                if !trade.succeeded() {
+                    self.stats.grid_profit_sol -= trade.fee_sol();
+                    self.stats.failed += 1;
+                    return Ok(());
+                }
+
+                let tokens = trade.token_amount().unwrap_or(self.config.sol_per_level / price);
+                self.grid.cells[cell] = Some(Holding { tokens, cost_sol: trade.sol_amount() + trade.fee_sol() });
+                self.stats.buys += 1;
+            }
+            Fill::Sell(cell) => {
+                let holding = match self.grid.cells[cell].clone() {
+                    Some(holding) => holding,
+                    None => return Ok(()),
+                };
+                let sol_amount = self.sell_amount(holding.tokens, self.grid.levels[cell + 1]);
+                let trade = ctx.trade(&self.trade_request("sell", sol_amount)).await?;
+                if !trade.succeeded() {
+                    self.stats.grid_profit_sol -= trade.fee_sol();
+                    self.stats.failed += 1;
+                    return Ok(());
+                }
+
+                self.stats.grid_profit_sol += trade.sol_amount() - trade.fee_sol() - holding.cost_sol;
+                self.grid.cells[cell] = None;
+                self.stats.sells += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sell the whole inventory and rebuild the grid, same width, around `price`
+    async fn rebalance(&mut self, ctx: &BotContext, price: Decimal) -> Result<Step> {
+        let (tokens, cost) = self.grid.inventory();
+        if tokens > Decimal::ZERO {
+            let trade = ctx.trade(&self.trade_request("sell", self.sell_amount(tokens, price))).await?;
+            if !trade.succeeded() {
+                self.stats.realized_inventory_pnl_sol -= trade.fee_sol();
+                self.stats.failed += 1;
+                return Ok(Step::Wait(Duration::from_secs(self.config.poll_seconds)));
+            }
+            self.stats.realized_inventory_pnl_sol += trade.sol_amount() - trade.fee_sol() - cost;
+        }
+
+        let half_width = (self.grid.upper() - self.grid.lower()) / Decimal::from(2);
+        if price <= half_width {
+            return Ok(Step::Done("Price fell too far to re-center the grid".to_string()));
+        }
+        self.grid = Grid::new(price - half_width, price + half_width, self.config.levels);
+        self.stats.rebalances += 1;
+        log::info!("Grid bot {} re-centered on {}", ctx.bot.id, price);
+        Ok(Step::Wait(Duration::from_secs(self.config.poll_seconds)))
+    }
+}
+
+#[async_trait]
+impl Bot for GridBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        let price = self.price_feed
+            .price_in_sol(&self.config.token_address)
+            .await?
+            .ok_or_else(|| anyhow!("No price available for {}", self.config.token_address))?;
+        let previous = self.stats.last_price.replace(price);
+
+        if !self.grid.contains(price) {
+            return match self.config.on_exit {
+                GridExit::Stop => Ok(Step::Done(format!(
+                    "Price {} left the grid range {}-{}",
+                    price,
+                    self.grid.lower(),
+                    self.grid.upper()
+                ))),
+                GridExit::Rebalance => self.rebalance(ctx, price).await,
+            };
+        }
+
+        if let Some(previous) = previous {
+            for fill in self.grid.crossings(previous, price) {
+                self.fill(ctx, fill).await?;
+            }
+        }
+
+        Ok(Step::Wait(Duration::from_secs(self.config.poll_seconds)))
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        let (tokens, cost) = self.grid.inventory();
+        let mut progress = serde_json::to_value(&self.stats).ok()?;
+        progress["inventory_tokens"] = serde_json::json!(tokens);
+        progress["inventory_cost_sol"] = serde_json::json!(cost);
+        progress["unrealized_inventory_pnl_sol"] =
+            serde_json::json!(self.stats.last_price.map(|price| (tokens * price - cost).round_dp(9)));
+        progress["range"] = serde_json::json!([self.grid.lower(), self.grid.upper()]);
+        progress["cells_holding"] = serde_json::json!(self.grid.cells.iter().flatten().count());
+        Some(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::bots::{BotTrade, Trader};
+    use crate::database::models::{CreateOrderRequest, Order};
+    use crate::trading::SimulationReport;
+    use chrono::Utc;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    /// Fills every trade at the price set by the test, free of fees
+    #[derive(Default)]
+    struct Market {
+        price: Mutex<Decimal>,
+    }
+
+    impl Market {
+        fn set(&self, price: &str) {
+            *self.price.lock().unwrap() = dec(price);
+        }
+    }
+
+    #[async_trait]
+    impl PriceFeed for Market {
+        async fn prices_in_sol(&self, mints: &[String]) -> Result<HashMap<String, Decimal>> {
+            let price = *self.price.lock().unwrap();
+            Ok(mints.iter().map(|mint| (mint.clone(), price)).collect())
+        }
+    }
+
+    #[async_trait]
+    impl Trader for Market {
+        async fn trade(&self, _bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade> {
+            let price = *self.price.lock().unwrap();
+            Ok(BotTrade::Simulated(Box::new(SimulationReport {
+                success: true,
+                error: None,
+                input_mint: String::new(),
+                output_mint: String::new(),
+                sol_amount: req.sol_amount,
+                expected_token_amount: req.sol_amount / price,
+                worst_case_token_amount: req.sol_amount / price,
+                price_per_token: Some(price),
+                price_impact_pct: Decimal::ZERO,
+                network_fee_lamports: 0,
+                priority_fee_lamports: 0,
+                compute_units_consumed: None,
+                logs: vec![],
+            })))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("grid bots place no orders"))
+        }
+    }
+
+    #[test]
+    fn test_crossings() {
+        let mut grid = Grid::new(dec("1"), dec("2"), 5);
+        assert_eq!(grid.levels, [dec("1"), dec("1.25"), dec("1.5"), dec("1.75"), dec("2")]);
+
+        assert_eq!(grid.crossings(dec("1.8"), dec("1.3")), [Fill::Buy(3), Fill::Buy(2)]);
+        // Empty cells don't sell, and nothing fires without a crossing
+        assert!(grid.crossings(dec("1.3"), dec("1.9")).is_empty());
+
+        grid.cells[1] = Some(Holding { tokens: dec("1"), cost_sol: dec("1.25") });
+        assert_eq!(grid.crossings(dec("1.3"), dec("1.5")), [Fill::Sell(1)]);
+        // A cell holding tokens doesn't buy again
+        assert!(grid.crossings(dec("1.3"), dec("1.2")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cycles_profit_and_rebalance() {
+        let config = GridConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_id: Uuid::new_v4(),
+            lower_price: dec("1"),
+            upper_price: dec("2"),
+            levels: 5,
+            sol_per_level: dec("1.25"),
+            on_exit: GridExit::Rebalance,
+            poll_seconds: 30,
+            slippage_tolerance: Some(dec("0")),
+            priority_fee: None,
+            common: CommonSettings { dry_run: true, ..Default::default() },
+        };
+        let market = Arc::new(Market::default());
+        let ctx = BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "grid".to_string(),
+                name: "grid".to_string(),
+                is_active: true,
+                config_json: serde_json::json!({}),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader: market.clone(),
+        };
+        let mut bot = GridBot::new(config, market.clone());
+
+        for price in ["1.6", "1.25", "1.5", "1.25"] {
+            market.set(price);
+            bot.step(&ctx).await.unwrap();
+        }
+        // Bought at 1.5 and 1.25, sold the 1.25 cell at 1.5, bought it again
+        assert_eq!(bot.stats.buys, 3);
+        assert_eq!(bot.stats.sells, 1);
+        assert_eq!(bot.stats.grid_profit_sol, dec("0.25"));
+        assert_eq!(bot.grid.inventory(), (dec("2"), dec("2.5")));
+
+        // Leaving the range sells the inventory at a loss and re-centers
+        market.set("0.9");
+        bot.step(&ctx).await.unwrap();
+        assert_eq!(bot.stats.realized_inventory_pnl_sol, dec("-0.7"));
+        assert_eq!((bot.grid.lower(), bot.grid.upper()), (dec("0.4"), dec("1.4")));
+        assert_eq!(bot.grid.inventory(), (Decimal::ZERO, Decimal::ZERO));
+        assert_eq!(bot.stats.grid_profit_sol, dec("0.25"));
+    }
+}
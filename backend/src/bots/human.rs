@@ -0,0 +1,400 @@
+//! Human bot
+//!
+//! Trades the way a crowd of people would rather than on a timer. Buys
+//! arrive as a Poisson process whose rate follows the time of day, sizes are
+//! log-normal so most are small and a few are large, and every wallet has a
+//! persona that decides how big it trades and how long it holds before
+//! selling. All randomness comes from one seedable RNG, so a seeded bot
+//! makes the same decisions every run.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Timelike, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::f64::consts::PI;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::bots::config::{BotSettings, HumanConfig};
+use crate::bots::{Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+
+/// Activity at the quietest hour, as a fraction of the peak
+const NIGHT_ACTIVITY: f64 = 0.2;
+
+/// Spread of the log-normal trade size; larger means heavier tails
+const SIZE_SIGMA: f64 = 0.8;
+
+/// Smallest trade, as a fraction of the median size
+const MIN_SIZE_FRACTION: f64 = 0.1;
+
+/// How long a failed sell waits before it is tried again
+const SELL_RETRY: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Trading style assigned to each wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Persona {
+    /// Small trades, out within minutes
+    Scalper,
+    Swing,
+    /// Large trades held for a day or two
+    Holder,
+}
+
+impl Persona {
+    fn pick<R: Rng>(rng: &mut R) -> Self {
+        match rng.gen_range(0..10) {
+            0..=3 => Persona::Scalper,
+            4..=7 => Persona::Swing,
+            _ => Persona::Holder,
+        }
+    }
+
+    /// Shortest and longest time a position is held, in minutes
+    fn holding_minutes(&self) -> (i64, i64) {
+        match self {
+            Persona::Scalper => (2, 20),
+            Persona::Swing => (60, 360),
+            Persona::Holder => (720, 2880),
+        }
+    }
+
+    fn size_factor(&self) -> f64 {
+        match self {
+            Persona::Scalper => 0.5,
+            Persona::Swing => 1.0,
+            Persona::Holder => 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Position {
+    sol_amount: Decimal,
+    sell_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Participant {
+    wallet_id: Uuid,
+    persona: Persona,
+    position: Option<Position>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Buy { participant: usize, sol_amount: Decimal },
+    Sell { participant: usize, sol_amount: Decimal },
+    Wait(Duration),
+    Done(String),
+}
+
+pub struct HumanBot {
+    config: HumanConfig,
+    rng: StdRng,
+    participants: Vec<Participant>,
+    next_arrival: Option<DateTime<Utc>>,
+    /// SOL spent on buys and fees
+    spent_sol: Decimal,
+    buys: u64,
+    sells: u64,
+    failed: u64,
+}
+
+impl HumanBot {
+    pub fn new(config: HumanConfig, mut rng: StdRng) -> Self {
+        let participants = config.wallet_ids.iter()
+            .map(|wallet_id| Participant {
+                wallet_id: *wallet_id,
+                persona: Persona::pick(&mut rng),
+                position: None,
+            })
+            .collect();
+
+        Self {
+            config,
+            rng,
+            participants,
+            next_arrival: None,
+            spent_sol: Decimal::ZERO,
+            buys: 0,
+            sells: 0,
+            failed: 0,
+        }
+    }
+
+    pub fn from_config(bot: &BotConfig) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Human(config) => {
+                let rng = match config.seed {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy(),
+                };
+                Ok(Self::new(config, rng))
+            }
+            _ => Err(anyhow!("Bot {} is not a human bot", bot.id)),
+        }
+    }
+
+    fn remaining_sol(&self) -> Decimal {
+        (self.config.total_sol_budget - self.spent_sol).max(Decimal::ZERO)
+    }
+
+    fn min_trade_sol(&self) -> Decimal {
+        (self.config.median_sol_amount * Decimal::from_f64(MIN_SIZE_FRACTION).unwrap_or_default()).round_dp(9)
+    }
+
+    /// Trading activity at a moment, from `NIGHT_ACTIVITY` to 1 at the peak hour
+    fn activity(&self, at: DateTime<Utc>) -> f64 {
+        let hour = at.hour() as f64 + at.minute() as f64 / 60.0;
+        let phase = 2.0 * PI * (hour - self.config.peak_hour_utc as f64) / 24.0;
+        NIGHT_ACTIVITY + (1.0 - NIGHT_ACTIVITY) * (1.0 + phase.cos()) / 2.0
+    }
+
+    /// Next buy of a Poisson process whose rate follows the daily cycle,
+    /// sampled by thinning candidates drawn at the peak rate
+    fn next_arrival_after(&mut self, from: DateTime<Utc>) -> DateTime<Utc> {
+        let peak_per_second = self.config.trades_per_hour.to_f64().unwrap_or(1.0) / 3600.0;
+        let mut at = from;
+        loop {
+            let gap = -(1.0 - self.rng.gen::<f64>()).ln() / peak_per_second;
+            at += chrono::Duration::milliseconds((gap * 1000.0) as i64);
+            if self.rng.gen::<f64>() < self.activity(at) {
+                return at;
+            }
+        }
+    }
+
+    /// Log-normal size around the persona's typical trade, kept between the
+    /// minimum trade and `max_sol_amount`
+    fn trade_size(&mut self, persona: Persona) -> Decimal {
+        // Box-Muller standard normal
+        let (u1, u2) = (1.0 - self.rng.gen::<f64>(), self.rng.gen::<f64>());
+        let normal = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+
+        let median = self.config.median_sol_amount.to_f64().unwrap_or_default() * persona.size_factor();
+        let size = Decimal::from_f64(median * (SIZE_SIGMA * normal).exp()).unwrap_or_default();
+        size.round_dp(9).clamp(self.min_trade_sol(), self.config.max_sol_amount)
+    }
+
+    fn holding_period(&mut self, persona: Persona) -> chrono::Duration {
+        let (min, max) = persona.holding_minutes();
+        chrono::Duration::seconds(self.rng.gen_range(min * 60..=max * 60))
+    }
+
+    fn plan(&mut self, now: DateTime<Utc>) -> Action {
+        let next_arrival = match self.next_arrival {
+            Some(at) => at,
+            None => {
+                let at = self.next_arrival_after(now);
+                self.next_arrival = Some(at);
+                at
+            }
+        };
+
+        let due = self.participants.iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.position.as_ref().map(|pos| (i, pos)))
+            .filter(|(_, pos)| pos.sell_at <= now)
+            .min_by_key(|(_, pos)| pos.sell_at);
+        if let Some((participant, position)) = due {
+            // Sells receive an exact SOL amount; leaving room for slippage keeps
+            // them within the tokens the buy received
+            let slippage = self.config.slippage_tolerance.unwrap_or(Decimal::ONE) / Decimal::from(100);
+            return Action::Sell {
+                participant,
+                sol_amount: (position.sol_amount * (Decimal::ONE - slippage)).round_dp(9),
+            };
+        }
+
+        let next_sell = self.participants.iter().filter_map(|p| p.position.as_ref()).map(|pos| pos.sell_at).min();
+        let until = |at: DateTime<Utc>| Action::Wait((at - now).to_std().unwrap_or_default().max(Duration::from_secs(1)));
+
+        if self.remaining_sol() < self.min_trade_sol() {
+            return match next_sell {
+                Some(at) => until(at),
+                None => Action::Done(format!("Total SOL budget of {} spent", self.config.total_sol_budget)),
+            };
+        }
+
+        if now < next_arrival {
+            return until(next_sell.map_or(next_arrival, |at| at.min(next_arrival)));
+        }
+
+        // The arrival is used up whether or not anyone is free to act on it
+        self.next_arrival = Some(self.next_arrival_after(now));
+        let flat: Vec<usize> = (0..self.participants.len())
+            .filter(|&i| self.participants[i].position.is_none())
+            .collect();
+        if flat.is_empty() {
+            return self.plan(now);
+        }
+
+        let participant = flat[self.rng.gen_range(0..flat.len())];
+        let sol_amount = self.trade_size(self.participants[participant].persona).min(self.remaining_sol());
+        Action::Buy { participant, sol_amount }
+    }
+
+    fn record(&mut self, now: DateTime<Utc>, action: &Action, succeeded: bool, fee_sol: Decimal) {
+        self.spent_sol += match action {
+            Action::Buy { sol_amount, .. } if succeeded => *sol_amount + fee_sol,
+            _ => fee_sol,
+        };
+
+        match action {
+            Action::Buy { participant, sol_amount } if succeeded => {
+                let holding = self.holding_period(self.participants[*participant].persona);
+                self.participants[*participant].position = Some(Position {
+                    sol_amount: *sol_amount,
+                    sell_at: now + holding,
+                });
+                self.buys += 1;
+            }
+            Action::Sell { participant, .. } if succeeded => {
+                self.participants[*participant].position = None;
+                self.sells += 1;
+            }
+            Action::Sell { participant, .. } => {
+                if let Some(position) = self.participants[*participant].position.as_mut() {
+                    position.sell_at = now + SELL_RETRY;
+                }
+                self.failed += 1;
+            }
+            Action::Buy { .. } => self.failed += 1,
+            Action::Wait(_) | Action::Done(_) => {}
+        }
+    }
+
+    fn trade_request(&self, participant: usize, trade_type: &str, sol_amount: Decimal) -> CreateTradeRequest {
+        CreateTradeRequest {
+            wallet_id: self.participants[participant].wallet_id,
+            token_address: self.config.token_address.clone(),
+            trade_type: trade_type.to_string(),
+            sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        }
+    }
+}
+
+#[async_trait]
+impl Bot for HumanBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        let now = Utc::now();
+        let action = self.plan(now);
+        let req = match &action {
+            Action::Buy { participant, sol_amount } => self.trade_request(*participant, "buy", *sol_amount),
+            Action::Sell { participant, sol_amount } => self.trade_request(*participant, "sell", *sol_amount),
+            Action::Wait(delay) => return Ok(Step::Wait(*delay)),
+            Action::Done(reason) => return Ok(Step::Done(reason.clone())),
+        };
+
+        let trade = ctx.trade(&req).await?;
+        if !trade.succeeded() {
+            log::warn!("Human bot {} {} of {} SOL failed", ctx.bot.id, req.trade_type, req.sol_amount);
+        }
+        self.record(now, &action, trade.succeeded(), trade.fee_sol());
+
+        Ok(Step::Wait(Duration::ZERO))
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        let personas: Vec<_> = self.participants.iter()
+            .map(|p| serde_json::json!({"wallet_id": p.wallet_id, "persona": p.persona, "holding": p.position.is_some()}))
+            .collect();
+
+        Some(serde_json::json!({
+            "spent_sol": self.spent_sol,
+            "remaining_sol": self.remaining_sol(),
+            "buys": self.buys,
+            "sells": self.sells,
+            "failed": self.failed,
+            "next_buy_at": self.next_arrival,
+            "wallets": personas,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use chrono::TimeZone;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn bot(seed: u64, wallets: usize, budget: &str) -> HumanBot {
+        let config = HumanConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_ids: (0..wallets).map(|i| Uuid::from_u128(i as u128 + 1)).collect(),
+            median_sol_amount: dec("0.1"),
+            max_sol_amount: dec("1"),
+            trades_per_hour: dec("6"),
+            peak_hour_utc: 15,
+            total_sol_budget: dec(budget),
+            slippage_tolerance: Some(dec("2")),
+            priority_fee: None,
+            seed: Some(seed),
+            common: CommonSettings { dry_run: true, ..Default::default() },
+        };
+        HumanBot::new(config, StdRng::seed_from_u64(seed))
+    }
+
+    /// Run the plan against a simulated clock, filling every trade
+    fn simulate(bot: &mut HumanBot, start: DateTime<Utc>, limit: usize) -> Vec<(DateTime<Utc>, Action)> {
+        let mut now = start;
+        let mut trades = Vec::new();
+        while trades.len() < limit {
+            match bot.plan(now) {
+                Action::Wait(delay) => now += chrono::Duration::from_std(delay).unwrap(),
+                Action::Done(_) => break,
+                action => {
+                    bot.record(now, &action, true, Decimal::ZERO);
+                    trades.push((now, action));
+                }
+            }
+        }
+        trades
+    }
+
+    #[test]
+    fn test_seeded_runs_are_reproducible() {
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let (mut first, mut second) = (bot(42, 4, "1000"), bot(42, 4, "1000"));
+
+        let run = simulate(&mut first, start, 200);
+        assert_eq!(run, simulate(&mut second, start, 200));
+        assert_eq!(first.participants, second.participants);
+        assert_ne!(run, simulate(&mut bot(43, 4, "1000"), start, 200));
+    }
+
+    #[test]
+    fn test_sizes_are_heavy_tailed_and_activity_follows_the_day() {
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let mut bot = bot(7, 50, "100000");
+        let run = simulate(&mut bot, start, 4000);
+
+        let mut sizes: Vec<Decimal> = run.iter()
+            .filter_map(|(_, a)| match a { Action::Buy { sol_amount, .. } => Some(*sol_amount), _ => None })
+            .collect();
+        sizes.sort();
+        assert!(sizes.iter().all(|s| *s >= dec("0.01") && *s <= dec("1")));
+        // Most trades are small, but the largest are many times the median
+        let median = sizes[sizes.len() / 2];
+        assert!(median > dec("0.05") && median < dec("0.2"), "median {}", median);
+        assert!(sizes[sizes.len() * 99 / 100] > median * dec("4"));
+
+        let buys_at = |hours: [u32; 2]| run.iter()
+            .filter(|(at, a)| matches!(a, Action::Buy { .. }) && hours.contains(&at.hour()))
+            .count();
+        assert!(buys_at([14, 15]) > buys_at([2, 3]) * 3);
+    }
+}
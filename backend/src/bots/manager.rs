@@ -0,0 +1,692 @@
+//! Bot supervisor: one Tokio task per active bot
+//!
+//! Each bot runs its strategy's `step` in a loop on its own task. Errors back
+//! off exponentially and eventually park the bot as errored; a panic is caught
+//! at the step boundary and only takes down the bot that raised it. A bot
+//! whose owner is halted by the kill switch stops before its next step.
+//! Runtime state is mirrored to `bot_runtime` so status survives restarts,
+//! and bots still marked active are restored when the server comes back up.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::bots::{Bot, BotContext, BotRegistry, BotState, Step, StopHook, Trader};
+use crate::database::models::{BotConfig, BotRuntimeRecord};
+use crate::trading::kill_switch::{self, TradingHalted};
+
+/// Supervisor settings
+#[derive(Debug, Clone)]
+pub struct ManagerConfig {
+    /// How long `stop` waits for the current step to finish before aborting
+    pub grace_period: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Consecutive failed steps before the bot is parked as errored
+    pub max_consecutive_failures: u32,
+}
+
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(30),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            max_consecutive_failures: 10,
+        }
+    }
+}
+
+impl ManagerConfig {
+    fn backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.initial_backoff.saturating_mul(1 << exponent).min(self.max_backoff)
+    }
+}
+
+/// Live state of a supervised bot
+#[derive(Debug, Clone, Serialize)]
+pub struct BotRuntimeStatus {
+    pub state: BotState,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// Why the bot stopped by itself, e.g. a spent budget
+    pub stop_reason: Option<String>,
+    pub progress: Option<serde_json::Value>,
+}
+
+impl BotRuntimeStatus {
+    fn starting() -> Self {
+        Self {
+            state: BotState::Starting,
+            started_at: Some(Utc::now()),
+            last_heartbeat: None,
+            last_error: None,
+            consecutive_failures: 0,
+            stop_reason: None,
+            progress: None,
+        }
+    }
+}
+
+impl From<BotRuntimeRecord> for BotRuntimeStatus {
+    fn from(record: BotRuntimeRecord) -> Self {
+        Self {
+            state: record.run_state.parse().unwrap_or(BotState::Stopped),
+            started_at: record.started_at,
+            last_heartbeat: record.last_heartbeat,
+            last_error: record.last_error,
+            consecutive_failures: record.consecutive_failures.max(0) as u32,
+            stop_reason: record.stop_reason,
+            progress: record.progress,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Pause,
+    Stop,
+}
+
+struct BotHandle {
+    control: watch::Sender<Command>,
+    status: Arc<RwLock<BotRuntimeStatus>>,
+    task: JoinHandle<()>,
+}
+
+/// Starts, pauses and stops bots and reports their state
+#[derive(Clone)]
+pub struct BotManager {
+    pool: PgPool,
+    trader: Arc<dyn Trader>,
+    registry: BotRegistry,
+    config: ManagerConfig,
+    stop_hooks: Vec<Arc<dyn StopHook>>,
+    bots: Arc<Mutex<HashMap<Uuid, BotHandle>>>,
+}
+
+impl BotManager {
+    pub fn new(pool: PgPool, trader: Arc<dyn Trader>, registry: BotRegistry, config: ManagerConfig) -> Self {
+        Self {
+            pool,
+            trader,
+            registry,
+            config,
+            stop_hooks: Vec::new(),
+            bots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Run `hook` whenever a bot's task ends
+    pub fn with_stop_hook(mut self, hook: Arc<dyn StopHook>) -> Self {
+        self.stop_hooks.push(hook);
+        self
+    }
+
+    /// Relaunch every bot left active by a previous run, keeping paused bots
+    /// paused. Returns how many were restored.
+    pub async fn restore(&self) -> Result<usize> {
+        let bots = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE is_active = true")
+            .fetch_all(&self.pool)
+            .await?;
+        let paused: Vec<Uuid> = sqlx::query_scalar("SELECT bot_id FROM bot_runtime WHERE run_state = $1")
+            .bind(BotState::Paused.as_str())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut restored = 0;
+        for bot in bots {
+            let bot_id = bot.id;
+            match self.launch(bot, paused.contains(&bot_id)).await {
+                Ok(()) => restored += 1,
+                Err(e) => log::error!("Failed to restore bot {}: {}", bot_id, e),
+            }
+        }
+
+        log::info!("Restored {} bot(s)", restored);
+        Ok(restored)
+    }
+
+    pub async fn start(&self, bot: BotConfig) -> Result<()> {
+        self.launch(bot, false).await
+    }
+
+    async fn launch(&self, bot: BotConfig, paused: bool) -> Result<()> {
+        let mut bots = self.bots.lock().await;
+        if bots.get(&bot.id).is_some_and(|handle| !handle.task.is_finished()) {
+            return Err(anyhow!("Bot {} is already running", bot.id));
+        }
+
+        let status = Arc::new(RwLock::new(BotRuntimeStatus::starting()));
+
+        let strategy = match self.registry.build(&bot) {
+            Ok(strategy) => strategy,
+            Err(e) => {
+                let snapshot = {
+                    let mut status = status.write().unwrap();
+                    status.state = BotState::Errored;
+                    status.last_error = Some(e.to_string());
+                    status.clone()
+                };
+                persist(&self.pool, bot.id, &snapshot).await;
+                return Err(e);
+            }
+        };
+
+        let snapshot = status.read().unwrap().clone();
+        persist(&self.pool, bot.id, &snapshot).await;
+        if let Err(e) = sqlx::query("UPDATE bot_configs SET last_run = NOW() WHERE id = $1")
+            .bind(bot.id)
+            .execute(&self.pool)
+            .await
+        {
+            log::warn!("Failed to record start of bot {}: {}", bot.id, e);
+        }
+
+        let (control, receiver) = watch::channel(if paused { Command::Pause } else { Command::Run });
+        let bot_id = bot.id;
+        let task = BotTask {
+            ctx: BotContext {
+                bot,
+                pool: self.pool.clone(),
+                trader: self.trader.clone(),
+            },
+            strategy,
+            control: receiver,
+            status: StatusCell {
+                pool: self.pool.clone(),
+                bot_id,
+                status: status.clone(),
+            },
+            config: self.config.clone(),
+            stop_hooks: self.stop_hooks.clone(),
+        };
+
+        bots.insert(bot_id, BotHandle {
+            control,
+            status,
+            task: tokio::spawn(task.run()),
+        });
+
+        Ok(())
+    }
+
+    /// Suspend a bot between steps
+    pub async fn pause(&self, bot_id: Uuid) -> Result<()> {
+        self.command(bot_id, Command::Pause).await
+    }
+
+    pub async fn resume(&self, bot_id: Uuid) -> Result<()> {
+        self.command(bot_id, Command::Run).await
+    }
+
+    async fn command(&self, bot_id: Uuid, command: Command) -> Result<()> {
+        let bots = self.bots.lock().await;
+        match bots.get(&bot_id) {
+            Some(handle) if !handle.task.is_finished() && handle.status.read().unwrap().state.is_live() => {
+                handle.control.send_replace(command);
+                Ok(())
+            }
+            _ => Err(anyhow!("Bot {} is not running", bot_id)),
+        }
+    }
+
+    /// Stop a bot after its current step, aborting it if that outlasts the
+    /// grace period
+    pub async fn stop(&self, bot_id: Uuid) -> Result<()> {
+        let handle = self.bots.lock().await.remove(&bot_id);
+        if let Some(handle) = handle {
+            self.shut_down(bot_id, handle).await;
+        }
+        Ok(())
+    }
+
+    async fn shut_down(&self, bot_id: Uuid, mut handle: BotHandle) {
+        handle.control.send_replace(Command::Stop);
+
+        if tokio::time::timeout(self.config.grace_period, &mut handle.task).await.is_err() {
+            log::warn!("Bot {} did not stop within {:?}; aborting it", bot_id, self.config.grace_period);
+            handle.task.abort();
+
+            let snapshot = {
+                let mut status = handle.status.write().unwrap();
+                status.state = BotState::Stopped;
+                status.clone()
+            };
+            persist(&self.pool, bot_id, &snapshot).await;
+        }
+    }
+
+    /// Stop every bot for a server shutdown, leaving them active so that
+    /// `restore` picks them up again
+    pub async fn shutdown(&self) {
+        let bots: Vec<(Uuid, BotHandle)> = self.bots.lock().await.drain().collect();
+
+        for (bot_id, handle) in bots {
+            let was_paused = *handle.control.borrow() == Command::Pause;
+            self.shut_down(bot_id, handle).await;
+
+            if was_paused {
+                if let Err(e) = sqlx::query("UPDATE bot_runtime SET run_state = $2 WHERE bot_id = $1")
+                    .bind(bot_id)
+                    .bind(BotState::Paused.as_str())
+                    .execute(&self.pool)
+                    .await
+                {
+                    log::warn!("Failed to keep bot {} paused across restart: {}", bot_id, e);
+                }
+            }
+        }
+    }
+
+    /// Live status of a bot this server is supervising
+    pub async fn status(&self, bot_id: Uuid) -> Option<BotRuntimeStatus> {
+        let bots = self.bots.lock().await;
+        bots.get(&bot_id).map(|handle| handle.status.read().unwrap().clone())
+    }
+
+    /// Live status, falling back to the last state recorded in the database
+    pub async fn status_or_recorded(&self, bot_id: Uuid) -> Result<Option<BotRuntimeStatus>> {
+        if let Some(status) = self.status(bot_id).await {
+            return Ok(Some(status));
+        }
+
+        let record = sqlx::query_as::<_, BotRuntimeRecord>("SELECT * FROM bot_runtime WHERE bot_id = $1")
+            .bind(bot_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record.map(BotRuntimeStatus::from))
+    }
+}
+
+/// Mirror a bot's runtime state to the database. Failures are logged rather
+/// than propagated so a database hiccup never stops a healthy bot.
+async fn persist(pool: &PgPool, bot_id: Uuid, status: &BotRuntimeStatus) {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO bot_runtime (bot_id, run_state, started_at, last_heartbeat, last_error,
+                                 consecutive_failures, stop_reason, progress, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+        ON CONFLICT (bot_id) DO UPDATE SET
+            run_state = EXCLUDED.run_state,
+            started_at = EXCLUDED.started_at,
+            last_heartbeat = EXCLUDED.last_heartbeat,
+            last_error = EXCLUDED.last_error,
+            consecutive_failures = EXCLUDED.consecutive_failures,
+            stop_reason = EXCLUDED.stop_reason,
+            progress = EXCLUDED.progress,
+            updated_at = NOW()
+        "#
+    )
+    .bind(bot_id)
+    .bind(status.state.as_str())
+    .bind(status.started_at)
+    .bind(status.last_heartbeat)
+    .bind(&status.last_error)
+    .bind(status.consecutive_failures as i32)
+    .bind(&status.stop_reason)
+    .bind(&status.progress)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        log::warn!("Failed to record state of bot {}: {}", bot_id, e);
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// A bot's shared status plus its database mirror
+struct StatusCell {
+    pool: PgPool,
+    bot_id: Uuid,
+    status: Arc<RwLock<BotRuntimeStatus>>,
+}
+
+impl StatusCell {
+    async fn update(&self, change: impl FnOnce(&mut BotRuntimeStatus)) {
+        let snapshot = {
+            let mut status = self.status.write().unwrap();
+            change(&mut status);
+            status.clone()
+        };
+        persist(&self.pool, self.bot_id, &snapshot).await;
+    }
+}
+
+/// The supervised loop of a single bot
+struct BotTask {
+    ctx: BotContext,
+    strategy: Box<dyn Bot>,
+    control: watch::Receiver<Command>,
+    status: StatusCell,
+    config: ManagerConfig,
+    stop_hooks: Vec<Arc<dyn StopHook>>,
+}
+
+impl BotTask {
+    async fn run(mut self) {
+        let bot_id = self.ctx.bot.id;
+        let mut failures = 0u32;
+        let mut deactivate = false;
+        let mut panicked = false;
+
+        log::info!("Bot {} ({}) started", bot_id, self.ctx.bot.bot_type);
+
+        let final_state = loop {
+            let command = *self.control.borrow_and_update();
+            match command {
+                Command::Stop => break BotState::Stopped,
+                Command::Pause => {
+                    self.status.update(|s| s.state = BotState::Paused).await;
+                    if self.control.changed().await.is_err() {
+                        break BotState::Stopped;
+                    }
+                    continue;
+                }
+                Command::Run => {}
+            }
+
+            // The engine refuses the bot's trades while a switch is engaged, but
+            // the bot would keep stepping, so it stops here instead
+            match kill_switch::halting(&self.ctx.pool, self.ctx.bot.user_id).await {
+                Ok(Some(switch)) => {
+                    let reason = TradingHalted { switch }.to_string();
+                    log::info!("Bot {} stopped: {}", bot_id, reason);
+                    self.status.update(|s| s.stop_reason = Some(reason)).await;
+                    deactivate = true;
+                    break BotState::Stopped;
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Bot {} could not check the kill switch: {}", bot_id, e),
+            }
+
+            let outcome = AssertUnwindSafe(self.strategy.step(&self.ctx)).catch_unwind().await;
+            let progress = self.strategy.progress();
+
+            let delay = match outcome {
+                Ok(Ok(Step::Wait(delay))) => {
+                    failures = 0;
+                    self.status.update(|s| {
+                        s.state = BotState::Running;
+                        s.last_heartbeat = Some(Utc::now());
+                        s.consecutive_failures = 0;
+                        s.progress = progress;
+                    })
+                    .await;
+                    delay
+                }
+                Ok(Ok(Step::Done(reason))) => {
+                    log::info!("Bot {} finished: {}", bot_id, reason);
+                    self.status.update(|s| {
+                        s.last_heartbeat = Some(Utc::now());
+                        s.stop_reason = Some(reason);
+                        s.progress = progress;
+                    })
+                    .await;
+                    deactivate = true;
+                    break BotState::Stopped;
+                }
+                Ok(Err(e)) => {
+                    failures += 1;
+                    log::warn!("Bot {} step failed ({} in a row): {}", bot_id, failures, e);
+
+                    let errored = failures >= self.config.max_consecutive_failures;
+                    self.status.update(|s| {
+                        s.state = if errored { BotState::Errored } else { BotState::BackingOff };
+                        s.last_error = Some(e.to_string());
+                        s.consecutive_failures = failures;
+                        s.progress = progress;
+                    })
+                    .await;
+
+                    if errored {
+                        deactivate = true;
+                        break BotState::Errored;
+                    }
+                    self.config.backoff(failures)
+                }
+                Err(panic) => {
+                    let message = format!("Bot panicked: {}", panic_message(panic.as_ref()));
+                    log::error!("Bot {}: {}", bot_id, message);
+                    self.status.update(|s| s.last_error = Some(message)).await;
+                    deactivate = true;
+                    panicked = true;
+                    break BotState::Errored;
+                }
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                changed = self.control.changed() => {
+                    if changed.is_err() {
+                        break BotState::Stopped;
+                    }
+                }
+            }
+        };
+
+        // A strategy that panicked may be in any state, so it gets no cleanup
+        if !panicked {
+            match AssertUnwindSafe(self.strategy.on_stop(&self.ctx)).catch_unwind().await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::warn!("Bot {} cleanup failed: {}", bot_id, e),
+                Err(panic) => log::error!("Bot {} panicked during cleanup: {}", bot_id, panic_message(panic.as_ref())),
+            }
+        }
+
+        self.status.update(|s| s.state = final_state).await;
+
+        if deactivate {
+            if let Err(e) = sqlx::query("UPDATE bot_configs SET is_active = false WHERE id = $1")
+                .bind(bot_id)
+                .execute(&self.ctx.pool)
+                .await
+            {
+                log::warn!("Failed to deactivate bot {}: {}", bot_id, e);
+            }
+        }
+
+        log::info!("Bot {} {}", bot_id, final_state);
+
+        for hook in self.stop_hooks {
+            let bot = self.ctx.bot.clone();
+            tokio::spawn(async move { hook.bot_stopped(&bot, final_state).await });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::BotTrade;
+    use crate::database::models::{CreateOrderRequest, CreateTradeRequest, Order};
+    use async_trait::async_trait;
+    use sqlx::postgres::PgPoolOptions;
+
+    struct NoTrades;
+
+    #[async_trait]
+    impl Trader for NoTrades {
+        async fn trade(&self, _bot: &BotConfig, _req: &CreateTradeRequest) -> Result<BotTrade> {
+            Err(anyhow!("trading disabled in tests"))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, _req: &CreateOrderRequest) -> Result<Order> {
+            Err(anyhow!("trading disabled in tests"))
+        }
+    }
+
+    struct Ticker;
+
+    #[async_trait]
+    impl Bot for Ticker {
+        async fn step(&mut self, _ctx: &BotContext) -> Result<Step> {
+            Ok(Step::Wait(Duration::from_millis(5)))
+        }
+    }
+
+    struct Panicker;
+
+    #[async_trait]
+    impl Bot for Panicker {
+        async fn step(&mut self, _ctx: &BotContext) -> Result<Step> {
+            panic!("boom");
+        }
+    }
+
+    struct Failing;
+
+    #[async_trait]
+    impl Bot for Failing {
+        async fn step(&mut self, _ctx: &BotContext) -> Result<Step> {
+            Err(anyhow!("rpc unavailable"))
+        }
+    }
+
+    /// Reports the bots it is told have stopped
+    struct Recorder(tokio::sync::mpsc::UnboundedSender<(Uuid, BotState)>);
+
+    #[async_trait]
+    impl StopHook for Recorder {
+        async fn bot_stopped(&self, bot: &BotConfig, state: BotState) {
+            let _ = self.0.send((bot.id, state));
+        }
+    }
+
+    fn bot(bot_type: &str) -> BotConfig {
+        BotConfig {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            bot_type: bot_type.to_string(),
+            name: bot_type.to_string(),
+            is_active: true,
+            config_json: serde_json::json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_run: None,
+            revision: 1,
+        }
+    }
+
+    fn manager() -> BotManager {
+        // State mirroring fails fast against an unreachable database and is only logged
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(10))
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap();
+        let registry = BotRegistry::new()
+            .register("ticker", |_| Ok(Box::new(Ticker)))
+            .register("panicker", |_| Ok(Box::new(Panicker)))
+            .register("failing", |_| Ok(Box::new(Failing)));
+        let config = ManagerConfig {
+            grace_period: Duration::from_secs(1),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            max_consecutive_failures: 3,
+        };
+
+        BotManager::new(pool, Arc::new(NoTrades), registry, config)
+    }
+
+    async fn wait_for(manager: &BotManager, bot_id: Uuid, state: BotState) -> BotRuntimeStatus {
+        for _ in 0..500 {
+            if let Some(status) = manager.status(bot_id).await {
+                if status.state == state {
+                    return status;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("bot {} never reached {}", bot_id, state);
+    }
+
+    #[tokio::test]
+    async fn test_panic_is_isolated_to_one_bot() {
+        let manager = manager();
+        let healthy = bot("ticker");
+        let broken = bot("panicker");
+        let (healthy_id, broken_id) = (healthy.id, broken.id);
+
+        manager.start(healthy).await.unwrap();
+        manager.start(broken).await.unwrap();
+
+        let status = wait_for(&manager, broken_id, BotState::Errored).await;
+        assert!(status.last_error.unwrap().contains("boom"));
+        let status = wait_for(&manager, healthy_id, BotState::Running).await;
+        assert!(status.last_heartbeat.is_some());
+
+        manager.stop(healthy_id).await.unwrap();
+        assert!(manager.status(healthy_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failures_back_off_then_park_the_bot() {
+        let manager = manager();
+        let failing = bot("failing");
+        let bot_id = failing.id;
+
+        manager.start(failing).await.unwrap();
+
+        let status = wait_for(&manager, bot_id, BotState::Errored).await;
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.last_error.as_deref(), Some("rpc unavailable"));
+        assert!(manager.pause(bot_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let manager = manager();
+        let ticker = bot("ticker");
+        let bot_id = ticker.id;
+
+        manager.start(ticker).await.unwrap();
+        wait_for(&manager, bot_id, BotState::Running).await;
+
+        manager.pause(bot_id).await.unwrap();
+        wait_for(&manager, bot_id, BotState::Paused).await;
+
+        manager.resume(bot_id).await.unwrap();
+        wait_for(&manager, bot_id, BotState::Running).await;
+
+        assert!(manager.start(bot("unknown")).await.is_err());
+        manager.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_stop_hooks_run_once_bot_stops() {
+        let (sender, mut stopped) = tokio::sync::mpsc::unbounded_channel();
+        let manager = manager().with_stop_hook(Arc::new(Recorder(sender)));
+        let ticker = bot("ticker");
+        let bot_id = ticker.id;
+
+        manager.start(ticker).await.unwrap();
+        wait_for(&manager, bot_id, BotState::Running).await;
+        manager.stop(bot_id).await.unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), stopped.recv()).await.unwrap();
+        assert_eq!(event, Some((bot_id, BotState::Stopped)));
+    }
+}
@@ -0,0 +1,423 @@
+//! Trading bots for Cerberus Chain: Hydra
+//! Bot strategies and the supervisor that runs them
+
+pub mod bump;
+pub mod bundle;
+pub mod config;
+pub mod copy;
+pub mod dca;
+pub mod grid;
+pub mod human;
+pub mod manager;
+pub mod revisions;
+pub mod sniper;
+pub mod volume;
+
+pub use config::BotSettings;
+pub use manager::{BotManager, BotRuntimeStatus, ManagerConfig};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rand::Rng;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::database::models::{BotConfig, Bundle, CreateOrderRequest, CreateTradeRequest, Order, Trade};
+use crate::market::{PoolEventSource, PriceFeed, SwapEventSource, TokenFilter};
+use crate::orders::{insert_order, validate_order_request};
+use crate::trading::paper::is_paper_config;
+use crate::trading::simulation::{is_dry_run_config, SimulationReport};
+use crate::trading::executor::lamports_to_sol;
+use crate::trading::{validate_trade_request, BlockEngine, TradeEngine, TradeOptions, TradeStatus};
+
+/// What a bot wants to happen after a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Run again after the delay
+    Wait(Duration),
+    /// The bot has reached its goal or a hard limit and should stop
+    Done(String),
+}
+
+/// A bot strategy. The supervisor calls `step` in a loop, so each call should
+/// do one bounded unit of work and return promptly.
+#[async_trait]
+pub trait Bot: Send {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step>;
+
+    /// Strategy-specific progress reported alongside the runtime state
+    fn progress(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Called once after the bot has stopped for any reason but a panic
+    async fn on_stop(&mut self, _ctx: &BotContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Work to do for a bot once it has stopped, such as tidying up its wallets.
+/// Hooks run in the background so they don't hold up stopping; a bot
+/// aborted after its grace period doesn't get them.
+#[async_trait]
+pub trait StopHook: Send + Sync {
+    async fn bot_stopped(&self, bot: &BotConfig, state: BotState);
+}
+
+/// Result of a trade placed by a bot
+#[derive(Debug, Clone)]
+pub enum BotTrade {
+    Executed(Box<Trade>),
+    /// The bot runs in dry-run mode; nothing was persisted or broadcast
+    Simulated(Box<SimulationReport>),
+}
+
+impl BotTrade {
+    /// Whether the trade went through, or would have outside a dry run
+    pub fn succeeded(&self) -> bool {
+        match self {
+            BotTrade::Executed(trade) => !matches!(
+                trade.lifecycle_status(),
+                Ok(TradeStatus::Failed | TradeStatus::Expired | TradeStatus::Cancelled) | Err(_)
+            ),
+            BotTrade::Simulated(report) => report.success,
+        }
+    }
+
+    /// SOL spent (buys) or received (sells)
+    pub fn sol_amount(&self) -> Decimal {
+        match self {
+            BotTrade::Executed(trade) => trade.sol_amount,
+            BotTrade::Simulated(report) => report.sol_amount,
+        }
+    }
+
+    /// Tokens received (buys) or spent (sells), as filled or expected
+    pub fn token_amount(&self) -> Option<Decimal> {
+        match self {
+            BotTrade::Executed(trade) => trade.token_amount,
+            BotTrade::Simulated(report) => Some(report.expected_token_amount),
+        }
+    }
+
+    /// Network and priority fees paid, in SOL
+    pub fn fee_sol(&self) -> Decimal {
+        match self {
+            BotTrade::Executed(trade) => trade.fee_sol.unwrap_or_default(),
+            BotTrade::Simulated(report) => {
+                lamports_to_sol(report.network_fee_lamports + report.priority_fee_lamports)
+            }
+        }
+    }
+}
+
+/// Result of a bundle placed by a bot
+#[derive(Debug, Clone)]
+pub enum BotBundle {
+    /// Sent to the block engine; poll it with `Trader::refresh_bundle`
+    Submitted { bundle: Box<Bundle>, trades: Vec<Trade> },
+    /// Nothing was sent; the legs record why
+    Rejected(Vec<Trade>),
+    /// Paper or dry-run bots place each leg on its own, with no bundle
+    Legs(Vec<BotTrade>),
+}
+
+/// Where bots send their trades
+#[async_trait]
+pub trait Trader: Send + Sync {
+    async fn trade(&self, bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade>;
+
+    /// Rest a conditional order, such as an exit for a position the bot opened
+    async fn place_order(&self, bot: &BotConfig, req: &CreateOrderRequest) -> Result<Order>;
+
+    /// Place a trade that copies someone else's transaction, recording its signature
+    async fn mirror_trade(&self, bot: &BotConfig, req: &CreateTradeRequest, _source_signature: &str) -> Result<BotTrade> {
+        self.trade(bot, req).await
+    }
+
+    /// Place trades as one atomic bundle, tipped from `tip_wallet_id`
+    async fn trade_bundle(
+        &self,
+        _bot: &BotConfig,
+        _reqs: &[CreateTradeRequest],
+        _tip_wallet_id: Uuid,
+        _tip_sol: Decimal,
+        _engine: &dyn BlockEngine,
+    ) -> Result<BotBundle> {
+        Err(anyhow!("This trader cannot place bundles"))
+    }
+
+    /// Check on a submitted bundle and record its outcome
+    async fn refresh_bundle(&self, _bundle: &Bundle, _engine: &dyn BlockEngine) -> Result<Bundle> {
+        Err(anyhow!("This trader cannot place bundles"))
+    }
+}
+
+#[async_trait]
+impl Trader for TradeEngine {
+    async fn trade(&self, bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade> {
+        place_bot_trade(self, bot, req, None).await
+    }
+
+    async fn mirror_trade(&self, bot: &BotConfig, req: &CreateTradeRequest, source_signature: &str) -> Result<BotTrade> {
+        place_bot_trade(self, bot, req, Some(source_signature.to_string())).await
+    }
+
+    async fn place_order(&self, bot: &BotConfig, req: &CreateOrderRequest) -> Result<Order> {
+        if is_dry_run_config(&bot.config_json) {
+            return Err(anyhow!("Dry-run bots do not place orders"));
+        }
+
+        let order_type = validate_order_request(req)?;
+        let paper = is_paper_config(&bot.config_json) || self.user_paper_mode(bot.user_id).await?;
+        insert_order(self.pool(), bot.user_id, req, order_type, paper).await
+    }
+
+    async fn trade_bundle(
+        &self,
+        bot: &BotConfig,
+        reqs: &[CreateTradeRequest],
+        tip_wallet_id: Uuid,
+        tip_sol: Decimal,
+        engine: &dyn BlockEngine,
+    ) -> Result<BotBundle> {
+        for req in reqs {
+            validate_trade_request(req)?;
+        }
+
+        // Neither dry runs nor the paper ledger have a block engine to bundle through
+        let paper = is_paper_config(&bot.config_json) || self.user_paper_mode(bot.user_id).await?;
+        if paper || is_dry_run_config(&bot.config_json) {
+            let mut legs = Vec::with_capacity(reqs.len());
+            for req in reqs {
+                legs.push(self.trade(bot, req).await?);
+            }
+            return Ok(BotBundle::Legs(legs));
+        }
+
+        let options = TradeOptions {
+            bot_type: Some(bot.bot_type.clone()),
+            bot_config_id: Some(bot.id),
+            bot_config_revision: Some(bot.revision),
+            ..Default::default()
+        };
+        let mut trades = Vec::with_capacity(reqs.len());
+        for req in reqs {
+            trades.push(self.create_trade(bot.user_id, req, &options).await?);
+        }
+
+        let execution = self.execute_bundle(&trades, tip_wallet_id, tip_sol, engine).await?;
+        Ok(match execution.bundle {
+            Some(bundle) => BotBundle::Submitted { bundle: Box::new(bundle), trades: execution.trades },
+            None => BotBundle::Rejected(execution.trades),
+        })
+    }
+
+    async fn refresh_bundle(&self, bundle: &Bundle, engine: &dyn BlockEngine) -> Result<Bundle> {
+        TradeEngine::refresh_bundle(self, bundle, engine).await
+    }
+}
+
+async fn place_bot_trade(
+    engine: &TradeEngine,
+    bot: &BotConfig,
+    req: &CreateTradeRequest,
+    source_signature: Option<String>,
+) -> Result<BotTrade> {
+    validate_trade_request(req)?;
+
+    let options = TradeOptions {
+        bot_type: Some(bot.bot_type.clone()),
+        bot_config_id: Some(bot.id),
+        bot_config_revision: Some(bot.revision),
+        source_signature,
+        paper: is_paper_config(&bot.config_json) || engine.user_paper_mode(bot.user_id).await?,
+        liquidation: false,
+    };
+
+    if is_dry_run_config(&bot.config_json) {
+        let report = engine.dry_run(bot.user_id, req, &options).await?;
+        return Ok(BotTrade::Simulated(Box::new(report)));
+    }
+
+    let trade = engine.create_trade(bot.user_id, req, &options).await?;
+    Ok(BotTrade::Executed(Box::new(engine.execute(&trade).await?)))
+}
+
+/// Everything a running bot can reach
+#[derive(Clone)]
+pub struct BotContext {
+    pub bot: BotConfig,
+    pub pool: PgPool,
+    pub trader: Arc<dyn Trader>,
+}
+
+impl BotContext {
+    pub async fn trade(&self, req: &CreateTradeRequest) -> Result<BotTrade> {
+        self.trader.trade(&self.bot, req).await
+    }
+
+    pub async fn mirror_trade(&self, req: &CreateTradeRequest, source_signature: &str) -> Result<BotTrade> {
+        self.trader.mirror_trade(&self.bot, req, source_signature).await
+    }
+
+    pub async fn place_order(&self, req: &CreateOrderRequest) -> Result<Order> {
+        self.trader.place_order(&self.bot, req).await
+    }
+
+    pub async fn trade_bundle(
+        &self,
+        reqs: &[CreateTradeRequest],
+        tip_wallet_id: Uuid,
+        tip_sol: Decimal,
+        engine: &dyn BlockEngine,
+    ) -> Result<BotBundle> {
+        self.trader.trade_bundle(&self.bot, reqs, tip_wallet_id, tip_sol, engine).await
+    }
+
+    pub async fn refresh_bundle(&self, bundle: &Bundle, engine: &dyn BlockEngine) -> Result<Bundle> {
+        self.trader.refresh_bundle(bundle, engine).await
+    }
+}
+
+/// Builds a bot from its stored configuration
+pub type BotBuilder = Arc<dyn Fn(&BotConfig) -> Result<Box<dyn Bot>> + Send + Sync>;
+
+/// Shared services strategies are built with
+#[derive(Clone)]
+pub struct BotServices {
+    pub pool_events: Arc<dyn PoolEventSource>,
+    pub token_filter: Arc<dyn TokenFilter>,
+    pub swap_events: Arc<dyn SwapEventSource>,
+    pub price_feed: Arc<dyn PriceFeed>,
+    /// Used by bundle bots that don't name a block engine of their own
+    pub block_engine: Arc<dyn BlockEngine>,
+}
+
+/// Strategies available to the supervisor, keyed by `bot_type`
+#[derive(Clone, Default)]
+pub struct BotRegistry {
+    builders: HashMap<String, BotBuilder>,
+}
+
+impl BotRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(mut self, bot_type: &str, builder: F) -> Self
+    where
+        F: Fn(&BotConfig) -> Result<Box<dyn Bot>> + Send + Sync + 'static,
+    {
+        self.builders.insert(bot_type.to_string(), Arc::new(builder));
+        self
+    }
+
+    /// Registry with every strategy that ships with the backend
+    pub fn builtin(services: BotServices) -> Self {
+        Self::new()
+            .register("volume", |bot| Ok(Box::new(volume::VolumeBot::from_config(bot)?)))
+            .register("bump", |bot| Ok(Box::new(bump::BumpBot::from_config(bot)?)))
+            .register("bundle", {
+                let block_engine = services.block_engine.clone();
+                move |bot| Ok(Box::new(bundle::BundleBot::from_config(bot, block_engine.clone())?))
+            })
+            .register("copy", {
+                let swap_events = services.swap_events.clone();
+                let token_filter = services.token_filter.clone();
+                move |bot| Ok(Box::new(copy::CopyBot::from_config(bot, swap_events.clone(), token_filter.clone())?))
+            })
+            .register("dca", {
+                let price_feed = services.price_feed.clone();
+                move |bot| Ok(Box::new(dca::DcaBot::from_config(bot, price_feed.clone())?))
+            })
+            .register("grid", {
+                let price_feed = services.price_feed.clone();
+                move |bot| Ok(Box::new(grid::GridBot::from_config(bot, price_feed.clone())?))
+            })
+            .register("human", |bot| Ok(Box::new(human::HumanBot::from_config(bot)?)))
+            .register("sniper", move |bot| {
+                Ok(Box::new(sniper::SniperBot::from_config(
+                    bot,
+                    services.pool_events.clone(),
+                    services.token_filter.clone(),
+                )?))
+            })
+    }
+
+    pub fn build(&self, config: &BotConfig) -> Result<Box<dyn Bot>> {
+        let builder = self.builders
+            .get(&config.bot_type)
+            .ok_or_else(|| anyhow!("No strategy is available for bot type {}", config.bot_type))?;
+        builder(config)
+    }
+}
+
+/// Uniformly random SOL amount in `[min, max]`, rounded to whole lamports
+pub fn random_amount<R: Rng>(rng: &mut R, min: Decimal, max: Decimal) -> Decimal {
+    if max <= min {
+        return min;
+    }
+    let fraction = Decimal::from_f64(rng.gen::<f64>()).unwrap_or_default();
+    (min + (max - min) * fraction).round_dp(9).clamp(min, max)
+}
+
+/// Lifecycle state of a supervised bot, stored in `bot_runtime.run_state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotState {
+    Starting,
+    Running,
+    Paused,
+    BackingOff,
+    Errored,
+    Stopped,
+}
+
+impl BotState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BotState::Starting => "starting",
+            BotState::Running => "running",
+            BotState::Paused => "paused",
+            BotState::BackingOff => "backing_off",
+            BotState::Errored => "errored",
+            BotState::Stopped => "stopped",
+        }
+    }
+
+    /// Whether the bot's task is still alive
+    pub fn is_live(&self) -> bool {
+        !matches!(self, BotState::Errored | BotState::Stopped)
+    }
+}
+
+impl fmt::Display for BotState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BotState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "starting" => Ok(BotState::Starting),
+            "running" => Ok(BotState::Running),
+            "paused" => Ok(BotState::Paused),
+            "backing_off" => Ok(BotState::BackingOff),
+            "errored" => Ok(BotState::Errored),
+            "stopped" => Ok(BotState::Stopped),
+            other => Err(anyhow!("Unknown bot state: {}", other)),
+        }
+    }
+}
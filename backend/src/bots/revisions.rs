@@ -0,0 +1,230 @@
+//! Bot configuration history
+//!
+//! Every change to a bot's name or `config_json` bumps `bot_configs.revision`
+//! and writes an immutable row to `bot_config_revisions` in the same
+//! transaction. Rolling back copies an old revision forward as a new one, so
+//! history is only ever appended to.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::database::models::{BotConfig, BotConfigRevision};
+
+/// Kind of change at one path of a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// One changed value. Objects are compared key by key; arrays and scalars
+/// are replaced as a whole.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    /// JSON Pointer to the changed value
+    pub path: String,
+    pub op: ChangeOp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Value>,
+}
+
+/// Changes that turn `old` into `new`
+pub fn diff(old: &Value, new: &Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    diff_at("", old, new, &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}/{}", path, escape_pointer(key));
+                match new.get(key) {
+                    Some(new_value) => diff_at(&child, old_value, new_value, changes),
+                    None => changes.push(ConfigChange {
+                        path: child,
+                        op: ChangeOp::Remove,
+                        from: Some(old_value.clone()),
+                        to: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                changes.push(ConfigChange {
+                    path: format!("{}/{}", path, escape_pointer(key)),
+                    op: ChangeOp::Add,
+                    from: None,
+                    to: Some(new_value.clone()),
+                });
+            }
+        }
+        (old, new) if old != new => changes.push(ConfigChange {
+            path: path.to_string(),
+            op: ChangeOp::Replace,
+            from: Some(old.clone()),
+            to: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Create a bot configuration together with its first revision
+pub async fn create_config(
+    pool: &PgPool,
+    user_id: Uuid,
+    bot_type: &str,
+    name: &str,
+    config: &Value,
+) -> Result<BotConfig> {
+    let mut tx = pool.begin().await?;
+
+    let bot = sqlx::query_as::<_, BotConfig>(
+        r#"
+        INSERT INTO bot_configs (id, user_id, bot_type, name, config_json)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(bot_type)
+    .bind(name)
+    .bind(config)
+    .fetch_one(&mut tx)
+    .await?;
+
+    insert_revision(&mut tx, &bot, user_id, &[], None).await?;
+    tx.commit().await?;
+
+    Ok(bot)
+}
+
+/// Apply a change to a bot's name and/or configuration as a new revision.
+/// A change that leaves both as they were records nothing.
+pub async fn update_config(
+    pool: &PgPool,
+    bot_id: Uuid,
+    changed_by: Uuid,
+    name: Option<&str>,
+    config: Option<&Value>,
+    rolled_back_from: Option<i32>,
+) -> Result<BotConfig> {
+    let mut tx = pool.begin().await?;
+
+    let current = sqlx::query_as::<_, BotConfig>("SELECT * FROM bot_configs WHERE id = $1 FOR UPDATE")
+        .bind(bot_id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or_else(|| anyhow!("Bot configuration {} not found", bot_id))?;
+
+    let name = name.unwrap_or(&current.name);
+    let config = config.unwrap_or(&current.config_json);
+    let changes = diff(&current.config_json, config);
+    if name == current.name && changes.is_empty() && rolled_back_from.is_none() {
+        return Ok(current);
+    }
+
+    let bot = sqlx::query_as::<_, BotConfig>(
+        r#"
+        UPDATE bot_configs SET name = $2, config_json = $3, revision = revision + 1
+        WHERE id = $1
+        RETURNING *
+        "#
+    )
+    .bind(bot_id)
+    .bind(name)
+    .bind(config)
+    .fetch_one(&mut tx)
+    .await?;
+
+    insert_revision(&mut tx, &bot, changed_by, &changes, rolled_back_from).await?;
+    tx.commit().await?;
+
+    Ok(bot)
+}
+
+async fn insert_revision(
+    tx: &mut Transaction<'_, Postgres>,
+    bot: &BotConfig,
+    changed_by: Uuid,
+    changes: &[ConfigChange],
+    rolled_back_from: Option<i32>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO bot_config_revisions (id, bot_id, revision, name, config_json, diff, changed_by, rolled_back_from)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(bot.id)
+    .bind(bot.revision)
+    .bind(&bot.name)
+    .bind(&bot.config_json)
+    .bind(serde_json::to_value(changes)?)
+    .bind(changed_by)
+    .bind(rolled_back_from)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+/// A bot's revisions, newest first
+pub async fn list_revisions(pool: &PgPool, bot_id: Uuid) -> Result<Vec<BotConfigRevision>> {
+    let revisions = sqlx::query_as::<_, BotConfigRevision>(
+        "SELECT * FROM bot_config_revisions WHERE bot_id = $1 ORDER BY revision DESC"
+    )
+    .bind(bot_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(revisions)
+}
+
+pub async fn find_revision(pool: &PgPool, bot_id: Uuid, revision: i32) -> Result<Option<BotConfigRevision>> {
+    let revision = sqlx::query_as::<_, BotConfigRevision>(
+        "SELECT * FROM bot_config_revisions WHERE bot_id = $1 AND revision = $2"
+    )
+    .bind(bot_id)
+    .bind(revision)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(revision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_reports_leaf_changes() {
+        let old = json!({"min_sol_amount": 0.1, "wallet_ids": ["a"], "limits": {"per_hour": 1, "a/b": 2}});
+        let new = json!({"min_sol_amount": 0.2, "wallet_ids": ["a", "b"], "limits": {"per_hour": 1}, "paper": true});
+
+        let changes = diff(&old, &new);
+        let paths: Vec<(&str, ChangeOp)> = changes.iter().map(|c| (c.path.as_str(), c.op)).collect();
+
+        assert_eq!(paths, vec![
+            ("/limits/a~1b", ChangeOp::Remove),
+            ("/min_sol_amount", ChangeOp::Replace),
+            ("/wallet_ids", ChangeOp::Replace),
+            ("/paper", ChangeOp::Add),
+        ]);
+        assert!(diff(&new, &new).is_empty());
+    }
+}
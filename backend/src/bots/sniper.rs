@@ -0,0 +1,415 @@
+//! Sniper bot
+//!
+//! Waits for pools to be created for the watched mints, or by the watched
+//! creators, and buys from every configured wallet as soon as liquidity
+//! appears. Each token is security-checked first and considered only once.
+//! Filled buys get take-profit and stop-loss orders attached, which the order
+//! monitor then watches like any other conditional order.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bots::config::{BotSettings, SniperConfig};
+use crate::bots::{Bot, BotContext, BotTrade, Step};
+use crate::database::models::{BotConfig, CreateOrderRequest, CreateTradeRequest, Trade};
+use crate::market::events::PoolEventStream;
+use crate::market::{PoolCreated, PoolEventSource, TokenFilter, TokenVerdict};
+use crate::orders::OrderType;
+
+/// How long one step waits for a pool event before yielding to the supervisor
+const EVENT_WAIT: Duration = Duration::from_secs(5);
+
+/// Counters reported as the bot's progress
+#[derive(Debug, Clone, Default, Serialize)]
+struct SniperStats {
+    pools_seen: u64,
+    matched: u64,
+    rejected: u64,
+    buys: u64,
+    failed_buys: u64,
+    exits_placed: u64,
+    last_rejection: Option<String>,
+}
+
+pub struct SniperBot {
+    config: SniperConfig,
+    pool_events: Arc<dyn PoolEventSource>,
+    token_filter: Arc<dyn TokenFilter>,
+    events: Option<PoolEventStream>,
+    event_wait: Duration,
+    /// Mints already sniped or rejected
+    handled: HashSet<String>,
+    stats: SniperStats,
+}
+
+impl SniperBot {
+    pub fn new(
+        config: SniperConfig,
+        pool_events: Arc<dyn PoolEventSource>,
+        token_filter: Arc<dyn TokenFilter>,
+    ) -> Self {
+        Self {
+            config,
+            pool_events,
+            token_filter,
+            events: None,
+            event_wait: EVENT_WAIT,
+            handled: HashSet::new(),
+            stats: SniperStats::default(),
+        }
+    }
+
+    pub fn from_config(
+        bot: &BotConfig,
+        pool_events: Arc<dyn PoolEventSource>,
+        token_filter: Arc<dyn TokenFilter>,
+    ) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Sniper(config) => Ok(Self::new(config, pool_events, token_filter)),
+            _ => Err(anyhow!("Bot {} is not a sniper bot", bot.id)),
+        }
+    }
+
+    fn is_target(&self, event: &PoolCreated) -> bool {
+        self.config.watch_mints.contains(&event.token_mint) || self.config.watch_creators.contains(&event.creator)
+    }
+
+    /// Only creator watches can produce new targets once every mint is handled
+    fn finished(&self) -> bool {
+        self.config.watch_creators.is_empty()
+            && self.config.watch_mints.iter().all(|mint| self.handled.contains(mint))
+    }
+
+    async fn snipe(&mut self, ctx: &BotContext, event: &PoolCreated) {
+        let verdict = match self.token_filter.check(&event.token_mint, &self.config.security).await {
+            Ok(verdict) => verdict,
+            // Fail closed: a token we couldn't check is not bought
+            Err(e) => TokenVerdict::from_reasons(vec![format!("Security check failed: {}", e)]),
+        };
+        if !verdict.passed {
+            let reasons = verdict.reasons.join("; ");
+            log::info!("Sniper {} skipped {}: {}", ctx.bot.id, event.token_mint, reasons);
+            self.stats.rejected += 1;
+            self.stats.last_rejection = Some(format!("{}: {}", event.token_mint, reasons));
+            return;
+        }
+        if !verdict.warnings.is_empty() {
+            log::info!("Sniper {} buying {} despite: {}", ctx.bot.id, event.token_mint, verdict.warnings.join("; "));
+        }
+
+        let requests: Vec<CreateTradeRequest> = self.config.wallet_ids.iter()
+            .map(|wallet_id| CreateTradeRequest {
+                wallet_id: *wallet_id,
+                token_address: event.token_mint.clone(),
+                trade_type: "buy".to_string(),
+                sol_amount: self.config.sol_amount,
+                slippage_tolerance: Some(self.config.max_slippage_percent),
+                priority_fee: self.config.priority_fee,
+            })
+            .collect();
+        let results = join_all(requests.iter().map(|req| ctx.trade(req))).await;
+
+        for (req, result) in requests.iter().zip(results) {
+            match result {
+                Ok(trade) if trade.succeeded() => {
+                    self.stats.buys += 1;
+                    if let BotTrade::Executed(trade) = trade {
+                        self.attach_exits(ctx, &trade).await;
+                    }
+                }
+                Ok(_) => {
+                    self.stats.failed_buys += 1;
+                    log::warn!("Sniper {} buy of {} from wallet {} failed", ctx.bot.id, event.token_mint, req.wallet_id);
+                }
+                Err(e) => {
+                    self.stats.failed_buys += 1;
+                    log::warn!("Sniper {} could not buy {} from wallet {}: {}", ctx.bot.id, event.token_mint, req.wallet_id, e);
+                }
+            }
+        }
+    }
+
+    /// Place take-profit and stop-loss sells around the entry price. Sells
+    /// receive an exact SOL amount, so each exit asks for what the position
+    /// is worth at its trigger.
+    async fn attach_exits(&mut self, ctx: &BotContext, trade: &Trade) {
+        let entry = match trade.price_per_token {
+            Some(price) => price,
+            None => {
+                log::warn!("Sniper {} has no entry price for trade {}; no exits placed", ctx.bot.id, trade.id);
+                return;
+            }
+        };
+
+        let hundred = Decimal::from(100);
+        let exits = [
+            (OrderType::TakeProfit, self.config.take_profit_percent.map(|pct| Decimal::ONE + pct / hundred)),
+            (OrderType::StopLoss, self.config.stop_loss_percent.map(|pct| Decimal::ONE - pct / hundred)),
+        ];
+
+        for (order_type, factor) in exits {
+            let factor = match factor {
+                Some(factor) if factor > Decimal::ZERO => factor,
+                _ => continue,
+            };
+            let req = CreateOrderRequest {
+                wallet_id: trade.wallet_id,
+                token_address: trade.token_address.clone(),
+                order_type: order_type.as_str().to_string(),
+                sol_amount: (trade.sol_amount * factor).round_dp(9),
+                trigger_price: Some(entry * factor),
+                trail_percent: None,
+                slippage_tolerance: Some(self.config.max_slippage_percent),
+                priority_fee: self.config.priority_fee,
+            };
+
+            match ctx.place_order(&req).await {
+                Ok(_) => self.stats.exits_placed += 1,
+                Err(e) => log::error!("Sniper {} could not place {} for trade {}: {}", ctx.bot.id, order_type, trade.id, e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Bot for SniperBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        if self.finished() {
+            return Ok(Step::Done("Every watched mint has been handled".to_string()));
+        }
+
+        let mut events = match self.events.take() {
+            Some(events) => events,
+            None => self.pool_events.subscribe().await?,
+        };
+
+        // A failed or closed stream is dropped so the next step resubscribes
+        let event = match tokio::time::timeout(self.event_wait, events.next()).await {
+            Err(_) => {
+                self.events = Some(events);
+                return Ok(Step::Wait(Duration::ZERO));
+            }
+            Ok(Some(Ok(event))) => {
+                self.events = Some(events);
+                event
+            }
+            Ok(Some(Err(e))) => return Err(e),
+            Ok(None) => return Err(anyhow!("Pool event stream closed")),
+        };
+
+        self.stats.pools_seen += 1;
+        if self.is_target(&event) && self.handled.insert(event.token_mint.clone()) {
+            self.stats.matched += 1;
+            self.snipe(ctx, &event).await;
+        }
+
+        Ok(Step::Wait(Duration::ZERO))
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.stats).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use crate::database::models::Order;
+    use crate::bots::Trader;
+    use crate::market::security::{evaluate, TokenFacts};
+    use crate::market::{ChannelPoolEvents, RiskReport, SecurityPolicy};
+    use crate::trading::TradeStatus;
+    use chrono::Utc;
+    use sqlx::postgres::PgPoolOptions;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    const TARGET: &str = "TargetMint";
+    const RISKY: &str = "RiskyMint";
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    struct RejectRisky;
+
+    #[async_trait]
+    impl TokenFilter for RejectRisky {
+        async fn assess(&self, mint: &str, policy: &SecurityPolicy) -> Result<RiskReport> {
+            let facts = TokenFacts { mint_authority: mint == RISKY, ..TokenFacts::default() };
+            Ok(evaluate(mint, facts, policy))
+        }
+    }
+
+    /// Fills every buy at 0.001 SOL per token
+    #[derive(Default)]
+    struct FillingTrader {
+        trades: Mutex<Vec<CreateTradeRequest>>,
+        orders: Mutex<Vec<CreateOrderRequest>>,
+    }
+
+    #[async_trait]
+    impl Trader for FillingTrader {
+        async fn trade(&self, bot: &BotConfig, req: &CreateTradeRequest) -> Result<BotTrade> {
+            self.trades.lock().unwrap().push(req.clone());
+            Ok(BotTrade::Executed(Box::new(Trade {
+                id: Uuid::new_v4(),
+                user_id: bot.user_id,
+                wallet_id: req.wallet_id,
+                token_address: req.token_address.clone(),
+                token_symbol: None,
+                trade_type: req.trade_type.clone(),
+                sol_amount: req.sol_amount,
+                token_amount: Some(req.sol_amount / dec("0.001")),
+                price_per_token: Some(dec("0.001")),
+                slippage_tolerance: req.slippage_tolerance,
+                priority_fee: req.priority_fee,
+                transaction_hash: None,
+                signature: Some("sig".to_string()),
+                status: TradeStatus::Submitted.as_str().to_string(),
+                error_message: None,
+                bot_type: Some(bot.bot_type.clone()),
+                created_at: Utc::now(),
+                simulated_at: None,
+                executed_at: Some(Utc::now()),
+                confirmed_at: None,
+                finalized_at: None,
+                last_valid_block_height: None,
+                submit_attempts: 1,
+                fee_sol: Some(dec("0.000005")),
+                is_paper: false,
+                is_liquidation: false,
+                bot_config_id: Some(bot.id),
+                bot_config_revision: Some(bot.revision),
+                bundle_id: None,
+                source_signature: None,
+            })))
+        }
+
+        async fn place_order(&self, _bot: &BotConfig, req: &CreateOrderRequest) -> Result<Order> {
+            self.orders.lock().unwrap().push(req.clone());
+            Err(anyhow!("orders are only recorded in tests"))
+        }
+    }
+
+    fn pool(mint: &str, creator: &str) -> PoolCreated {
+        PoolCreated {
+            pool_address: format!("{}Pool", mint),
+            token_mint: mint.to_string(),
+            creator: creator.to_string(),
+            signature: "sig".to_string(),
+            slot: 1,
+        }
+    }
+
+    fn context(trader: Arc<FillingTrader>) -> BotContext {
+        BotContext {
+            bot: BotConfig {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                bot_type: "sniper".to_string(),
+                name: "sniper".to_string(),
+                is_active: true,
+                config_json: serde_json::json!({}),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_run: None,
+                revision: 1,
+            },
+            pool: PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap(),
+            trader,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snipes_watched_mint_from_event_stream() {
+        let config = SniperConfig {
+            wallet_ids: vec![Uuid::new_v4(), Uuid::new_v4()],
+            watch_mints: vec![TARGET.to_string(), RISKY.to_string()],
+            watch_creators: vec![],
+            sol_amount: dec("0.5"),
+            max_slippage_percent: dec("15"),
+            priority_fee: Some(dec("0.001")),
+            take_profit_percent: Some(dec("100")),
+            stop_loss_percent: Some(dec("30")),
+            security: SecurityPolicy::default(),
+            common: CommonSettings::default(),
+        };
+        let events = ChannelPoolEvents::new();
+        let trader = Arc::new(FillingTrader::default());
+        let ctx = context(trader.clone());
+
+        let mut bot = SniperBot::new(config, Arc::new(events.clone()), Arc::new(RejectRisky));
+        bot.event_wait = Duration::from_millis(20);
+
+        // The first step subscribes and finds nothing yet
+        assert_eq!(bot.step(&ctx).await.unwrap(), Step::Wait(Duration::ZERO));
+
+        events.publish(pool("OtherMint", "someone"));
+        events.publish(pool(TARGET, "someone"));
+        events.publish(pool(TARGET, "someone"));
+        events.publish(pool(RISKY, "someone"));
+        for _ in 0..4 {
+            bot.step(&ctx).await.unwrap();
+        }
+
+        let trades = trader.trades.lock().unwrap().clone();
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().all(|t| t.token_address == TARGET && t.trade_type == "buy"));
+        assert!(trades.iter().all(|t| t.slippage_tolerance == Some(dec("15")) && t.priority_fee == Some(dec("0.001"))));
+
+        let orders = trader.orders.lock().unwrap().clone();
+        assert_eq!(orders.len(), 4);
+        let take_profit = orders.iter().find(|o| o.order_type == "take_profit").unwrap();
+        assert_eq!(take_profit.trigger_price, Some(dec("0.002")));
+        assert_eq!(take_profit.sol_amount, dec("1"));
+        let stop_loss = orders.iter().find(|o| o.order_type == "stop_loss").unwrap();
+        assert_eq!(stop_loss.trigger_price, Some(dec("0.0007")));
+        assert_eq!(stop_loss.sol_amount, dec("0.35"));
+
+        assert_eq!(bot.stats.pools_seen, 4);
+        assert_eq!(bot.stats.rejected, 1);
+        assert!(matches!(bot.step(&ctx).await.unwrap(), Step::Done(_)));
+    }
+
+    #[tokio::test]
+    async fn test_resubscribes_after_stream_drops() {
+        let events = ChannelPoolEvents::new();
+        let config = SniperConfig {
+            wallet_ids: vec![Uuid::new_v4()],
+            watch_mints: vec![],
+            watch_creators: vec!["dev".to_string()],
+            sol_amount: dec("0.1"),
+            max_slippage_percent: dec("10"),
+            priority_fee: None,
+            take_profit_percent: None,
+            stop_loss_percent: None,
+            security: SecurityPolicy::default(),
+            common: CommonSettings::default(),
+        };
+        let trader = Arc::new(FillingTrader::default());
+        let ctx = context(trader.clone());
+        let mut bot = SniperBot::new(config, Arc::new(events.clone()), Arc::new(RejectRisky));
+        bot.event_wait = Duration::from_millis(20);
+
+        bot.step(&ctx).await.unwrap();
+        events.disconnect();
+        assert!(bot.step(&ctx).await.is_err());
+
+        bot.step(&ctx).await.unwrap();
+        events.publish(pool("FreshMint", "dev"));
+        bot.step(&ctx).await.unwrap();
+
+        assert_eq!(trader.trades.lock().unwrap().len(), 1);
+        assert!(trader.orders.lock().unwrap().is_empty());
+    }
+}
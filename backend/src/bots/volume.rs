@@ -0,0 +1,347 @@
+//! Volume bot
+//!
+//! Rotates through the configured wallets placing randomized buys and sells
+//! of one token. A wallet sells back what it bought before it buys again and
+//! buys and sells alternate, so the bot stays roughly inventory-neutral while
+//! it generates volume. A holding that keeps failing to sell is abandoned.
+//! Spend is capped by a lifetime budget and an optional rolling hourly limit.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::bots::config::{BotSettings, VolumeConfig};
+use crate::bots::{random_amount, Bot, BotContext, Step};
+use crate::database::models::{BotConfig, CreateTradeRequest};
+
+/// Sells retried before a holding is abandoned
+const MAX_SELL_ATTEMPTS: u32 = 3;
+
+/// SOL bought by a wallet and not yet sold back
+#[derive(Debug, Clone, PartialEq)]
+struct Holding {
+    wallet_id: Uuid,
+    sol_amount: Decimal,
+    sell_attempts: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Buy { wallet_index: usize, sol_amount: Decimal },
+    /// Sell the oldest holding
+    Sell { wallet_id: Uuid, sol_amount: Decimal },
+    Wait(Duration),
+    Done(String),
+}
+
+pub struct VolumeBot {
+    config: VolumeConfig,
+    rng: StdRng,
+    /// Wallet the next buy starts looking from
+    next_wallet: usize,
+    holdings: VecDeque<Holding>,
+    /// SOL spent on buys and fees
+    spent_sol: Decimal,
+    /// Spend within the last hour, oldest first
+    recent_spend: VecDeque<(DateTime<Utc>, Decimal)>,
+    last_was_buy: bool,
+    buys: u64,
+    sells: u64,
+    failed: u64,
+    abandoned: u64,
+}
+
+impl VolumeBot {
+    pub fn new(config: VolumeConfig, rng: StdRng) -> Self {
+        Self {
+            config,
+            rng,
+            next_wallet: 0,
+            holdings: VecDeque::new(),
+            spent_sol: Decimal::ZERO,
+            recent_spend: VecDeque::new(),
+            last_was_buy: false,
+            buys: 0,
+            sells: 0,
+            failed: 0,
+            abandoned: 0,
+        }
+    }
+
+    pub fn from_config(bot: &BotConfig) -> Result<Self> {
+        match BotSettings::parse(&bot.bot_type, &bot.config_json)? {
+            BotSettings::Volume(config) => Ok(Self::new(config, StdRng::from_entropy())),
+            _ => Err(anyhow!("Bot {} is not a volume bot", bot.id)),
+        }
+    }
+
+    fn remaining_sol(&self) -> Decimal {
+        (self.config.total_sol_budget - self.spent_sol).max(Decimal::ZERO)
+    }
+
+    fn spent_last_hour(&self) -> Decimal {
+        self.recent_spend.iter().map(|(_, amount)| *amount).sum()
+    }
+
+    fn plan(&mut self, now: DateTime<Utc>) -> Action {
+        let hour_ago = now - chrono::Duration::hours(1);
+        while self.recent_spend.front().is_some_and(|(at, _)| *at <= hour_ago) {
+            self.recent_spend.pop_front();
+        }
+
+        if self.last_was_buy && !self.holdings.is_empty() {
+            return self.plan_sell();
+        }
+
+        // Anything that blocks a buy is a chance to unwind inventory first
+        match self.plan_buy(now) {
+            Action::Buy { wallet_index, sol_amount } => Action::Buy { wallet_index, sol_amount },
+            _ if !self.holdings.is_empty() => self.plan_sell(),
+            blocked => blocked,
+        }
+    }
+
+    fn plan_buy(&mut self, now: DateTime<Utc>) -> Action {
+        let wallets = &self.config.wallet_ids;
+        let free = (0..wallets.len())
+            .map(|offset| (self.next_wallet + offset) % wallets.len())
+            .find(|&index| !self.holdings.iter().any(|h| h.wallet_id == wallets[index]));
+        let wallet_index = match free {
+            Some(index) => index,
+            None => return Action::Wait(self.next_interval()),
+        };
+
+        let sol_amount = random_amount(&mut self.rng, self.config.min_sol_amount, self.config.max_sol_amount)
+            .min(self.remaining_sol());
+        if sol_amount < self.config.min_sol_amount {
+            return Action::Done(format!("Total SOL budget of {} spent", self.config.total_sol_budget));
+        }
+
+        if let Some(hourly) = self.config.max_sol_per_hour {
+            if self.spent_last_hour() + sol_amount > hourly {
+                let resumes = self.recent_spend.front()
+                    .map(|(at, _)| *at + chrono::Duration::hours(1) - now)
+                    .and_then(|wait| wait.to_std().ok())
+                    .unwrap_or_default();
+                return Action::Wait(resumes.max(Duration::from_secs(1)));
+            }
+        }
+
+        Action::Buy { wallet_index, sol_amount }
+    }
+
+    fn plan_sell(&self) -> Action {
+        let holding = &self.holdings[0];
+        // Sells receive an exact SOL amount; leaving room for slippage keeps
+        // them within the tokens the matching buy received
+        let slippage = self.config.slippage_tolerance.unwrap_or(Decimal::ONE) / Decimal::from(100);
+        Action::Sell {
+            wallet_id: holding.wallet_id,
+            sol_amount: (holding.sol_amount * (Decimal::ONE - slippage)).round_dp(9),
+        }
+    }
+
+    fn record(&mut self, now: DateTime<Utc>, action: &Action, succeeded: bool, fee_sol: Decimal) {
+        let spent = match action {
+            Action::Buy { sol_amount, .. } if succeeded => *sol_amount + fee_sol,
+            _ => fee_sol,
+        };
+        self.spent_sol += spent;
+        if spent > Decimal::ZERO {
+            self.recent_spend.push_back((now, spent));
+        }
+
+        if !succeeded {
+            self.failed += 1;
+            if matches!(action, Action::Sell { .. }) {
+                self.sell_failed();
+            }
+            return;
+        }
+
+        match action {
+            Action::Buy { wallet_index, sol_amount } => {
+                self.holdings.push_back(Holding {
+                    wallet_id: self.config.wallet_ids[*wallet_index],
+                    sol_amount: *sol_amount,
+                    sell_attempts: 0,
+                });
+                self.next_wallet = wallet_index + 1;
+                self.last_was_buy = true;
+                self.buys += 1;
+            }
+            Action::Sell { .. } => {
+                self.holdings.pop_front();
+                self.last_was_buy = false;
+                self.sells += 1;
+            }
+            Action::Wait(_) | Action::Done(_) => {}
+        }
+    }
+
+    /// Count a failed sell of the oldest holding, giving up on it after
+    /// `MAX_SELL_ATTEMPTS` so one unsellable holding can't stall the bot
+    fn sell_failed(&mut self) {
+        let Some(holding) = self.holdings.front_mut() else {
+            return;
+        };
+        holding.sell_attempts += 1;
+        if holding.sell_attempts < MAX_SELL_ATTEMPTS {
+            return;
+        }
+
+        log::warn!("Volume bot gave up selling {} SOL of holdings from {}", holding.sol_amount, holding.wallet_id);
+        self.holdings.pop_front();
+        self.last_was_buy = false;
+        self.abandoned += 1;
+    }
+
+    fn next_interval(&mut self) -> Duration {
+        Duration::from_secs(self.rng.gen_range(self.config.min_interval_seconds..=self.config.max_interval_seconds))
+    }
+
+    fn trade_request(&self, wallet_id: Uuid, trade_type: &str, sol_amount: Decimal) -> CreateTradeRequest {
+        CreateTradeRequest {
+            wallet_id,
+            token_address: self.config.token_address.clone(),
+            trade_type: trade_type.to_string(),
+            sol_amount,
+            slippage_tolerance: self.config.slippage_tolerance,
+            priority_fee: self.config.priority_fee,
+        }
+    }
+}
+
+#[async_trait]
+impl Bot for VolumeBot {
+    async fn step(&mut self, ctx: &BotContext) -> Result<Step> {
+        let now = Utc::now();
+        let action = self.plan(now);
+        let req = match &action {
+            Action::Buy { wallet_index, sol_amount } => {
+                self.trade_request(self.config.wallet_ids[*wallet_index], "buy", *sol_amount)
+            }
+            Action::Sell { wallet_id, sol_amount } => self.trade_request(*wallet_id, "sell", *sol_amount),
+            Action::Wait(delay) => return Ok(Step::Wait(*delay)),
+            Action::Done(reason) => return Ok(Step::Done(reason.clone())),
+        };
+
+        let trade = ctx.trade(&req).await?;
+        if !trade.succeeded() {
+            log::warn!("Volume bot {} {} of {} SOL failed", ctx.bot.id, req.trade_type, req.sol_amount);
+        }
+        self.record(now, &action, trade.succeeded(), trade.fee_sol());
+
+        Ok(Step::Wait(self.next_interval()))
+    }
+
+    fn progress(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "spent_sol": self.spent_sol,
+            "remaining_sol": self.remaining_sol(),
+            "spent_last_hour_sol": self.spent_last_hour(),
+            "buys": self.buys,
+            "sells": self.sells,
+            "failed": self.failed,
+            "abandoned": self.abandoned,
+            "open_positions": self.holdings.len(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::config::CommonSettings;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn bot(wallets: usize, budget: &str, hourly: Option<&str>) -> VolumeBot {
+        let config = VolumeConfig {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            wallet_ids: (0..wallets).map(|_| Uuid::new_v4()).collect(),
+            min_sol_amount: dec("0.1"),
+            max_sol_amount: dec("0.2"),
+            min_interval_seconds: 10,
+            max_interval_seconds: 20,
+            total_sol_budget: dec(budget),
+            max_sol_per_hour: hourly.map(dec),
+            slippage_tolerance: Some(dec("2")),
+            priority_fee: None,
+            common: CommonSettings::default(),
+        };
+        VolumeBot::new(config, StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn test_wallets_rotate_and_sides_alternate() {
+        let mut bot = bot(2, "10", None);
+        let wallets = bot.config.wallet_ids.clone();
+        let now = Utc::now();
+        let mut sides = Vec::new();
+
+        for _ in 0..6 {
+            let action = bot.plan(now);
+            let (side, wallet, sol_amount) = match &action {
+                Action::Buy { wallet_index, sol_amount } => ("buy", *wallet_index, *sol_amount),
+                Action::Sell { wallet_id, sol_amount } => {
+                    ("sell", wallets.iter().position(|w| w == wallet_id).unwrap(), *sol_amount)
+                }
+                other => panic!("unexpected {:?}", other),
+            };
+            assert!(sol_amount >= dec("0.098") && sol_amount <= dec("0.2"));
+            sides.push((side, wallet));
+            bot.record(now, &action, true, Decimal::ZERO);
+        }
+
+        assert_eq!(sides, [("buy", 0), ("sell", 0), ("buy", 1), ("sell", 1), ("buy", 0), ("sell", 0)]);
+        assert!(bot.holdings.is_empty());
+    }
+
+    #[test]
+    fn test_budget_and_hourly_cap_are_respected() {
+        let mut capped = bot(3, "10", Some("0.3"));
+        let now = Utc::now();
+        capped.record(now, &Action::Buy { wallet_index: 0, sol_amount: dec("0.2") }, true, Decimal::ZERO);
+        capped.record(now, &Action::Sell { wallet_id: capped.config.wallet_ids[0], sol_amount: dec("0.19") }, true, Decimal::ZERO);
+        capped.record(now, &Action::Buy { wallet_index: 1, sol_amount: dec("0.05") }, false, dec("0.01"));
+        assert!(matches!(capped.plan(now), Action::Wait(wait) if wait > Duration::from_secs(3000)));
+        assert!(matches!(capped.plan(now + chrono::Duration::hours(1)), Action::Buy { .. }));
+
+        let mut spent = bot(1, "0.25", None);
+        spent.record(now, &Action::Buy { wallet_index: 0, sol_amount: dec("0.2") }, true, dec("0.01"));
+        // Inventory is unwound before the bot reports its budget spent
+        assert!(matches!(spent.plan(now), Action::Sell { .. }));
+        let sell = spent.plan(now);
+        spent.record(now, &sell, true, dec("0.01"));
+        assert!(matches!(spent.plan(now), Action::Done(_)));
+        assert_eq!(spent.remaining_sol(), dec("0.03"));
+    }
+
+    #[test]
+    fn test_unsellable_holding_is_abandoned() {
+        let mut bot = bot(1, "10", None);
+        let now = Utc::now();
+        bot.record(now, &Action::Buy { wallet_index: 0, sol_amount: dec("0.2") }, true, Decimal::ZERO);
+
+        for attempt in 1..=MAX_SELL_ATTEMPTS {
+            let sell = bot.plan(now);
+            assert!(matches!(sell, Action::Sell { .. }), "attempt {} was {:?}", attempt, sell);
+            bot.record(now, &sell, false, dec("0.001"));
+        }
+
+        // The only wallet is free to buy again
+        assert!(bot.holdings.is_empty());
+        assert_eq!(bot.abandoned, 1);
+        assert!(matches!(bot.plan(now), Action::Buy { wallet_index: 0, .. }));
+    }
+}
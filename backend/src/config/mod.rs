@@ -1,6 +1,7 @@
 //! Configuration management
 
 use std::env;
+use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -9,7 +10,19 @@ pub struct Config {
     pub jwt_expiration_hours: u64,
     pub bind_address: String,
     pub helius_api_key: Option<String>,
+    pub solana_rpc_url: String,
+    pub solana_ws_url: Option<String>,
+    pub block_engine_url: String,
+    pub jupiter_api_url: String,
+    pub jupiter_price_api_url: String,
+    pub encryption_key: Option<String>,
     pub environment: String,
+    /// Users allowed to engage and release the global kill switch
+    pub operator_user_ids: Vec<Uuid>,
+    /// Hours before a new withdrawal address can be withdrawn to
+    pub withdrawal_address_delay_hours: u64,
+    /// Lifetime of step-up tokens
+    pub step_up_ttl_minutes: u64,
 }
 
 impl Config {
@@ -26,11 +39,60 @@ impl Config {
             bind_address: env::var("BIND_ADDRESS")
                 .unwrap_or_else(|_| "0.0.0.0:8080".to_string()),
             helius_api_key: env::var("HELIUS_API_KEY").ok(),
+            solana_rpc_url: env::var("SOLANA_RPC_URL")
+                .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
+            solana_ws_url: env::var("SOLANA_WS_URL").ok(),
+            block_engine_url: env::var("BLOCK_ENGINE_URL")
+                .unwrap_or_else(|_| "https://mainnet.block-engine.jito.wtf".to_string()),
+            jupiter_api_url: env::var("JUPITER_API_URL")
+                .unwrap_or_else(|_| "https://quote-api.jup.ag/v6".to_string()),
+            jupiter_price_api_url: env::var("JUPITER_PRICE_API_URL")
+                .unwrap_or_else(|_| "https://api.jup.ag/price/v2".to_string()),
+            encryption_key: env::var("ENCRYPTION_KEY").ok(),
             environment: env::var("ENVIRONMENT")
                 .unwrap_or_else(|_| "development".to_string()),
+            operator_user_ids: env::var("OPERATOR_USER_IDS")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|id| Uuid::parse_str(id.trim()).ok())
+                .collect(),
+            withdrawal_address_delay_hours: env::var("WITHDRAWAL_ADDRESS_DELAY_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .unwrap_or(24),
+            step_up_ttl_minutes: env::var("STEP_UP_TTL_MINUTES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
         }
     }
     
+    /// RPC endpoint to use, preferring Helius when an API key is configured
+    pub fn rpc_url(&self) -> String {
+        match &self.helius_api_key {
+            Some(key) => format!("https://mainnet.helius-rpc.com/?api-key={}", key),
+            None => self.solana_rpc_url.clone(),
+        }
+    }
+
+    /// Websocket endpoint for subscriptions, by default the RPC endpoint over ws(s)
+    pub fn ws_url(&self) -> String {
+        match &self.solana_ws_url {
+            Some(url) => url.clone(),
+            None => {
+                let rpc_url = self.rpc_url();
+                match rpc_url.strip_prefix("https://") {
+                    Some(rest) => format!("wss://{}", rest),
+                    None => rpc_url.replacen("http://", "ws://", 1),
+                }
+            }
+        }
+    }
+
+    pub fn is_operator(&self, user_id: Uuid) -> bool {
+        self.operator_user_ids.contains(&user_id)
+    }
+
     pub fn is_production(&self) -> bool {
         self.environment == "production"
     }
@@ -0,0 +1,80 @@
+//! Audit trail of security-sensitive actions, written to `audit_logs`
+
+use actix_web::HttpRequest;
+use anyhow::Result;
+use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
+use uuid::Uuid;
+
+/// One row of the audit trail
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub user_id: Option<Uuid>,
+    pub action: String,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<Uuid>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    pub fn new(user_id: Option<Uuid>, action: &str) -> Self {
+        Self {
+            user_id,
+            action: action.to_string(),
+            resource_type: None,
+            resource_id: None,
+            ip_address: None,
+            user_agent: None,
+            details: None,
+        }
+    }
+
+    pub fn resource(mut self, resource_type: &str, resource_id: Uuid) -> Self {
+        self.resource_type = Some(resource_type.to_string());
+        self.resource_id = Some(resource_id);
+        self
+    }
+
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Record where the request came from
+    pub fn client(mut self, req: &HttpRequest) -> Self {
+        let info = req.connection_info();
+        self.ip_address = info.realip_remote_addr().and_then(|addr| {
+            addr.parse::<SocketAddr>()
+                .map(|socket| socket.ip())
+                .or_else(|_| addr.parse::<IpAddr>())
+                .ok()
+        });
+        self.user_agent = req
+            .headers()
+            .get("User-Agent")
+            .and_then(|agent| agent.to_str().ok())
+            .map(str::to_string);
+        self
+    }
+}
+
+pub async fn record(pool: &PgPool, entry: &AuditEntry) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_logs (user_id, action, resource_type, resource_id, ip_address, user_agent, details)
+        VALUES ($1, $2, $3, $4, $5::inet, $6, $7)
+        "#
+    )
+    .bind(entry.user_id)
+    .bind(&entry.action)
+    .bind(&entry.resource_type)
+    .bind(entry.resource_id)
+    .bind(entry.ip_address.map(|ip| ip.to_string()))
+    .bind(&entry.user_agent)
+    .bind(&entry.details)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
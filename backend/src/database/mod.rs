@@ -1,6 +1,7 @@
 //! Database module for Cerberus Chain: Hydra
 //! Handles all database operations, connections, and migrations
 
+pub mod audit;
 pub mod connection;
 pub mod models;
 
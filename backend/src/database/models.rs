@@ -20,6 +20,7 @@ pub struct User {
     pub is_verified: bool,
     pub failed_login_attempts: i32,
     pub locked_until: Option<DateTime<Utc>>,
+    pub paper_trading: bool,
 }
 
 /// User creation request
@@ -127,6 +128,107 @@ pub struct TokenHolding {
     pub last_updated: DateTime<Utc>,
 }
 
+/// SOL moved between two of a user's wallets
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Transfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub batch_id: Uuid,
+    pub kind: String,
+    pub from_wallet_id: Option<Uuid>,
+    pub to_wallet_id: Option<Uuid>,
+    pub from_address: String,
+    pub to_address: String,
+    pub sol_amount: Decimal,
+    pub signature: Option<String>,
+    pub last_valid_block_height: Option<i64>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Wallet funded by a distribution; weights default to 1, so leaving them
+/// all out splits the total equally
+#[derive(Debug, Clone, Deserialize)]
+pub struct DistributionTarget {
+    pub wallet_id: Uuid,
+    pub weight: Option<Decimal>,
+}
+
+/// Fan SOL out from a funding wallet
+#[derive(Debug, Clone, Deserialize)]
+pub struct DistributeRequest {
+    pub from_wallet_id: Uuid,
+    pub total_sol: Decimal,
+    pub targets: Vec<DistributionTarget>,
+}
+
+/// Collect SOL back into one wallet; from every other active wallet of the
+/// user when none are named
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepRequest {
+    pub to_wallet_id: Uuid,
+    #[serde(default)]
+    pub from_wallet_ids: Vec<Uuid>,
+    /// SOL left in each swept wallet on top of its rent-exempt minimum
+    pub leave_sol: Option<Decimal>,
+}
+
+/// External address a user may withdraw to once `active_from` has passed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WithdrawalAddress {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub address: String,
+    pub label: Option<String>,
+    pub active_from: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+/// SOL or SPL tokens sent from a wallet to an allowlisted address
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Withdrawal {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Option<Uuid>,
+    pub address_id: Option<Uuid>,
+    pub from_address: String,
+    pub to_address: String,
+    /// Mint withdrawn; SOL when empty
+    pub token_address: Option<String>,
+    pub amount: Decimal,
+    pub signature: Option<String>,
+    pub last_valid_block_height: Option<i64>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Add an address to the withdrawal allowlist
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddWithdrawalAddressRequest {
+    pub address: String,
+    pub label: Option<String>,
+}
+
+/// Withdraw SOL, or the token `token_address` when set, to an allowlisted address
+#[derive(Debug, Clone, Deserialize)]
+pub struct WithdrawRequest {
+    pub wallet_id: Uuid,
+    pub to_address: String,
+    pub token_address: Option<String>,
+    pub amount: Decimal,
+}
+
+/// Password re-entered to unlock a sensitive action
+#[derive(Debug, Deserialize)]
+pub struct StepUpRequest {
+    pub password: String,
+}
+
 /// Trade model for transaction history
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Trade {
@@ -147,12 +249,118 @@ pub struct Trade {
     pub error_message: Option<String>,
     pub bot_type: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub simulated_at: Option<DateTime<Utc>>,
     pub executed_at: Option<DateTime<Utc>>,
     pub confirmed_at: Option<DateTime<Utc>>,
+    pub finalized_at: Option<DateTime<Utc>>,
+    pub last_valid_block_height: Option<i64>,
+    pub submit_attempts: i32,
+    pub fee_sol: Option<Decimal>,
+    pub is_paper: bool,
+    /// Panic-sell liquidation, allowed through an engaged kill switch
+    pub is_liquidation: bool,
+    /// Bot configuration and revision the trade was placed under
+    pub bot_config_id: Option<Uuid>,
+    pub bot_config_revision: Option<i32>,
+    /// Bundle the trade was submitted in, for multi-wallet bundle legs
+    pub bundle_id: Option<Uuid>,
+    /// Transaction a copy-trading bot mirrored with this trade
+    pub source_signature: Option<String>,
 }
 
-/// Trade creation request
+/// Trades submitted together through a block engine
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Bundle {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub bot_id: Option<Uuid>,
+    /// Id the block engine assigned on submission
+    pub engine_bundle_id: String,
+    pub status: String,
+    pub tip_wallet_id: Option<Uuid>,
+    pub tip_sol: Decimal,
+    pub tip_signature: Option<String>,
+    pub landed_slot: Option<i64>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Trade history filters, sorting and pagination
+#[derive(Debug, Default, Deserialize)]
+pub struct TradeListParams {
+    pub wallet_id: Option<Uuid>,
+    pub token_address: Option<String>,
+    pub trade_type: Option<String>,
+    pub status: Option<String>,
+    pub bot_type: Option<String>,
+    pub is_paper: Option<bool>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `created_at` (default) or `sol_amount`
+    pub sort_by: Option<String>,
+    /// `desc` (default) or `asc`
+    pub order: Option<String>,
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    /// `next_cursor` from the previous page; takes precedence over `page`
+    pub cursor: Option<String>,
+}
+
+/// Trade export options
 #[derive(Debug, Deserialize)]
+pub struct TradeExportParams {
+    /// `csv` (default) or `json`
+    pub format: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Cost method for realized PnL: `fifo` (default) or `average_cost`
+    pub method: Option<String>,
+    pub paper: Option<bool>,
+}
+
+/// Query options for trade creation
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateTradeParams {
+    /// Build and simulate only; nothing is persisted or broadcast
+    pub dry_run: Option<bool>,
+}
+
+/// Panic-sell request; sells from every active wallet of the user when
+/// no wallets are named
+#[derive(Debug, Clone, Deserialize)]
+pub struct PanicSellRequest {
+    #[serde(default)]
+    pub wallet_ids: Vec<Uuid>,
+}
+
+/// Background panic-sell run; `report` is set once it completes
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PanicSellJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_ids: Vec<Uuid>,
+    pub status: String,
+    pub report: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Trade status transition record
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TradeStatusEvent {
+    pub id: Uuid,
+    pub trade_id: Uuid,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub signature: Option<String>,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Trade creation request
+#[derive(Debug, Clone, Deserialize)]
 pub struct CreateTradeRequest {
     pub wallet_id: Uuid,
     pub token_address: String,
@@ -162,6 +370,160 @@ pub struct CreateTradeRequest {
     pub priority_fee: Option<Decimal>,
 }
 
+/// Resting conditional order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Order {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub order_type: String,
+    pub sol_amount: Decimal,
+    pub trigger_price: Option<Decimal>,
+    pub trail_percent: Option<Decimal>,
+    pub peak_price: Option<Decimal>,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+    pub is_paper: bool,
+    pub status: String,
+    pub trade_id: Option<Uuid>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub triggered_at: Option<DateTime<Utc>>,
+}
+
+/// Order creation request
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateOrderRequest {
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub order_type: String,
+    pub sol_amount: Decimal,
+    pub trigger_price: Option<Decimal>,
+    pub trail_percent: Option<Decimal>,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+}
+
+/// Order amendment request; omitted fields are left unchanged
+#[derive(Debug, Deserialize)]
+pub struct AmendOrderRequest {
+    pub sol_amount: Option<Decimal>,
+    pub trigger_price: Option<Decimal>,
+    pub trail_percent: Option<Decimal>,
+    pub slippage_tolerance: Option<Decimal>,
+    pub priority_fee: Option<Decimal>,
+}
+
+/// Order listing filters
+#[derive(Debug, Deserialize)]
+pub struct OrderListParams {
+    pub status: Option<String>,
+    pub token_address: Option<String>,
+}
+
+/// Virtual balance held by a wallet in paper-trading mode
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaperBalance {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub balance: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Paper balance funding request
+#[derive(Debug, Deserialize)]
+pub struct FundPaperWalletRequest {
+    pub sol_amount: Decimal,
+}
+
+/// Paper-trading mode toggle
+#[derive(Debug, Deserialize)]
+pub struct PaperModeRequest {
+    pub enabled: bool,
+}
+
+/// Position listing options
+#[derive(Debug, Deserialize)]
+pub struct PositionParams {
+    pub wallet_id: Option<Uuid>,
+    /// `fifo` (default) or `average_cost`
+    pub method: Option<String>,
+    /// Report paper positions instead of live ones; defaults to the user's mode
+    pub paper: Option<bool>,
+    pub include_closed: Option<bool>,
+}
+
+/// PnL report window and options
+#[derive(Debug, Deserialize)]
+pub struct PnlParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub wallet_id: Option<Uuid>,
+    pub method: Option<String>,
+    pub paper: Option<bool>,
+}
+
+/// Trade limits for a user, or for one of their wallets or bots
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RiskLimit {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Option<Uuid>,
+    pub bot_config_id: Option<Uuid>,
+    pub max_sol_per_trade: Option<Decimal>,
+    pub daily_loss_limit_sol: Option<Decimal>,
+    pub max_token_exposure_sol: Option<Decimal>,
+    pub max_trades_per_minute: Option<i32>,
+    pub min_sol_reserve: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Risk limit request; names at most one of a wallet or a bot, otherwise
+/// the limits apply to all of the user's trades. Omitted limits are unset.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetRiskLimitRequest {
+    pub wallet_id: Option<Uuid>,
+    pub bot_config_id: Option<Uuid>,
+    pub max_sol_per_trade: Option<Decimal>,
+    pub daily_loss_limit_sol: Option<Decimal>,
+    pub max_token_exposure_sol: Option<Decimal>,
+    pub max_trades_per_minute: Option<i32>,
+    pub min_sol_reserve: Option<Decimal>,
+}
+
+/// Emergency halt of trading for one user, or for everyone when `user_id`
+/// is empty. Engaged until `released_at` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct KillSwitch {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub engaged_by: Option<Uuid>,
+    pub engaged_at: DateTime<Utc>,
+    pub released_by: Option<Uuid>,
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+/// Kill switch request; `global` halts every user and needs an operator
+#[derive(Debug, Clone, Deserialize)]
+pub struct KillSwitchRequest {
+    #[serde(default)]
+    pub global: bool,
+    pub reason: Option<String>,
+}
+
+/// Token risk report options
+#[derive(Debug, Deserialize)]
+pub struct TokenRiskParams {
+    /// Apply this bot's security rules instead of the defaults
+    pub bot_id: Option<Uuid>,
+}
+
 /// Bot configuration model
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct BotConfig {
@@ -174,6 +536,44 @@ pub struct BotConfig {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_run: Option<DateTime<Utc>>,
+    pub revision: i32,
+}
+
+/// Immutable snapshot of a bot configuration, written on every change
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BotConfigRevision {
+    pub id: Uuid,
+    pub bot_id: Uuid,
+    pub revision: i32,
+    pub name: String,
+    pub config_json: serde_json::Value,
+    /// Changes from the previous revision, see `bots::revisions::ConfigChange`
+    pub diff: serde_json::Value,
+    pub changed_by: Option<Uuid>,
+    /// Set when this revision restored an earlier one
+    pub rolled_back_from: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Revisions to compare; `to` defaults to the current revision
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffParams {
+    pub from: i32,
+    pub to: Option<i32>,
+}
+
+/// Last recorded runtime state of a supervised bot
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BotRuntimeRecord {
+    pub bot_id: Uuid,
+    pub run_state: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: i32,
+    pub stop_reason: Option<String>,
+    pub progress: Option<serde_json::Value>,
+    pub updated_at: DateTime<Utc>,
 }
 
 /// Bot configuration request
@@ -184,6 +584,13 @@ pub struct CreateBotConfigRequest {
     pub config: serde_json::Value,
 }
 
+/// Bot configuration update; omitted fields are left unchanged
+#[derive(Debug, Deserialize)]
+pub struct UpdateBotConfigRequest {
+    pub name: Option<String>,
+    pub config: Option<serde_json::Value>,
+}
+
 /// JWT claims structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -194,6 +601,17 @@ pub struct Claims {
     pub session_id: String,
 }
 
+/// Claims of a short-lived step-up token, granted for one purpose within
+/// the session that re-authenticated
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepUpClaims {
+    pub sub: String,
+    pub session_id: String,
+    pub purpose: String,
+    pub exp: usize,
+    pub iat: usize,
+}
+
 /// API response wrapper
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
@@ -256,4 +674,7 @@ pub struct PaginatedResponse<T> {
     pub limit: u32,
     pub total: u64,
     pub total_pages: u32,
+    /// Keyset cursor for the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+//! Cerberus Chain: Hydra backend library
+//! API handlers, trading engine, bots and wallet services on Postgres
+
+pub mod api;
+pub mod auth;
+pub mod bots;
+pub mod config;
+pub mod database;
+pub mod market;
+pub mod orders;
+pub mod positions;
+pub mod services;
+pub mod trading;
+pub mod utils;
+pub mod wallet;
@@ -1,13 +1,20 @@
 //! Cerberus Chain: Hydra Backend - SQLite Version
 //! No network dependencies, perfect for local development
 
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use actix_web::{web, App, HttpResponse, HttpServer, middleware::Logger};
 use actix_cors::Cors;
 use dotenv::dotenv;
 use std::env;
 use std::fs;
 use std::path::Path;
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use sqlx::{PgPool, SqlitePool, postgres::PgPoolOptions, sqlite::SqlitePoolOptions};
+
+use cerberus_hydra_backend::api;
+use cerberus_hydra_backend::auth::middleware::AuthMiddleware;
+use cerberus_hydra_backend::config::Config;
+use cerberus_hydra_backend::database::models::ApiResponse;
+use cerberus_hydra_backend::services::TradingServices;
+use cerberus_hydra_backend::trading::kill_switch;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -91,6 +98,38 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    // Trading runs on the Postgres trading database: this server builds the
+    // trade engine, confirms trades, fires orders, runs the bots and serves
+    // the trading API, kill switch included
+    let trading = match env::var("TRADING_DATABASE_URL") {
+        Ok(url) => {
+            let pool = match PgPoolOptions::new().max_connections(10).connect_lazy(&url) {
+                Ok(pool) => pool,
+                Err(e) => {
+                    log::error!("❌ Invalid TRADING_DATABASE_URL: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let services = match TradingServices::build(pool, Config::from_env()) {
+                Ok(services) => services,
+                Err(e) => {
+                    log::error!("❌ Failed to set up trading: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            match services.start().await {
+                Ok(_) => log::info!("✅ Trading: confirmer, order monitor and bots running"),
+                Err(e) => log::error!("❌ Failed to restore bots: {}", e),
+            }
+            Some(services)
+        }
+        Err(_) => {
+            log::warn!("⚠️ TRADING_DATABASE_URL is not set; trading and the kill switch are unavailable");
+            None
+        }
+    };
+    let trading_pool = trading.as_ref().map(|services| services.pool.clone());
+
     let bind_address = env::var("BIND_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
     
     log::info!("🚀 Server starting on {}", bind_address);
@@ -108,13 +147,30 @@ async fn main() -> std::io::Result<()> {
             .supports_credentials()
             .max_age(3600);
 
-        App::new()
-            .app_data(web::Data::new(db_pool.clone()))
-            .wrap(cors)
+        let mut app = App::new()
+            .app_data(web::Data::new(db_pool.clone()));
+        if let Some(pool) = &trading_pool {
+            app = app.app_data(web::Data::new(pool.clone()));
+        }
+
+        app.wrap(cors)
             .wrap(Logger::default())
             .route("/", web::get().to(health_check_handler))
             .route("/health", web::get().to(health_check_handler))
             .route("/api/status", web::get().to(api_status_handler))
+            .configure(|cfg| match &trading {
+                Some(services) => {
+                    cfg.service(
+                        web::scope("")
+                            .wrap(AuthMiddleware::new(services.config.jwt_secret.clone()))
+                            .configure(|cfg| services.configure(cfg))
+                            .configure(api::configure_routes)
+                    );
+                }
+                None => {
+                    cfg.service(web::scope("/api/kill-switch").default_service(web::to(kill_switch_unavailable)));
+                }
+            })
     })
     .bind(&bind_address)?
     .run()
@@ -208,7 +264,10 @@ async fn create_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
-async fn health_check_handler(pool: web::Data<SqlitePool>) -> actix_web::Result<impl actix_web::Responder> {
+async fn health_check_handler(
+    pool: web::Data<SqlitePool>,
+    trading_pool: Option<web::Data<PgPool>>,
+) -> actix_web::Result<impl actix_web::Responder> {
     // Test database connection
     let db_healthy = sqlx::query("SELECT 1")
         .fetch_one(pool.get_ref())
@@ -237,15 +296,17 @@ async fn health_check_handler(pool: web::Data<SqlitePool>) -> actix_web::Result<
             "volume": "ready", 
             "security": "ready"
         },
+        "kill_switch": kill_switch_status(trading_pool.as_ref().map(|pool| pool.get_ref())).await,
         "message": "Local SQLite setup - no network dependencies!"
     })))
 }
 
-async fn api_status_handler() -> actix_web::Result<impl actix_web::Responder> {
+async fn api_status_handler(trading_pool: Option<web::Data<PgPool>>) -> actix_web::Result<impl actix_web::Responder> {
     Ok(web::Json(serde_json::json!({
         "backend": "healthy",
         "database": "sqlite",
         "mode": "local_development",
+        "kill_switch": kill_switch_status(trading_pool.as_ref().map(|pool| pool.get_ref())).await,
         "features": {
             "user_registration": true,
             "wallet_management": true,
@@ -254,4 +315,27 @@ async fn api_status_handler() -> actix_web::Result<impl actix_web::Responder> {
         },
         "message": "Ready for local development!"
     })))
-}
\ No newline at end of file
+}
+
+/// Engaged kill switches: whether trading is halted globally and how many
+/// users are halted individually
+async fn kill_switch_status(trading_pool: Option<&PgPool>) -> serde_json::Value {
+    let unknown = serde_json::json!({ "engaged": null, "halted_users": null });
+    let Some(pool) = trading_pool else {
+        return unknown;
+    };
+
+    match kill_switch::overview(pool).await {
+        Ok(overview) => serde_json::json!(overview),
+        Err(e) => {
+            log::error!("❌ Failed to read kill switches: {}", e);
+            unknown
+        }
+    }
+}
+
+async fn kill_switch_unavailable() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(
+        ApiResponse::<()>::error("Kill switch unavailable: TRADING_DATABASE_URL is not set".to_string())
+    )
+}
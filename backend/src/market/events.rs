@@ -0,0 +1,236 @@
+//! Pool creation events
+//!
+//! Bots that react to new liquidity read `PoolCreated` events from a
+//! `PoolEventSource`. The RPC source watches AMM program logs over a
+//! websocket and resolves each new pool's mints from the transaction that
+//! created it; the channel source is fed by hand, for tests and local runs.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::sync::{Arc, Mutex};
+
+use crate::trading::jupiter::SOL_MINT;
+
+/// Raydium AMM v4 program
+pub const RAYDIUM_AMM_V4: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+
+/// Account positions in Raydium's `initialize2` instruction
+const INIT_AMM: usize = 4;
+const INIT_COIN_MINT: usize = 8;
+const INIT_PC_MINT: usize = 9;
+const INIT_CREATOR: usize = 17;
+
+/// Liquidity appeared for a token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolCreated {
+    pub pool_address: String,
+    /// The non-SOL side of the pool
+    pub token_mint: String,
+    /// Wallet that created the pool
+    pub creator: String,
+    pub signature: String,
+    pub slot: u64,
+}
+
+pub type PoolEventStream = BoxStream<'static, Result<PoolCreated>>;
+
+/// Source of new-pool events
+#[async_trait]
+pub trait PoolEventSource: Send + Sync {
+    /// Subscribe to pools created from now on. The stream ends when the
+    /// subscription drops; callers subscribe again.
+    async fn subscribe(&self) -> Result<PoolEventStream>;
+}
+
+/// Pools published by hand to every subscriber
+#[derive(Clone, Default)]
+pub struct ChannelPoolEvents {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<Result<PoolCreated>>>>>,
+}
+
+impl ChannelPoolEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deliver an event to every current subscriber
+    pub fn publish(&self, event: PoolCreated) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers.retain(|tx| tx.unbounded_send(Ok(event.clone())).is_ok());
+    }
+
+    /// End every open subscription
+    pub fn disconnect(&self) {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+#[async_trait]
+impl PoolEventSource for ChannelPoolEvents {
+    async fn subscribe(&self) -> Result<PoolEventStream> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+        Ok(rx.boxed())
+    }
+}
+
+/// Raydium pools seen through an RPC websocket log subscription
+pub struct RpcPoolEvents {
+    ws_url: String,
+    rpc: Arc<RpcClient>,
+}
+
+impl RpcPoolEvents {
+    /// Events buffered ahead of a slow subscriber
+    const BUFFER: usize = 64;
+
+    pub fn new(ws_url: String, rpc: Arc<RpcClient>) -> Self {
+        Self { ws_url, rpc }
+    }
+
+    async fn resolve(rpc: &RpcClient, signature: &str, slot: u64) -> Result<Option<PoolCreated>> {
+        let transaction: Value = rpc
+            .send(
+                RpcRequest::GetTransaction,
+                json!([signature, {
+                    "encoding": "json",
+                    "commitment": "confirmed",
+                    "maxSupportedTransactionVersion": 0,
+                }]),
+            )
+            .await?;
+
+        Ok(parse_pool_creation(&transaction, signature, slot))
+    }
+}
+
+#[async_trait]
+impl PoolEventSource for RpcPoolEvents {
+    async fn subscribe(&self) -> Result<PoolEventStream> {
+        let client = PubsubClient::new(&self.ws_url).await?;
+        let rpc = self.rpc.clone();
+        let (mut tx, rx) = mpsc::channel(Self::BUFFER);
+
+        tokio::spawn(async move {
+            let filter = RpcTransactionLogsFilter::Mentions(vec![RAYDIUM_AMM_V4.to_string()]);
+            let config = RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) };
+            let (mut logs, unsubscribe) = match client.logs_subscribe(filter, config).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    let _ = tx.send(Err(anyhow!("Log subscription failed: {}", e))).await;
+                    return;
+                }
+            };
+
+            while let Some(response) = logs.next().await {
+                let entry = response.value;
+                if entry.err.is_some() || !entry.logs.iter().any(|line| line.contains("initialize2")) {
+                    continue;
+                }
+
+                match Self::resolve(&rpc, &entry.signature, response.context.slot).await {
+                    Ok(Some(event)) => {
+                        if tx.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Could not resolve pool creation {}: {}", entry.signature, e),
+                }
+            }
+
+            unsubscribe().await;
+        });
+
+        Ok(rx.boxed())
+    }
+}
+
+/// Pull the pool out of a `getTransaction` result for a Raydium
+/// `initialize2`. Pools that don't pair against SOL are ignored.
+pub fn parse_pool_creation(transaction: &Value, signature: &str, slot: u64) -> Option<PoolCreated> {
+    let as_strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(Value::as_array)
+            .map(|keys| keys.iter().filter_map(|k| k.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    // Versioned transactions append lookup-table addresses after the static keys
+    let message = transaction.pointer("/transaction/message")?;
+    let mut keys = as_strings(message.get("accountKeys"));
+    keys.extend(as_strings(transaction.pointer("/meta/loadedAddresses/writable")));
+    keys.extend(as_strings(transaction.pointer("/meta/loadedAddresses/readonly")));
+
+    let accounts = message.get("instructions")?.as_array()?.iter().find_map(|ix| {
+        let program = ix.get("programIdIndex")?.as_u64()? as usize;
+        if keys.get(program)? != RAYDIUM_AMM_V4 {
+            return None;
+        }
+        let accounts: Vec<usize> = ix.get("accounts")?.as_array()?
+            .iter()
+            .filter_map(|a| a.as_u64().map(|a| a as usize))
+            .collect();
+        (accounts.len() > INIT_CREATOR).then_some(accounts)
+    })?;
+
+    let key = |position: usize| keys.get(accounts[position]).cloned();
+    let (coin, pc) = (key(INIT_COIN_MINT)?, key(INIT_PC_MINT)?);
+    let token_mint = match (coin.as_str(), pc.as_str()) {
+        (SOL_MINT, token) | (token, SOL_MINT) => token.to_string(),
+        _ => return None,
+    };
+
+    Some(PoolCreated {
+        pool_address: key(INIT_AMM)?,
+        token_mint,
+        creator: key(INIT_CREATOR)?,
+        signature: signature.to_string(),
+        slot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pool_creation() {
+        let keys: Vec<String> = (0..21).map(|i| format!("key{}", i)).collect();
+        let mut keys_json = serde_json::to_value(&keys).unwrap();
+        keys_json[3] = json!(RAYDIUM_AMM_V4);
+        keys_json[12] = json!(SOL_MINT);
+        // Accounts are listed out of key order, as they are on chain
+        let accounts = vec![0, 1, 2, 4, 5, 6, 7, 8, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 9, 10, 1];
+
+        let transaction = json!({
+            "transaction": {"message": {
+                "accountKeys": keys_json,
+                "instructions": [
+                    {"programIdIndex": 2, "accounts": [0, 1]},
+                    {"programIdIndex": 3, "accounts": accounts},
+                ],
+            }},
+            "meta": {"loadedAddresses": {"writable": [], "readonly": []}},
+        });
+
+        let event = parse_pool_creation(&transaction, "sig", 42).unwrap();
+        assert_eq!(event.pool_address, "key5");
+        assert_eq!(event.token_mint, "key11");
+        assert_eq!(event.creator, "key20");
+        assert_eq!(event.slot, 42);
+
+        let mut no_sol = transaction.clone();
+        no_sol["transaction"]["message"]["accountKeys"][12] = json!("key12");
+        assert!(parse_pool_creation(&no_sol, "sig", 42).is_none());
+    }
+}
@@ -0,0 +1,12 @@
+//! Market data module for Cerberus Chain: Hydra
+//! Token prices in SOL, new-pool and wallet swap events and token safety checks
+
+pub mod events;
+pub mod prices;
+pub mod security;
+pub mod swaps;
+
+pub use events::{ChannelPoolEvents, PoolCreated, PoolEventSource, RpcPoolEvents};
+pub use prices::{JupiterPriceFeed, PriceFeed};
+pub use security::{RiskLevel, RiskReport, SecurityEngine, SecurityPolicy, TokenFilter, TokenVerdict};
+pub use swaps::{ChannelSwapEvents, RpcSwapEvents, SwapEventSource, WalletSwap};
@@ -0,0 +1,75 @@
+//! Token price feeds
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::trading::jupiter::SOL_MINT;
+
+/// Source of token prices in SOL per token
+#[async_trait]
+pub trait PriceFeed: Send + Sync {
+    /// Latest prices for the given mints; mints without a price are omitted
+    async fn prices_in_sol(&self, mints: &[String]) -> Result<HashMap<String, Decimal>>;
+
+    /// Latest price of a single mint
+    async fn price_in_sol(&self, mint: &str) -> Result<Option<Decimal>> {
+        let prices = self.prices_in_sol(&[mint.to_string()]).await?;
+        Ok(prices.get(mint).copied())
+    }
+}
+
+/// Jupiter price API feed, quoting every mint against SOL
+#[derive(Clone)]
+pub struct JupiterPriceFeed {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl JupiterPriceFeed {
+    /// The price API accepts up to 100 ids per request
+    const MAX_IDS_PER_REQUEST: usize = 100;
+
+    pub fn new(base_url: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl PriceFeed for JupiterPriceFeed {
+    async fn prices_in_sol(&self, mints: &[String]) -> Result<HashMap<String, Decimal>> {
+        let mut prices = HashMap::new();
+
+        for chunk in mints.chunks(Self::MAX_IDS_PER_REQUEST) {
+            let response = self.http
+                .get(&self.base_url)
+                .query(&[("ids", chunk.join(",")), ("vsToken", SOL_MINT.to_string())])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(anyhow!("Jupiter price request failed ({})", response.status()));
+            }
+
+            let body: serde_json::Value = response.json().await?;
+            let data = body.get("data").and_then(|d| d.as_object());
+
+            for (mint, entry) in data.into_iter().flatten() {
+                let price = entry.get("price")
+                    .and_then(|p| p.as_str())
+                    .and_then(|p| Decimal::from_str(p).or_else(|_| Decimal::from_scientific(p)).ok());
+
+                if let Some(price) = price {
+                    prices.insert(mint.clone(), price);
+                }
+            }
+        }
+
+        Ok(prices)
+    }
+}
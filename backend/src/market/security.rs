@@ -0,0 +1,732 @@
+//! Token safety checks run before a bot buys into an unfamiliar token
+//!
+//! The security engine gathers on-chain facts about a mint (authorities,
+//! holder concentration, how much pool liquidity is burned or locked,
+//! Token-2022 transfer fees, metadata mutability) and whether a sale back
+//! to SOL both routes through Jupiter and survives a simulation. A
+//! `SecurityPolicy` turns those facts into block or warn findings; each bot
+//! that buys unknown tokens carries its own.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rust_decimal::Decimal;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::trading::jupiter::{JupiterClient, NoRoute, SwapMode, SwapQuote, SOL_MINT};
+
+/// Outcome of a token check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenVerdict {
+    pub passed: bool,
+    /// Why the token was rejected; empty when it passed
+    pub reasons: Vec<String>,
+    /// Concerns that didn't block the token
+    pub warnings: Vec<String>,
+}
+
+impl TokenVerdict {
+    pub fn from_reasons(reasons: Vec<String>) -> Self {
+        Self { passed: reasons.is_empty(), reasons, warnings: Vec::new() }
+    }
+
+    fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+}
+
+/// What a rule does when it trips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    Block,
+    Warn,
+    Ignore,
+}
+
+/// Warn and block thresholds of a measured rule, in percent. Either may be
+/// left out to skip that level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub warn: Option<Decimal>,
+    pub block: Option<Decimal>,
+}
+
+impl Limit {
+    fn new(warn: i64, block: i64) -> Self {
+        Self { warn: Some(Decimal::from(warn)), block: Some(Decimal::from(block)) }
+    }
+}
+
+/// Per-bot security rules. Fields left out keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityPolicy {
+    pub mint_authority: RuleAction,
+    pub freeze_authority: RuleAction,
+    /// Largest single holder's share of supply, pool vaults excluded
+    pub top_holder_pct: Limit,
+    /// Ten largest holders' share of supply, pool vaults excluded
+    pub top10_holders_pct: Limit,
+    /// Minimum share of pool liquidity that is burned or locked; trips
+    /// below the thresholds
+    pub lp_locked_pct: Limit,
+    /// The token cannot be sold back to SOL
+    pub honeypot: RuleAction,
+    /// SOL lost buying then immediately selling a small amount
+    pub round_trip_loss_pct: Limit,
+    /// Token-2022 transfer fee
+    pub transfer_fee_pct: Limit,
+    pub mutable_metadata: RuleAction,
+    /// A fact that couldn't be determined, such as a token without a pool
+    pub unknown: RuleAction,
+}
+
+impl Default for SecurityPolicy {
+    fn default() -> Self {
+        Self {
+            mint_authority: RuleAction::Block,
+            freeze_authority: RuleAction::Block,
+            top_holder_pct: Limit::new(10, 25),
+            top10_holders_pct: Limit::new(40, 60),
+            lp_locked_pct: Limit::new(90, 50),
+            honeypot: RuleAction::Block,
+            round_trip_loss_pct: Limit::new(10, 30),
+            transfer_fee_pct: Limit::new(1, 5),
+            mutable_metadata: RuleAction::Warn,
+            unknown: RuleAction::Warn,
+        }
+    }
+}
+
+/// What the engine found out about a mint; `None` where it couldn't tell
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TokenFacts {
+    pub token_program: String,
+    pub decimals: u8,
+    pub supply: u64,
+    pub mint_authority: bool,
+    pub freeze_authority: bool,
+    pub top_holder_pct: Option<Decimal>,
+    pub top10_holders_pct: Option<Decimal>,
+    pub pool_address: Option<String>,
+    pub lp_locked_pct: Option<Decimal>,
+    pub sellable: Option<bool>,
+    pub round_trip_loss_pct: Option<Decimal>,
+    pub transfer_fee_pct: Option<Decimal>,
+    pub metadata_mutable: Option<bool>,
+}
+
+/// Overall outcome, worst finding first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Pass,
+    Warn,
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskFinding {
+    pub rule: String,
+    pub level: RiskLevel,
+    pub message: String,
+}
+
+/// Full security report on a mint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskReport {
+    pub mint: String,
+    pub level: RiskLevel,
+    /// 0 (nothing found) to 100, weighted over the findings
+    pub score: u32,
+    pub findings: Vec<RiskFinding>,
+    pub facts: TokenFacts,
+}
+
+impl RiskReport {
+    pub fn verdict(&self) -> TokenVerdict {
+        let messages = |level: RiskLevel| -> Vec<String> {
+            self.findings.iter().filter(|f| f.level == level).map(|f| f.message.clone()).collect()
+        };
+        TokenVerdict::from_reasons(messages(RiskLevel::Block)).with_warnings(messages(RiskLevel::Warn))
+    }
+}
+
+/// Decides whether a token is safe enough to buy
+#[async_trait]
+pub trait TokenFilter: Send + Sync {
+    /// Inspect a mint and report on it under a policy
+    async fn assess(&self, mint: &str, policy: &SecurityPolicy) -> Result<RiskReport>;
+
+    async fn check(&self, mint: &str, policy: &SecurityPolicy) -> Result<TokenVerdict> {
+        Ok(self.assess(mint, policy).await?.verdict())
+    }
+}
+
+/// Score weight of each rule at block level; warnings count half
+fn weight(rule: &str) -> u32 {
+    match rule {
+        "honeypot" => 100,
+        "mint_authority" | "freeze_authority" => 30,
+        "lp_locked_pct" => 25,
+        "top_holder_pct" | "round_trip_loss_pct" | "transfer_fee_pct" => 20,
+        "top10_holders_pct" => 15,
+        _ => 5,
+    }
+}
+
+struct Findings<'a> {
+    policy: &'a SecurityPolicy,
+    findings: Vec<RiskFinding>,
+}
+
+impl Findings<'_> {
+    fn flag(&mut self, rule: &str, action: RuleAction, message: String) {
+        let level = match action {
+            RuleAction::Block => RiskLevel::Block,
+            RuleAction::Warn => RiskLevel::Warn,
+            RuleAction::Ignore => return,
+        };
+        self.findings.push(RiskFinding { rule: rule.to_string(), level, message });
+    }
+
+    fn unknown(&mut self, rule: &str, what: &str) {
+        self.flag(rule, self.policy.unknown, format!("Could not determine {}", what));
+    }
+
+    /// Trip when `value` is above the limit, or below it when `minimum` is set
+    fn measure(&mut self, rule: &str, what: &str, value: Option<Decimal>, limit: &Limit, minimum: bool) {
+        let value = match value {
+            Some(value) => value,
+            None => return self.unknown(rule, what),
+        };
+        let trips = |threshold: Option<Decimal>| {
+            threshold.is_some_and(|t| if minimum { value < t } else { value > t })
+        };
+        let (action, threshold) = if trips(limit.block) {
+            (RuleAction::Block, limit.block)
+        } else if trips(limit.warn) {
+            (RuleAction::Warn, limit.warn)
+        } else {
+            return;
+        };
+        let side = if minimum { "below" } else { "above" };
+        let threshold = threshold.unwrap_or_default();
+        self.flag(rule, action, format!("{} is {}%, {} {}%", what, value.round_dp(2), side, threshold));
+    }
+}
+
+/// Apply a policy to what is known about a mint
+pub fn evaluate(mint: &str, facts: TokenFacts, policy: &SecurityPolicy) -> RiskReport {
+    let mut f = Findings { policy, findings: Vec::new() };
+
+    if facts.mint_authority {
+        f.flag("mint_authority", policy.mint_authority, "Mint authority has not been revoked".to_string());
+    }
+    if facts.freeze_authority {
+        f.flag("freeze_authority", policy.freeze_authority, "Freeze authority has not been revoked".to_string());
+    }
+    f.measure("top_holder_pct", "Largest holder's share", facts.top_holder_pct, &policy.top_holder_pct, false);
+    f.measure("top10_holders_pct", "Top 10 holders' share", facts.top10_holders_pct, &policy.top10_holders_pct, false);
+    f.measure("lp_locked_pct", "Burned or locked liquidity", facts.lp_locked_pct, &policy.lp_locked_pct, true);
+    match facts.sellable {
+        Some(false) => f.flag("honeypot", policy.honeypot, "The token cannot be sold back to SOL".to_string()),
+        Some(true) => f.measure(
+            "round_trip_loss_pct",
+            "Round-trip loss",
+            facts.round_trip_loss_pct,
+            &policy.round_trip_loss_pct,
+            false,
+        ),
+        None => f.unknown("honeypot", "whether the token can be sold"),
+    }
+    f.measure("transfer_fee_pct", "Transfer fee", facts.transfer_fee_pct, &policy.transfer_fee_pct, false);
+    match facts.metadata_mutable {
+        Some(true) => f.flag("mutable_metadata", policy.mutable_metadata, "Token metadata can still be changed".to_string()),
+        Some(false) => {}
+        None => f.unknown("mutable_metadata", "whether the metadata is mutable"),
+    }
+
+    let findings = f.findings;
+    let score = findings.iter()
+        .map(|finding| match finding.level {
+            RiskLevel::Block => weight(&finding.rule),
+            _ => weight(&finding.rule) / 2,
+        })
+        .sum::<u32>()
+        .min(100);
+    let level = findings.iter().map(|finding| finding.level).max().unwrap_or(RiskLevel::Pass);
+
+    RiskReport { mint: mint.to_string(), level, score, findings, facts }
+}
+
+/// SPL mint layout: `COption<Pubkey>` mint authority, supply, decimals,
+/// initialized flag, `COption<Pubkey>` freeze authority
+const MINT_LEN: usize = 82;
+const MINT_AUTHORITY_TAG: usize = 0;
+const SUPPLY: usize = 36;
+const DECIMALS: usize = 44;
+const FREEZE_AUTHORITY_TAG: usize = 46;
+
+/// Whether a mint still has a mint authority and a freeze authority
+pub fn mint_authorities(data: &[u8]) -> Result<(bool, bool)> {
+    if data.len() < MINT_LEN {
+        return Err(anyhow!("Account is not an SPL token mint"));
+    }
+
+    let is_set = |offset: usize| data[offset..offset + 4] != [0, 0, 0, 0];
+    Ok((is_set(MINT_AUTHORITY_TAG), is_set(FREEZE_AUTHORITY_TAG)))
+}
+
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Token-2022 extensions follow the 165-byte base account and a one-byte
+/// account type as type-length-value entries
+const EXTENSIONS_START: usize = 166;
+const TRANSFER_FEE_CONFIG: u16 = 1;
+/// `newer_transfer_fee.transfer_fee_basis_points` inside the extension
+const NEWER_FEE_BPS: usize = 106;
+
+/// Transfer fee in basis points of a Token-2022 mint; 0 without the extension
+pub fn transfer_fee_bps(data: &[u8]) -> u16 {
+    let mut offset = EXTENSIONS_START;
+    while let Some(header) = data.get(offset..offset + 4) {
+        let kind = u16::from_le_bytes([header[0], header[1]]);
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        if kind == TRANSFER_FEE_CONFIG {
+            let start = offset + 4 + NEWER_FEE_BPS;
+            return data.get(start..start + 2).map_or(0, |bps| u16::from_le_bytes([bps[0], bps[1]]));
+        }
+        // Type 0 marks the zeroed tail of the account
+        if kind == 0 {
+            break;
+        }
+        offset += 4 + len;
+    }
+    0
+}
+
+const METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// Whether a Metaplex metadata account can still be updated. The flag sits
+/// after the variable-length name, symbol, uri and creator list.
+pub fn metadata_is_mutable(data: &[u8]) -> Option<bool> {
+    let read_u32 = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    };
+
+    // Key, update authority, mint
+    let mut offset = 1 + 32 + 32;
+    // Name, symbol, uri
+    for _ in 0..3 {
+        offset += 4 + read_u32(offset)?;
+    }
+    // Seller fee basis points
+    offset += 2;
+    offset += match data.get(offset)? {
+        1 => 1 + 4 + read_u32(offset + 1)? * 34,
+        _ => 1,
+    };
+    // Primary sale happened
+    offset += 1;
+    data.get(offset).map(|flag| *flag != 0)
+}
+
+const RAYDIUM_AMM_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+const RAYDIUM_AMM_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+const RAYDIUM_AMM_LEN: u64 = 752;
+/// `AmmInfo` is read from the base vault through `lp_amount`
+const AMM_SLICE_START: usize = 336;
+const AMM_SLICE_LEN: usize = 392;
+const AMM_COIN_VAULT: usize = 0;
+const AMM_COIN_MINT: usize = 400 - AMM_SLICE_START;
+const AMM_PC_MINT: usize = 432 - AMM_SLICE_START;
+const AMM_LP_MINT: usize = 464 - AMM_SLICE_START;
+const AMM_LP_AMOUNT: usize = 720 - AMM_SLICE_START;
+
+/// LP tokens sent here can never be withdrawn
+const LP_LOCK_OWNERS: [&str; 1] = ["1nc1nerator11111111111111111111111111111111"];
+
+/// Round trip probed for the honeypot check
+const PROBE_LAMPORTS: u64 = 10_000_000;
+const PROBE_SLIPPAGE_BPS: u16 = 100;
+
+/// A token's Raydium AMM pool against SOL
+struct Pool {
+    address: String,
+    coin_vault: Pubkey,
+    lp_mint: Pubkey,
+    /// LP tokens the pool has issued and not taken back
+    lp_amount: u64,
+}
+
+/// A token account among a mint's largest
+struct Holding {
+    address: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+}
+
+/// Security engine reading facts from an RPC node and Jupiter
+pub struct SecurityEngine {
+    rpc: Arc<RpcClient>,
+    jupiter: JupiterClient,
+}
+
+impl SecurityEngine {
+    pub fn new(rpc: Arc<RpcClient>, jupiter: JupiterClient) -> Self {
+        Self { rpc, jupiter }
+    }
+
+    /// Gather the facts about a mint. Only a missing or malformed mint
+    /// account is an error; anything else that can't be read stays unknown.
+    pub async fn inspect(&self, mint: &str) -> Result<TokenFacts> {
+        let address = Pubkey::from_str(mint).map_err(|_| anyhow!("Invalid mint address: {}", mint))?;
+        let account = self.rpc.get_account(&address).await?;
+        let (mint_authority, freeze_authority) = mint_authorities(&account.data)?;
+        let is_token_2022 = account.owner.to_string() == TOKEN_2022_PROGRAM;
+        let supply = u64::from_le_bytes(account.data[SUPPLY..SUPPLY + 8].try_into()?);
+
+        let (pool, metadata_mutable, round_trip) = futures::join!(
+            self.find_pool(&address),
+            self.metadata_mutable(&address),
+            self.round_trip(&address),
+        );
+        let pool = pool.unwrap_or_else(|e| {
+            log::debug!("Pool lookup for {} failed: {}", mint, e);
+            None
+        });
+
+        let pool_vaults: HashSet<Pubkey> = pool.iter().map(|pool| pool.coin_vault).collect();
+        let concentration = self.holder_concentration(&address, supply, &pool_vaults).await.ok();
+        let lp_locked_pct = match &pool {
+            Some(pool) => self.lp_locked_pct(pool).await.ok(),
+            None => None,
+        };
+        let (sellable, round_trip_loss_pct) = match round_trip {
+            Ok(Some(loss)) => (Some(true), Some(loss)),
+            Ok(None) => (Some(false), None),
+            Err(_) => (None, None),
+        };
+
+        Ok(TokenFacts {
+            token_program: account.owner.to_string(),
+            decimals: account.data[DECIMALS],
+            supply,
+            mint_authority,
+            freeze_authority,
+            top_holder_pct: concentration.map(|(top, _)| top),
+            top10_holders_pct: concentration.map(|(_, top10)| top10),
+            pool_address: pool.as_ref().map(|pool| pool.address.clone()),
+            lp_locked_pct,
+            sellable,
+            round_trip_loss_pct,
+            transfer_fee_pct: Some(if is_token_2022 {
+                Decimal::new(transfer_fee_bps(&account.data) as i64, 2)
+            } else {
+                Decimal::ZERO
+            }),
+            metadata_mutable: metadata_mutable.ok().flatten(),
+        })
+    }
+
+    /// The token's Raydium pool quoted in SOL, if it has one
+    async fn find_pool(&self, mint: &Pubkey) -> Result<Option<Pool>> {
+        let accounts: Value = self.rpc
+            .send(
+                RpcRequest::GetProgramAccounts,
+                json!([RAYDIUM_AMM_PROGRAM, {
+                    "encoding": "base64",
+                    "dataSlice": {"offset": AMM_SLICE_START, "length": AMM_SLICE_LEN},
+                    "filters": [
+                        {"dataSize": RAYDIUM_AMM_LEN},
+                        {"memcmp": {"offset": AMM_COIN_MINT + AMM_SLICE_START, "bytes": mint.to_string()}},
+                        {"memcmp": {"offset": AMM_PC_MINT + AMM_SLICE_START, "bytes": SOL_MINT}},
+                    ],
+                }]),
+            )
+            .await?;
+
+        let entry = match accounts.as_array().and_then(|accounts| accounts.first()) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let address = entry.get("pubkey").and_then(Value::as_str).ok_or_else(|| anyhow!("Pool without address"))?;
+        let encoded = entry.pointer("/account/data/0").and_then(Value::as_str).ok_or_else(|| anyhow!("Pool without data"))?;
+        let data = BASE64.decode(encoded)?;
+        if data.len() < AMM_SLICE_LEN {
+            return Err(anyhow!("Pool account {} is too short", address));
+        }
+
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| anyhow!("Bad pool key"));
+        Ok(Some(Pool {
+            address: address.to_string(),
+            coin_vault: pubkey(AMM_COIN_VAULT)?,
+            lp_mint: pubkey(AMM_LP_MINT)?,
+            lp_amount: u64::from_le_bytes(data[AMM_LP_AMOUNT..AMM_LP_AMOUNT + 8].try_into()?),
+        }))
+    }
+
+    /// The largest token accounts of a mint with their owners
+    async fn largest_holdings(&self, mint: &Pubkey) -> Result<Vec<Holding>> {
+        let largest = self.rpc.get_token_largest_accounts(mint).await?;
+        let addresses = largest.iter()
+            .map(|balance| Pubkey::from_str(&balance.address))
+            .collect::<Result<Vec<_>, _>>()?;
+        let accounts = self.rpc.get_multiple_accounts(&addresses).await?;
+
+        let mut holdings = Vec::new();
+        for ((address, balance), account) in addresses.into_iter().zip(&largest).zip(accounts) {
+            // Token account layout: mint, then owner
+            let owner = match account.and_then(|a| a.data.get(32..64).and_then(|o| Pubkey::try_from(o).ok())) {
+                Some(owner) => owner,
+                None => continue,
+            };
+            holdings.push(Holding { address, owner, amount: balance.amount.amount.parse()? });
+        }
+        Ok(holdings)
+    }
+
+    /// Largest and top-10 holders' share of supply, leaving out pool vaults
+    async fn holder_concentration(
+        &self,
+        mint: &Pubkey,
+        supply: u64,
+        pool_vaults: &HashSet<Pubkey>,
+    ) -> Result<(Decimal, Decimal)> {
+        if supply == 0 {
+            return Err(anyhow!("Mint has no supply"));
+        }
+        let amm_authority = Pubkey::from_str(RAYDIUM_AMM_AUTHORITY)?;
+        let mut amounts: Vec<u64> = self.largest_holdings(mint).await?
+            .into_iter()
+            .filter(|h| !pool_vaults.contains(&h.address) && h.owner != amm_authority)
+            .map(|h| h.amount)
+            .collect();
+        amounts.sort_unstable_by(|a, b| b.cmp(a));
+
+        let pct = |amount: u64| (Decimal::from(amount) / Decimal::from(supply) * Decimal::from(100)).round_dp(4);
+        let top = amounts.first().copied().unwrap_or_default();
+        let top10 = amounts.iter().take(10).sum();
+        Ok((pct(top), pct(top10)))
+    }
+
+    /// Share of the pool's LP tokens that were burned or sent to a lock owner
+    async fn lp_locked_pct(&self, pool: &Pool) -> Result<Decimal> {
+        if pool.lp_amount == 0 {
+            return Err(anyhow!("Pool {} has issued no LP tokens", pool.address));
+        }
+        let account = self.rpc.get_account(&pool.lp_mint).await?;
+        let data = account.data.get(SUPPLY..SUPPLY + 8).ok_or_else(|| anyhow!("LP mint is malformed"))?;
+        let supply = u64::from_le_bytes(data.try_into()?);
+        let burned = pool.lp_amount.saturating_sub(supply);
+
+        let lock_owners = LP_LOCK_OWNERS.iter().map(|o| Pubkey::from_str(o)).collect::<Result<Vec<_>, _>>()?;
+        let locked: u64 = self.largest_holdings(&pool.lp_mint).await?
+            .iter()
+            .filter(|h| lock_owners.contains(&h.owner))
+            .map(|h| h.amount)
+            .sum();
+
+        let secured = Decimal::from(burned.saturating_add(locked).min(pool.lp_amount));
+        Ok((secured / Decimal::from(pool.lp_amount) * Decimal::from(100)).round_dp(4))
+    }
+
+    async fn metadata_mutable(&self, mint: &Pubkey) -> Result<Option<bool>> {
+        let program = Pubkey::from_str(METADATA_PROGRAM)?;
+        let (address, _) = Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program);
+        let account = self.rpc.get_account(&address).await?;
+        Ok(metadata_is_mutable(&account.data))
+    }
+
+    /// Percent of SOL lost buying a small amount and selling it straight
+    /// back; `None` when the token can't be sold. The sale has to route and
+    /// then succeed in a simulation from a wallet holding the token, which
+    /// catches tokens that quote fine but refuse transfers. Anything that
+    /// keeps the check from finishing is an error, leaving it unknown.
+    async fn round_trip(&self, mint: &Pubkey) -> Result<Option<Decimal>> {
+        let mint_str = mint.to_string();
+        let buy = self.jupiter.quote(SOL_MINT, &mint_str, PROBE_LAMPORTS, PROBE_SLIPPAGE_BPS, SwapMode::ExactIn).await?;
+        let tokens = buy.out_amount_raw()?;
+        let sell = match self.jupiter.quote(&mint_str, SOL_MINT, tokens, PROBE_SLIPPAGE_BPS, SwapMode::ExactIn).await {
+            Ok(quote) => quote,
+            Err(e) if e.is::<NoRoute>() => {
+                log::info!("Nothing routes a sale of {} back to SOL: {}", mint, e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(failure) = self.simulate_sale(mint, &sell).await? {
+            log::info!("Simulated sale of {} failed: {}", mint, failure);
+            return Ok(None);
+        }
+
+        let returned = Decimal::from(sell.out_amount_raw()?);
+        let loss = (Decimal::ONE - returned / Decimal::from(PROBE_LAMPORTS)) * Decimal::from(100);
+        Ok(Some(loss.max(Decimal::ZERO).round_dp(4)))
+    }
+
+    /// Simulate a holder selling the quoted amount, or all it holds if less.
+    /// Returns the transaction error if the sale would fail.
+    async fn simulate_sale(&self, mint: &Pubkey, quote: &SwapQuote) -> Result<Option<String>> {
+        let seller = self.find_seller(mint).await?
+            .ok_or_else(|| anyhow!("No holder of {} can sign a sale to simulate", mint))?;
+        let quote = if seller.amount >= quote.in_amount_raw()? {
+            quote.clone()
+        } else {
+            self.jupiter
+                .quote(&mint.to_string(), SOL_MINT, seller.amount, PROBE_SLIPPAGE_BPS, SwapMode::ExactIn)
+                .await?
+        };
+
+        let swap = self.jupiter.swap_transaction(&quote, &seller.owner, None).await?;
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            ..Default::default()
+        };
+        let result = self.rpc.simulate_transaction_with_config(&swap.transaction, config).await?.value;
+        Ok(result.err.map(|e| e.to_string()))
+    }
+
+    /// A large holder able to pay for a sale: a wallet rather than a pool or
+    /// program account, with SOL for fees
+    async fn find_seller(&self, mint: &Pubkey) -> Result<Option<Holding>> {
+        let amm_authority = Pubkey::from_str(RAYDIUM_AMM_AUTHORITY)?;
+        let holdings: Vec<Holding> = self.largest_holdings(mint).await?
+            .into_iter()
+            .filter(|h| h.amount > 0 && h.owner != amm_authority)
+            .collect();
+        let owners: Vec<Pubkey> = holdings.iter().map(|h| h.owner).collect();
+        let accounts = self.rpc.get_multiple_accounts(&owners).await?;
+
+        Ok(holdings
+            .into_iter()
+            .zip(accounts)
+            .find(|(_, account)| {
+                account.as_ref().is_some_and(|a| a.owner == system_program::id() && a.lamports >= PROBE_LAMPORTS)
+            })
+            .map(|(holding, _)| holding))
+    }
+}
+
+#[async_trait]
+impl TokenFilter for SecurityEngine {
+    async fn assess(&self, mint: &str, policy: &SecurityPolicy) -> Result<RiskReport> {
+        let facts = self.inspect(mint).await?;
+        Ok(evaluate(mint, facts, policy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_mint_authorities() {
+        let mut data = vec![0u8; MINT_LEN];
+        assert_eq!(mint_authorities(&data).unwrap(), (false, false));
+
+        data[FREEZE_AUTHORITY_TAG] = 1;
+        assert_eq!(mint_authorities(&data).unwrap(), (false, true));
+
+        data[MINT_AUTHORITY_TAG] = 1;
+        assert_eq!(mint_authorities(&data).unwrap(), (true, true));
+
+        assert!(mint_authorities(&data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_account_parsers() {
+        // Token-2022 mint with a metadata pointer, then a 2.5% transfer fee
+        let mut mint = vec![0u8; EXTENSIONS_START];
+        mint.extend([18, 0, 64, 0]);
+        mint.extend([0u8; 64]);
+        mint.extend([1, 0, 108, 0]);
+        let mut fee = [0u8; 108];
+        fee[NEWER_FEE_BPS..].copy_from_slice(&250u16.to_le_bytes());
+        mint.extend(fee);
+        assert_eq!(transfer_fee_bps(&mint), 250);
+        assert_eq!(transfer_fee_bps(&mint[..MINT_LEN]), 0);
+
+        let string = |s: &str| [&(s.len() as u32).to_le_bytes()[..], s.as_bytes()].concat();
+        let mut metadata = vec![4u8; 65];
+        metadata.extend(string("Token"));
+        metadata.extend(string("TKN"));
+        metadata.extend(string("https://example.com/t.json"));
+        metadata.extend([0, 0]);
+        // One creator
+        metadata.extend([1, 1, 0, 0, 0]);
+        metadata.extend([0u8; 34]);
+        metadata.extend([1, 0]);
+        assert_eq!(metadata_is_mutable(&metadata), Some(false));
+        *metadata.last_mut().unwrap() = 1;
+        assert_eq!(metadata_is_mutable(&metadata), Some(true));
+        assert_eq!(metadata_is_mutable(&metadata[..70]), None);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let facts = TokenFacts {
+            mint_authority: false,
+            freeze_authority: true,
+            top_holder_pct: Some(dec("12")),
+            top10_holders_pct: Some(dec("35")),
+            lp_locked_pct: Some(dec("100")),
+            sellable: Some(true),
+            round_trip_loss_pct: Some(dec("2.5")),
+            transfer_fee_pct: Some(Decimal::ZERO),
+            metadata_mutable: None,
+            ..TokenFacts::default()
+        };
+
+        let report = evaluate("Mint", facts.clone(), &SecurityPolicy::default());
+        assert_eq!(report.level, RiskLevel::Block);
+        let rules: Vec<_> = report.findings.iter().map(|f| (f.rule.as_str(), f.level)).collect();
+        assert_eq!(rules, [
+            ("freeze_authority", RiskLevel::Block),
+            ("top_holder_pct", RiskLevel::Warn),
+            ("mutable_metadata", RiskLevel::Warn),
+        ]);
+        assert_eq!(report.score, 30 + 10 + 2);
+        let verdict = report.verdict();
+        assert_eq!(verdict.reasons, ["Freeze authority has not been revoked"]);
+        assert_eq!(verdict.warnings[0], "Largest holder's share is 12%, above 10%");
+
+        // A looser policy lets the same token through
+        let policy = SecurityPolicy {
+            freeze_authority: RuleAction::Warn,
+            unknown: RuleAction::Ignore,
+            ..SecurityPolicy::default()
+        };
+        let report = evaluate("Mint", facts.clone(), &policy);
+        assert!(report.verdict().passed);
+        assert_eq!(report.level, RiskLevel::Warn);
+
+        let honeypot = TokenFacts { sellable: Some(false), ..facts };
+        let report = evaluate("Mint", honeypot, &policy);
+        assert_eq!(report.score, 100);
+        assert_eq!(report.level, RiskLevel::Block);
+    }
+}
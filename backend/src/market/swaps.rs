@@ -0,0 +1,276 @@
+//! Swaps made by a watched wallet
+//!
+//! Copy-trading bots read `WalletSwap` events from a `SwapEventSource`. The
+//! RPC source subscribes to logs mentioning the wallet and works out what
+//! each transaction swapped from its SOL and token balance changes, so it
+//! follows the wallet whichever DEX or aggregator it trades through.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::trading::jupiter::{LAMPORTS_PER_SOL, SOL_MINT};
+
+/// A token bought or sold by a watched wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletSwap {
+    pub wallet: String,
+    pub token_mint: String,
+    /// "buy" or "sell"
+    pub trade_type: String,
+    /// SOL spent or received, fees excluded
+    pub sol_amount: Decimal,
+    pub token_amount: Decimal,
+    pub signature: String,
+    pub slot: u64,
+}
+
+pub type SwapEventStream = BoxStream<'static, Result<WalletSwap>>;
+
+/// Source of a wallet's swaps
+#[async_trait]
+pub trait SwapEventSource: Send + Sync {
+    /// Subscribe to swaps the wallet makes from now on. The stream ends when
+    /// the subscription drops; callers subscribe again.
+    async fn subscribe(&self, wallet: &str) -> Result<SwapEventStream>;
+}
+
+type Subscribers = HashMap<String, Vec<mpsc::UnboundedSender<Result<WalletSwap>>>>;
+
+/// Swaps published by hand to the wallet's subscribers
+#[derive(Clone, Default)]
+pub struct ChannelSwapEvents {
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+impl ChannelSwapEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, swap: WalletSwap) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(senders) = subscribers.get_mut(&swap.wallet) {
+            senders.retain(|tx| tx.unbounded_send(Ok(swap.clone())).is_ok());
+        }
+    }
+}
+
+#[async_trait]
+impl SwapEventSource for ChannelSwapEvents {
+    async fn subscribe(&self, wallet: &str) -> Result<SwapEventStream> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(wallet.to_string())
+            .or_default()
+            .push(tx);
+        Ok(rx.boxed())
+    }
+}
+
+/// Swaps seen through an RPC websocket log subscription
+pub struct RpcSwapEvents {
+    ws_url: String,
+    rpc: Arc<RpcClient>,
+}
+
+impl RpcSwapEvents {
+    /// Swaps buffered ahead of a slow subscriber
+    const BUFFER: usize = 64;
+
+    pub fn new(ws_url: String, rpc: Arc<RpcClient>) -> Self {
+        Self { ws_url, rpc }
+    }
+
+    async fn resolve(rpc: &RpcClient, wallet: &str, signature: &str, slot: u64) -> Result<Option<WalletSwap>> {
+        let transaction: Value = rpc
+            .send(
+                RpcRequest::GetTransaction,
+                json!([signature, {
+                    "encoding": "json",
+                    "commitment": "confirmed",
+                    "maxSupportedTransactionVersion": 0,
+                }]),
+            )
+            .await?;
+
+        Ok(parse_wallet_swap(&transaction, wallet, signature, slot))
+    }
+}
+
+#[async_trait]
+impl SwapEventSource for RpcSwapEvents {
+    async fn subscribe(&self, wallet: &str) -> Result<SwapEventStream> {
+        let client = PubsubClient::new(&self.ws_url).await?;
+        let rpc = self.rpc.clone();
+        let wallet = wallet.to_string();
+        let (mut tx, rx) = mpsc::channel(Self::BUFFER);
+
+        tokio::spawn(async move {
+            let filter = RpcTransactionLogsFilter::Mentions(vec![wallet.clone()]);
+            let config = RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) };
+            let (mut logs, unsubscribe) = match client.logs_subscribe(filter, config).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    let _ = tx.send(Err(anyhow!("Log subscription failed: {}", e))).await;
+                    return;
+                }
+            };
+
+            while let Some(response) = logs.next().await {
+                let entry = response.value;
+                if entry.err.is_some() {
+                    continue;
+                }
+
+                match Self::resolve(&rpc, &wallet, &entry.signature, response.context.slot).await {
+                    Ok(Some(swap)) => {
+                        if tx.send(Ok(swap)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Could not resolve swap {}: {}", entry.signature, e),
+                }
+            }
+
+            unsubscribe().await;
+        });
+
+        Ok(rx.boxed())
+    }
+}
+
+/// Work out what a wallet swapped in a `getTransaction` result. Wrapped SOL
+/// counts as SOL. Transactions that aren't a SOL-for-token swap by the wallet
+/// (transfers, token-to-token swaps, failures) are ignored.
+pub fn parse_wallet_swap(transaction: &Value, wallet: &str, signature: &str, slot: u64) -> Option<WalletSwap> {
+    let meta = transaction.get("meta")?;
+    if meta.get("err").is_some_and(|err| !err.is_null()) {
+        return None;
+    }
+
+    // Signers, the wallet among them, are always static keys
+    let keys = transaction.pointer("/transaction/message/accountKeys")?.as_array()?;
+    let index = keys.iter().position(|key| key.as_str() == Some(wallet))?;
+    let lamports = |field: &str| meta.get(field)?.as_array()?.get(index)?.as_i64();
+    let mut lamport_delta = lamports("postBalances")? - lamports("preBalances")?;
+    // The fee payer's balance change includes the network fee
+    if index == 0 {
+        lamport_delta += meta.get("fee")?.as_i64()?;
+    }
+    let mut sol_delta = Decimal::from(lamport_delta) / Decimal::from(LAMPORTS_PER_SOL);
+
+    let mut token_deltas: HashMap<String, Decimal> = HashMap::new();
+    for (field, sign) in [("preTokenBalances", Decimal::NEGATIVE_ONE), ("postTokenBalances", Decimal::ONE)] {
+        for balance in meta.get(field).and_then(Value::as_array).into_iter().flatten() {
+            if balance.get("owner").and_then(Value::as_str) != Some(wallet) {
+                continue;
+            }
+            let mint = balance.get("mint")?.as_str()?;
+            let amount = balance.pointer("/uiTokenAmount/uiAmountString")
+                .and_then(Value::as_str)
+                .and_then(|a| Decimal::from_str(a).ok())
+                .unwrap_or_default();
+            *token_deltas.entry(mint.to_string()).or_default() += amount * sign;
+        }
+    }
+
+    if let Some(wrapped) = token_deltas.remove(SOL_MINT) {
+        sol_delta += wrapped;
+    }
+    let (token_mint, token_delta) = token_deltas
+        .into_iter()
+        .filter(|(_, delta)| !delta.is_zero())
+        .max_by_key(|(_, delta)| delta.abs())?;
+
+    let trade_type = match (sol_delta.is_sign_negative(), token_delta.is_sign_positive()) {
+        (true, true) => "buy",
+        (false, false) if !sol_delta.is_zero() => "sell",
+        _ => return None,
+    };
+
+    Some(WalletSwap {
+        wallet: wallet.to_string(),
+        token_mint,
+        trade_type: trade_type.to_string(),
+        sol_amount: sol_delta.abs(),
+        token_amount: token_delta.abs(),
+        signature: signature.to_string(),
+        slot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = "Watched1111111111111111111111111111111111111";
+
+    fn transaction(pre_sol: i64, post_sol: i64, pre_token: &str, post_token: &str, wrapped: Option<(&str, &str)>) -> Value {
+        let balance = |mint: &str, amount: &str| json!({
+            "accountIndex": 2, "mint": mint, "owner": WALLET,
+            "uiTokenAmount": {"uiAmountString": amount},
+        });
+        let mut pre = vec![balance("TokenMint", pre_token)];
+        let mut post = vec![balance("TokenMint", post_token)];
+        if let Some((before, after)) = wrapped {
+            pre.push(balance(SOL_MINT, before));
+            post.push(balance(SOL_MINT, after));
+        }
+
+        json!({
+            "transaction": {"message": {"accountKeys": [WALLET, "Pool", "TokenAccount"]}},
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [pre_sol, 10, 10],
+                "postBalances": [post_sol, 10, 10],
+                "preTokenBalances": pre,
+                "postTokenBalances": post,
+            },
+        })
+    }
+
+    #[test]
+    fn test_parse_wallet_swap() {
+        let buy = parse_wallet_swap(&transaction(2_000_000_000, 1_499_995_000, "0", "1000", None), WALLET, "sig", 7)
+            .unwrap();
+        assert_eq!((buy.trade_type.as_str(), buy.token_mint.as_str()), ("buy", "TokenMint"));
+        assert_eq!(buy.sol_amount, Decimal::from_str("0.5").unwrap());
+        assert_eq!(buy.token_amount, Decimal::from(1000));
+
+        // Sold for wrapped SOL that stays wrapped
+        let sell = parse_wallet_swap(
+            &transaction(1_000_000_000, 999_995_000, "1000", "400", Some(("0", "0.3"))),
+            WALLET,
+            "sig",
+            8,
+        )
+        .unwrap();
+        assert_eq!(sell.trade_type, "sell");
+        assert_eq!(sell.sol_amount, Decimal::from_str("0.3").unwrap());
+        assert_eq!(sell.token_amount, Decimal::from(600));
+
+        // A plain token transfer out is not a swap
+        assert!(parse_wallet_swap(&transaction(1_000, 1_000 - 5000, "10", "0", None), WALLET, "sig", 9).is_none());
+        let mut failed = transaction(2_000_000_000, 1_500_000_000, "0", "1000", None);
+        failed["meta"]["err"] = json!({"InstructionError": [0, "Custom"]});
+        assert!(parse_wallet_swap(&failed, WALLET, "sig", 9).is_none());
+    }
+}
@@ -0,0 +1,274 @@
+//! Conditional orders for Cerberus Chain: Hydra
+//!
+//! Limit buys, stop-losses, take-profits and trailing stops rest in the
+//! `orders` table until the order monitor sees their trigger price, at which
+//! point they become ordinary trades through the trade engine. Prices are
+//! SOL per token.
+
+pub mod monitor;
+
+pub use monitor::{OrderMonitor, OrderMonitorConfig};
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use sqlx::PgPool;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::{AmendOrderRequest, CreateOrderRequest, Order};
+
+/// Kind of conditional order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    /// Buy once the price falls to the trigger
+    LimitBuy,
+    /// Sell once the price falls to the trigger
+    StopLoss,
+    /// Sell once the price rises to the trigger
+    TakeProfit,
+    /// Sell once the price falls `trail_percent` below its highest point
+    TrailingStop,
+}
+
+impl OrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::LimitBuy => "limit_buy",
+            OrderType::StopLoss => "stop_loss",
+            OrderType::TakeProfit => "take_profit",
+            OrderType::TrailingStop => "trailing_stop",
+        }
+    }
+
+    /// Trade type the order turns into when triggered
+    pub fn trade_type(&self) -> &'static str {
+        match self {
+            OrderType::LimitBuy => "buy",
+            _ => "sell",
+        }
+    }
+}
+
+impl fmt::Display for OrderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "limit_buy" => Ok(OrderType::LimitBuy),
+            "stop_loss" => Ok(OrderType::StopLoss),
+            "take_profit" => Ok(OrderType::TakeProfit),
+            "trailing_stop" => Ok(OrderType::TrailingStop),
+            other => Err(anyhow!("Unknown order type: {}", other)),
+        }
+    }
+}
+
+/// Order statuses as stored in `orders.status`
+pub mod status {
+    pub const OPEN: &str = "open";
+    pub const TRIGGERED: &str = "triggered";
+    pub const FILLED: &str = "filled";
+    pub const FAILED: &str = "failed";
+    pub const CANCELLED: &str = "cancelled";
+}
+
+/// What the monitor should do with an open order at a given price
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderEvaluation {
+    Hold,
+    /// Trailing stop saw a new high; store it as the peak
+    RaisePeak(Decimal),
+    Trigger,
+}
+
+/// Decide whether an order fires at `price`
+pub fn evaluate(
+    order_type: OrderType,
+    trigger_price: Option<Decimal>,
+    trail_percent: Option<Decimal>,
+    peak_price: Option<Decimal>,
+    price: Decimal,
+) -> OrderEvaluation {
+    match order_type {
+        OrderType::LimitBuy | OrderType::StopLoss => match trigger_price {
+            Some(trigger) if price <= trigger => OrderEvaluation::Trigger,
+            _ => OrderEvaluation::Hold,
+        },
+        OrderType::TakeProfit => match trigger_price {
+            Some(trigger) if price >= trigger => OrderEvaluation::Trigger,
+            _ => OrderEvaluation::Hold,
+        },
+        OrderType::TrailingStop => {
+            let trail = match trail_percent {
+                Some(trail) => trail,
+                None => return OrderEvaluation::Hold,
+            };
+
+            match peak_price {
+                Some(peak) if price > peak => OrderEvaluation::RaisePeak(price),
+                Some(peak) => {
+                    let stop = peak * (Decimal::ONE - trail / Decimal::from(100));
+                    if price <= stop {
+                        OrderEvaluation::Trigger
+                    } else {
+                        OrderEvaluation::Hold
+                    }
+                }
+                None => OrderEvaluation::RaisePeak(price),
+            }
+        }
+    }
+}
+
+impl Order {
+    pub fn kind(&self) -> Result<OrderType> {
+        self.order_type.parse()
+    }
+
+    pub fn evaluate(&self, price: Decimal) -> Result<OrderEvaluation> {
+        Ok(evaluate(self.kind()?, self.trigger_price, self.trail_percent, self.peak_price, price))
+    }
+}
+
+fn validate_fields(
+    order_type: OrderType,
+    sol_amount: Decimal,
+    trigger_price: Option<Decimal>,
+    trail_percent: Option<Decimal>,
+    slippage_tolerance: Option<Decimal>,
+) -> Result<()> {
+    if sol_amount <= Decimal::ZERO {
+        return Err(anyhow!("SOL amount must be greater than zero"));
+    }
+
+    match order_type {
+        OrderType::TrailingStop => match trail_percent {
+            Some(trail) if trail > Decimal::ZERO && trail < Decimal::from(100) => {}
+            _ => return Err(anyhow!("Trailing stops require a trail percent between 0 and 100")),
+        },
+        _ => match trigger_price {
+            Some(price) if price > Decimal::ZERO => {}
+            _ => return Err(anyhow!("{} orders require a positive trigger price", order_type)),
+        },
+    }
+
+    if let Some(slippage) = slippage_tolerance {
+        if slippage < Decimal::ZERO || slippage > Decimal::from(100) {
+            return Err(anyhow!("Slippage tolerance must be between 0 and 100 percent"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a new order
+pub fn validate_order_request(req: &CreateOrderRequest) -> Result<OrderType> {
+    let order_type: OrderType = req.order_type.parse()?;
+
+    if Pubkey::from_str(&req.token_address).is_err() {
+        return Err(anyhow!("Token address is not a valid Solana public key"));
+    }
+
+    validate_fields(order_type, req.sol_amount, req.trigger_price, req.trail_percent, req.slippage_tolerance)?;
+    Ok(order_type)
+}
+
+/// Store a validated order as open
+pub async fn insert_order(
+    pool: &PgPool,
+    user_id: Uuid,
+    req: &CreateOrderRequest,
+    order_type: OrderType,
+    paper: bool,
+) -> Result<Order> {
+    let order = sqlx::query_as::<_, Order>(
+        r#"
+        INSERT INTO orders (id, user_id, wallet_id, token_address, order_type, sol_amount,
+                            trigger_price, trail_percent, slippage_tolerance, priority_fee, is_paper, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(req.wallet_id)
+    .bind(&req.token_address)
+    .bind(order_type.as_str())
+    .bind(req.sol_amount)
+    .bind(req.trigger_price)
+    .bind(req.trail_percent)
+    .bind(req.slippage_tolerance)
+    .bind(req.priority_fee)
+    .bind(paper)
+    .bind(status::OPEN)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(order)
+}
+
+/// Validate an amendment against the order it changes
+pub fn validate_amendment(order: &Order, req: &AmendOrderRequest) -> Result<()> {
+    if order.status != status::OPEN {
+        return Err(anyhow!("Only open orders can be amended"));
+    }
+
+    validate_fields(
+        order.kind()?,
+        req.sol_amount.unwrap_or(order.sol_amount),
+        req.trigger_price.or(order.trigger_price),
+        req.trail_percent.or(order.trail_percent),
+        req.slippage_tolerance.or(order.slippage_tolerance),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_price_triggers() {
+        let trigger = Some(dec("0.001"));
+
+        assert_eq!(evaluate(OrderType::LimitBuy, trigger, None, None, dec("0.0011")), OrderEvaluation::Hold);
+        assert_eq!(evaluate(OrderType::LimitBuy, trigger, None, None, dec("0.001")), OrderEvaluation::Trigger);
+        assert_eq!(evaluate(OrderType::StopLoss, trigger, None, None, dec("0.0009")), OrderEvaluation::Trigger);
+        assert_eq!(evaluate(OrderType::TakeProfit, trigger, None, None, dec("0.0009")), OrderEvaluation::Hold);
+        assert_eq!(evaluate(OrderType::TakeProfit, trigger, None, None, dec("0.002")), OrderEvaluation::Trigger);
+    }
+
+    #[test]
+    fn test_trailing_stop_follows_peak() {
+        let trail = Some(dec("10"));
+
+        // First observation seeds the peak
+        assert_eq!(evaluate(OrderType::TrailingStop, None, trail, None, dec("1")), OrderEvaluation::RaisePeak(dec("1")));
+        assert_eq!(evaluate(OrderType::TrailingStop, None, trail, Some(dec("1")), dec("1.5")), OrderEvaluation::RaisePeak(dec("1.5")));
+
+        // 10% below a 1.5 peak is 1.35
+        assert_eq!(evaluate(OrderType::TrailingStop, None, trail, Some(dec("1.5")), dec("1.4")), OrderEvaluation::Hold);
+        assert_eq!(evaluate(OrderType::TrailingStop, None, trail, Some(dec("1.5")), dec("1.35")), OrderEvaluation::Trigger);
+    }
+
+    #[test]
+    fn test_order_type_sides() {
+        assert_eq!(OrderType::LimitBuy.trade_type(), "buy");
+        assert_eq!(OrderType::TrailingStop.trade_type(), "sell");
+        assert_eq!("stop_loss".parse::<OrderType>().unwrap(), OrderType::StopLoss);
+        assert!("market".parse::<OrderType>().is_err());
+    }
+}
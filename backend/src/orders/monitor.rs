@@ -0,0 +1,264 @@
+//! Order monitor: watches prices and fires triggered orders
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::database::models::{CreateTradeRequest, Order};
+use crate::market::PriceFeed;
+use crate::orders::{status, OrderEvaluation};
+use crate::trading::lifecycle::{self, TransitionDetails};
+use crate::trading::{TradeEngine, TradeOptions, TradeStatus};
+
+/// Order monitor settings
+#[derive(Debug, Clone)]
+pub struct OrderMonitorConfig {
+    pub poll_interval: Duration,
+}
+
+impl Default for OrderMonitorConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Converts triggered orders into trades through the trade engine
+pub struct OrderMonitor {
+    pool: PgPool,
+    engine: TradeEngine,
+    prices: Arc<dyn PriceFeed>,
+    config: OrderMonitorConfig,
+}
+
+impl OrderMonitor {
+    pub fn new(pool: PgPool, engine: TradeEngine, prices: Arc<dyn PriceFeed>, config: OrderMonitorConfig) -> Self {
+        Self {
+            pool,
+            engine,
+            prices,
+            config,
+        }
+    }
+
+    /// Run the monitor on the Tokio runtime until the handle is aborted
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.poll_once().await {
+                    log::error!("Order monitor poll failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Settle triggered orders whose trades have finished, then evaluate
+    /// every open order against the latest prices once
+    pub async fn poll_once(&self) -> Result<()> {
+        self.settle_triggered().await?;
+
+        let orders = sqlx::query_as::<_, Order>(
+            "SELECT * FROM orders WHERE status = $1 ORDER BY created_at ASC"
+        )
+        .bind(status::OPEN)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if orders.is_empty() {
+            return Ok(());
+        }
+
+        let mints: Vec<String> = orders.iter()
+            .map(|o| o.token_address.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let prices = self.prices.prices_in_sol(&mints).await?;
+
+        for order in &orders {
+            let price = match prices.get(&order.token_address) {
+                Some(price) => *price,
+                None => continue,
+            };
+
+            let result = match order.evaluate(price) {
+                Ok(OrderEvaluation::Hold) => Ok(()),
+                Ok(OrderEvaluation::RaisePeak(peak)) => self.raise_peak(order, peak).await,
+                Ok(OrderEvaluation::Trigger) => self.fire(order, price).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                log::error!("Failed to process order {}: {}", order.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Give triggered orders the outcome of their trade once it is final
+    async fn settle_triggered(&self) -> Result<()> {
+        let triggered = sqlx::query_as::<_, (Uuid, Uuid, String, Option<String>)>(
+            r#"
+            SELECT o.id, t.id, t.status, t.error_message
+            FROM orders o JOIN trades t ON t.id = o.trade_id
+            WHERE o.status = $1
+            "#
+        )
+        .bind(status::TRIGGERED)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for (order_id, trade_id, trade_status, error) in triggered {
+            let trade_status = match TradeStatus::from_str(&trade_status) {
+                Ok(trade_status) => trade_status,
+                Err(e) => {
+                    log::error!("Order {} has a trade in an unknown state: {}", order_id, e);
+                    continue;
+                }
+            };
+            if let Some(new_status) = order_outcome(trade_status) {
+                let error = if new_status == status::FAILED { error } else { None };
+                self.finish(order_id, new_status, error).await?;
+            } else if !trade_status.is_in_flight() {
+                // Execution broke off before anything was sent; nothing else
+                // will pick the trade up, so it is called off with the order
+                let reason = "Trade of the triggered order was never executed";
+                if lifecycle::transition(
+                    &self.pool,
+                    trade_id,
+                    trade_status,
+                    TradeStatus::Cancelled,
+                    TransitionDetails::message(reason),
+                )
+                .await?
+                {
+                    self.finish(order_id, status::FAILED, Some(reason.to_string())).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn raise_peak(&self, order: &Order, peak: Decimal) -> Result<()> {
+        sqlx::query(
+            "UPDATE orders SET peak_price = $2, updated_at = NOW() WHERE id = $1 AND status = $3"
+        )
+        .bind(order.id)
+        .bind(peak)
+        .bind(status::OPEN)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Claim a triggered order and route it through the trade engine. The
+    /// order stays triggered until its trade confirms or fails.
+    async fn fire(&self, order: &Order, price: Decimal) -> Result<()> {
+        let claimed = sqlx::query(
+            r#"
+            UPDATE orders SET status = $2, triggered_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status = $3
+            "#
+        )
+        .bind(order.id)
+        .bind(status::TRIGGERED)
+        .bind(status::OPEN)
+        .execute(&self.pool)
+        .await?;
+
+        if claimed.rows_affected() == 0 {
+            // Cancelled or amended away underneath us
+            return Ok(());
+        }
+
+        log::info!("Order {} ({}) triggered at {} SOL", order.id, order.order_type, price);
+
+        let req = CreateTradeRequest {
+            wallet_id: order.wallet_id,
+            token_address: order.token_address.clone(),
+            trade_type: order.kind()?.trade_type().to_string(),
+            sol_amount: order.sol_amount,
+            slippage_tolerance: order.slippage_tolerance,
+            priority_fee: order.priority_fee,
+        };
+        let options = TradeOptions {
+            paper: order.is_paper,
+            ..Default::default()
+        };
+
+        let trade = match self.engine.create_trade(order.user_id, &req, &options).await {
+            Ok(trade) => trade,
+            Err(e) => return self.finish(order.id, status::FAILED, Some(e.to_string())).await,
+        };
+        sqlx::query("UPDATE orders SET trade_id = $2 WHERE id = $1")
+            .bind(order.id)
+            .bind(trade.id)
+            .execute(&self.pool)
+            .await?;
+
+        // A trade that isn't final yet is settled on a later poll
+        let trade = match self.engine.execute(&trade).await {
+            Ok(trade) => trade,
+            Err(e) => {
+                log::error!("Failed to execute trade {} of order {}: {}", trade.id, order.id, e);
+                return Ok(());
+            }
+        };
+        if let Some(new_status) = order_outcome(trade.lifecycle_status()?) {
+            let error = if new_status == status::FAILED { trade.error_message } else { None };
+            self.finish(order.id, new_status, error).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn finish(&self, order_id: Uuid, new_status: &str, error: Option<String>) -> Result<()> {
+        sqlx::query(
+            "UPDATE orders SET status = $2, error_message = $3, updated_at = NOW() WHERE id = $1 AND status = $4"
+        )
+        .bind(order_id)
+        .bind(new_status)
+        .bind(error)
+        .bind(status::TRIGGERED)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Order status for a trade that has reached a final outcome
+fn order_outcome(trade_status: TradeStatus) -> Option<&'static str> {
+    match trade_status {
+        TradeStatus::Confirmed | TradeStatus::Finalized => Some(status::FILLED),
+        TradeStatus::Failed | TradeStatus::Expired => Some(status::FAILED),
+        TradeStatus::Cancelled => Some(status::CANCELLED),
+        TradeStatus::Created | TradeStatus::Simulated | TradeStatus::Submitted => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orders_settle_only_on_final_trades() {
+        assert_eq!(order_outcome(TradeStatus::Submitted), None);
+        assert_eq!(order_outcome(TradeStatus::Simulated), None);
+        assert_eq!(order_outcome(TradeStatus::Confirmed), Some(status::FILLED));
+        assert_eq!(order_outcome(TradeStatus::Finalized), Some(status::FILLED));
+        assert_eq!(order_outcome(TradeStatus::Expired), Some(status::FAILED));
+        assert_eq!(order_outcome(TradeStatus::Failed), Some(status::FAILED));
+        assert_eq!(order_outcome(TradeStatus::Cancelled), Some(status::CANCELLED));
+    }
+}
@@ -0,0 +1,444 @@
+//! Cost-basis accounting over a stream of fills
+//!
+//! Amounts are in SOL. A buy's cost basis is the SOL spent plus its network
+//! fee; a sell's proceeds are the SOL received minus its network fee.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::Trade;
+
+/// How sold tokens are matched against their purchase cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostMethod {
+    /// Oldest lots are sold first
+    #[default]
+    Fifo,
+    /// Every token carries the running average cost
+    AverageCost,
+}
+
+impl CostMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostMethod::Fifo => "fifo",
+            CostMethod::AverageCost => "average_cost",
+        }
+    }
+}
+
+impl fmt::Display for CostMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CostMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fifo" => Ok(CostMethod::Fifo),
+            "average_cost" | "average" => Ok(CostMethod::AverageCost),
+            other => Err(anyhow!("Unknown cost method: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// A settled trade reduced to what the accounting needs
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub trade_id: Uuid,
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub bot_type: Option<String>,
+    pub bot_config_id: Option<Uuid>,
+    pub side: Side,
+    pub token_amount: Decimal,
+    pub sol_amount: Decimal,
+    pub fee_sol: Decimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl Fill {
+    /// Build a fill from a settled trade; `None` if it moved no tokens
+    pub fn from_trade(trade: &Trade) -> Option<Self> {
+        let side = match trade.trade_type.as_str() {
+            "buy" => Side::Buy,
+            "sell" => Side::Sell,
+            _ => return None,
+        };
+        let token_amount = trade.token_amount.filter(|amount| *amount > Decimal::ZERO)?;
+
+        Some(Self {
+            trade_id: trade.id,
+            wallet_id: trade.wallet_id,
+            token_address: trade.token_address.clone(),
+            bot_type: trade.bot_type.clone(),
+            bot_config_id: trade.bot_config_id,
+            side,
+            token_amount,
+            sol_amount: trade.sol_amount,
+            fee_sol: trade.fee_sol.unwrap_or(Decimal::ZERO),
+            executed_at: trade.executed_at.unwrap_or(trade.created_at),
+        })
+    }
+}
+
+/// Tokens bought by one fill and still held, with the bot that bought them
+#[derive(Debug, Clone)]
+struct Lot {
+    quantity: Decimal,
+    cost: Decimal,
+    bot_config_id: Option<Uuid>,
+    bot_type: Option<String>,
+}
+
+/// Tokens of a position bought by one bot, or by hand
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotHolding {
+    pub bot_config_id: Option<Uuid>,
+    pub bot_type: Option<String>,
+    pub quantity: Decimal,
+    pub cost_basis: Decimal,
+}
+
+/// Holdings of one mint in one wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct Position {
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub method: CostMethod,
+    pub quantity: Decimal,
+    /// SOL cost of the tokens still held
+    pub cost_basis: Decimal,
+    pub realized_pnl: Decimal,
+    pub fees_sol: Decimal,
+    pub buys: u32,
+    pub sells: u32,
+    /// Tokens sold that were never bought through us; they carry no cost
+    /// basis and are left out of realized PnL
+    pub unmatched_quantity: Decimal,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub last_fill_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    lots: VecDeque<Lot>,
+}
+
+impl Position {
+    pub fn new(wallet_id: Uuid, token_address: String, method: CostMethod) -> Self {
+        Self {
+            wallet_id,
+            token_address,
+            method,
+            quantity: Decimal::ZERO,
+            cost_basis: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            fees_sol: Decimal::ZERO,
+            buys: 0,
+            sells: 0,
+            unmatched_quantity: Decimal::ZERO,
+            opened_at: None,
+            last_fill_at: None,
+            lots: VecDeque::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.quantity > Decimal::ZERO
+    }
+
+    /// Average SOL paid per token still held
+    pub fn average_cost(&self) -> Option<Decimal> {
+        if self.is_open() {
+            Some(self.cost_basis / self.quantity)
+        } else {
+            None
+        }
+    }
+
+    /// Mark-to-market gain on the tokens still held
+    pub fn unrealized_pnl(&self, price: Decimal) -> Decimal {
+        self.quantity * price - self.cost_basis
+    }
+
+    /// Apply a fill and return the PnL it realized
+    pub fn apply(&mut self, fill: &Fill) -> Decimal {
+        self.fees_sol += fill.fee_sol;
+        self.last_fill_at = Some(fill.executed_at);
+
+        match fill.side {
+            Side::Buy => {
+                self.buys += 1;
+                if !self.is_open() {
+                    self.opened_at = Some(fill.executed_at);
+                }
+
+                let cost = fill.sol_amount + fill.fee_sol;
+                self.quantity += fill.token_amount;
+                self.cost_basis += cost;
+                self.lots.push_back(Lot {
+                    quantity: fill.token_amount,
+                    cost,
+                    bot_config_id: fill.bot_config_id,
+                    bot_type: fill.bot_type.clone(),
+                });
+                Decimal::ZERO
+            }
+            Side::Sell => {
+                self.sells += 1;
+
+                let (matched, removed_cost) = match self.method {
+                    CostMethod::Fifo => self.consume_lots(fill.token_amount),
+                    CostMethod::AverageCost => {
+                        let matched = fill.token_amount.min(self.quantity);
+                        let removed_cost = if matched == self.quantity {
+                            self.cost_basis
+                        } else if matched > Decimal::ZERO {
+                            self.cost_basis * matched / self.quantity
+                        } else {
+                            Decimal::ZERO
+                        };
+                        // Every lot gives up the same share of its tokens
+                        if matched > Decimal::ZERO {
+                            let kept = Decimal::ONE - matched / self.quantity;
+                            for lot in &mut self.lots {
+                                lot.quantity *= kept;
+                                lot.cost *= kept;
+                            }
+                        }
+                        (matched, removed_cost)
+                    }
+                };
+
+                self.quantity -= matched;
+                self.cost_basis -= removed_cost;
+                self.unmatched_quantity += fill.token_amount - matched;
+                if !self.is_open() {
+                    self.quantity = Decimal::ZERO;
+                    self.cost_basis = Decimal::ZERO;
+                    self.lots.clear();
+                }
+
+                let proceeds = fill.sol_amount - fill.fee_sol;
+                let matched_proceeds = if matched == fill.token_amount {
+                    proceeds
+                } else {
+                    proceeds * matched / fill.token_amount
+                };
+
+                let realized = if matched > Decimal::ZERO {
+                    matched_proceeds - removed_cost
+                } else {
+                    Decimal::ZERO
+                };
+                self.realized_pnl += realized;
+                realized
+            }
+        }
+    }
+
+    /// Tokens still held, split by the bot that bought them
+    pub fn holdings_by_bot(&self) -> Vec<BotHolding> {
+        let mut holdings: Vec<BotHolding> = Vec::new();
+        for lot in self.lots.iter().filter(|lot| lot.quantity > Decimal::ZERO) {
+            match holdings.iter_mut().find(|h| h.bot_config_id == lot.bot_config_id && h.bot_type == lot.bot_type) {
+                Some(holding) => {
+                    holding.quantity += lot.quantity;
+                    holding.cost_basis += lot.cost;
+                }
+                None => holdings.push(BotHolding {
+                    bot_config_id: lot.bot_config_id,
+                    bot_type: lot.bot_type.clone(),
+                    quantity: lot.quantity,
+                    cost_basis: lot.cost,
+                }),
+            }
+        }
+        holdings
+    }
+
+    /// Remove `quantity` tokens from the oldest lots; returns how many were
+    /// matched and the cost they carried
+    fn consume_lots(&mut self, quantity: Decimal) -> (Decimal, Decimal) {
+        let mut remaining = quantity;
+        let mut removed_cost = Decimal::ZERO;
+
+        while remaining > Decimal::ZERO {
+            let lot = match self.lots.front_mut() {
+                Some(lot) => lot,
+                None => break,
+            };
+
+            if lot.quantity <= remaining {
+                remaining -= lot.quantity;
+                removed_cost += lot.cost;
+                self.lots.pop_front();
+            } else {
+                let cost = lot.cost * remaining / lot.quantity;
+                lot.quantity -= remaining;
+                lot.cost -= cost;
+                removed_cost += cost;
+                remaining = Decimal::ZERO;
+            }
+        }
+
+        (quantity - remaining, removed_cost)
+    }
+}
+
+/// PnL realized by a single sell
+#[derive(Debug, Clone)]
+pub struct Realization {
+    pub trade_id: Uuid,
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub bot_type: Option<String>,
+    pub bot_config_id: Option<Uuid>,
+    pub realized_pnl: Decimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Positions across wallets and mints, built by replaying fills in order
+#[derive(Debug, Clone)]
+pub struct PositionBook {
+    method: CostMethod,
+    positions: BTreeMap<(Uuid, String), Position>,
+    realizations: Vec<Realization>,
+}
+
+impl PositionBook {
+    pub fn new(method: CostMethod) -> Self {
+        Self {
+            method,
+            positions: BTreeMap::new(),
+            realizations: Vec::new(),
+        }
+    }
+
+    pub fn method(&self) -> CostMethod {
+        self.method
+    }
+
+    /// Apply fills, which must be in execution order
+    pub fn replay<'a>(&mut self, fills: impl IntoIterator<Item = &'a Fill>) {
+        for fill in fills {
+            self.apply(fill);
+        }
+    }
+
+    pub fn apply(&mut self, fill: &Fill) {
+        let method = self.method;
+        let position = self.positions
+            .entry((fill.wallet_id, fill.token_address.clone()))
+            .or_insert_with(|| Position::new(fill.wallet_id, fill.token_address.clone(), method));
+
+        let realized = position.apply(fill);
+        if fill.side == Side::Sell {
+            self.realizations.push(Realization {
+                trade_id: fill.trade_id,
+                wallet_id: fill.wallet_id,
+                token_address: fill.token_address.clone(),
+                bot_type: fill.bot_type.clone(),
+                bot_config_id: fill.bot_config_id,
+                realized_pnl: realized,
+                executed_at: fill.executed_at,
+            });
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn realizations(&self) -> &[Realization] {
+        &self.realizations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn fill(wallet_id: Uuid, side: Side, tokens: &str, sol: &str, minute: u32) -> Fill {
+        Fill {
+            trade_id: Uuid::new_v4(),
+            wallet_id,
+            token_address: "Mint".to_string(),
+            bot_type: None,
+            bot_config_id: None,
+            side,
+            token_amount: dec(tokens),
+            sol_amount: dec(sol),
+            fee_sol: Decimal::ZERO,
+            executed_at: Utc.with_ymd_and_hms(2025, 6, 1, 12, minute, 0).unwrap(),
+        }
+    }
+
+    fn history(wallet_id: Uuid) -> Vec<Fill> {
+        vec![
+            fill(wallet_id, Side::Buy, "100", "1", 0),  // 0.01 SOL/token
+            fill(wallet_id, Side::Buy, "100", "3", 1),  // 0.03 SOL/token
+            fill(wallet_id, Side::Sell, "150", "3", 2), // 0.02 SOL/token
+        ]
+    }
+
+    #[test]
+    fn test_fifo_sells_oldest_lots_first() {
+        let wallet_id = Uuid::new_v4();
+        let mut book = PositionBook::new(CostMethod::Fifo);
+        book.replay(&history(wallet_id));
+
+        let position = book.positions().next().unwrap();
+        // 100 @ 0.01 + 50 @ 0.03 = 2.5 SOL cost against 3 SOL proceeds
+        assert_eq!(position.realized_pnl, dec("0.5"));
+        assert_eq!(position.quantity, dec("50"));
+        assert_eq!(position.cost_basis, dec("1.5"));
+        assert_eq!(position.unrealized_pnl(dec("0.02")), dec("-0.5"));
+        assert_eq!(book.realizations().len(), 1);
+    }
+
+    #[test]
+    fn test_average_cost_blends_lots() {
+        let wallet_id = Uuid::new_v4();
+        let mut book = PositionBook::new(CostMethod::AverageCost);
+        book.replay(&history(wallet_id));
+
+        let position = book.positions().next().unwrap();
+        // 150 @ 0.02 average = 3 SOL cost against 3 SOL proceeds
+        assert_eq!(position.realized_pnl, Decimal::ZERO);
+        assert_eq!(position.quantity, dec("50"));
+        assert_eq!(position.average_cost(), Some(dec("0.02")));
+    }
+
+    #[test]
+    fn test_oversell_is_left_unmatched() {
+        let wallet_id = Uuid::new_v4();
+        let mut position = Position::new(wallet_id, "Mint".to_string(), CostMethod::Fifo);
+        position.apply(&fill(wallet_id, Side::Buy, "100", "1", 0));
+        let realized = position.apply(&fill(wallet_id, Side::Sell, "200", "4", 1));
+
+        // Only the 100 tokens we bought count: 2 SOL proceeds - 1 SOL cost
+        assert_eq!(realized, dec("1"));
+        assert_eq!(position.unmatched_quantity, dec("100"));
+        assert!(!position.is_open());
+    }
+}
@@ -0,0 +1,9 @@
+//! Position tracking for Cerberus Chain: Hydra
+//! Aggregates settled trades per wallet and mint into positions with
+//! realized and unrealized PnL
+
+pub mod book;
+pub mod report;
+
+pub use book::{CostMethod, Fill, Position, PositionBook, Side};
+pub use report::{FillScope, PnlReport, PositionView};
@@ -0,0 +1,306 @@
+//! Position and PnL reports built from settled trades
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+use crate::database::models::Trade;
+use crate::market::PriceFeed;
+use crate::positions::book::{CostMethod, Fill, Position, PositionBook};
+
+/// Bot type reported for trades placed by hand
+pub const MANUAL_BOT_TYPE: &str = "manual";
+
+/// Which trades feed a report
+#[derive(Debug, Clone)]
+pub struct FillScope {
+    pub user_id: Uuid,
+    pub paper: bool,
+    pub wallet_id: Option<Uuid>,
+    /// Only trades placed by this bot
+    pub bot_config_id: Option<Uuid>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Load settled fills in execution order
+pub async fn load_fills(pool: &PgPool, scope: &FillScope) -> Result<Vec<Fill>> {
+    let trades = sqlx::query_as::<_, Trade>(
+        r#"
+        SELECT * FROM trades
+        WHERE user_id = $1
+          AND is_paper = $2
+          AND status IN ('confirmed', 'finalized')
+          AND token_amount IS NOT NULL
+          AND ($3::uuid IS NULL OR wallet_id = $3)
+          AND ($4::timestamptz IS NULL OR COALESCE(executed_at, created_at) <= $4)
+          AND ($5::uuid IS NULL OR bot_config_id = $5)
+        ORDER BY COALESCE(executed_at, created_at) ASC, created_at ASC
+        "#
+    )
+    .bind(scope.user_id)
+    .bind(scope.paper)
+    .bind(scope.wallet_id)
+    .bind(scope.until)
+    .bind(scope.bot_config_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(trades.iter().filter_map(Fill::from_trade).collect())
+}
+
+/// Replay a user's settled trades into a position book
+pub async fn build_book(pool: &PgPool, scope: &FillScope, method: CostMethod) -> Result<PositionBook> {
+    let fills = load_fills(pool, scope).await?;
+    let mut book = PositionBook::new(method);
+    book.replay(&fills);
+    Ok(book)
+}
+
+/// Latest prices for the mints still held; a failed lookup yields no prices
+pub async fn mark_prices(prices: &dyn PriceFeed, book: &PositionBook) -> BTreeMap<String, Decimal> {
+    let mints: Vec<String> = book.positions()
+        .filter(|p| p.is_open())
+        .map(|p| p.token_address.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    if mints.is_empty() {
+        return BTreeMap::new();
+    }
+
+    match prices.prices_in_sol(&mints).await {
+        Ok(prices) => prices.into_iter().collect(),
+        Err(e) => {
+            log::warn!("Failed to fetch prices for unrealized PnL: {}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+/// Position with its mark-to-market valuation
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionView {
+    #[serde(flatten)]
+    pub position: Position,
+    pub average_cost: Option<Decimal>,
+    pub price: Option<Decimal>,
+    pub market_value: Option<Decimal>,
+    pub unrealized_pnl: Option<Decimal>,
+}
+
+impl PositionView {
+    pub fn new(position: &Position, price: Option<Decimal>) -> Self {
+        Self {
+            average_cost: position.average_cost(),
+            price,
+            market_value: price.map(|p| position.quantity * p),
+            unrealized_pnl: price.map(|p| position.unrealized_pnl(p)),
+            position: position.clone(),
+        }
+    }
+}
+
+/// Value every position in the book
+pub fn position_views(
+    book: &PositionBook,
+    prices: &BTreeMap<String, Decimal>,
+    include_closed: bool,
+) -> Vec<PositionView> {
+    book.positions()
+        .filter(|p| include_closed || p.is_open())
+        .map(|p| {
+            let price = if p.is_open() { prices.get(&p.token_address).copied() } else { None };
+            PositionView::new(p, price)
+        })
+        .collect()
+}
+
+/// PnL attributed to one bot. Trades placed by hand, and bot trades that
+/// predate bot attribution, are grouped under their bot type with no id.
+#[derive(Debug, Clone, Serialize)]
+pub struct BotPnl {
+    pub bot_config_id: Option<Uuid>,
+    pub bot_type: String,
+    pub realized_pnl: Decimal,
+    /// Tokens the bot bought and still held, at current prices
+    pub unrealized_pnl: Option<Decimal>,
+    pub sells: u32,
+}
+
+impl BotPnl {
+    fn new(bot_config_id: Option<Uuid>, bot_type: String) -> Self {
+        Self {
+            bot_config_id,
+            bot_type,
+            realized_pnl: Decimal::ZERO,
+            unrealized_pnl: None,
+            sells: 0,
+        }
+    }
+}
+
+/// PnL attributed to one wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletPnl {
+    pub wallet_id: Uuid,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Option<Decimal>,
+    pub sells: u32,
+}
+
+/// Realized PnL over a window plus current unrealized PnL
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlReport {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub method: CostMethod,
+    pub realized_pnl: Decimal,
+    /// Only reported for windows that run to the present
+    pub unrealized_pnl: Option<Decimal>,
+    pub by_bot: Vec<BotPnl>,
+    pub by_wallet: Vec<WalletPnl>,
+}
+
+/// Summarize sells executed within `[from, to]`. Unrealized PnL uses current
+/// prices, so it is only included when `to` is open-ended.
+pub fn pnl_report(
+    book: &PositionBook,
+    prices: &BTreeMap<String, Decimal>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> PnlReport {
+    // A bot config has one type, so the type only separates trades without one
+    let mut by_bot: BTreeMap<(Option<Uuid>, String), BotPnl> = BTreeMap::new();
+    let bot_type = |bot_type: &Option<String>| bot_type.clone().unwrap_or_else(|| MANUAL_BOT_TYPE.to_string());
+    let mut by_wallet: BTreeMap<Uuid, WalletPnl> = BTreeMap::new();
+    let mut realized_pnl = Decimal::ZERO;
+
+    let in_window = |at: &DateTime<Utc>| {
+        from.is_none_or(|from| *at >= from) && to.is_none_or(|to| *at <= to)
+    };
+
+    for realization in book.realizations().iter().filter(|r| in_window(&r.executed_at)) {
+        realized_pnl += realization.realized_pnl;
+
+        let key = (realization.bot_config_id, bot_type(&realization.bot_type));
+        let bot = by_bot.entry(key.clone()).or_insert_with(|| BotPnl::new(key.0, key.1));
+        bot.realized_pnl += realization.realized_pnl;
+        bot.sells += 1;
+
+        let wallet = by_wallet.entry(realization.wallet_id).or_insert_with(|| WalletPnl {
+            wallet_id: realization.wallet_id,
+            realized_pnl: Decimal::ZERO,
+            unrealized_pnl: None,
+            sells: 0,
+        });
+        wallet.realized_pnl += realization.realized_pnl;
+        wallet.sells += 1;
+    }
+
+    let mut unrealized_pnl = None;
+    if to.is_none() {
+        for position in book.positions().filter(|p| p.is_open()) {
+            let price = match prices.get(&position.token_address) {
+                Some(price) => *price,
+                None => continue,
+            };
+            let unrealized = position.unrealized_pnl(price);
+
+            *unrealized_pnl.get_or_insert(Decimal::ZERO) += unrealized;
+            let wallet = by_wallet.entry(position.wallet_id).or_insert_with(|| WalletPnl {
+                wallet_id: position.wallet_id,
+                realized_pnl: Decimal::ZERO,
+                unrealized_pnl: None,
+                sells: 0,
+            });
+            *wallet.unrealized_pnl.get_or_insert(Decimal::ZERO) += unrealized;
+
+            for holding in position.holdings_by_bot() {
+                let key = (holding.bot_config_id, bot_type(&holding.bot_type));
+                let bot = by_bot.entry(key.clone()).or_insert_with(|| BotPnl::new(key.0, key.1));
+                *bot.unrealized_pnl.get_or_insert(Decimal::ZERO) += holding.quantity * price - holding.cost_basis;
+            }
+        }
+    }
+
+    PnlReport {
+        from,
+        to,
+        method: book.method(),
+        realized_pnl,
+        unrealized_pnl,
+        by_bot: by_bot.into_values().collect(),
+        by_wallet: by_wallet.into_values().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::positions::book::Side;
+    use chrono::TimeZone;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn fill(wallet_id: Uuid, bot: Uuid, side: Side, tokens: &str, sol: &str, minute: u32) -> Fill {
+        Fill {
+            trade_id: Uuid::new_v4(),
+            wallet_id,
+            token_address: "Mint".to_string(),
+            bot_type: Some("volume".to_string()),
+            bot_config_id: Some(bot),
+            side,
+            token_amount: dec(tokens),
+            sol_amount: dec(sol),
+            fee_sol: Decimal::ZERO,
+            executed_at: Utc.with_ymd_and_hms(2025, 6, 1, 12, minute, 0).unwrap(),
+        }
+    }
+
+    /// Two bots of the same type trading from one wallet
+    fn report(method: CostMethod) -> (PnlReport, Uuid, Uuid) {
+        let (wallet_id, first, second) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut book = PositionBook::new(method);
+        book.replay(&[
+            fill(wallet_id, first, Side::Buy, "100", "1", 0),   // 0.01 SOL/token
+            fill(wallet_id, second, Side::Buy, "100", "3", 1),  // 0.03 SOL/token
+            fill(wallet_id, first, Side::Sell, "50", "1", 2),   // 0.02 SOL/token
+        ]);
+        let prices = BTreeMap::from([("Mint".to_string(), dec("0.02"))]);
+        (pnl_report(&book, &prices, None, None), first, second)
+    }
+
+    fn bot(report: &PnlReport, id: Uuid) -> &BotPnl {
+        report.by_bot.iter().find(|bot| bot.bot_config_id == Some(id)).unwrap()
+    }
+
+    #[test]
+    fn test_pnl_is_broken_down_per_bot_config() {
+        let (fifo, first, second) = report(CostMethod::Fifo);
+        assert_eq!(fifo.by_bot.len(), 2);
+        // The sell matches the first bot's own lot: 1 SOL for 50 @ 0.01
+        assert_eq!(fifo.realized_pnl, dec("0.5"));
+        assert_eq!(fifo.unrealized_pnl, Some(dec("-0.5")));
+        assert_eq!(bot(&fifo, first).realized_pnl, dec("0.5"));
+        assert_eq!(bot(&fifo, first).unrealized_pnl, Some(dec("0.5")));
+        assert_eq!(bot(&fifo, first).sells, 1);
+        assert_eq!(bot(&fifo, second).realized_pnl, Decimal::ZERO);
+        assert_eq!(bot(&fifo, second).unrealized_pnl, Some(dec("-1")));
+        assert_eq!(bot(&fifo, second).sells, 0);
+
+        // At average cost the sell takes a quarter of every bot's tokens
+        let (average, first, second) = report(CostMethod::AverageCost);
+        assert_eq!(average.realized_pnl, Decimal::ZERO);
+        assert_eq!(average.unrealized_pnl, Some(Decimal::ZERO));
+        assert_eq!(bot(&average, first).unrealized_pnl, Some(dec("0.75")));
+        assert_eq!(bot(&average, second).unrealized_pnl, Some(dec("-0.75")));
+    }
+}
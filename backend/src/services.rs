@@ -0,0 +1,122 @@
+//! Trading services on the trading database
+//!
+//! Builds the trade engine and the services around it, starts the background
+//! workers that move trades, orders and bots along, and registers the shared
+//! state the API handlers take. The server that builds these owns the bots,
+//! so stopping one through the API stops its task.
+
+use actix_web::web;
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::auth::AuthService;
+use crate::bots::{BotManager, BotRegistry, BotServices, ManagerConfig};
+use crate::config::Config;
+use crate::market::{JupiterPriceFeed, PriceFeed, RpcPoolEvents, RpcSwapEvents, SecurityEngine, TokenFilter};
+use crate::orders::{OrderMonitor, OrderMonitorConfig};
+use crate::trading::bundle::JitoBlockEngine;
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::{
+    ConfirmerConfig, PaperConfig, PaperExecutor, SolanaExecutor, TradeConfirmer, TradeEngine,
+};
+use crate::wallet::parse_master_key;
+use crate::wallet::reclaim::{ReclaimOnStop, Reclaimer};
+use crate::wallet::settle;
+use crate::wallet::transfers::WalletTransfers;
+use crate::wallet::withdrawals::Withdrawals;
+
+/// Everything the trading API runs on
+#[derive(Clone)]
+pub struct TradingServices {
+    pub pool: PgPool,
+    pub config: Config,
+    pub executor: Arc<SolanaExecutor>,
+    pub engine: TradeEngine,
+    pub bot_manager: BotManager,
+    pub prices: Arc<dyn PriceFeed>,
+    pub token_filter: Arc<dyn TokenFilter>,
+    pub reclaimer: Arc<Reclaimer>,
+    pub transfers: Arc<WalletTransfers>,
+    pub withdrawals: Arc<Withdrawals>,
+}
+
+impl TradingServices {
+    /// Wire up the services; nothing runs until `start`
+    pub fn build(pool: PgPool, config: Config) -> Result<Self> {
+        let encryption_key = config.encryption_key.as_deref()
+            .ok_or_else(|| anyhow!("ENCRYPTION_KEY is required to sign trades"))?;
+        let master_key = parse_master_key(encryption_key)?;
+
+        let rpc = Arc::new(RpcClient::new(config.rpc_url()));
+        let jupiter = JupiterClient::new(config.jupiter_api_url.clone());
+        let executor = Arc::new(SolanaExecutor::new(pool.clone(), rpc.clone(), jupiter.clone(), master_key));
+        let paper = Arc::new(PaperExecutor::new(pool.clone(), executor.clone(), PaperConfig::default()));
+        let engine = TradeEngine::new(pool.clone(), executor.clone()).with_paper_executor(paper);
+
+        let prices: Arc<dyn PriceFeed> = Arc::new(JupiterPriceFeed::new(config.jupiter_price_api_url.clone()));
+        let token_filter: Arc<dyn TokenFilter> = Arc::new(SecurityEngine::new(rpc.clone(), jupiter));
+        let reclaimer = Arc::new(Reclaimer::new(executor.clone()));
+
+        let registry = BotRegistry::builtin(BotServices {
+            pool_events: Arc::new(RpcPoolEvents::new(config.ws_url(), rpc.clone())),
+            token_filter: token_filter.clone(),
+            swap_events: Arc::new(RpcSwapEvents::new(config.ws_url(), rpc)),
+            price_feed: prices.clone(),
+            block_engine: Arc::new(JitoBlockEngine::new(&config.block_engine_url)),
+        });
+        let bot_manager = BotManager::new(pool.clone(), Arc::new(engine.clone()), registry, ManagerConfig::default())
+            .with_stop_hook(Arc::new(ReclaimOnStop::new(pool.clone(), reclaimer.clone())));
+
+        Ok(Self {
+            transfers: Arc::new(WalletTransfers::new(pool.clone(), executor.clone())),
+            withdrawals: Arc::new(Withdrawals::new(pool.clone(), executor.clone())),
+            pool,
+            config,
+            executor,
+            engine,
+            bot_manager,
+            prices,
+            token_filter,
+            reclaimer,
+        })
+    }
+
+    /// Start the trade confirmer, order monitor and settlement of wallet
+    /// transactions, and relaunch the bots left active by the previous run
+    pub async fn start(&self) -> Result<Vec<JoinHandle<()>>> {
+        let workers = vec![
+            TradeConfirmer::new(self.pool.clone(), self.executor.clone(), ConfirmerConfig::default()).spawn(),
+            OrderMonitor::new(self.pool.clone(), self.engine.clone(), self.prices.clone(), OrderMonitorConfig::default())
+                .spawn(),
+            settle::spawn_resolver(
+                self.pool.clone(),
+                self.executor.rpc().clone(),
+                &[settle::TRANSFERS, settle::WITHDRAWALS],
+                Duration::from_secs(30),
+            ),
+        ];
+        self.bot_manager.restore().await?;
+        Ok(workers)
+    }
+
+    /// Register the shared state the API handlers take
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(web::Data::new(self.pool.clone()))
+            .app_data(web::Data::new(self.config.clone()))
+            .app_data(web::Data::new(AuthService::new(
+                self.config.jwt_secret.clone(),
+                self.config.jwt_expiration_hours,
+            )))
+            .app_data(web::Data::new(self.engine.clone()))
+            .app_data(web::Data::new(self.bot_manager.clone()))
+            .app_data(web::Data::from(self.prices.clone()))
+            .app_data(web::Data::from(self.token_filter.clone()))
+            .app_data(web::Data::from(self.reclaimer.clone()))
+            .app_data(web::Data::from(self.transfers.clone()))
+            .app_data(web::Data::from(self.withdrawals.clone()));
+    }
+}
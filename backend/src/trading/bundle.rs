@@ -0,0 +1,296 @@
+//! Atomic multi-transaction bundles
+//!
+//! A bundle is a list of signed transactions a block engine lands together,
+//! in order, in one slot, or not at all. The last transaction pays the
+//! engine's tip. `JitoBlockEngine` speaks the Jito block-engine HTTP API;
+//! `LocalBlockEngine` accepts bundles in memory for tests and local runs.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Block engines accept at most five transactions per bundle
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// A bundle still pending this long after submission has outlived its
+/// blockhash and is treated as failed
+pub const BUNDLE_TIMEOUT_SECS: i64 = 90;
+
+/// Bundle statuses as stored in `bundles.status`
+pub mod status {
+    pub const PENDING: &str = "pending";
+    pub const LANDED: &str = "landed";
+    pub const FAILED: &str = "failed";
+}
+
+/// What a block engine reports about a bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleStatus {
+    Pending,
+    Landed { slot: u64 },
+    Failed(String),
+    /// Not known to the engine: not yet indexed, or dropped
+    Unknown,
+}
+
+/// Bundle submission error after which the bundle is known not to have
+/// been accepted. Any other error leaves the bundle possibly in flight.
+#[derive(Debug)]
+pub struct BundleNotSent(pub String);
+
+impl fmt::Display for BundleNotSent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for BundleNotSent {}
+
+/// Id a Jito engine gives a bundle: the SHA-256 of its transactions'
+/// signatures, hex encoded. Known before sending, so a bundle whose
+/// submission timed out can still be asked about.
+pub fn bundle_id(transactions: &[VersionedTransaction]) -> String {
+    let mut hasher = Sha256::new();
+    for transaction in transactions {
+        if let Some(signature) = transaction.signatures.first() {
+            hasher.update(signature.as_ref());
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Submits bundles and reports on them
+#[async_trait]
+pub trait BlockEngine: Send + Sync {
+    /// One of the accounts the engine accepts tips on
+    async fn tip_account(&self) -> Result<Pubkey>;
+
+    /// Submit signed transactions as one bundle, returning the engine's id.
+    /// Fails with `BundleNotSent` when the engine refused the bundle.
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String>;
+
+    async fn bundle_status(&self, bundle_id: &str) -> Result<BundleStatus>;
+}
+
+/// Sign a plain SOL transfer, used for the tip that closes a bundle
+pub fn transfer_transaction(
+    from: &Keypair,
+    to: &Pubkey,
+    lamports: u64,
+    recent_blockhash: Hash,
+) -> VersionedTransaction {
+    let instruction = system_instruction::transfer(&from.pubkey(), to, lamports);
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&from.pubkey()), &[from], recent_blockhash);
+    VersionedTransaction::from(transaction)
+}
+
+/// Jito-style block engine over JSON-RPC
+#[derive(Clone)]
+pub struct JitoBlockEngine {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl JitoBlockEngine {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Make a JSON-RPC call. A client error status or an error response is
+    /// the engine refusing the request and fails with `BundleNotSent`;
+    /// transport failures, timeouts and server errors leave it unknown.
+    async fn call(&self, path: &str, method: &str, params: Value) -> Result<Value> {
+        let response = self.http
+            .post(format!("{}/api/v1/{}", self.base_url, path))
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(BundleNotSent(format!("Block engine {} failed ({})", method, status)).into());
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Block engine {} failed ({})", method, status));
+        }
+
+        let mut body: Value = response.json().await?;
+        if let Some(error) = body.get("error") {
+            return Err(BundleNotSent(format!("Block engine {} failed: {}", method, error)).into());
+        }
+        Ok(body["result"].take())
+    }
+}
+
+/// Read a `getInflightBundleStatuses` result for one bundle
+pub fn parse_inflight_status(result: &Value) -> BundleStatus {
+    let entry = match result.pointer("/value/0") {
+        Some(entry) if !entry.is_null() => entry,
+        _ => return BundleStatus::Unknown,
+    };
+
+    match entry.get("status").and_then(Value::as_str) {
+        Some("Pending") => BundleStatus::Pending,
+        Some("Landed") => match entry.get("landed_slot").and_then(Value::as_u64) {
+            Some(slot) => BundleStatus::Landed { slot },
+            None => BundleStatus::Pending,
+        },
+        Some("Failed") => BundleStatus::Failed("Bundle failed to land".to_string()),
+        Some("Invalid") => BundleStatus::Unknown,
+        other => BundleStatus::Failed(format!("Unexpected bundle status {:?}", other)),
+    }
+}
+
+#[async_trait]
+impl BlockEngine for JitoBlockEngine {
+    async fn tip_account(&self) -> Result<Pubkey> {
+        let accounts = self.call("getTipAccounts", "getTipAccounts", json!([])).await?;
+        let accounts: Vec<String> = serde_json::from_value(accounts)?;
+        let account = accounts
+            .choose(&mut rand::thread_rng())
+            .ok_or_else(|| anyhow!("Block engine returned no tip accounts"))?;
+        Ok(Pubkey::from_str(account)?)
+    }
+
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(BundleNotSent(format!("Bundles hold 1 to {} transactions", MAX_BUNDLE_TRANSACTIONS)).into());
+        }
+
+        let encoded = transactions.iter()
+            .map(|tx| Ok(STANDARD.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let result = self.call("bundles", "sendBundle", json!([encoded, {"encoding": "base64"}])).await?;
+
+        result.as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Block engine returned no bundle id"))
+    }
+
+    async fn bundle_status(&self, bundle_id: &str) -> Result<BundleStatus> {
+        let result = self.call("getInflightBundleStatuses", "getInflightBundleStatuses", json!([[bundle_id]])).await?;
+        Ok(parse_inflight_status(&result))
+    }
+}
+
+/// In-memory block engine. Bundles land immediately, one slot apart,
+/// unless a rejection has been queued.
+#[derive(Default)]
+pub struct LocalBlockEngine {
+    tip_account: Pubkey,
+    state: Mutex<LocalEngineState>,
+}
+
+#[derive(Default)]
+struct LocalEngineState {
+    slot: u64,
+    bundles: HashMap<String, (Vec<VersionedTransaction>, BundleStatus)>,
+    order: Vec<String>,
+    reject_next: Option<String>,
+}
+
+impl LocalBlockEngine {
+    pub fn new() -> Self {
+        Self {
+            tip_account: Pubkey::new_unique(),
+            state: Mutex::new(LocalEngineState::default()),
+        }
+    }
+
+    /// Fail the next bundle with `reason` instead of landing it
+    pub fn reject_next(&self, reason: &str) {
+        self.state.lock().unwrap().reject_next = Some(reason.to_string());
+    }
+
+    /// Transactions of every bundle received, oldest first
+    pub fn bundles(&self) -> Vec<Vec<VersionedTransaction>> {
+        let state = self.state.lock().unwrap();
+        state.order.iter().map(|id| state.bundles[id].0.clone()).collect()
+    }
+}
+
+#[async_trait]
+impl BlockEngine for LocalBlockEngine {
+    async fn tip_account(&self) -> Result<Pubkey> {
+        Ok(self.tip_account)
+    }
+
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(BundleNotSent(format!("Bundles hold 1 to {} transactions", MAX_BUNDLE_TRANSACTIONS)).into());
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.slot += 1;
+        let status = match state.reject_next.take() {
+            Some(reason) => BundleStatus::Failed(reason),
+            None => BundleStatus::Landed { slot: state.slot },
+        };
+        let id = format!("local-{}", state.order.len() + 1);
+        state.bundles.insert(id.clone(), (transactions.to_vec(), status));
+        state.order.push(id.clone());
+        Ok(id)
+    }
+
+    async fn bundle_status(&self, bundle_id: &str) -> Result<BundleStatus> {
+        let state = self.state.lock().unwrap();
+        Ok(state.bundles.get(bundle_id).map(|(_, status)| status.clone()).unwrap_or(BundleStatus::Unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inflight_status() {
+        let landed = json!({"context": {"slot": 10}, "value": [{"bundle_id": "b", "status": "Landed", "landed_slot": 9}]});
+        assert_eq!(parse_inflight_status(&landed), BundleStatus::Landed { slot: 9 });
+
+        let pending = json!({"value": [{"bundle_id": "b", "status": "Pending", "landed_slot": null}]});
+        assert_eq!(parse_inflight_status(&pending), BundleStatus::Pending);
+
+        assert!(matches!(parse_inflight_status(&json!({"value": [{"status": "Failed"}]})), BundleStatus::Failed(_)));
+        assert_eq!(parse_inflight_status(&json!({"value": [null]})), BundleStatus::Unknown);
+    }
+
+    #[test]
+    fn test_bundle_id_hashes_signatures_in_order() {
+        let first = transfer_transaction(&Keypair::new(), &Pubkey::new_unique(), 1, Hash::new_unique());
+        let second = transfer_transaction(&Keypair::new(), &Pubkey::new_unique(), 1, Hash::new_unique());
+
+        let id = bundle_id(&[first.clone(), second.clone()]);
+        assert_eq!(id.len(), 64);
+        assert_eq!(id, bundle_id(&[first.clone(), second.clone()]));
+        assert_ne!(id, bundle_id(&[second, first]));
+    }
+
+    #[test]
+    fn test_tip_transfer_is_signed_by_payer() {
+        let payer = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        let tip = transfer_transaction(&payer, &tip_account, 10_000, Hash::new_unique());
+
+        assert!(tip.verify_with_results().iter().all(|ok| *ok));
+        let keys = tip.message.static_account_keys();
+        assert_eq!(keys[0], payer.pubkey());
+        assert!(keys.contains(&tip_account));
+    }
+}
@@ -0,0 +1,595 @@
+//! Background confirmation tracking for submitted trades
+//!
+//! Polls signature statuses until each in-flight trade reaches finality,
+//! fails on chain, or its blockhash expires. Once the blockhash has expired
+//! every signature the trade was ever sent with is looked up in transaction
+//! history; only if none of them landed is the trade rebuilt with a fresh
+//! blockhash. The new signature is recorded before it is sent, so a crash
+//! never loses track of a live transaction. Landed trades have their actual
+//! fill read back from the chain.
+
+use anyhow::{anyhow, Result};
+use solana_sdk::signature::Signature;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::database::models::Trade;
+use crate::trading::executor::{ChainStatus, PreparedTrade, TradeExecutor};
+use crate::trading::lifecycle::{self, TradeStatus, TransitionDetails};
+
+/// `getSignatureStatuses` accepts at most 256 signatures per call
+const MAX_SIGNATURES_PER_POLL: i64 = 256;
+
+/// Confirmer settings
+#[derive(Debug, Clone)]
+pub struct ConfirmerConfig {
+    pub poll_interval: Duration,
+    /// Total submissions allowed per trade, including the first
+    pub max_submit_attempts: i32,
+}
+
+impl Default for ConfirmerConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(2),
+            max_submit_attempts: 3,
+        }
+    }
+}
+
+/// What a poll found out about an in-flight trade
+#[derive(Debug)]
+enum Settlement {
+    /// Not final yet, nor expired
+    Pending,
+    /// Landed under the given signature, which may be an earlier submission
+    Confirmed(Signature),
+    Finalized(Signature),
+    Failed(String),
+    /// Expired without landing; send this transaction instead
+    Resubmit(Box<PreparedTrade>),
+    Expired(String),
+}
+
+/// Whether a trade's current blockhash can no longer land
+fn blockhash_expired(trade: &Trade, block_height: u64) -> bool {
+    trade.last_valid_block_height
+        .map(|height| block_height > height as u64)
+        .unwrap_or(false)
+}
+
+/// Polls in-flight trades and advances their lifecycle
+pub struct TradeConfirmer {
+    pool: PgPool,
+    executor: Arc<dyn TradeExecutor>,
+    config: ConfirmerConfig,
+}
+
+impl TradeConfirmer {
+    pub fn new(pool: PgPool, executor: Arc<dyn TradeExecutor>, config: ConfirmerConfig) -> Self {
+        Self {
+            pool,
+            executor,
+            config,
+        }
+    }
+
+    /// Run the confirmer on the Tokio runtime until the handle is aborted
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.poll_once().await {
+                    log::error!("Trade confirmer poll failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Check every in-flight trade once
+    pub async fn poll_once(&self) -> Result<()> {
+        let trades = sqlx::query_as::<_, Trade>(
+            r#"
+            SELECT * FROM trades
+            WHERE status IN ('submitted', 'confirmed') AND signature IS NOT NULL AND is_paper = false
+            ORDER BY executed_at ASC
+            LIMIT $1
+            "#
+        )
+        .bind(MAX_SIGNATURES_PER_POLL)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if trades.is_empty() {
+            return Ok(());
+        }
+
+        let mut tracked = Vec::with_capacity(trades.len());
+        for trade in trades {
+            let signature = trade.signature.as_deref().and_then(|s| Signature::from_str(s).ok());
+            match signature {
+                Some(signature) => tracked.push((trade, signature)),
+                None => log::warn!("Trade {} has an unparseable signature", trade.id),
+            }
+        }
+
+        let signatures: Vec<Signature> = tracked.iter().map(|(_, sig)| *sig).collect();
+        let statuses = self.executor.signature_statuses(&signatures).await?;
+        let block_height = self.executor.block_height().await?;
+
+        for ((trade, signature), status) in tracked.iter().zip(statuses) {
+            if let Err(e) = self.advance(trade, *signature, status, block_height).await {
+                log::error!("Failed to advance trade {}: {}", trade.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn advance(&self, trade: &Trade, signature: Signature, status: ChainStatus, block_height: u64) -> Result<()> {
+        let recorded = if status == ChainStatus::Unknown && blockhash_expired(trade, block_height) {
+            self.recorded_signatures(trade.id).await?
+        } else {
+            Vec::new()
+        };
+
+        let settlement = self.settle(trade, signature, status, block_height, &recorded).await?;
+        self.apply(trade, signature, settlement).await
+    }
+
+    /// Every signature the trade has been submitted with, oldest first
+    async fn recorded_signatures(&self, trade_id: uuid::Uuid) -> Result<Vec<Signature>> {
+        let recorded = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT signature FROM trade_status_events
+            WHERE trade_id = $1 AND signature IS NOT NULL
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(trade_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut signatures = Vec::with_capacity(recorded.len());
+        for signature in recorded {
+            let signature = Signature::from_str(&signature)?;
+            if !signatures.contains(&signature) {
+                signatures.push(signature);
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Decide what a poll means for a trade. `recorded` holds every
+    /// signature the trade was sent with and is only consulted once the
+    /// current blockhash has expired.
+    async fn settle(
+        &self,
+        trade: &Trade,
+        signature: Signature,
+        status: ChainStatus,
+        block_height: u64,
+        recorded: &[Signature],
+    ) -> Result<Settlement> {
+        let settlement = match status {
+            ChainStatus::Failed(err) => Settlement::Failed(format!("Transaction failed: {}", err)),
+            ChainStatus::Finalized => Settlement::Finalized(signature),
+            ChainStatus::Confirmed => Settlement::Confirmed(signature),
+            ChainStatus::Processed => Settlement::Pending,
+            ChainStatus::Unknown => {
+                if trade.lifecycle_status()? == TradeStatus::Submitted && blockhash_expired(trade, block_height) {
+                    self.settle_expired(trade, signature, recorded).await?
+                } else {
+                    Settlement::Pending
+                }
+            }
+        };
+        Ok(settlement)
+    }
+
+    /// The current blockhash has expired. An earlier submission may still
+    /// have landed after dropping out of the status cache, so history is
+    /// searched before anything is sent again.
+    async fn settle_expired(&self, trade: &Trade, signature: Signature, recorded: &[Signature]) -> Result<Settlement> {
+        let mut signatures = recorded.to_vec();
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
+        }
+
+        let statuses = self.executor.signature_history(&signatures).await?;
+        let history: Vec<(Signature, ChainStatus)> = signatures.into_iter().zip(statuses).collect();
+
+        // A landed submission wins over one that failed on chain
+        for (signature, status) in &history {
+            match status {
+                ChainStatus::Finalized => return Ok(Settlement::Finalized(*signature)),
+                ChainStatus::Confirmed => return Ok(Settlement::Confirmed(*signature)),
+                _ => {}
+            }
+        }
+        if history.iter().any(|(_, status)| *status == ChainStatus::Processed) {
+            // Landed and may still confirm
+            return Ok(Settlement::Pending);
+        }
+        if let Some(err) = history.iter().find_map(|(_, status)| match status {
+            ChainStatus::Failed(err) => Some(err),
+            _ => None,
+        }) {
+            return Ok(Settlement::Failed(format!("Transaction failed: {}", err)));
+        }
+
+        if trade.submit_attempts >= self.config.max_submit_attempts {
+            return Ok(Settlement::Expired(format!(
+                "Blockhash expired after {} submission attempts",
+                trade.submit_attempts
+            )));
+        }
+
+        let rebuilt = async {
+            let prepared = self.executor.prepare(trade).await?;
+            let report = self.executor.simulate(trade, &prepared).await?;
+            if !report.success {
+                return Err(anyhow!(report.failure_message()));
+            }
+            Ok::<_, anyhow::Error>(prepared)
+        }
+        .await;
+
+        Ok(match rebuilt {
+            Ok(prepared) => Settlement::Resubmit(Box::new(prepared)),
+            Err(e) => Settlement::Expired(format!("Blockhash expired and resubmission failed: {}", e)),
+        })
+    }
+
+    async fn apply(&self, trade: &Trade, signature: Signature, settlement: Settlement) -> Result<()> {
+        let current = trade.lifecycle_status()?;
+
+        match settlement {
+            Settlement::Pending => {}
+            Settlement::Failed(message) => {
+                lifecycle::transition(&self.pool, trade.id, current, TradeStatus::Failed, TransitionDetails::message(message))
+                    .await?;
+            }
+            Settlement::Finalized(landed) => {
+                self.adopt_signature(trade, signature, landed).await?;
+                if current == TradeStatus::Submitted {
+                    // Record the intermediate step so every transition has a timestamp
+                    lifecycle::transition(&self.pool, trade.id, current, TradeStatus::Confirmed, TransitionDetails::default())
+                        .await?;
+                }
+                let finalized = lifecycle::transition(
+                    &self.pool,
+                    trade.id,
+                    TradeStatus::Confirmed,
+                    TradeStatus::Finalized,
+                    TransitionDetails::default(),
+                )
+                .await?;
+                if finalized {
+                    self.record_fill(trade, &landed).await;
+                }
+            }
+            Settlement::Confirmed(landed) => {
+                if current == TradeStatus::Submitted {
+                    self.adopt_signature(trade, signature, landed).await?;
+                    let confirmed = lifecycle::transition(
+                        &self.pool,
+                        trade.id,
+                        current,
+                        TradeStatus::Confirmed,
+                        TransitionDetails::default(),
+                    )
+                    .await?;
+                    if confirmed {
+                        self.record_fill(trade, &landed).await;
+                    }
+                }
+            }
+            Settlement::Expired(message) => {
+                lifecycle::transition(
+                    &self.pool,
+                    trade.id,
+                    TradeStatus::Submitted,
+                    TradeStatus::Expired,
+                    TransitionDetails::message(message),
+                )
+                .await?;
+            }
+            Settlement::Resubmit(prepared) => self.resubmit(trade, &prepared).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Point the trade at the signature that landed when it was an earlier
+    /// submission than the one last recorded
+    async fn adopt_signature(&self, trade: &Trade, current: Signature, landed: Signature) -> Result<()> {
+        if landed == current {
+            return Ok(());
+        }
+
+        log::info!("Trade {} landed under its earlier signature {}", trade.id, landed);
+        sqlx::query(
+            "UPDATE trades SET signature = $2, transaction_hash = $2 WHERE id = $1 AND status IN ('submitted', 'confirmed')"
+        )
+        .bind(trade.id)
+        .bind(landed.to_string())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Replace the simulated amounts with what the landed transaction
+    /// actually traded. Recorded on confirmation and again on finality, so
+    /// a failed read is retried once; until then the expected fill stands.
+    async fn record_fill(&self, trade: &Trade, signature: &Signature) {
+        let recorded = async {
+            let fill = self.executor.fill(trade, signature).await?;
+            sqlx::query(
+                "UPDATE trades SET token_amount = $2, price_per_token = $3, fee_sol = $4 WHERE id = $1"
+            )
+            .bind(trade.id)
+            .bind(fill.token_amount)
+            .bind(fill.price_per_token)
+            .bind(fill.fee_sol)
+            .execute(&self.pool)
+            .await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        if let Err(e) = recorded {
+            log::warn!("Keeping the expected fill of trade {}: {}", trade.id, e);
+        }
+    }
+
+    /// Record the rebuilt transaction's signature and blockhash, then send
+    /// it. If the send fails the trade stays submitted under the new
+    /// signature and is settled again once that blockhash expires.
+    async fn resubmit(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<()> {
+        log::info!("Resubmitting trade {} with a fresh blockhash (attempt {})", trade.id, trade.submit_attempts + 1);
+
+        let recorded = lifecycle::transition(
+            &self.pool,
+            trade.id,
+            TradeStatus::Submitted,
+            TradeStatus::Submitted,
+            TransitionDetails::submission(prepared.signature().to_string(), prepared.last_valid_block_height),
+        )
+        .await?;
+        if !recorded {
+            return Ok(());
+        }
+
+        if let Err(e) = self.executor.submit(trade, prepared).await {
+            log::warn!("Resubmission of trade {} is unconfirmed: {}", trade.id, e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::jupiter::{SwapMode, SwapQuote};
+    use crate::trading::simulation::SimulationReport;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use solana_sdk::transaction::VersionedTransaction;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// Chain whose transaction history is set up by the test
+    #[derive(Default)]
+    struct StubChain {
+        history: HashMap<Signature, ChainStatus>,
+        simulation_error: Option<String>,
+        prepared: Mutex<u32>,
+        submitted: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl TradeExecutor for StubChain {
+        async fn prepare(&self, _trade: &Trade) -> Result<PreparedTrade> {
+            *self.prepared.lock().unwrap() += 1;
+            Ok(PreparedTrade {
+                transaction: VersionedTransaction {
+                    signatures: vec![Signature::new_unique()],
+                    ..Default::default()
+                },
+                last_valid_block_height: 2_000,
+                quote: SwapQuote {
+                    input_mint: String::new(),
+                    in_amount: "0".to_string(),
+                    output_mint: String::new(),
+                    out_amount: "0".to_string(),
+                    other_amount_threshold: "0".to_string(),
+                    swap_mode: SwapMode::ExactIn,
+                    slippage_bps: 100,
+                    price_impact_pct: "0".to_string(),
+                    route_plan: serde_json::Value::Null,
+                    extra: serde_json::Map::new(),
+                },
+            })
+        }
+
+        async fn simulate(&self, _trade: &Trade, _prepared: &PreparedTrade) -> Result<SimulationReport> {
+            Ok(SimulationReport {
+                success: self.simulation_error.is_none(),
+                error: self.simulation_error.clone(),
+                input_mint: String::new(),
+                output_mint: String::new(),
+                sol_amount: Decimal::ONE,
+                expected_token_amount: Decimal::ONE,
+                worst_case_token_amount: Decimal::ONE,
+                price_per_token: None,
+                price_impact_pct: Decimal::ZERO,
+                network_fee_lamports: 5_000,
+                priority_fee_lamports: 0,
+                compute_units_consumed: None,
+                logs: vec![],
+            })
+        }
+
+        async fn dry_run(&self, _trade: &Trade) -> Result<SimulationReport> {
+            Err(anyhow!("not used"))
+        }
+
+        async fn submit(&self, _trade: &Trade, prepared: &PreparedTrade) -> Result<Signature> {
+            *self.submitted.lock().unwrap() += 1;
+            Ok(prepared.signature())
+        }
+
+        async fn signature_statuses(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+            Ok(vec![ChainStatus::Unknown; signatures.len()])
+        }
+
+        async fn signature_history(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+            Ok(signatures
+                .iter()
+                .map(|signature| self.history.get(signature).cloned().unwrap_or(ChainStatus::Unknown))
+                .collect())
+        }
+
+        async fn block_height(&self) -> Result<u64> {
+            Ok(1_000)
+        }
+    }
+
+    fn stub_confirmer(chain: StubChain) -> (TradeConfirmer, Arc<StubChain>) {
+        let chain = Arc::new(chain);
+        let pool = PgPoolOptions::new().connect_lazy("postgres://localhost:1/unused").unwrap();
+        (TradeConfirmer::new(pool, chain.clone(), ConfirmerConfig::default()), chain)
+    }
+
+    /// A submitted trade whose blockhash is valid through block 900
+    fn submitted(signature: Signature, submit_attempts: i32) -> Trade {
+        Trade {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            wallet_id: Uuid::new_v4(),
+            token_address: "Mint".to_string(),
+            token_symbol: None,
+            trade_type: "buy".to_string(),
+            sol_amount: Decimal::ONE,
+            token_amount: None,
+            price_per_token: None,
+            slippage_tolerance: None,
+            priority_fee: None,
+            transaction_hash: Some(signature.to_string()),
+            signature: Some(signature.to_string()),
+            status: TradeStatus::Submitted.as_str().to_string(),
+            error_message: None,
+            bot_type: None,
+            created_at: Utc::now(),
+            simulated_at: None,
+            executed_at: Some(Utc::now()),
+            confirmed_at: None,
+            finalized_at: None,
+            last_valid_block_height: Some(900),
+            submit_attempts,
+            fee_sol: None,
+            is_paper: false,
+            is_liquidation: false,
+            bot_config_id: None,
+            bot_config_revision: None,
+            bundle_id: None,
+            source_signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_and_finalized_trades_advance() {
+        let (confirmer, chain) = stub_confirmer(StubChain::default());
+        let signature = Signature::new_unique();
+        let trade = submitted(signature, 1);
+
+        let confirmed = confirmer.settle(&trade, signature, ChainStatus::Confirmed, 1_000, &[]).await.unwrap();
+        assert!(matches!(confirmed, Settlement::Confirmed(s) if s == signature));
+
+        let finalized = confirmer.settle(&trade, signature, ChainStatus::Finalized, 1_000, &[]).await.unwrap();
+        assert!(matches!(finalized, Settlement::Finalized(s) if s == signature));
+
+        let processed = confirmer.settle(&trade, signature, ChainStatus::Processed, 1_000, &[]).await.unwrap();
+        assert!(matches!(processed, Settlement::Pending));
+
+        // Not seen yet, but its blockhash is still valid
+        let unseen = confirmer.settle(&trade, signature, ChainStatus::Unknown, 800, &[]).await.unwrap();
+        assert!(matches!(unseen, Settlement::Pending));
+        assert_eq!(*chain.prepared.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_fails_the_trade() {
+        let (confirmer, _) = stub_confirmer(StubChain::default());
+        let signature = Signature::new_unique();
+        let trade = submitted(signature, 1);
+
+        let status = ChainStatus::Failed("custom program error: 0x1771".to_string());
+        match confirmer.settle(&trade, signature, status, 1_000, &[]).await.unwrap() {
+            Settlement::Failed(message) => assert!(message.contains("0x1771")),
+            other => panic!("expected a failure, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_trade_is_rebuilt_but_not_sent_before_it_is_recorded() {
+        let (confirmer, chain) = stub_confirmer(StubChain::default());
+        let first = Signature::new_unique();
+        let second = Signature::new_unique();
+        let trade = submitted(second, 2);
+
+        match confirmer.settle(&trade, second, ChainStatus::Unknown, 1_000, &[first, second]).await.unwrap() {
+            Settlement::Resubmit(prepared) => {
+                assert_ne!(prepared.signature(), second);
+                assert_eq!(prepared.last_valid_block_height, 2_000);
+            }
+            other => panic!("expected a resubmission, got {:?}", other),
+        }
+        // Sending waits until the new signature is recorded
+        assert_eq!(*chain.submitted.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_trade_that_landed_earlier_is_not_resubmitted() {
+        let first = Signature::new_unique();
+        let second = Signature::new_unique();
+        let mut chain = StubChain::default();
+        // The first submission aged out of the status cache after landing
+        chain.history.insert(first, ChainStatus::Finalized);
+        let (confirmer, chain) = stub_confirmer(chain);
+        let trade = submitted(second, 2);
+
+        let settlement = confirmer.settle(&trade, second, ChainStatus::Unknown, 1_000, &[first, second]).await.unwrap();
+        assert!(matches!(settlement, Settlement::Finalized(s) if s == first));
+        assert_eq!(*chain.prepared.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_trade_expires_once_attempts_are_exhausted() {
+        let (confirmer, chain) = stub_confirmer(StubChain::default());
+        let signature = Signature::new_unique();
+        let trade = submitted(signature, ConfirmerConfig::default().max_submit_attempts);
+
+        match confirmer.settle(&trade, signature, ChainStatus::Unknown, 1_000, &[signature]).await.unwrap() {
+            Settlement::Expired(message) => assert!(message.contains("3 submission attempts")),
+            other => panic!("expected expiry, got {:?}", other),
+        }
+        assert_eq!(*chain.prepared.lock().unwrap(), 0);
+
+        // A rebuild that no longer simulates expires the trade too
+        let (confirmer, _) = stub_confirmer(StubChain {
+            simulation_error: Some("slippage".to_string()),
+            ..Default::default()
+        });
+        let settlement = confirmer.settle(&submitted(signature, 1), signature, ChainStatus::Unknown, 1_000, &[signature]).await.unwrap();
+        assert!(matches!(settlement, Settlement::Expired(message) if message.contains("resubmission failed")));
+    }
+}
@@ -0,0 +1,656 @@
+//! Trade engine: the single path every trade takes from request to chain
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::{Bundle, CreateTradeRequest, Trade};
+use crate::trading::bundle::{
+    bundle_id, status as bundle_status, BlockEngine, BundleNotSent, BundleStatus, BUNDLE_TIMEOUT_SECS,
+    MAX_BUNDLE_TRANSACTIONS,
+};
+use crate::trading::executor::{lamports_to_sol, sol_to_lamports, NotSent, TradeExecutor};
+use crate::trading::kill_switch::{self, TradingHalted};
+use crate::trading::lifecycle::{self, TradeStatus, TransitionDetails};
+use crate::trading::risk::RiskManager;
+use crate::trading::simulation::SimulationReport;
+
+/// Validate a trade request before anything is persisted
+pub fn validate_trade_request(req: &CreateTradeRequest) -> Result<()> {
+    if req.trade_type != "buy" && req.trade_type != "sell" {
+        return Err(anyhow!("Trade type must be 'buy' or 'sell'"));
+    }
+
+    if req.sol_amount <= Decimal::ZERO {
+        return Err(anyhow!("SOL amount must be greater than zero"));
+    }
+
+    if let Some(slippage) = req.slippage_tolerance {
+        if slippage < Decimal::ZERO || slippage > Decimal::from(100) {
+            return Err(anyhow!("Slippage tolerance must be between 0 and 100 percent"));
+        }
+    }
+
+    if let Some(fee) = req.priority_fee {
+        if fee < Decimal::ZERO {
+            return Err(anyhow!("Priority fee cannot be negative"));
+        }
+    }
+
+    if Pubkey::from_str(&req.token_address).is_err() {
+        return Err(anyhow!("Token address is not a valid Solana public key"));
+    }
+
+    Ok(())
+}
+
+/// Where a trade came from and how it should be executed
+#[derive(Debug, Clone, Default)]
+pub struct TradeOptions {
+    pub bot_type: Option<String>,
+    /// Bot configuration revision the trade is placed under
+    pub bot_config_id: Option<Uuid>,
+    pub bot_config_revision: Option<i32>,
+    /// Transaction a copy-trading bot is mirroring
+    pub source_signature: Option<String>,
+    /// Settle against the virtual ledger instead of the chain
+    pub paper: bool,
+    /// Panic-sell liquidation; a sell placed with it goes through an
+    /// engaged kill switch
+    pub liquidation: bool,
+}
+
+impl TradeOptions {
+    fn is_liquidation(&self, req: &CreateTradeRequest) -> bool {
+        self.liquidation && req.trade_type == "sell"
+    }
+}
+
+/// Outcome of executing a bundle. `bundle` is `None` when nothing was
+/// sent; the trades then carry the reason.
+#[derive(Debug, Clone)]
+pub struct BundleExecution {
+    pub bundle: Option<Bundle>,
+    pub trades: Vec<Trade>,
+}
+
+/// Creates trades and drives them onto the chain
+#[derive(Clone)]
+pub struct TradeEngine {
+    pool: PgPool,
+    executor: Arc<dyn TradeExecutor>,
+    paper: Option<Arc<dyn TradeExecutor>>,
+    risk: RiskManager,
+}
+
+impl TradeEngine {
+    pub fn new(pool: PgPool, executor: Arc<dyn TradeExecutor>) -> Self {
+        Self {
+            risk: RiskManager::new(pool.clone()),
+            pool,
+            executor,
+            paper: None,
+        }
+    }
+
+    /// Enable paper trading through the given executor
+    pub fn with_paper_executor(mut self, paper: Arc<dyn TradeExecutor>) -> Self {
+        self.paper = Some(paper);
+        self
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    pub fn executor(&self) -> &Arc<dyn TradeExecutor> {
+        &self.executor
+    }
+
+    /// Executor responsible for a trade, live or paper
+    pub fn executor_for(&self, trade: &Trade) -> Result<&Arc<dyn TradeExecutor>> {
+        self.executor_for_mode(trade.is_paper)
+    }
+
+    pub fn executor_for_mode(&self, paper: bool) -> Result<&Arc<dyn TradeExecutor>> {
+        if paper {
+            self.paper.as_ref().ok_or_else(|| anyhow!("Paper trading is not enabled"))
+        } else {
+            Ok(&self.executor)
+        }
+    }
+
+    /// Whether a user has paper-trading mode switched on
+    pub async fn user_paper_mode(&self, user_id: Uuid) -> Result<bool> {
+        let enabled = sqlx::query_scalar::<_, bool>("SELECT paper_trading FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(enabled.unwrap_or(false))
+    }
+
+    /// Simulate a trade request without persisting, signing or broadcasting it
+    pub async fn dry_run(
+        &self,
+        user_id: Uuid,
+        req: &CreateTradeRequest,
+        options: &TradeOptions,
+    ) -> Result<SimulationReport> {
+        let draft = draft_trade(user_id, req, options);
+        self.executor_for(&draft)?.dry_run(&draft).await
+    }
+
+    /// Persist a new trade in the `created` state. Fails with
+    /// `TradingHalted` while a kill switch covers the user, unless it is a
+    /// liquidation sell.
+    pub async fn create_trade(
+        &self,
+        user_id: Uuid,
+        req: &CreateTradeRequest,
+        options: &TradeOptions,
+    ) -> Result<Trade> {
+        let liquidation = options.is_liquidation(req);
+        if !liquidation {
+            kill_switch::ensure_trading_allowed(&self.pool, user_id).await?;
+        }
+
+        let trade_id = Uuid::new_v4();
+
+        sqlx::query(
+            r#"
+            INSERT INTO trades (id, user_id, wallet_id, token_address, trade_type, sol_amount,
+                                slippage_tolerance, priority_fee, bot_type, is_paper, status,
+                                bot_config_id, bot_config_revision, source_signature, is_liquidation)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            "#
+        )
+        .bind(trade_id)
+        .bind(user_id)
+        .bind(req.wallet_id)
+        .bind(&req.token_address)
+        .bind(&req.trade_type)
+        .bind(req.sol_amount)
+        .bind(req.slippage_tolerance)
+        .bind(req.priority_fee)
+        .bind(&options.bot_type)
+        .bind(options.paper)
+        .bind(TradeStatus::Created.as_str())
+        .bind(options.bot_config_id)
+        .bind(options.bot_config_revision)
+        .bind(&options.source_signature)
+        .bind(liquidation)
+        .execute(&self.pool)
+        .await?;
+
+        lifecycle::record_created(&self.pool, trade_id).await?;
+
+        self.load(trade_id).await
+    }
+
+    /// Build, sign, simulate and broadcast a trade. A trade that breaks a
+    /// risk limit or whose simulation fails is never broadcast. Failures
+    /// before broadcast are recorded on the trade rather than returned.
+    pub async fn execute(&self, trade: &Trade) -> Result<Trade> {
+        let status = trade.lifecycle_status()?;
+        if status != TradeStatus::Created {
+            return Err(anyhow!("Trade {} cannot be executed from status {}", trade.id, status));
+        }
+
+        let executor = self.executor_for(trade)?;
+
+        if let Some(rejection) = self.check_risk(trade, executor.as_ref()).await {
+            log::warn!("Rejecting trade {}: {}", trade.id, rejection);
+            self.fail(trade.id, status, &rejection).await?;
+            return self.load(trade.id).await;
+        }
+
+        let prepared = match executor.prepare(trade).await {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                log::warn!("Failed to prepare trade {}: {}", trade.id, e);
+                self.fail(trade.id, status, &e.to_string()).await?;
+                return self.load(trade.id).await;
+            }
+        };
+
+        let report = match executor.simulate(trade, &prepared).await {
+            Ok(report) => report,
+            Err(e) => {
+                log::warn!("Failed to simulate trade {}: {}", trade.id, e);
+                self.fail(trade.id, status, &format!("Simulation failed: {}", e)).await?;
+                return self.load(trade.id).await;
+            }
+        };
+
+        if !report.success {
+            log::warn!("Aborting trade {}: {}", trade.id, report.failure_message());
+            self.fail(trade.id, status, &report.failure_message()).await?;
+            return self.load(trade.id).await;
+        }
+
+        self.record_expected_fill(trade.id, &report).await?;
+        let simulated = lifecycle::transition(
+            &self.pool,
+            trade.id,
+            status,
+            TradeStatus::Simulated,
+            TransitionDetails::message(format!(
+                "Simulated: {} compute units",
+                report.compute_units_consumed.unwrap_or_default()
+            )),
+        )
+        .await?;
+
+        if !simulated {
+            // Cancelled while we were simulating
+            return self.load(trade.id).await;
+        }
+
+        // Claim the trade before broadcasting so a cancellation after
+        // simulation either wins here or comes too late to stop it
+        let claimed = lifecycle::transition(
+            &self.pool,
+            trade.id,
+            TradeStatus::Simulated,
+            TradeStatus::Submitted,
+            TransitionDetails::submission(prepared.signature().to_string(), prepared.last_valid_block_height),
+        )
+        .await?;
+
+        if !claimed {
+            // Cancelled after simulation
+            return self.load(trade.id).await;
+        }
+
+        match executor.submit(trade, &prepared).await {
+            Ok(_) => {
+                if trade.is_paper {
+                    self.settle_paper(trade.id).await?;
+                }
+            }
+            Err(e) if e.is::<NotSent>() => {
+                log::warn!("Failed to submit trade {}: {}", trade.id, e);
+                self.fail(trade.id, TradeStatus::Submitted, &e.to_string()).await?;
+            }
+            Err(e) => {
+                // It may have gone out; the confirmer settles it by its
+                // recorded signature or once its blockhash expires
+                log::warn!("Submission of trade {} is unconfirmed: {}", trade.id, e);
+            }
+        }
+
+        self.load(trade.id).await
+    }
+
+    /// Execute trades as one atomic bundle: every leg is prepared and
+    /// simulated, then the signed legs and a tip transfer from the tip
+    /// wallet go to the block engine together. If any leg fails before
+    /// submission nothing is sent and every leg is failed.
+    pub async fn execute_bundle(
+        &self,
+        trades: &[Trade],
+        tip_wallet_id: Uuid,
+        tip_sol: Decimal,
+        engine: &dyn BlockEngine,
+    ) -> Result<BundleExecution> {
+        let first = trades.first().ok_or_else(|| anyhow!("A bundle needs at least one trade"))?;
+        if trades.len() >= MAX_BUNDLE_TRANSACTIONS {
+            return Err(anyhow!("A bundle holds at most {} trades plus its tip", MAX_BUNDLE_TRANSACTIONS - 1));
+        }
+        for trade in trades {
+            if trade.is_paper {
+                return Err(anyhow!("Paper trades cannot be bundled"));
+            }
+            let status = trade.lifecycle_status()?;
+            if status != TradeStatus::Created {
+                return Err(anyhow!("Trade {} cannot be bundled from status {}", trade.id, status));
+            }
+        }
+
+        for trade in trades {
+            if let Some(rejection) = self.check_risk(trade, self.executor.as_ref()).await {
+                log::warn!("Aborting bundle, trade {} rejected: {}", trade.id, rejection);
+                return self.abort_bundle(trades, trade.id, &rejection).await;
+            }
+        }
+
+        let mut prepared = Vec::with_capacity(trades.len());
+        for trade in trades {
+            let failure = match self.executor.prepare(trade).await {
+                Ok(leg) => match self.executor.simulate(trade, &leg).await {
+                    Ok(report) if report.success => {
+                        prepared.push((leg, report));
+                        continue;
+                    }
+                    Ok(report) => report.failure_message(),
+                    Err(e) => format!("Simulation failed: {}", e),
+                },
+                Err(e) => e.to_string(),
+            };
+
+            log::warn!("Aborting bundle, trade {} failed: {}", trade.id, failure);
+            return self.abort_bundle(trades, trade.id, &failure).await;
+        }
+
+        for (trade, (_, report)) in trades.iter().zip(&prepared) {
+            self.record_expected_fill(trade.id, report).await?;
+            let simulated = lifecycle::transition(
+                &self.pool,
+                trade.id,
+                TradeStatus::Created,
+                TradeStatus::Simulated,
+                TransitionDetails::message("Simulated as a bundle leg"),
+            )
+            .await?;
+
+            if !simulated {
+                // Cancelled while we were simulating; the rest can't land without it
+                return self.abort_bundle(trades, trade.id, "Cancelled").await;
+            }
+        }
+
+        // Claim every leg before anything is sent, as `execute` does
+        for (trade, (leg, _)) in trades.iter().zip(&prepared) {
+            let claimed = lifecycle::transition(
+                &self.pool,
+                trade.id,
+                TradeStatus::Simulated,
+                TradeStatus::Submitted,
+                TransitionDetails::submission(leg.signature().to_string(), leg.last_valid_block_height),
+            )
+            .await?;
+
+            if !claimed {
+                // Cancelled after simulation; the rest can't land without it
+                return self.abort_bundle(trades, trade.id, "Cancelled").await;
+            }
+        }
+
+        let tipped = async {
+            let tip_account = engine.tip_account().await?;
+            let blockhash = *prepared[0].0.transaction.message.recent_blockhash();
+            self.executor
+                .sign_transfer(tip_wallet_id, &tip_account, sol_to_lamports(tip_sol)?, blockhash)
+                .await
+        };
+
+        let tip = match tipped.await {
+            Ok(tip) => tip,
+            Err(e) => {
+                log::warn!("Failed to sign bundle tip: {}", e);
+                let message = format!("Bundle submission failed: {}", e);
+                self.fail_all(trades, TradeStatus::Submitted, &message).await?;
+                return Ok(BundleExecution { bundle: None, trades: self.load_all(trades).await? });
+            }
+        };
+
+        let mut transactions: Vec<_> = prepared.iter().map(|(leg, _)| leg.transaction.clone()).collect();
+        transactions.push(tip.clone());
+
+        let engine_bundle_id = match engine.send_bundle(&transactions).await {
+            Ok(engine_bundle_id) => engine_bundle_id,
+            Err(e) if e.is::<BundleNotSent>() => {
+                log::warn!("Failed to submit bundle: {}", e);
+                let message = format!("Bundle submission failed: {}", e);
+                self.fail_all(trades, TradeStatus::Submitted, &message).await?;
+                return Ok(BundleExecution { bundle: None, trades: self.load_all(trades).await? });
+            }
+            Err(e) => {
+                // It may have been accepted; track it by the id the engine
+                // would have given it until it lands or times out
+                log::warn!("Bundle submission is unconfirmed: {}", e);
+                bundle_id(&transactions)
+            }
+        };
+
+        let bundle = sqlx::query_as::<_, Bundle>(
+            r#"
+            INSERT INTO bundles (user_id, bot_id, engine_bundle_id, status, tip_wallet_id, tip_sol, tip_signature)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(first.user_id)
+        .bind(first.bot_config_id)
+        .bind(&engine_bundle_id)
+        .bind(bundle_status::PENDING)
+        .bind(tip_wallet_id)
+        .bind(tip_sol)
+        .bind(tip.signatures.first().map(|s| s.to_string()))
+        .fetch_one(&self.pool)
+        .await?;
+
+        for trade in trades {
+            sqlx::query("UPDATE trades SET bundle_id = $2 WHERE id = $1")
+                .bind(trade.id)
+                .bind(bundle.id)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(BundleExecution { bundle: Some(bundle), trades: self.load_all(trades).await? })
+    }
+
+    /// Ask the block engine about a pending bundle and record the answer.
+    /// A bundle that fails, or is still pending after its blockhash has
+    /// expired, fails its legs that are still awaiting confirmation.
+    pub async fn refresh_bundle(&self, bundle: &Bundle, engine: &dyn BlockEngine) -> Result<Bundle> {
+        if bundle.status != bundle_status::PENDING {
+            return Ok(bundle.clone());
+        }
+
+        let failure = match engine.bundle_status(&bundle.engine_bundle_id).await? {
+            BundleStatus::Landed { slot } => {
+                let landed = sqlx::query_as::<_, Bundle>(
+                    "UPDATE bundles SET status = $2, landed_slot = $3 WHERE id = $1 RETURNING *"
+                )
+                .bind(bundle.id)
+                .bind(bundle_status::LANDED)
+                .bind(slot as i64)
+                .fetch_one(&self.pool)
+                .await?;
+                return Ok(landed);
+            }
+            BundleStatus::Failed(reason) => reason,
+            BundleStatus::Pending | BundleStatus::Unknown => {
+                if Utc::now() - bundle.created_at < chrono::Duration::seconds(BUNDLE_TIMEOUT_SECS) {
+                    return Ok(bundle.clone());
+                }
+                "Bundle did not land before its blockhash expired".to_string()
+            }
+        };
+
+        let failed = sqlx::query_as::<_, Bundle>(
+            "UPDATE bundles SET status = $2, error_message = $3 WHERE id = $1 RETURNING *"
+        )
+        .bind(bundle.id)
+        .bind(bundle_status::FAILED)
+        .bind(&failure)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let legs = sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE bundle_id = $1 AND status = $2")
+            .bind(bundle.id)
+            .bind(TradeStatus::Submitted.as_str())
+            .fetch_all(&self.pool)
+            .await?;
+        self.fail_all(&legs, TradeStatus::Submitted, &failure).await?;
+
+        Ok(failed)
+    }
+
+    /// Fail every leg of a bundle that never left, naming the one at fault
+    async fn abort_bundle(&self, trades: &[Trade], culprit: Uuid, reason: &str) -> Result<BundleExecution> {
+        for trade in trades {
+            let message = if trade.id == culprit {
+                reason.to_string()
+            } else {
+                format!("Bundle aborted: trade {} failed", culprit)
+            };
+            // Legs ahead of the culprit may already be simulated or claimed but
+            // never sent; a cancelled one stays cancelled
+            let status = self.load(trade.id).await?.lifecycle_status()?;
+            if matches!(status, TradeStatus::Created | TradeStatus::Simulated | TradeStatus::Submitted) {
+                self.fail(trade.id, status, &message).await?;
+            }
+        }
+        Ok(BundleExecution { bundle: None, trades: self.load_all(trades).await? })
+    }
+
+    async fn fail_all(&self, trades: &[Trade], from: TradeStatus, message: &str) -> Result<()> {
+        for trade in trades {
+            self.fail(trade.id, from, message).await?;
+        }
+        Ok(())
+    }
+
+    async fn load_all(&self, trades: &[Trade]) -> Result<Vec<Trade>> {
+        let mut loaded = Vec::with_capacity(trades.len());
+        for trade in trades {
+            loaded.push(self.load(trade.id).await?);
+        }
+        Ok(loaded)
+    }
+
+    /// Why a trade may not execute: a kill switch engaged since it was
+    /// created, or a broken risk limit. A check that can't be completed
+    /// rejects the trade too. Liquidation sells ignore the kill switch.
+    async fn check_risk(&self, trade: &Trade, executor: &dyn TradeExecutor) -> Option<String> {
+        if !trade.is_liquidation {
+            match kill_switch::halting(&self.pool, trade.user_id).await {
+                Ok(Some(switch)) => return Some(TradingHalted { switch }.to_string()),
+                Ok(None) => {}
+                Err(e) => return Some(format!("Kill switch check failed: {}", e)),
+            }
+        }
+
+        match self.risk.check(trade, executor).await {
+            Ok(rejection) => rejection.map(|rejection| rejection.to_string()),
+            Err(e) => Some(format!("Risk check failed: {}", e)),
+        }
+    }
+
+    /// Paper fills settle on submission, so walk straight to finality
+    async fn settle_paper(&self, trade_id: Uuid) -> Result<()> {
+        let details = || TransitionDetails::message("Paper fill");
+        lifecycle::transition(&self.pool, trade_id, TradeStatus::Submitted, TradeStatus::Confirmed, details())
+            .await?;
+        lifecycle::transition(&self.pool, trade_id, TradeStatus::Confirmed, TradeStatus::Finalized, details())
+            .await?;
+        Ok(())
+    }
+
+    /// Store the simulated token amount, price and fee until the fill is
+    /// known; a trade cancelled meanwhile keeps what it had
+    async fn record_expected_fill(&self, trade_id: Uuid, report: &SimulationReport) -> Result<()> {
+        let fee_lamports = report.network_fee_lamports + report.priority_fee_lamports;
+
+        sqlx::query(
+            "UPDATE trades SET token_amount = $2, price_per_token = $3, fee_sol = $4 WHERE id = $1 AND status = $5"
+        )
+        .bind(trade_id)
+        .bind(report.expected_token_amount)
+        .bind(report.price_per_token)
+        .bind(lamports_to_sol(fee_lamports))
+        .bind(TradeStatus::Created.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn fail(&self, trade_id: Uuid, from: TradeStatus, message: &str) -> Result<()> {
+        lifecycle::transition(
+            &self.pool,
+            trade_id,
+            from,
+            TradeStatus::Failed,
+            TransitionDetails::message(message),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Reload a trade from the database
+    pub async fn load(&self, trade_id: Uuid) -> Result<Trade> {
+        let trade = sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE id = $1")
+            .bind(trade_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(trade)
+    }
+}
+
+/// In-memory trade for a request, used where nothing should be persisted
+pub(crate) fn draft_trade(user_id: Uuid, req: &CreateTradeRequest, options: &TradeOptions) -> Trade {
+    Trade {
+        id: Uuid::new_v4(),
+        user_id,
+        wallet_id: req.wallet_id,
+        token_address: req.token_address.clone(),
+        token_symbol: None,
+        trade_type: req.trade_type.clone(),
+        sol_amount: req.sol_amount,
+        token_amount: None,
+        price_per_token: None,
+        slippage_tolerance: req.slippage_tolerance,
+        priority_fee: req.priority_fee,
+        transaction_hash: None,
+        signature: None,
+        status: TradeStatus::Created.as_str().to_string(),
+        error_message: None,
+        bot_type: options.bot_type.clone(),
+        created_at: chrono::Utc::now(),
+        simulated_at: None,
+        executed_at: None,
+        confirmed_at: None,
+        finalized_at: None,
+        last_valid_block_height: None,
+        submit_attempts: 0,
+        fee_sol: None,
+        is_paper: options.paper,
+        is_liquidation: options.is_liquidation(req),
+        bot_config_id: options.bot_config_id,
+        bot_config_revision: options.bot_config_revision,
+        bundle_id: None,
+        source_signature: options.source_signature.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(trade_type: &str, sol_amount: &str) -> CreateTradeRequest {
+        CreateTradeRequest {
+            wallet_id: Uuid::new_v4(),
+            token_address: "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string(),
+            trade_type: trade_type.to_string(),
+            sol_amount: Decimal::from_str(sol_amount).unwrap(),
+            slippage_tolerance: None,
+            priority_fee: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_request_validation() {
+        assert!(validate_trade_request(&request("buy", "0.1")).is_ok());
+        assert!(validate_trade_request(&request("sell", "0.1")).is_ok());
+        assert!(validate_trade_request(&request("hold", "0.1")).is_err());
+        assert!(validate_trade_request(&request("buy", "0")).is_err());
+
+        let mut bad_mint = request("buy", "0.1");
+        bad_mint.token_address = "not-a-mint".to_string();
+        assert!(validate_trade_request(&bad_mint).is_err());
+
+        let mut bad_slippage = request("buy", "0.1");
+        bad_slippage.slippage_tolerance = Some(Decimal::from(150));
+        assert!(validate_trade_request(&bad_slippage).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! Trade execution against Solana
+//!
+//! The `TradeExecutor` trait is everything the trade lifecycle needs from
+//! the chain: building a signed swap, broadcasting it and checking on it.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::{TransactionStatus, UiTransactionEncoding};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::database::models::{Trade, Wallet};
+use crate::trading::bundle::transfer_transaction;
+use crate::trading::fill::{self, ExecutedFill};
+use crate::trading::jupiter::{JupiterClient, SwapMode, SwapQuote, LAMPORTS_PER_SOL, SOL_MINT};
+use crate::trading::simulation::{SimulationReport, LAMPORTS_PER_SIGNATURE};
+use crate::wallet::decrypt_private_key;
+use crate::wallet::holdings::{sum_balances, token_accounts, TokenBalance};
+
+/// Slippage applied when a trade does not specify one (percent)
+pub const DEFAULT_SLIPPAGE_PERCENT: u32 = 1;
+
+/// On-chain state of a submitted signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainStatus {
+    /// Not seen by the cluster (yet)
+    Unknown,
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed(String),
+}
+
+impl ChainStatus {
+    pub(crate) fn from_status(status: Option<TransactionStatus>) -> Self {
+        match status {
+            None => ChainStatus::Unknown,
+            Some(status) => {
+                if let Some(err) = status.err {
+                    ChainStatus::Failed(err.to_string())
+                } else if status.satisfies_commitment(CommitmentConfig::finalized()) {
+                    ChainStatus::Finalized
+                } else if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                    ChainStatus::Confirmed
+                } else {
+                    ChainStatus::Processed
+                }
+            }
+        }
+    }
+}
+
+/// Submission error after which the transaction is known not to have been
+/// broadcast, so its trade can be failed. Any other submission error leaves
+/// the transaction possibly on the wire.
+#[derive(Debug)]
+pub struct NotSent(pub anyhow::Error);
+
+impl fmt::Display for NotSent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for NotSent {}
+
+/// An RPC error response to `sendTransaction` means the node refused the
+/// transaction; transport errors and timeouts say nothing either way
+pub(crate) fn send_error(error: ClientError) -> anyhow::Error {
+    match error.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { .. }) => NotSent(error.into()).into(),
+        _ => error.into(),
+    }
+}
+
+/// A signed transaction ready to broadcast
+#[derive(Debug, Clone)]
+pub struct PreparedTrade {
+    pub transaction: VersionedTransaction,
+    pub last_valid_block_height: u64,
+    pub quote: SwapQuote,
+}
+
+impl PreparedTrade {
+    pub fn signature(&self) -> Signature {
+        self.transaction.signatures.first().copied().unwrap_or_default()
+    }
+}
+
+/// Chain access used by the trade lifecycle
+#[async_trait]
+pub trait TradeExecutor: Send + Sync {
+    /// Quote, build and sign the swap for a trade against a fresh blockhash
+    async fn prepare(&self, trade: &Trade) -> Result<PreparedTrade>;
+
+    /// Simulate a signed transaction before it is broadcast
+    async fn simulate(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<SimulationReport>;
+
+    /// Build an unsigned transaction for a trade and simulate it without
+    /// signature verification; nothing is signed or broadcast
+    async fn dry_run(&self, trade: &Trade) -> Result<SimulationReport>;
+
+    /// Broadcast a prepared transaction. Fails with `NotSent` when the
+    /// transaction certainly didn't go out.
+    async fn submit(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<Signature>;
+
+    /// Look up the status of previously submitted signatures, in order
+    async fn signature_statuses(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>>;
+
+    /// Like `signature_statuses`, but also searching transaction history, so
+    /// transactions that have aged out of the status cache are still found
+    async fn signature_history(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>>;
+
+    /// Current block height, used to detect expired blockhashes
+    async fn block_height(&self) -> Result<u64>;
+
+    /// What a confirmed transaction of the trade actually traded
+    async fn fill(&self, _trade: &Trade, _signature: &Signature) -> Result<ExecutedFill> {
+        Err(anyhow!("This executor cannot read fills"))
+    }
+
+    /// Sign a SOL transfer from a wallet, such as a bundle tip
+    async fn sign_transfer(
+        &self,
+        _wallet_id: uuid::Uuid,
+        _to: &Pubkey,
+        _lamports: u64,
+        _recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        Err(anyhow!("This executor cannot sign transfers"))
+    }
+
+    /// SOL a wallet holds, as seen by this executor
+    async fn sol_balance(&self, _wallet_id: uuid::Uuid) -> Result<Decimal> {
+        Err(anyhow!("This executor cannot read balances"))
+    }
+
+    /// Non-zero token balances a wallet holds, wrapped SOL excluded
+    async fn token_balances(&self, _wallet_id: uuid::Uuid) -> Result<Vec<TokenBalance>> {
+        Err(anyhow!("This executor cannot read balances"))
+    }
+}
+
+/// Convert a SOL amount to lamports, rejecting negative or oversized values
+pub fn sol_to_lamports(sol: Decimal) -> Result<u64> {
+    (sol * Decimal::from(LAMPORTS_PER_SOL))
+        .trunc()
+        .to_u64()
+        .ok_or_else(|| anyhow!("Invalid SOL amount: {}", sol))
+}
+
+/// Convert lamports to a SOL amount
+pub fn lamports_to_sol(lamports: u64) -> Decimal {
+    Decimal::from(lamports) / Decimal::from(LAMPORTS_PER_SOL)
+}
+
+/// Convert a raw token amount to UI units
+pub fn raw_to_ui_amount(raw: u64, decimals: u8) -> Decimal {
+    Decimal::from_i128_with_scale(raw as i128, decimals as u32)
+}
+
+/// Slippage tolerance of a trade in basis points
+pub fn slippage_bps(trade: &Trade) -> u16 {
+    trade.slippage_tolerance
+        .map(|pct| (pct * Decimal::from(100)).round())
+        .and_then(|bps| bps.to_u16())
+        .unwrap_or(DEFAULT_SLIPPAGE_PERCENT as u16 * 100)
+}
+
+/// Executor that swaps through Jupiter and broadcasts over RPC
+pub struct SolanaExecutor {
+    pool: PgPool,
+    rpc: Arc<RpcClient>,
+    jupiter: JupiterClient,
+    master_key: [u8; 32],
+    decimals_cache: Mutex<HashMap<String, u8>>,
+}
+
+impl SolanaExecutor {
+    pub fn new(pool: PgPool, rpc: Arc<RpcClient>, jupiter: JupiterClient, master_key: [u8; 32]) -> Self {
+        Self {
+            pool,
+            rpc,
+            jupiter,
+            master_key,
+            decimals_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn rpc(&self) -> &Arc<RpcClient> {
+        &self.rpc
+    }
+
+    async fn load_wallet(&self, wallet_id: uuid::Uuid) -> Result<Wallet> {
+        sqlx::query_as::<_, Wallet>(
+            "SELECT * FROM wallets WHERE id = $1 AND is_active = true"
+        )
+        .bind(wallet_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Wallet {} not found", wallet_id))
+    }
+
+    /// Load and decrypt the signing keypair of a wallet
+    pub async fn load_keypair(&self, wallet_id: uuid::Uuid) -> Result<Keypair> {
+        let wallet = self.load_wallet(wallet_id).await?;
+        decrypt_private_key(&wallet.encrypted_private_key, &wallet.encryption_nonce, &self.master_key)
+    }
+
+    /// Decimals of a token mint, cached after the first lookup
+    pub async fn token_decimals(&self, mint: &str) -> Result<u8> {
+        if let Some(decimals) = self.decimals_cache.lock().unwrap().get(mint) {
+            return Ok(*decimals);
+        }
+
+        let supply = self.rpc.get_token_supply(&Pubkey::from_str(mint)?).await?;
+        self.decimals_cache.lock().unwrap().insert(mint.to_string(), supply.decimals);
+        Ok(supply.decimals)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        trade: &Trade,
+        transaction: &VersionedTransaction,
+        quote: &SwapQuote,
+        sig_verify: bool,
+    ) -> Result<SimulationReport> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify,
+            // Unsigned dry-run transactions may carry a stale blockhash
+            replace_recent_blockhash: !sig_verify,
+            commitment: Some(CommitmentConfig::processed()),
+            ..Default::default()
+        };
+
+        let result = self.rpc
+            .simulate_transaction_with_config(transaction, config)
+            .await?
+            .value;
+
+        let decimals = self.token_decimals(&trade.token_address).await?;
+        let (sol_raw, token_raw) = match quote.swap_mode {
+            SwapMode::ExactIn => (quote.in_amount_raw()?, quote.out_amount_raw()?),
+            SwapMode::ExactOut => (quote.out_amount_raw()?, quote.in_amount_raw()?),
+        };
+        let threshold: u64 = quote.other_amount_threshold.parse()
+            .map_err(|e| anyhow!("Invalid quote otherAmountThreshold: {}", e))?;
+
+        let sol_amount = lamports_to_sol(sol_raw);
+        let expected_token_amount = raw_to_ui_amount(token_raw, decimals);
+        let price_per_token = if expected_token_amount.is_zero() {
+            None
+        } else {
+            Some(sol_amount / expected_token_amount)
+        };
+        let price_impact_pct = Decimal::from_str(&quote.price_impact_pct)
+            .or_else(|_| Decimal::from_scientific(&quote.price_impact_pct))
+            .unwrap_or_default();
+
+        let signatures = transaction.message.header().num_required_signatures as u64;
+
+        Ok(SimulationReport {
+            success: result.err.is_none(),
+            error: result.err.map(|e| e.to_string()),
+            input_mint: quote.input_mint.clone(),
+            output_mint: quote.output_mint.clone(),
+            sol_amount,
+            expected_token_amount,
+            worst_case_token_amount: raw_to_ui_amount(threshold, decimals),
+            price_per_token,
+            price_impact_pct,
+            network_fee_lamports: signatures * LAMPORTS_PER_SIGNATURE,
+            priority_fee_lamports: trade.priority_fee.map(sol_to_lamports).transpose()?.unwrap_or(0),
+            compute_units_consumed: result.units_consumed,
+            logs: result.logs.unwrap_or_default(),
+        })
+    }
+
+    /// Quote the swap for a trade: buys spend exactly `sol_amount`,
+    /// sells receive exactly `sol_amount`
+    pub async fn quote(&self, trade: &Trade) -> Result<SwapQuote> {
+        let lamports = sol_to_lamports(trade.sol_amount)?;
+        let slippage = slippage_bps(trade);
+
+        match trade.trade_type.as_str() {
+            "buy" => self.jupiter
+                .quote(SOL_MINT, &trade.token_address, lamports, slippage, SwapMode::ExactIn)
+                .await,
+            "sell" => self.jupiter
+                .quote(&trade.token_address, SOL_MINT, lamports, slippage, SwapMode::ExactOut)
+                .await,
+            other => Err(anyhow!("Unsupported trade type: {}", other)),
+        }
+    }
+}
+
+#[async_trait]
+impl TradeExecutor for SolanaExecutor {
+    async fn prepare(&self, trade: &Trade) -> Result<PreparedTrade> {
+        let keypair = self.load_keypair(trade.wallet_id).await?;
+        let quote = self.quote(trade).await?;
+
+        let priority_fee = trade.priority_fee.map(sol_to_lamports).transpose()?;
+        let swap = self.jupiter
+            .swap_transaction(&quote, &keypair.pubkey(), priority_fee)
+            .await?;
+
+        let transaction = VersionedTransaction::try_new(swap.transaction.message, &[&keypair])
+            .map_err(|e| anyhow!("Failed to sign swap transaction: {}", e))?;
+
+        Ok(PreparedTrade {
+            transaction,
+            last_valid_block_height: swap.last_valid_block_height,
+            quote,
+        })
+    }
+
+    async fn simulate(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<SimulationReport> {
+        self.simulate_transaction(trade, &prepared.transaction, &prepared.quote, true).await
+    }
+
+    async fn dry_run(&self, trade: &Trade) -> Result<SimulationReport> {
+        let wallet = self.load_wallet(trade.wallet_id).await?;
+        let owner = Pubkey::from_str(&wallet.public_key)?;
+        let quote = self.quote(trade).await?;
+
+        let priority_fee = trade.priority_fee.map(sol_to_lamports).transpose()?;
+        let swap = self.jupiter.swap_transaction(&quote, &owner, priority_fee).await?;
+
+        self.simulate_transaction(trade, &swap.transaction, &quote, false).await
+    }
+
+    async fn submit(&self, _trade: &Trade, prepared: &PreparedTrade) -> Result<Signature> {
+        self.rpc.send_transaction(&prepared.transaction).await.map_err(send_error)
+    }
+
+    async fn signature_statuses(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+        let statuses = self.rpc.get_signature_statuses(signatures).await?.value;
+        Ok(statuses.into_iter().map(ChainStatus::from_status).collect())
+    }
+
+    async fn signature_history(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+        let statuses = self.rpc.get_signature_statuses_with_history(signatures).await?.value;
+        Ok(statuses.into_iter().map(ChainStatus::from_status).collect())
+    }
+
+    async fn block_height(&self) -> Result<u64> {
+        Ok(self.rpc.get_block_height().await?)
+    }
+
+    async fn fill(&self, trade: &Trade, signature: &Signature) -> Result<ExecutedFill> {
+        let wallet = self.load_wallet(trade.wallet_id).await?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let transaction = self.rpc.get_transaction_with_config(signature, config).await?;
+        let meta = transaction.transaction.meta
+            .ok_or_else(|| anyhow!("Transaction {} has no status metadata", signature))?;
+        fill::from_meta(trade, &wallet.public_key, &meta)
+    }
+
+    async fn sign_transfer(
+        &self,
+        wallet_id: uuid::Uuid,
+        to: &Pubkey,
+        lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        let keypair = self.load_keypair(wallet_id).await?;
+        Ok(transfer_transaction(&keypair, to, lamports, recent_blockhash))
+    }
+
+    async fn sol_balance(&self, wallet_id: uuid::Uuid) -> Result<Decimal> {
+        let wallet = self.load_wallet(wallet_id).await?;
+        let address = Pubkey::from_str(&wallet.public_key)?;
+        Ok(lamports_to_sol(self.rpc.get_balance(&address).await?))
+    }
+
+    async fn token_balances(&self, wallet_id: uuid::Uuid) -> Result<Vec<TokenBalance>> {
+        let wallet = self.load_wallet(wallet_id).await?;
+        let accounts = token_accounts(&self.rpc, &wallet.public_key).await?;
+        Ok(sum_balances(&accounts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_lamport_conversion() {
+        assert_eq!(sol_to_lamports(Decimal::from_str("1.5").unwrap()).unwrap(), 1_500_000_000);
+        assert_eq!(sol_to_lamports(Decimal::from_str("0.0000000019").unwrap()).unwrap(), 1);
+        assert!(sol_to_lamports(Decimal::from_str("-1").unwrap()).is_err());
+        assert_eq!(lamports_to_sol(250_000_000), Decimal::from_str("0.25").unwrap());
+    }
+}
@@ -0,0 +1,314 @@
+//! Trade exports for tax and accounting
+//!
+//! Settled trades are streamed from the database in execution order and
+//! written out one row at a time. Realized PnL is computed on the fly by
+//! replaying every fill through the position accounting, so memory use is
+//! bounded by open lots rather than by the length of the history.
+
+use anyhow::{anyhow, Result};
+use actix_web::web::Bytes;
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::Trade;
+use crate::positions::{CostMethod, Fill, Position};
+
+/// Rows buffered ahead of a slow client before the query is paused
+const EXPORT_BUFFER_ROWS: usize = 64;
+
+/// Export file format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(anyhow!("Unsupported export format: {}", other)),
+        }
+    }
+}
+
+/// Export column order. Columns are only ever appended so that existing
+/// spreadsheets and import scripts keep working.
+pub const EXPORT_COLUMNS: [&str; 14] = [
+    "trade_id",
+    "executed_at",
+    "wallet_id",
+    "token_address",
+    "token_symbol",
+    "trade_type",
+    "bot_type",
+    "signature",
+    "sol_amount",
+    "token_amount",
+    "price_per_token",
+    "fee_sol",
+    "realized_pnl",
+    "status",
+];
+
+/// One exported fill; field order matches `EXPORT_COLUMNS`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub trade_id: Uuid,
+    pub executed_at: DateTime<Utc>,
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    pub token_symbol: Option<String>,
+    pub trade_type: String,
+    pub bot_type: Option<String>,
+    pub signature: Option<String>,
+    pub sol_amount: Decimal,
+    pub token_amount: Option<Decimal>,
+    pub price_per_token: Option<Decimal>,
+    pub fee_sol: Option<Decimal>,
+    /// Set on sells; buys realize nothing
+    pub realized_pnl: Option<Decimal>,
+    pub status: String,
+}
+
+impl ExportRow {
+    fn new(trade: Trade, realized_pnl: Option<Decimal>) -> Self {
+        Self {
+            trade_id: trade.id,
+            executed_at: trade.executed_at.unwrap_or(trade.created_at),
+            wallet_id: trade.wallet_id,
+            token_address: trade.token_address,
+            token_symbol: trade.token_symbol,
+            trade_type: trade.trade_type,
+            bot_type: trade.bot_type,
+            signature: trade.signature,
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            price_per_token: trade.price_per_token,
+            fee_sol: trade.fee_sol,
+            realized_pnl,
+            status: trade.status,
+        }
+    }
+
+    fn csv_fields(&self) -> [String; 14] {
+        fn opt<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+        }
+
+        [
+            self.trade_id.to_string(),
+            self.executed_at.to_rfc3339(),
+            self.wallet_id.to_string(),
+            self.token_address.clone(),
+            opt(&self.token_symbol),
+            self.trade_type.clone(),
+            opt(&self.bot_type),
+            opt(&self.signature),
+            self.sol_amount.to_string(),
+            opt(&self.token_amount),
+            opt(&self.price_per_token),
+            opt(&self.fee_sol),
+            opt(&self.realized_pnl),
+            self.status.clone(),
+        ]
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields.iter()
+        .map(|f| csv_escape(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Which trades go into an export
+#[derive(Debug, Clone)]
+pub struct ExportQuery {
+    pub user_id: Uuid,
+    pub paper: bool,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub method: CostMethod,
+    pub format: ExportFormat,
+}
+
+/// Encodes rows for one export, tracking where it is in the document
+struct ExportWriter {
+    format: ExportFormat,
+    rows: u64,
+}
+
+impl ExportWriter {
+    fn header(&self) -> String {
+        match self.format {
+            ExportFormat::Csv => csv_line(&EXPORT_COLUMNS),
+            ExportFormat::Json => "[".to_string(),
+        }
+    }
+
+    fn row(&mut self, row: &ExportRow) -> Result<String> {
+        let encoded = match self.format {
+            ExportFormat::Csv => csv_line(&row.csv_fields()),
+            ExportFormat::Json => {
+                let separator = if self.rows == 0 { "\n" } else { ",\n" };
+                format!("{}{}", separator, serde_json::to_string(row)?)
+            }
+        };
+        self.rows += 1;
+        Ok(encoded)
+    }
+
+    fn footer(&self) -> String {
+        match self.format {
+            ExportFormat::Csv => String::new(),
+            ExportFormat::Json if self.rows == 0 => "]".to_string(),
+            ExportFormat::Json => "\n]".to_string(),
+        }
+    }
+}
+
+/// Start streaming an export. The query runs on its own task and feeds a
+/// bounded channel, so a slow client applies back-pressure to the database.
+pub fn stream_trades(pool: PgPool, query: ExportQuery) -> mpsc::Receiver<Result<Bytes, io::Error>> {
+    let (mut tx, rx) = mpsc::channel(EXPORT_BUFFER_ROWS);
+
+    tokio::spawn(async move {
+        if let Err(e) = write_export(&pool, &query, &mut tx).await {
+            log::error!("Trade export for user {} failed: {}", query.user_id, e);
+            let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+        }
+    });
+
+    rx
+}
+
+async fn write_export(
+    pool: &PgPool,
+    query: &ExportQuery,
+    tx: &mut mpsc::Sender<Result<Bytes, io::Error>>,
+) -> Result<()> {
+    let mut writer = ExportWriter { format: query.format, rows: 0 };
+    tx.send(Ok(Bytes::from(writer.header()))).await?;
+
+    // Every earlier fill is replayed so sells in the window carry the right
+    // cost basis; only rows from `from` onwards are written
+    let mut trades = sqlx::query_as::<_, Trade>(
+        r#"
+        SELECT * FROM trades
+        WHERE user_id = $1
+          AND is_paper = $2
+          AND status IN ('confirmed', 'finalized')
+          AND ($3::timestamptz IS NULL OR COALESCE(executed_at, created_at) <= $3)
+        ORDER BY COALESCE(executed_at, created_at) ASC, created_at ASC
+        "#
+    )
+    .bind(query.user_id)
+    .bind(query.paper)
+    .bind(query.to)
+    .fetch(pool);
+
+    let mut positions: HashMap<(Uuid, String), Position> = HashMap::new();
+
+    while let Some(trade) = trades.next().await {
+        let trade = trade?;
+
+        let realized = Fill::from_trade(&trade).and_then(|fill| {
+            let realized = positions
+                .entry((fill.wallet_id, fill.token_address.clone()))
+                .or_insert_with(|| Position::new(fill.wallet_id, fill.token_address.clone(), query.method))
+                .apply(&fill);
+            (trade.trade_type == "sell").then_some(realized)
+        });
+
+        let executed_at = trade.executed_at.unwrap_or(trade.created_at);
+        if query.from.is_some_and(|from| executed_at < from) {
+            continue;
+        }
+
+        let line = writer.row(&ExportRow::new(trade, realized))?;
+        tx.send(Ok(Bytes::from(line))).await?;
+    }
+
+    tx.send(Ok(Bytes::from(writer.footer()))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escaping() {
+        assert_eq!(csv_line(&["a", "b,c", "say \"hi\""]), "a,\"b,c\",\"say \"\"hi\"\"\"\r\n");
+    }
+
+    #[test]
+    fn test_json_document_is_well_formed() {
+        let mut writer = ExportWriter { format: ExportFormat::Json, rows: 0 };
+        assert_eq!(format!("{}{}", writer.header(), writer.footer()), "[]");
+
+        let row = ExportRow {
+            trade_id: Uuid::nil(),
+            executed_at: Utc::now(),
+            wallet_id: Uuid::nil(),
+            token_address: "Mint".to_string(),
+            token_symbol: None,
+            trade_type: "sell".to_string(),
+            bot_type: None,
+            signature: None,
+            sol_amount: Decimal::ONE,
+            token_amount: Some(Decimal::TEN),
+            price_per_token: None,
+            fee_sol: None,
+            realized_pnl: Some(Decimal::ONE),
+            status: "finalized".to_string(),
+        };
+        let mut document = writer.header();
+        document.push_str(&writer.row(&row).unwrap());
+        document.push_str(&writer.row(&row).unwrap());
+        document.push_str(&writer.footer());
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&document).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(row.csv_fields().len(), EXPORT_COLUMNS.len());
+    }
+}
@@ -0,0 +1,157 @@
+//! Actual fills read back from confirmed transactions
+//!
+//! Until a trade lands it carries the amounts its simulation expected. Once
+//! it is confirmed the wallet's token balance change and the fee actually
+//! charged are read from the transaction metadata and replace them. The SOL
+//! side is exact by construction: buys spend exactly `sol_amount` and sells
+//! receive exactly `sol_amount`, so only the token side can drift.
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_transaction_status::{UiTransactionStatusMeta, UiTransactionTokenBalance};
+
+use crate::database::models::Trade;
+use crate::trading::executor::{lamports_to_sol, raw_to_ui_amount};
+
+/// What a confirmed trade actually traded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutedFill {
+    /// Tokens received (buys) or spent (sells), in UI units
+    pub token_amount: Decimal,
+    pub price_per_token: Option<Decimal>,
+    /// Network and priority fees charged
+    pub fee_sol: Decimal,
+}
+
+/// Read a trade's fill from its confirmed transaction's metadata. `owner` is
+/// the public key of the trade's wallet.
+pub fn from_meta(trade: &Trade, owner: &str, meta: &UiTransactionStatusMeta) -> Result<ExecutedFill> {
+    if let Some(err) = &meta.err {
+        return Err(anyhow!("Transaction failed: {}", err));
+    }
+
+    let pre = token_balance(meta.pre_token_balances.as_ref().into(), owner, &trade.token_address)?;
+    let post = token_balance(meta.post_token_balances.as_ref().into(), owner, &trade.token_address)?;
+    let decimals = post.or(pre).map(|(_, decimals)| decimals).unwrap_or(0);
+    let (pre, post) = (pre.map_or(0, |(raw, _)| raw), post.map_or(0, |(raw, _)| raw));
+
+    let raw = match trade.trade_type.as_str() {
+        "buy" => post.checked_sub(pre),
+        "sell" => pre.checked_sub(post),
+        other => return Err(anyhow!("Unsupported trade type: {}", other)),
+    }
+    .ok_or_else(|| anyhow!("Token balance moved the wrong way for a {}", trade.trade_type))?;
+
+    let token_amount = raw_to_ui_amount(raw, decimals);
+    let price_per_token = if token_amount.is_zero() {
+        None
+    } else {
+        Some(trade.sol_amount / token_amount)
+    };
+
+    Ok(ExecutedFill {
+        token_amount,
+        price_per_token,
+        fee_sol: lamports_to_sol(meta.fee),
+    })
+}
+
+/// Raw amount and decimals of `mint` held by `owner` across its token
+/// accounts in a balance snapshot; nothing if it held no account
+fn token_balance(
+    balances: Option<&Vec<UiTransactionTokenBalance>>,
+    owner: &str,
+    mint: &str,
+) -> Result<Option<(u64, u8)>> {
+    let mut total: Option<(u64, u8)> = None;
+    for balance in balances.into_iter().flatten() {
+        let owned = Option::<&String>::from(balance.owner.as_ref()).is_some_and(|o| o == owner);
+        if !owned || balance.mint != mint {
+            continue;
+        }
+
+        let raw: u64 = balance.ui_token_amount.amount.parse()
+            .map_err(|e| anyhow!("Invalid token amount {}: {}", balance.ui_token_amount.amount, e))?;
+        let held = total.map_or(0, |(raw, _)| raw);
+        total = Some((held + raw, balance.ui_token_amount.decimals));
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    const OWNER: &str = "Owner1111111111111111111111111111111111111";
+    const MINT: &str = "Mint11111111111111111111111111111111111111";
+
+    fn trade(trade_type: &str) -> Trade {
+        serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "user_id": Uuid::new_v4(),
+            "wallet_id": Uuid::new_v4(),
+            "token_address": MINT,
+            "trade_type": trade_type,
+            "sol_amount": "0.5",
+            "status": "confirmed",
+            "created_at": Utc::now(),
+            "submit_attempts": 1,
+            "is_paper": false,
+            "is_liquidation": false,
+        }))
+        .unwrap()
+    }
+
+    fn balance(index: u8, owner: &str, mint: &str, raw: u64) -> serde_json::Value {
+        serde_json::json!({
+            "accountIndex": index,
+            "mint": mint,
+            "owner": owner,
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": raw.to_string(),
+                "uiAmountString": "",
+            },
+        })
+    }
+
+    fn meta(pre: Vec<serde_json::Value>, post: Vec<serde_json::Value>) -> UiTransactionStatusMeta {
+        serde_json::from_value(serde_json::json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 105_000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": pre,
+            "postTokenBalances": post,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fill_from_wallet_token_delta() {
+        // First buy: the wallet had no token account before
+        let buy = meta(
+            vec![balance(3, "Pool", MINT, 9_000_000_000)],
+            vec![balance(2, OWNER, MINT, 1_250_000_000), balance(3, "Pool", MINT, 7_750_000_000)],
+        );
+        let fill = from_meta(&trade("buy"), OWNER, &buy).unwrap();
+        assert_eq!(fill.token_amount, Decimal::from(1250));
+        assert_eq!(fill.price_per_token, Some(Decimal::new(4, 4)));
+        assert_eq!(fill.fee_sol, Decimal::new(105, 6));
+
+        let sell = meta(
+            vec![balance(2, OWNER, MINT, 1_250_000_000), balance(4, OWNER, "Other", 5)],
+            vec![balance(2, OWNER, MINT, 250_000_000), balance(4, OWNER, "Other", 5)],
+        );
+        let fill = from_meta(&trade("sell"), OWNER, &sell).unwrap();
+        assert_eq!(fill.token_amount, Decimal::from(1000));
+
+        // A buy whose balance went down is not a fill we understand
+        assert!(from_meta(&trade("buy"), OWNER, &sell).is_err());
+    }
+}
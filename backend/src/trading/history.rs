@@ -0,0 +1,288 @@
+//! Trade history queries: filtering, sorting and pagination
+//!
+//! Listings support both page/offset pagination and keyset cursors. A cursor
+//! encodes the sort key and id of the last row returned, so following it stays
+//! cheap and stable however deep into a large history the caller goes.
+
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::{PaginatedResponse, Trade, TradeListParams};
+use crate::trading::TradeStatus;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Column a trade listing is ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TradeSort {
+    #[default]
+    CreatedAt,
+    SolAmount,
+}
+
+impl TradeSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeSort::CreatedAt => "created_at",
+            TradeSort::SolAmount => "sol_amount",
+        }
+    }
+}
+
+impl FromStr for TradeSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "created_at" => Ok(TradeSort::CreatedAt),
+            "sol_amount" => Ok(TradeSort::SolAmount),
+            other => Err(anyhow!("Cannot sort trades by {}", other)),
+        }
+    }
+}
+
+/// Position after the last row of a page
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    CreatedAt(DateTime<Utc>),
+    SolAmount(Decimal),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub key: SortKey,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    fn after(trade: &Trade, sort: TradeSort) -> Self {
+        let key = match sort {
+            TradeSort::CreatedAt => SortKey::CreatedAt(trade.created_at),
+            TradeSort::SolAmount => SortKey::SolAmount(trade.sol_amount),
+        };
+        Self { key, id: trade.id }
+    }
+
+    fn sort(&self) -> TradeSort {
+        match self.key {
+            SortKey::CreatedAt(_) => TradeSort::CreatedAt,
+            SortKey::SolAmount(_) => TradeSort::SolAmount,
+        }
+    }
+
+    /// Opaque, URL-safe form handed to clients
+    pub fn encode(&self) -> String {
+        let value = match &self.key {
+            SortKey::CreatedAt(at) => at.to_rfc3339(),
+            SortKey::SolAmount(amount) => amount.to_string(),
+        };
+        URL_SAFE_NO_PAD.encode(format!("{}|{}|{}", self.sort().as_str(), value, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid cursor");
+
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let mut parts = raw.splitn(3, '|');
+        let (sort, value, id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(sort), Some(value), Some(id)) => (sort, value, id),
+            _ => return Err(invalid()),
+        };
+
+        let key = match sort.parse::<TradeSort>().map_err(|_| invalid())? {
+            TradeSort::CreatedAt => SortKey::CreatedAt(
+                DateTime::parse_from_rfc3339(value).map_err(|_| invalid())?.with_timezone(&Utc)
+            ),
+            TradeSort::SolAmount => SortKey::SolAmount(Decimal::from_str(value).map_err(|_| invalid())?),
+        };
+
+        Ok(Self {
+            key,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Validated listing options
+#[derive(Debug, Clone)]
+pub struct TradeQuery {
+    pub user_id: Uuid,
+    pub wallet_id: Option<Uuid>,
+    pub token_address: Option<String>,
+    pub trade_type: Option<String>,
+    pub status: Option<TradeStatus>,
+    pub bot_type: Option<String>,
+    pub is_paper: Option<bool>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub sort: TradeSort,
+    pub ascending: bool,
+    pub page: u32,
+    pub limit: u32,
+    pub cursor: Option<Cursor>,
+}
+
+impl TradeQuery {
+    pub fn from_params(user_id: Uuid, params: &TradeListParams) -> Result<Self> {
+        if let Some(trade_type) = &params.trade_type {
+            if trade_type != "buy" && trade_type != "sell" {
+                return Err(anyhow!("Trade type must be 'buy' or 'sell'"));
+            }
+        }
+
+        if let (Some(from), Some(to)) = (params.from, params.to) {
+            if from > to {
+                return Err(anyhow!("'from' must not be after 'to'"));
+            }
+        }
+
+        let sort = params.sort_by.as_deref().map(str::parse).transpose()?.unwrap_or_default();
+        let ascending = match params.order.as_deref() {
+            None | Some("desc") => false,
+            Some("asc") => true,
+            Some(other) => return Err(anyhow!("Sort order must be 'asc' or 'desc', got {}", other)),
+        };
+
+        let cursor = params.cursor.as_deref().map(Cursor::decode).transpose()?;
+        if let Some(cursor) = &cursor {
+            if cursor.sort() != sort {
+                return Err(anyhow!("Cursor was issued for a listing sorted by {}", cursor.sort().as_str()));
+            }
+        }
+
+        Ok(Self {
+            user_id,
+            wallet_id: params.wallet_id,
+            token_address: params.token_address.clone(),
+            trade_type: params.trade_type.clone(),
+            status: params.status.as_deref().map(str::parse).transpose()?,
+            bot_type: params.bot_type.clone(),
+            is_paper: params.is_paper,
+            from: params.from,
+            to: params.to,
+            sort,
+            ascending,
+            page: params.page.unwrap_or(1).max(1),
+            limit: params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            cursor,
+        })
+    }
+
+    fn push_filters(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push(" WHERE user_id = ").push_bind(self.user_id);
+
+        if let Some(wallet_id) = self.wallet_id {
+            builder.push(" AND wallet_id = ").push_bind(wallet_id);
+        }
+        if let Some(token_address) = &self.token_address {
+            builder.push(" AND token_address = ").push_bind(token_address.clone());
+        }
+        if let Some(trade_type) = &self.trade_type {
+            builder.push(" AND trade_type = ").push_bind(trade_type.clone());
+        }
+        if let Some(status) = self.status {
+            builder.push(" AND status = ").push_bind(status.as_str());
+        }
+        if let Some(bot_type) = &self.bot_type {
+            builder.push(" AND bot_type = ").push_bind(bot_type.clone());
+        }
+        if let Some(is_paper) = self.is_paper {
+            builder.push(" AND is_paper = ").push_bind(is_paper);
+        }
+        if let Some(from) = self.from {
+            builder.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = self.to {
+            builder.push(" AND created_at <= ").push_bind(to);
+        }
+    }
+}
+
+/// Run a trade listing, returning one page plus totals for the whole filter
+pub async fn list_trades(pool: &PgPool, query: &TradeQuery) -> Result<PaginatedResponse<Trade>> {
+    let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM trades");
+    query.push_filters(&mut count);
+    let (total,): (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    let column = query.sort.as_str();
+    let direction = if query.ascending { "ASC" } else { "DESC" };
+
+    let mut select = QueryBuilder::<Postgres>::new("SELECT * FROM trades");
+    query.push_filters(&mut select);
+
+    if let Some(cursor) = &query.cursor {
+        let comparison = if query.ascending { ">" } else { "<" };
+        select.push(format!(" AND ({}, id) {} (", column, comparison));
+        match cursor.key {
+            SortKey::CreatedAt(at) => select.push_bind(at),
+            SortKey::SolAmount(amount) => select.push_bind(amount),
+        };
+        select.push(", ").push_bind(cursor.id).push(")");
+    }
+
+    select.push(format!(" ORDER BY {} {}, id {}", column, direction, direction));
+    // One extra row tells us whether another page follows
+    select.push(" LIMIT ").push_bind(i64::from(query.limit) + 1);
+    if query.cursor.is_none() {
+        select.push(" OFFSET ").push_bind(i64::from(query.page - 1) * i64::from(query.limit));
+    }
+
+    let mut trades: Vec<Trade> = select.build_query_as().fetch_all(pool).await?;
+
+    let next_cursor = if trades.len() > query.limit as usize {
+        trades.truncate(query.limit as usize);
+        trades.last().map(|trade| Cursor::after(trade, query.sort).encode())
+    } else {
+        None
+    };
+
+    let total = total.max(0) as u64;
+    let total_pages = total.div_ceil(u64::from(query.limit)) as u32;
+
+    Ok(PaginatedResponse {
+        data: trades,
+        page: query.page,
+        limit: query.limit,
+        total,
+        total_pages,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursors = [
+            Cursor { key: SortKey::CreatedAt(Utc::now()), id: Uuid::new_v4() },
+            Cursor { key: SortKey::SolAmount(Decimal::from_str("1.250000001").unwrap()), id: Uuid::new_v4() },
+        ];
+
+        for cursor in cursors {
+            assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        }
+        assert!(Cursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_cursor_must_match_sort() {
+        let cursor = Cursor { key: SortKey::CreatedAt(Utc::now()), id: Uuid::new_v4() };
+        let params = TradeListParams {
+            sort_by: Some("sol_amount".to_string()),
+            cursor: Some(cursor.encode()),
+            ..Default::default()
+        };
+
+        assert!(TradeQuery::from_params(Uuid::new_v4(), &params).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Jupiter aggregator client for swap quotes and transactions
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Wrapped SOL mint used as the SOL side of every swap
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Lamports per SOL
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// Jupiter error codes meaning no market routes the swap
+const NO_ROUTE_CODES: [&str; 3] = ["COULD_NOT_FIND_ANY_ROUTE", "NO_ROUTES_FOUND", "TOKEN_NOT_TRADABLE"];
+
+/// Quote error for a swap that no market routes, as opposed to Jupiter
+/// being unreachable, rate limited or failing
+#[derive(Debug, Clone)]
+pub struct NoRoute {
+    pub message: String,
+}
+
+impl std::fmt::Display for NoRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No route for swap: {}", self.message)
+    }
+}
+
+impl std::error::Error for NoRoute {}
+
+/// Whether a failed quote response says the swap has no route
+fn is_no_route(status: reqwest::StatusCode, body: &str) -> bool {
+    if status != reqwest::StatusCode::BAD_REQUEST {
+        return false;
+    }
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|error| error.get("errorCode")?.as_str().map(|code| NO_ROUTE_CODES.contains(&code)))
+        .unwrap_or(false)
+}
+
+/// Whether the quoted amount is the exact input or the exact output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapMode {
+    ExactIn,
+    ExactOut,
+}
+
+/// Swap quote as returned by `/quote`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapQuote {
+    pub input_mint: String,
+    pub in_amount: String,
+    pub output_mint: String,
+    pub out_amount: String,
+    pub other_amount_threshold: String,
+    pub swap_mode: SwapMode,
+    pub slippage_bps: u16,
+    pub price_impact_pct: String,
+    pub route_plan: serde_json::Value,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SwapQuote {
+    pub fn in_amount_raw(&self) -> Result<u64> {
+        self.in_amount.parse().map_err(|e| anyhow!("Invalid quote inAmount: {}", e))
+    }
+
+    pub fn out_amount_raw(&self) -> Result<u64> {
+        self.out_amount.parse().map_err(|e| anyhow!("Invalid quote outAmount: {}", e))
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapRequest<'a> {
+    quote_response: &'a SwapQuote,
+    user_public_key: String,
+    wrap_and_unwrap_sol: bool,
+    dynamic_compute_unit_limit: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prioritization_fee_lamports: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapResponse {
+    swap_transaction: String,
+    last_valid_block_height: u64,
+}
+
+/// Unsigned swap transaction built by Jupiter against a fresh blockhash
+#[derive(Debug, Clone)]
+pub struct SwapTransaction {
+    pub transaction: VersionedTransaction,
+    pub last_valid_block_height: u64,
+}
+
+/// Thin HTTP client for the Jupiter swap API
+#[derive(Clone)]
+pub struct JupiterClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl JupiterClient {
+    pub fn new(base_url: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Fetch a route quote for swapping `amount` raw units. Fails with
+    /// `NoRoute` when no market routes the swap.
+    pub async fn quote(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+        slippage_bps: u16,
+        swap_mode: SwapMode,
+    ) -> Result<SwapQuote> {
+        let mode = match swap_mode {
+            SwapMode::ExactIn => "ExactIn",
+            SwapMode::ExactOut => "ExactOut",
+        };
+
+        let response = self.http
+            .get(format!("{}/quote", self.base_url))
+            .query(&[
+                ("inputMint", input_mint.to_string()),
+                ("outputMint", output_mint.to_string()),
+                ("amount", amount.to_string()),
+                ("slippageBps", slippage_bps.to_string()),
+                ("swapMode", mode.to_string()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            if is_no_route(status, &body) {
+                return Err(NoRoute { message: body }.into());
+            }
+            return Err(anyhow!("Jupiter quote failed ({}): {}", status, body));
+        }
+
+        Ok(response.json::<SwapQuote>().await?)
+    }
+
+    /// Build the swap transaction for a quote; each call returns a fresh blockhash
+    pub async fn swap_transaction(
+        &self,
+        quote: &SwapQuote,
+        user: &Pubkey,
+        priority_fee_lamports: Option<u64>,
+    ) -> Result<SwapTransaction> {
+        let request = SwapRequest {
+            quote_response: quote,
+            user_public_key: user.to_string(),
+            wrap_and_unwrap_sol: true,
+            dynamic_compute_unit_limit: true,
+            prioritization_fee_lamports: priority_fee_lamports,
+        };
+
+        let response = self.http
+            .post(format!("{}/swap", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Jupiter swap failed ({}): {}", status, body));
+        }
+
+        let swap = response.json::<SwapResponse>().await?;
+        let bytes = BASE64.decode(swap.swap_transaction)
+            .map_err(|e| anyhow!("Invalid swap transaction encoding: {}", e))?;
+        let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+            .map_err(|e| anyhow!("Invalid swap transaction: {}", e))?;
+
+        Ok(SwapTransaction {
+            transaction,
+            last_valid_block_height: swap.last_valid_block_height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_no_route_only_for_routing_errors() {
+        let no_route = r#"{"error":"Could not find any route","errorCode":"COULD_NOT_FIND_ANY_ROUTE"}"#;
+        assert!(is_no_route(StatusCode::BAD_REQUEST, no_route));
+        assert!(!is_no_route(StatusCode::TOO_MANY_REQUESTS, no_route));
+        assert!(!is_no_route(StatusCode::BAD_REQUEST, r#"{"error":"Invalid amount","errorCode":"INVALID_AMOUNT"}"#));
+        assert!(!is_no_route(StatusCode::BAD_GATEWAY, "<html>Bad gateway</html>"));
+    }
+}
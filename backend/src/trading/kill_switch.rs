@@ -0,0 +1,229 @@
+//! Emergency kill switch
+//!
+//! An engaged switch halts trading for one user, or for everyone when it is
+//! global. While one covers a user the trade engine refuses to create or
+//! execute their trades, except panic-sell liquidations. Engaging also
+//! cancels trades that haven't been broadcast and resting orders; stopping
+//! bots is left to the caller, which owns the bot manager, and bots that are
+//! still running stop at their next step. A switch stays engaged until
+//! explicitly released.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::database::models::{KillSwitch, Trade};
+use crate::orders::status;
+use crate::trading::lifecycle::{self, TradeStatus, TransitionDetails};
+
+/// Error returned for trades attempted while a kill switch is engaged
+#[derive(Debug, Clone)]
+pub struct TradingHalted {
+    pub switch: KillSwitch,
+}
+
+impl fmt::Display for TradingHalted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = if self.switch.user_id.is_some() { "for this user" } else { "globally" };
+        write!(f, "Trading is halted {} by the kill switch", scope)?;
+        if let Some(reason) = &self.switch.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TradingHalted {}
+
+/// Orders and trades cancelled when a switch was engaged
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Cancellations {
+    pub trades: Vec<Uuid>,
+    pub orders: Vec<Uuid>,
+}
+
+/// Engaged switches at a glance, for health reporting
+#[derive(Debug, Clone, Serialize)]
+pub struct KillSwitchOverview {
+    /// The global switch is engaged
+    pub engaged: bool,
+    /// Users halted by a switch of their own
+    pub halted_users: i64,
+}
+
+/// The engaged switch for a scope: a user's own, or the global one for `None`
+pub async fn engaged(pool: &PgPool, scope: Option<Uuid>) -> Result<Option<KillSwitch>> {
+    let switch = sqlx::query_as::<_, KillSwitch>(
+        "SELECT * FROM kill_switches WHERE released_at IS NULL AND user_id IS NOT DISTINCT FROM $1"
+    )
+    .bind(scope)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(switch)
+}
+
+/// Whether the global switch is engaged and how many users are halted individually
+pub async fn overview(pool: &PgPool) -> Result<KillSwitchOverview> {
+    let (engaged, halted_users) = sqlx::query_as::<_, (bool, i64)>(
+        r#"
+        SELECT COALESCE(BOOL_OR(user_id IS NULL), false), COUNT(user_id)
+        FROM kill_switches
+        WHERE released_at IS NULL
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(KillSwitchOverview { engaged, halted_users })
+}
+
+/// The switch halting a user's trading, global first
+pub async fn halting(pool: &PgPool, user_id: Uuid) -> Result<Option<KillSwitch>> {
+    let switch = sqlx::query_as::<_, KillSwitch>(
+        r#"
+        SELECT * FROM kill_switches
+        WHERE released_at IS NULL AND (user_id IS NULL OR user_id = $1)
+        ORDER BY user_id NULLS FIRST
+        LIMIT 1
+        "#
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(switch)
+}
+
+/// Fail with `TradingHalted` if a switch covers the user
+pub async fn ensure_trading_allowed(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    match halting(pool, user_id).await? {
+        Some(switch) => Err(TradingHalted { switch }.into()),
+        None => Ok(()),
+    }
+}
+
+/// Engage the switch for a scope. Returns the switch and whether this call
+/// engaged it; engaging one that is already engaged leaves it as it is.
+pub async fn engage(
+    pool: &PgPool,
+    scope: Option<Uuid>,
+    engaged_by: Uuid,
+    reason: Option<&str>,
+) -> Result<(KillSwitch, bool)> {
+    let inserted = sqlx::query_as::<_, KillSwitch>(
+        r#"
+        INSERT INTO kill_switches (user_id, reason, engaged_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING
+        RETURNING *
+        "#
+    )
+    .bind(scope)
+    .bind(reason)
+    .bind(engaged_by)
+    .fetch_optional(pool)
+    .await?;
+
+    match inserted {
+        Some(switch) => Ok((switch, true)),
+        None => {
+            let switch = engaged(pool, scope)
+                .await?
+                .ok_or_else(|| anyhow!("Kill switch was released while being engaged"))?;
+            Ok((switch, false))
+        }
+    }
+}
+
+/// Release the engaged switch for a scope, if there is one
+pub async fn release(pool: &PgPool, scope: Option<Uuid>, released_by: Uuid) -> Result<Option<KillSwitch>> {
+    let switch = sqlx::query_as::<_, KillSwitch>(
+        r#"
+        UPDATE kill_switches SET released_at = NOW(), released_by = $2
+        WHERE released_at IS NULL AND user_id IS NOT DISTINCT FROM $1
+        RETURNING *
+        "#
+    )
+    .bind(scope)
+    .bind(released_by)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(switch)
+}
+
+/// Cancel every trade that hasn't been broadcast and every open order in
+/// a scope, all users' for `None`. Trades already on the wire are left to
+/// land; a trade that moves on while being cancelled is skipped.
+pub async fn cancel_pending(pool: &PgPool, scope: Option<Uuid>) -> Result<Cancellations> {
+    let trades = sqlx::query_as::<_, Trade>(
+        "SELECT * FROM trades WHERE status IN ($1, $2) AND NOT is_liquidation AND ($3::uuid IS NULL OR user_id = $3)"
+    )
+    .bind(TradeStatus::Created.as_str())
+    .bind(TradeStatus::Simulated.as_str())
+    .bind(scope)
+    .fetch_all(pool)
+    .await?;
+
+    let mut cancellations = Cancellations::default();
+    for trade in trades {
+        let cancelled = lifecycle::transition(
+            pool,
+            trade.id,
+            trade.lifecycle_status()?,
+            TradeStatus::Cancelled,
+            TransitionDetails::message("Cancelled by kill switch"),
+        )
+        .await?;
+        if cancelled {
+            cancellations.trades.push(trade.id);
+        }
+    }
+
+    cancellations.orders = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE orders SET status = $1, error_message = 'Cancelled by kill switch', updated_at = NOW()
+        WHERE status = $2 AND ($3::uuid IS NULL OR user_id = $3)
+        RETURNING id
+        "#
+    )
+    .bind(status::CANCELLED)
+    .bind(status::OPEN)
+    .bind(scope)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(cancellations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_halted_message_names_scope_and_reason() {
+        let mut switch = KillSwitch {
+            id: Uuid::new_v4(),
+            user_id: None,
+            reason: Some("Token rugged".to_string()),
+            engaged_by: Some(Uuid::new_v4()),
+            engaged_at: Utc::now(),
+            released_by: None,
+            released_at: None,
+        };
+        assert_eq!(
+            TradingHalted { switch: switch.clone() }.to_string(),
+            "Trading is halted globally by the kill switch: Token rugged"
+        );
+
+        switch.user_id = Some(Uuid::new_v4());
+        switch.reason = None;
+        let error: anyhow::Error = TradingHalted { switch }.into();
+        assert!(error.is::<TradingHalted>());
+        assert_eq!(error.to_string(), "Trading is halted for this user by the kill switch");
+    }
+}
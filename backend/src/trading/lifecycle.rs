@@ -0,0 +1,286 @@
+//! Trade lifecycle state machine
+//!
+//! created → simulated → submitted → confirmed → finalized, with failed,
+//! expired and cancelled as the other terminal states. Every transition is
+//! applied with a compare-and-set on the current status and recorded in
+//! `trade_status_events`.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::{Trade, TradeStatusEvent};
+
+/// Status of a trade as stored in `trades.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeStatus {
+    Created,
+    Simulated,
+    Submitted,
+    Confirmed,
+    Finalized,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+impl TradeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeStatus::Created => "created",
+            TradeStatus::Simulated => "simulated",
+            TradeStatus::Submitted => "submitted",
+            TradeStatus::Confirmed => "confirmed",
+            TradeStatus::Finalized => "finalized",
+            TradeStatus::Failed => "failed",
+            TradeStatus::Expired => "expired",
+            TradeStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the state machine allows moving from `self` to `next`.
+    /// Submitted → submitted is a resubmission with a fresh blockhash.
+    pub fn can_transition_to(&self, next: TradeStatus) -> bool {
+        use TradeStatus::*;
+
+        matches!(
+            (self, next),
+            (Created, Simulated | Submitted | Failed | Cancelled)
+                | (Simulated, Submitted | Failed | Cancelled)
+                | (Submitted, Submitted | Confirmed | Finalized | Failed | Expired)
+                | (Confirmed, Finalized | Failed)
+        )
+    }
+
+    /// No further transitions are possible
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TradeStatus::Finalized | TradeStatus::Failed | TradeStatus::Expired | TradeStatus::Cancelled
+        )
+    }
+
+    /// Only trades that have not been broadcast can be cancelled
+    pub fn is_cancellable(&self) -> bool {
+        matches!(self, TradeStatus::Created | TradeStatus::Simulated)
+    }
+
+    /// Trades whose transaction is on the wire and still awaiting finality
+    pub fn is_in_flight(&self) -> bool {
+        matches!(self, TradeStatus::Submitted | TradeStatus::Confirmed)
+    }
+
+    /// Timestamp column stamped when a trade enters this status
+    fn timestamp_column(&self) -> Option<&'static str> {
+        match self {
+            TradeStatus::Simulated => Some("simulated_at"),
+            TradeStatus::Submitted => Some("executed_at"),
+            TradeStatus::Confirmed => Some("confirmed_at"),
+            TradeStatus::Finalized => Some("finalized_at"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TradeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TradeStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "created" => Ok(TradeStatus::Created),
+            "simulated" => Ok(TradeStatus::Simulated),
+            "submitted" => Ok(TradeStatus::Submitted),
+            "confirmed" => Ok(TradeStatus::Confirmed),
+            "finalized" => Ok(TradeStatus::Finalized),
+            "failed" => Ok(TradeStatus::Failed),
+            "expired" => Ok(TradeStatus::Expired),
+            "cancelled" => Ok(TradeStatus::Cancelled),
+            other => Err(anyhow!("Unknown trade status: {}", other)),
+        }
+    }
+}
+
+impl Trade {
+    /// Parsed lifecycle status of this trade
+    pub fn lifecycle_status(&self) -> Result<TradeStatus> {
+        self.status.parse()
+    }
+}
+
+/// Details recorded alongside a status transition
+#[derive(Debug, Default, Clone)]
+pub struct TransitionDetails {
+    pub signature: Option<String>,
+    pub last_valid_block_height: Option<u64>,
+    pub message: Option<String>,
+}
+
+impl TransitionDetails {
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            message: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn submission(signature: String, last_valid_block_height: u64) -> Self {
+        Self {
+            signature: Some(signature),
+            last_valid_block_height: Some(last_valid_block_height),
+            message: None,
+        }
+    }
+}
+
+/// Move a trade from `from` to `to`, returning false if the trade was no
+/// longer in `from` (another worker got there first).
+pub async fn transition(
+    pool: &PgPool,
+    trade_id: Uuid,
+    from: TradeStatus,
+    to: TradeStatus,
+    details: TransitionDetails,
+) -> Result<bool> {
+    if !from.can_transition_to(to) {
+        return Err(anyhow!("Invalid trade transition: {} → {}", from, to));
+    }
+
+    let mut sets = vec!["status = $3".to_string()];
+    if let Some(column) = to.timestamp_column() {
+        sets.push(format!("{} = NOW()", column));
+    }
+    if details.signature.is_some() {
+        sets.push("signature = $4, transaction_hash = $4".to_string());
+        sets.push("last_valid_block_height = $5".to_string());
+        sets.push("submit_attempts = submit_attempts + 1".to_string());
+    }
+    if matches!(to, TradeStatus::Failed | TradeStatus::Expired | TradeStatus::Cancelled) {
+        sets.push("error_message = COALESCE($6, error_message)".to_string());
+    }
+
+    let sql = format!(
+        "UPDATE trades SET {} WHERE id = $1 AND status = $2",
+        sets.join(", ")
+    );
+
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(&sql)
+        .bind(trade_id)
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .bind(&details.signature)
+        .bind(details.last_valid_block_height.map(|h| h as i64))
+        .bind(&details.message)
+        .execute(&mut tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO trade_status_events (id, trade_id, from_status, to_status, signature, message)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(trade_id)
+    .bind(from.as_str())
+    .bind(to.as_str())
+    .bind(&details.signature)
+    .bind(&details.message)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    log::info!("Trade {} moved {} → {}", trade_id, from, to);
+    Ok(true)
+}
+
+/// Record the initial `created` event for a freshly inserted trade
+pub async fn record_created(pool: &PgPool, trade_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO trade_status_events (id, trade_id, to_status) VALUES ($1, $2, $3)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(trade_id)
+    .bind(TradeStatus::Created.as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Full transition history for a trade, oldest first
+pub async fn history(pool: &PgPool, trade_id: Uuid) -> Result<Vec<TradeStatusEvent>> {
+    let events = sqlx::query_as::<_, TradeStatusEvent>(
+        "SELECT * FROM trade_status_events WHERE trade_id = $1 ORDER BY created_at ASC"
+    )
+    .bind(trade_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TradeStatus::*;
+
+    const ALL: [TradeStatus; 8] = [
+        Created, Simulated, Submitted, Confirmed, Finalized, Failed, Expired, Cancelled,
+    ];
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in ALL {
+            assert_eq!(status.as_str().parse::<TradeStatus>().unwrap(), status);
+        }
+        assert!("pending".parse::<TradeStatus>().is_err());
+    }
+
+    #[test]
+    fn test_happy_path_transitions() {
+        assert!(Created.can_transition_to(Simulated));
+        assert!(Simulated.can_transition_to(Submitted));
+        assert!(Submitted.can_transition_to(Confirmed));
+        assert!(Confirmed.can_transition_to(Finalized));
+
+        // Resubmission with a fresh blockhash
+        assert!(Submitted.can_transition_to(Submitted));
+    }
+
+    #[test]
+    fn test_terminal_states_are_final() {
+        for from in ALL.iter().filter(|s| s.is_terminal()) {
+            for to in ALL {
+                assert!(!from.can_transition_to(to), "{} → {} should be rejected", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_pre_submission_trades_cancel() {
+        let cancellable: Vec<_> = ALL.iter().filter(|s| s.can_transition_to(Cancelled)).collect();
+        assert_eq!(cancellable, vec![&Created, &Simulated]);
+
+        for status in ALL {
+            assert_eq!(status.is_cancellable(), status.can_transition_to(Cancelled));
+        }
+    }
+}
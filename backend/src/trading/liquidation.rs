@@ -0,0 +1,420 @@
+//! Panic-sell: liquidate every token holding back to SOL
+//!
+//! The chosen wallets' holdings are synced and sold most valuable first
+//! through the trade engine, with wide slippage and a priority fee. A sell
+//! that fails is retried with more slippage. Sells are sized in SOL like
+//! every other trade, so the slippage margin leaves some tokens behind; a
+//! sell that lands is followed by another for the rest until only dust is
+//! left or the attempts run out. Each attempt reprices the token first.
+//!
+//! The sells are liquidations, so an engaged kill switch doesn't stop them.
+//! A run can take minutes; it is started as a `panic_sell_jobs` row that
+//! holds its report once it finishes. A user has one run at a time.
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::database::models::{CreateTradeRequest, PanicSellJob, TokenHolding, Trade};
+use crate::market::PriceFeed;
+use crate::trading::engine::{TradeEngine, TradeOptions};
+use crate::trading::executor::TradeExecutor;
+use crate::trading::lifecycle::TradeStatus;
+use crate::wallet::holdings::sync_holdings;
+
+/// Panic-sell job statuses
+pub mod job_status {
+    pub const RUNNING: &str = "running";
+    pub const COMPLETED: &str = "completed";
+    pub const FAILED: &str = "failed";
+}
+
+/// Runs still marked running after this long were cut short by a restart
+const STALE_JOB_MINUTES: i32 = 60;
+
+/// Record a new panic-sell run for the user, or `None` while another is
+/// still running
+pub async fn start_job(pool: &PgPool, user_id: Uuid, wallet_ids: &[Uuid]) -> Result<Option<PanicSellJob>> {
+    sqlx::query(
+        r#"
+        UPDATE panic_sell_jobs SET status = $2, error_message = 'Interrupted', finished_at = NOW()
+        WHERE user_id = $1 AND status = $3 AND created_at < NOW() - make_interval(mins => $4)
+        "#
+    )
+    .bind(user_id)
+    .bind(job_status::FAILED)
+    .bind(job_status::RUNNING)
+    .bind(STALE_JOB_MINUTES)
+    .execute(pool)
+    .await?;
+
+    let job = sqlx::query_as::<_, PanicSellJob>(
+        r#"
+        INSERT INTO panic_sell_jobs (user_id, wallet_ids, status)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) WHERE status = 'running' DO NOTHING
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(wallet_ids)
+    .bind(job_status::RUNNING)
+    .fetch_optional(pool)
+    .await?;
+    Ok(job)
+}
+
+/// Store the outcome of a run
+async fn finish_job(pool: &PgPool, job_id: Uuid, outcome: &Result<PanicSellReport>) -> Result<()> {
+    let (status, report, error) = match outcome {
+        Ok(report) => (job_status::COMPLETED, Some(serde_json::to_value(report)?), None),
+        Err(e) => (job_status::FAILED, None, Some(e.to_string())),
+    };
+    sqlx::query(
+        "UPDATE panic_sell_jobs SET status = $2, report = $3, error_message = $4, finished_at = NOW() WHERE id = $1"
+    )
+    .bind(job_id)
+    .bind(status)
+    .bind(report)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Panic-sell settings
+#[derive(Debug, Clone)]
+pub struct PanicSellConfig {
+    /// Slippage tolerance (percent) after 0, 1, 2... failed attempts; the
+    /// last step repeats
+    pub slippage_steps: Vec<Decimal>,
+    /// Priority fee per sell, in SOL
+    pub priority_fee: Decimal,
+    /// Sells tried per holding, successful ones included
+    pub max_attempts: u32,
+    /// Holdings worth less than this many SOL are left alone
+    pub dust_sol: Decimal,
+    /// How long a submitted sell gets to confirm before it is given up on
+    pub settle_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for PanicSellConfig {
+    fn default() -> Self {
+        Self {
+            slippage_steps: vec![Decimal::from(15), Decimal::from(30), Decimal::from(50)],
+            priority_fee: Decimal::new(1, 3),
+            max_attempts: 5,
+            dust_sol: Decimal::new(1, 3),
+            settle_timeout: Duration::from_secs(90),
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl PanicSellConfig {
+    fn slippage(&self, failures: u32) -> Decimal {
+        let step = (failures as usize).min(self.slippage_steps.len().saturating_sub(1));
+        self.slippage_steps.get(step).copied().unwrap_or(Decimal::from(50))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SellOutcome {
+    /// Sold down to dust
+    Sold,
+    /// Some sold, but more than dust is left
+    Partial,
+    Failed,
+    /// Only dust, or no price to size a sell with
+    Skipped,
+}
+
+/// What happened to one holding
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenSellReport {
+    pub wallet_id: Uuid,
+    pub token_address: String,
+    /// Balance before selling
+    pub balance: Decimal,
+    pub value_sol: Option<Decimal>,
+    pub outcome: SellOutcome,
+    pub attempts: u32,
+    pub sol_received: Decimal,
+    pub remaining: Decimal,
+    pub trade_ids: Vec<Uuid>,
+    /// Why the last attempt failed, or why nothing was tried
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PanicSellReport {
+    pub sol_received: Decimal,
+    /// In the order the holdings were sold
+    pub tokens: Vec<TokenSellReport>,
+}
+
+/// Sells a user's holdings back to SOL through the trade engine
+pub struct PanicSeller {
+    engine: TradeEngine,
+    prices: Arc<dyn PriceFeed>,
+    config: PanicSellConfig,
+}
+
+impl PanicSeller {
+    pub fn new(engine: TradeEngine, prices: Arc<dyn PriceFeed>, config: PanicSellConfig) -> Self {
+        Self { engine, prices, config }
+    }
+
+    /// Run a started job in the background, storing its report when done
+    pub fn spawn(self, job: PanicSellJob) {
+        tokio::spawn(async move {
+            let outcome = self.run(job.user_id, &job.wallet_ids).await;
+            if let Err(e) = &outcome {
+                log::error!("Panic-sell {} failed: {}", job.id, e);
+            }
+            if let Err(e) = finish_job(self.engine.pool(), job.id, &outcome).await {
+                log::error!("Failed to record the outcome of panic-sell {}: {}", job.id, e);
+            }
+        });
+    }
+
+    /// Sell every token the wallets hold, most valuable first. The wallets
+    /// must belong to the user.
+    pub async fn run(&self, user_id: Uuid, wallet_ids: &[Uuid]) -> Result<PanicSellReport> {
+        let paper = self.engine.user_paper_mode(user_id).await?;
+        let executor = self.engine.executor_for_mode(paper)?;
+
+        let mut holdings = Vec::new();
+        for wallet_id in wallet_ids {
+            holdings.extend(self.holdings(executor.as_ref(), *wallet_id).await?);
+        }
+        sort_by_value(&mut holdings);
+
+        let mut tokens = Vec::with_capacity(holdings.len());
+        for holding in &holdings {
+            let report = self.sell(user_id, paper, executor.as_ref(), holding).await;
+            log::info!(
+                "Panic-sell of {} in wallet {}: {:?} after {} attempt(s)",
+                holding.token_address, holding.wallet_id, report.outcome, report.attempts
+            );
+            tokens.push(report);
+        }
+
+        // Leave `token_holdings` showing what is left
+        for wallet_id in wallet_ids {
+            if let Err(e) = sync_holdings(self.engine.pool(), executor.as_ref(), self.prices.as_ref(), *wallet_id).await {
+                log::warn!("Failed to sync holdings of wallet {} after panic-sell: {}", wallet_id, e);
+            }
+        }
+
+        Ok(PanicSellReport {
+            sol_received: tokens.iter().map(|t| t.sol_received).sum(),
+            tokens,
+        })
+    }
+
+    /// Fresh holdings of a wallet, or the last synced ones if the chain
+    /// can't be read right now
+    async fn holdings(&self, executor: &dyn TradeExecutor, wallet_id: Uuid) -> Result<Vec<TokenHolding>> {
+        match sync_holdings(self.engine.pool(), executor, self.prices.as_ref(), wallet_id).await {
+            Ok(holdings) => Ok(holdings),
+            Err(e) => {
+                log::warn!("Selling last synced holdings of wallet {}: {}", wallet_id, e);
+                let holdings = sqlx::query_as::<_, TokenHolding>(
+                    "SELECT * FROM token_holdings WHERE wallet_id = $1 AND balance > 0"
+                )
+                .bind(wallet_id)
+                .fetch_all(self.engine.pool())
+                .await?;
+                Ok(holdings)
+            }
+        }
+    }
+
+    async fn sell(&self, user_id: Uuid, paper: bool, executor: &dyn TradeExecutor, holding: &TokenHolding) -> TokenSellReport {
+        let mut report = TokenSellReport {
+            wallet_id: holding.wallet_id,
+            token_address: holding.token_address.clone(),
+            balance: holding.balance,
+            value_sol: holding.last_price_sol.map(|price| (holding.balance * price).round_dp(9)),
+            outcome: SellOutcome::Skipped,
+            attempts: 0,
+            sol_received: Decimal::ZERO,
+            remaining: holding.balance,
+            trade_ids: Vec::new(),
+            error: None,
+        };
+        let mut price = holding.last_price_sol;
+        let mut failures = 0;
+
+        while report.attempts < self.config.max_attempts {
+            match self.prices.price_in_sol(&holding.token_address).await {
+                Ok(Some(latest)) => price = Some(latest),
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to price {}: {}", holding.token_address, e),
+            }
+            let Some(price) = price else {
+                report.error = Some("No price to size a sell with".to_string());
+                break;
+            };
+
+            let slippage = self.config.slippage(failures);
+            let sol_amount = sell_size(report.remaining, price, slippage);
+            if sol_amount < self.config.dust_sol {
+                break;
+            }
+
+            report.attempts += 1;
+            let req = CreateTradeRequest {
+                wallet_id: holding.wallet_id,
+                token_address: holding.token_address.clone(),
+                trade_type: "sell".to_string(),
+                sol_amount,
+                slippage_tolerance: Some(slippage),
+                priority_fee: Some(self.config.priority_fee),
+            };
+
+            match self.sell_once(user_id, paper, &req).await {
+                Ok(trade) => {
+                    report.trade_ids.push(trade.id);
+                    match trade.lifecycle_status() {
+                        Ok(TradeStatus::Confirmed | TradeStatus::Finalized) => {
+                            report.sol_received += trade.sol_amount;
+                            report.error = None;
+                        }
+                        Ok(TradeStatus::Submitted) => {
+                            // It may still land, so selling again could oversell
+                            report.error = Some(format!("Sell {} was not confirmed in time", trade.id));
+                            break;
+                        }
+                        _ => {
+                            failures += 1;
+                            report.error = Some(trade.error_message.unwrap_or_else(|| format!("Sell {}", trade.status)));
+                        }
+                    }
+                }
+                Err(e) => {
+                    report.error = Some(e.to_string());
+                    failures += 1;
+                }
+            }
+
+            match remaining_balance(executor, holding).await {
+                Ok(remaining) => report.remaining = remaining,
+                Err(e) => {
+                    report.error = Some(format!("Could not read the remaining balance: {}", e));
+                    break;
+                }
+            }
+        }
+
+        let cleared = price.map_or(report.remaining.is_zero(), |price| report.remaining * price < self.config.dust_sol);
+        report.outcome = outcome(!report.sol_received.is_zero(), cleared, report.attempts > 0);
+        report
+    }
+
+    /// Create and execute a sell, then wait for it to confirm or fail
+    async fn sell_once(&self, user_id: Uuid, paper: bool, req: &CreateTradeRequest) -> Result<Trade> {
+        let options = TradeOptions {
+            paper,
+            liquidation: true,
+            ..Default::default()
+        };
+        let trade = self.engine.create_trade(user_id, req, &options).await?;
+        let mut trade = self.engine.execute(&trade).await?;
+
+        let deadline = tokio::time::Instant::now() + self.config.settle_timeout;
+        while trade.lifecycle_status()? == TradeStatus::Submitted && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(self.config.poll_interval).await;
+            trade = self.engine.load(trade.id).await?;
+        }
+        Ok(trade)
+    }
+}
+
+async fn remaining_balance(executor: &dyn TradeExecutor, holding: &TokenHolding) -> Result<Decimal> {
+    let balances = executor.token_balances(holding.wallet_id).await?;
+    Ok(balances
+        .into_iter()
+        .find(|balance| balance.mint == holding.token_address)
+        .map(|balance| balance.amount)
+        .unwrap_or_default())
+}
+
+/// Most valuable holdings first; unpriced ones last
+fn sort_by_value(holdings: &mut [TokenHolding]) {
+    holdings.sort_by_key(|holding| {
+        std::cmp::Reverse(holding.last_price_sol.map(|price| holding.balance * price))
+    });
+}
+
+/// SOL to ask for when selling `balance` at `price`, leaving room for the
+/// slippage so the sell doesn't need more tokens than the wallet holds
+fn sell_size(balance: Decimal, price: Decimal, slippage_pct: Decimal) -> Decimal {
+    (balance * price * (Decimal::ONE - slippage_pct / Decimal::from(100))).round_dp(9)
+}
+
+fn outcome(sold_any: bool, cleared: bool, attempted: bool) -> SellOutcome {
+    match (sold_any, cleared) {
+        (true, true) => SellOutcome::Sold,
+        (true, false) => SellOutcome::Partial,
+        (false, _) if attempted => SellOutcome::Failed,
+        (false, _) => SellOutcome::Skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn holding(mint: &str, balance: &str, price: Option<&str>) -> TokenHolding {
+        TokenHolding {
+            id: Uuid::new_v4(),
+            wallet_id: Uuid::new_v4(),
+            token_address: mint.to_string(),
+            token_symbol: None,
+            token_name: None,
+            balance: dec(balance),
+            decimals: 6,
+            last_price_sol: price.map(dec),
+            last_updated: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_sells_in_value_order_with_escalating_slippage() {
+        let mut holdings = vec![
+            holding("Unpriced", "1000000", None),
+            holding("Small", "1000", Some("0.001")),
+            holding("Large", "50", Some("0.5")),
+        ];
+        sort_by_value(&mut holdings);
+        let order: Vec<&str> = holdings.iter().map(|h| h.token_address.as_str()).collect();
+        assert_eq!(order, ["Large", "Small", "Unpriced"]);
+
+        let config = PanicSellConfig::default();
+        assert_eq!(
+            (config.slippage(0), config.slippage(1), config.slippage(2), config.slippage(7)),
+            (dec("15"), dec("30"), dec("50"), dec("50"))
+        );
+        // 25 SOL worth at 15% slippage
+        assert_eq!(sell_size(dec("50"), dec("0.5"), dec("15")), dec("21.25"));
+
+        assert_eq!(outcome(true, true, true), SellOutcome::Sold);
+        assert_eq!(outcome(true, false, true), SellOutcome::Partial);
+        assert_eq!(outcome(false, false, true), SellOutcome::Failed);
+        assert_eq!(outcome(false, true, false), SellOutcome::Skipped);
+    }
+}
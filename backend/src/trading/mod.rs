@@ -0,0 +1,29 @@
+//! Trading module for Cerberus Chain: Hydra
+//! Owns the trade lifecycle from request through on-chain finality
+
+pub mod bundle;
+pub mod confirmer;
+pub mod engine;
+pub mod executor;
+pub mod export;
+pub mod fill;
+pub mod history;
+pub mod jupiter;
+pub mod kill_switch;
+pub mod lifecycle;
+pub mod liquidation;
+pub mod paper;
+pub mod risk;
+pub mod simulation;
+
+pub use bundle::{BlockEngine, BundleStatus, JitoBlockEngine, LocalBlockEngine};
+pub use confirmer::{ConfirmerConfig, TradeConfirmer};
+pub use engine::{validate_trade_request, BundleExecution, TradeEngine, TradeOptions};
+pub use executor::{ChainStatus, NotSent, PreparedTrade, SolanaExecutor, TradeExecutor};
+pub use fill::ExecutedFill;
+pub use kill_switch::TradingHalted;
+pub use lifecycle::TradeStatus;
+pub use liquidation::{PanicSellConfig, PanicSellReport, PanicSeller};
+pub use paper::{PaperConfig, PaperExecutor};
+pub use risk::{RiskManager, RiskRejection, RiskRule};
+pub use simulation::SimulationReport;
@@ -0,0 +1,408 @@
+//! Paper trading: fills at live quotes against a virtual ledger
+//!
+//! Paper trades go through the same lifecycle as live ones, but the
+//! executor never signs or broadcasts anything. Fills are modeled from the
+//! Jupiter quote with slippage and fees, and settle against per-wallet
+//! virtual balances in `paper_balances` rather than `wallets.sol_balance`.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::{PaperBalance, Trade};
+use crate::trading::executor::{
+    lamports_to_sol, raw_to_ui_amount, slippage_bps, sol_to_lamports, ChainStatus, PreparedTrade,
+    NotSent, SolanaExecutor, TradeExecutor,
+};
+use crate::trading::jupiter::{SwapMode, SwapQuote, SOL_MINT};
+use crate::trading::simulation::{SimulationReport, LAMPORTS_PER_SIGNATURE};
+use crate::wallet::holdings::TokenBalance;
+
+/// Paper fill model settings
+#[derive(Debug, Clone)]
+pub struct PaperConfig {
+    /// Slippage applied to every fill on top of the quoted price impact
+    pub base_slippage_bps: u16,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self { base_slippage_bps: 30 }
+    }
+}
+
+/// Modeled result of a paper fill
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaperFill {
+    pub sol_amount: Decimal,
+    pub token_amount: Decimal,
+    pub fee_sol: Decimal,
+}
+
+/// Error for a paper fill whose modeled slippage is beyond the trade's
+/// tolerance; a live swap would fail its slippage check the same way
+#[derive(Debug, Clone)]
+pub struct SlippageExceeded {
+    /// The fill as modeled, slippage included
+    pub fill: PaperFill,
+    pub slippage_pct: Decimal,
+    pub tolerance_pct: Decimal,
+}
+
+impl fmt::Display for SlippageExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Slippage of {}% exceeds the tolerance of {}%",
+            self.slippage_pct.normalize(),
+            self.tolerance_pct.normalize()
+        )
+    }
+}
+
+impl std::error::Error for SlippageExceeded {}
+
+/// Model a fill from quoted amounts. Slippage is the base slippage plus the
+/// quoted price impact; buys receive fewer tokens and sells spend more.
+/// Fails with `SlippageExceeded` when that is more than the trade's tolerance.
+pub fn model_fill(
+    trade_type: &str,
+    quoted_sol: Decimal,
+    quoted_tokens: Decimal,
+    price_impact_pct: Decimal,
+    tolerance_bps: u16,
+    fee_sol: Decimal,
+    config: &PaperConfig,
+) -> Result<PaperFill> {
+    let bps = Decimal::from(10_000);
+    let slippage = Decimal::from(config.base_slippage_bps) / bps + price_impact_pct / Decimal::from(100);
+    let tolerance = Decimal::from(tolerance_bps) / bps;
+
+    let token_amount = if trade_type == "buy" {
+        quoted_tokens * (Decimal::ONE - slippage)
+    } else {
+        quoted_tokens * (Decimal::ONE + slippage)
+    };
+    let fill = PaperFill {
+        sol_amount: quoted_sol,
+        token_amount: token_amount.round_dp(9),
+        fee_sol,
+    };
+
+    if slippage > tolerance {
+        let percent = Decimal::from(100);
+        return Err(SlippageExceeded {
+            fill,
+            slippage_pct: slippage * percent,
+            tolerance_pct: tolerance * percent,
+        }
+        .into());
+    }
+    Ok(fill)
+}
+
+/// Whether a bot's `config_json` asks for paper execution
+pub fn is_paper_config(config: &serde_json::Value) -> bool {
+    config.get("paper").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Executor that quotes live but settles against the virtual ledger
+pub struct PaperExecutor {
+    pool: PgPool,
+    quoter: Arc<SolanaExecutor>,
+    config: PaperConfig,
+}
+
+impl PaperExecutor {
+    pub fn new(pool: PgPool, quoter: Arc<SolanaExecutor>, config: PaperConfig) -> Self {
+        Self {
+            pool,
+            quoter,
+            config,
+        }
+    }
+
+    async fn fill_for(&self, trade: &Trade, quote: &SwapQuote) -> Result<PaperFill> {
+        let decimals = self.quoter.token_decimals(&trade.token_address).await?;
+        let (sol_raw, token_raw) = match quote.swap_mode {
+            SwapMode::ExactIn => (quote.in_amount_raw()?, quote.out_amount_raw()?),
+            SwapMode::ExactOut => (quote.out_amount_raw()?, quote.in_amount_raw()?),
+        };
+        let price_impact_pct = Decimal::from_str(&quote.price_impact_pct).unwrap_or_default();
+        let priority_fee = trade.priority_fee.map(sol_to_lamports).transpose()?.unwrap_or(0);
+
+        model_fill(
+            &trade.trade_type,
+            lamports_to_sol(sol_raw),
+            raw_to_ui_amount(token_raw, decimals),
+            price_impact_pct,
+            slippage_bps(trade),
+            lamports_to_sol(LAMPORTS_PER_SIGNATURE + priority_fee),
+            &self.config,
+        )
+    }
+
+    /// Amounts a fill debits from and credits to the ledger, as (mint, amount)
+    fn legs(trade: &Trade, fill: &PaperFill) -> ((String, Decimal), (String, Decimal)) {
+        if trade.trade_type == "buy" {
+            (
+                (SOL_MINT.to_string(), fill.sol_amount + fill.fee_sol),
+                (trade.token_address.clone(), fill.token_amount),
+            )
+        } else {
+            (
+                (trade.token_address.clone(), fill.token_amount),
+                (SOL_MINT.to_string(), fill.sol_amount - fill.fee_sol),
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl TradeExecutor for PaperExecutor {
+    async fn prepare(&self, trade: &Trade) -> Result<PreparedTrade> {
+        let quote = self.quoter.quote(trade).await?;
+
+        // Paper trades carry a synthetic signature so they can be referenced like live ones
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            ..Default::default()
+        };
+
+        Ok(PreparedTrade {
+            transaction,
+            last_valid_block_height: 0,
+            quote,
+        })
+    }
+
+    async fn simulate(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<SimulationReport> {
+        // Too much slippage fails the simulation, as it would a live one
+        let (fill, slippage_error) = match self.fill_for(trade, &prepared.quote).await {
+            Ok(fill) => (fill, None),
+            Err(e) => match e.downcast::<SlippageExceeded>() {
+                Ok(exceeded) => (exceeded.fill.clone(), Some(exceeded.to_string())),
+                Err(e) => return Err(e),
+            },
+        };
+        let (debit, _) = Self::legs(trade, &fill);
+        let available = balance(&self.pool, trade.wallet_id, &debit.0).await?;
+
+        let error = slippage_error.or_else(|| {
+            (available < debit.1)
+                .then(|| format!("Insufficient paper balance: {} available, {} required", available, debit.1))
+        });
+
+        Ok(SimulationReport {
+            success: error.is_none(),
+            error,
+            input_mint: prepared.quote.input_mint.clone(),
+            output_mint: prepared.quote.output_mint.clone(),
+            sol_amount: fill.sol_amount,
+            expected_token_amount: fill.token_amount,
+            worst_case_token_amount: fill.token_amount,
+            price_per_token: if fill.token_amount.is_zero() {
+                None
+            } else {
+                Some(fill.sol_amount / fill.token_amount)
+            },
+            price_impact_pct: Decimal::from_str(&prepared.quote.price_impact_pct).unwrap_or_default(),
+            network_fee_lamports: LAMPORTS_PER_SIGNATURE,
+            priority_fee_lamports: trade.priority_fee.map(sol_to_lamports).transpose()?.unwrap_or(0),
+            compute_units_consumed: None,
+            logs: vec!["Paper fill".to_string()],
+        })
+    }
+
+    async fn dry_run(&self, trade: &Trade) -> Result<SimulationReport> {
+        let prepared = self.prepare(trade).await?;
+        self.simulate(trade, &prepared).await
+    }
+
+    async fn submit(&self, trade: &Trade, prepared: &PreparedTrade) -> Result<Signature> {
+        // Nothing is broadcast, so a fill that doesn't settle never happened
+        let settled = async {
+            let fill = self.fill_for(trade, &prepared.quote).await?;
+            let (debit, credit) = Self::legs(trade, &fill);
+
+            let mut tx = self.pool.begin().await?;
+            debit_balance(&mut tx, trade.wallet_id, &debit.0, debit.1).await?;
+            credit_balance(&mut tx, trade.user_id, trade.wallet_id, &credit.0, credit.1).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        settled.map_err(|e| NotSent(e).into()).map(|()| prepared.signature())
+    }
+
+    async fn signature_statuses(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+        // Paper fills settle immediately
+        Ok(vec![ChainStatus::Finalized; signatures.len()])
+    }
+
+    async fn signature_history(&self, signatures: &[Signature]) -> Result<Vec<ChainStatus>> {
+        self.signature_statuses(signatures).await
+    }
+
+    async fn block_height(&self) -> Result<u64> {
+        Ok(0)
+    }
+
+    async fn sol_balance(&self, wallet_id: Uuid) -> Result<Decimal> {
+        balance(&self.pool, wallet_id, SOL_MINT).await
+    }
+
+    async fn token_balances(&self, wallet_id: Uuid) -> Result<Vec<TokenBalance>> {
+        let mut holdings = Vec::new();
+        for paper in balances(&self.pool, wallet_id).await? {
+            if paper.token_address == SOL_MINT || paper.balance <= Decimal::ZERO {
+                continue;
+            }
+            holdings.push(TokenBalance {
+                decimals: self.quoter.token_decimals(&paper.token_address).await?,
+                mint: paper.token_address,
+                amount: paper.balance,
+            });
+        }
+        Ok(holdings)
+    }
+}
+
+/// Current virtual balance of a mint in a wallet
+pub async fn balance(pool: &PgPool, wallet_id: Uuid, mint: &str) -> Result<Decimal> {
+    let balance = sqlx::query_scalar::<_, Decimal>(
+        "SELECT balance FROM paper_balances WHERE wallet_id = $1 AND token_address = $2"
+    )
+    .bind(wallet_id)
+    .bind(mint)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(balance.unwrap_or(Decimal::ZERO))
+}
+
+/// All virtual balances of a wallet
+pub async fn balances(pool: &PgPool, wallet_id: Uuid) -> Result<Vec<PaperBalance>> {
+    let balances = sqlx::query_as::<_, PaperBalance>(
+        "SELECT * FROM paper_balances WHERE wallet_id = $1 AND balance > 0 ORDER BY token_address"
+    )
+    .bind(wallet_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(balances)
+}
+
+/// Add virtual SOL to a wallet
+pub async fn fund(pool: &PgPool, user_id: Uuid, wallet_id: Uuid, sol_amount: Decimal) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    credit_balance(&mut tx, user_id, wallet_id, SOL_MINT, sol_amount).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Wipe every virtual balance of a wallet
+pub async fn reset(pool: &PgPool, wallet_id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM paper_balances WHERE wallet_id = $1")
+        .bind(wallet_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn debit_balance(
+    tx: &mut Transaction<'_, Postgres>,
+    wallet_id: Uuid,
+    mint: &str,
+    amount: Decimal,
+) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE paper_balances SET balance = balance - $3, updated_at = NOW()
+        WHERE wallet_id = $1 AND token_address = $2 AND balance >= $3
+        "#
+    )
+    .bind(wallet_id)
+    .bind(mint)
+    .bind(amount)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow!("Insufficient paper balance of {}", mint));
+    }
+    Ok(())
+}
+
+async fn credit_balance(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    mint: &str,
+    amount: Decimal,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO paper_balances (id, user_id, wallet_id, token_address, balance)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (wallet_id, token_address)
+        DO UPDATE SET balance = paper_balances.balance + EXCLUDED.balance, updated_at = NOW()
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(wallet_id)
+    .bind(mint)
+    .bind(amount)
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_buy_fill_receives_fewer_tokens() {
+        let config = PaperConfig { base_slippage_bps: 50 };
+        let fill = model_fill("buy", dec("1"), dec("1000"), dec("0.5"), 500, dec("0.000005"), &config).unwrap();
+
+        // 0.5% base + 0.5% impact
+        assert_eq!(fill.token_amount, dec("990"));
+        assert_eq!(fill.sol_amount, dec("1"));
+    }
+
+    #[test]
+    fn test_sell_fill_spends_more_tokens() {
+        let config = PaperConfig { base_slippage_bps: 50 };
+        let fill = model_fill("sell", dec("1"), dec("1000"), dec("0"), 500, dec("0"), &config).unwrap();
+        assert_eq!(fill.token_amount, dec("1005"));
+    }
+
+    #[test]
+    fn test_slippage_beyond_tolerance_fails_the_fill() {
+        let config = PaperConfig { base_slippage_bps: 50 };
+        let error = model_fill("buy", dec("1"), dec("1000"), dec("10"), 100, dec("0"), &config).unwrap_err();
+
+        let exceeded = error.downcast_ref::<SlippageExceeded>().unwrap();
+        assert_eq!(exceeded.to_string(), "Slippage of 10.5% exceeds the tolerance of 1%");
+        assert_eq!(exceeded.fill.token_amount, dec("895"));
+
+        // Exactly at the tolerance still fills
+        let fill = model_fill("buy", dec("1"), dec("1000"), dec("0.5"), 100, dec("0"), &config).unwrap();
+        assert_eq!(fill.token_amount, dec("990"));
+    }
+}
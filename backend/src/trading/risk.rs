@@ -0,0 +1,392 @@
+//! Risk limits every trade is checked against before it executes
+//!
+//! Limits are set per user, per wallet or per bot in `risk_limits`. A trade
+//! is held to every row that covers it, and each row measures usage over its
+//! own scope: a wallet row counts that wallet's trades, a bot row that bot's.
+//! A rejected trade fails with the broken rule in its `error_message`.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::database::models::{RiskLimit, SetRiskLimitRequest, Trade};
+use crate::positions::report::{build_book, FillScope};
+use crate::positions::CostMethod;
+use crate::trading::executor::{lamports_to_sol, TradeExecutor};
+use crate::trading::lifecycle::TradeStatus;
+use crate::trading::simulation::LAMPORTS_PER_SIGNATURE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskRule {
+    MaxSolPerTrade,
+    DailyLossLimit,
+    MaxTokenExposure,
+    MaxTradesPerMinute,
+    MinSolReserve,
+}
+
+impl RiskRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskRule::MaxSolPerTrade => "max_sol_per_trade",
+            RiskRule::DailyLossLimit => "daily_loss_limit",
+            RiskRule::MaxTokenExposure => "max_token_exposure",
+            RiskRule::MaxTradesPerMinute => "max_trades_per_minute",
+            RiskRule::MinSolReserve => "min_sol_reserve",
+        }
+    }
+}
+
+/// Why a trade was refused: the rule, the scope of the limit row ("user",
+/// "wallet" or "bot"), the limit and the value that broke it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskRejection {
+    pub rule: RiskRule,
+    pub scope: &'static str,
+    pub limit: Decimal,
+    pub actual: Decimal,
+}
+
+impl fmt::Display for RiskRejection {
+    /// Rendered as `Risk limit <rule> (<scope>): <detail>`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Risk limit {} ({}): ", self.rule.as_str(), self.scope)?;
+        match self.rule {
+            RiskRule::MaxSolPerTrade => {
+                write!(f, "trade of {} SOL is over the {} SOL limit", self.actual, self.limit)
+            }
+            RiskRule::DailyLossLimit => {
+                write!(f, "{} SOL lost today reaches the {} SOL limit", self.actual, self.limit)
+            }
+            RiskRule::MaxTokenExposure => {
+                write!(f, "{} SOL held in the token would pass the {} SOL limit", self.actual, self.limit)
+            }
+            RiskRule::MaxTradesPerMinute => {
+                write!(f, "trade {} in a minute passes the limit of {}", self.actual, self.limit)
+            }
+            RiskRule::MinSolReserve => {
+                write!(f, "{} SOL left in the wallet is under the {} SOL reserve", self.actual, self.limit)
+            }
+        }
+    }
+}
+
+/// Validate a risk limit request before it is stored
+pub fn validate_risk_limit(req: &SetRiskLimitRequest) -> Result<()> {
+    if req.wallet_id.is_some() && req.bot_config_id.is_some() {
+        return Err(anyhow!("A risk limit applies to a wallet or a bot, not both"));
+    }
+
+    let positive = [
+        ("max_sol_per_trade", req.max_sol_per_trade),
+        ("daily_loss_limit_sol", req.daily_loss_limit_sol),
+        ("max_token_exposure_sol", req.max_token_exposure_sol),
+    ];
+    for (field, value) in positive {
+        if value.is_some_and(|value| value <= Decimal::ZERO) {
+            return Err(anyhow!("{} must be greater than zero", field));
+        }
+    }
+    if req.max_trades_per_minute.is_some_and(|value| value <= 0) {
+        return Err(anyhow!("max_trades_per_minute must be greater than zero"));
+    }
+    if req.min_sol_reserve.is_some_and(|value| value < Decimal::ZERO) {
+        return Err(anyhow!("min_sol_reserve cannot be negative"));
+    }
+
+    Ok(())
+}
+
+/// Create or replace the limits of a scope
+pub async fn set_limit(pool: &PgPool, user_id: Uuid, req: &SetRiskLimitRequest) -> Result<RiskLimit> {
+    let updated = sqlx::query_as::<_, RiskLimit>(
+        r#"
+        UPDATE risk_limits
+        SET max_sol_per_trade = $4, daily_loss_limit_sol = $5, max_token_exposure_sol = $6,
+            max_trades_per_minute = $7, min_sol_reserve = $8, updated_at = NOW()
+        WHERE user_id = $1 AND wallet_id IS NOT DISTINCT FROM $2 AND bot_config_id IS NOT DISTINCT FROM $3
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(req.wallet_id)
+    .bind(req.bot_config_id)
+    .bind(req.max_sol_per_trade)
+    .bind(req.daily_loss_limit_sol)
+    .bind(req.max_token_exposure_sol)
+    .bind(req.max_trades_per_minute)
+    .bind(req.min_sol_reserve)
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(limit) = updated {
+        return Ok(limit);
+    }
+
+    let limit = sqlx::query_as::<_, RiskLimit>(
+        r#"
+        INSERT INTO risk_limits (user_id, wallet_id, bot_config_id, max_sol_per_trade, daily_loss_limit_sol,
+                                 max_token_exposure_sol, max_trades_per_minute, min_sol_reserve)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(req.wallet_id)
+    .bind(req.bot_config_id)
+    .bind(req.max_sol_per_trade)
+    .bind(req.daily_loss_limit_sol)
+    .bind(req.max_token_exposure_sol)
+    .bind(req.max_trades_per_minute)
+    .bind(req.min_sol_reserve)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(limit)
+}
+
+/// What a limit row's scope has used, measured only for the limits it sets
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    /// Other live trades created in the last minute
+    trades_last_minute: i64,
+    /// Net realized loss since midnight UTC
+    daily_loss_sol: Decimal,
+    /// Open cost basis in the trade's token
+    token_exposure_sol: Decimal,
+    sol_balance: Option<Decimal>,
+}
+
+fn scope_of(limit: &RiskLimit) -> &'static str {
+    if limit.bot_config_id.is_some() {
+        "bot"
+    } else if limit.wallet_id.is_some() {
+        "wallet"
+    } else {
+        "user"
+    }
+}
+
+/// SOL a trade costs beyond its amount: one signature plus its priority fee
+fn fee_estimate(trade: &Trade) -> Decimal {
+    lamports_to_sol(LAMPORTS_PER_SIGNATURE) + trade.priority_fee.unwrap_or_default()
+}
+
+/// Check a trade against one limit row. Sells only face the size and rate
+/// limits, so a position can always be unwound.
+fn check_limit(limit: &RiskLimit, trade: &Trade, usage: &Usage) -> Option<RiskRejection> {
+    let scope = scope_of(limit);
+    let rejection = |rule, limit: Decimal, actual: Decimal| Some(RiskRejection { rule, scope, limit, actual });
+    let is_buy = trade.trade_type == "buy";
+
+    if let Some(max) = limit.max_sol_per_trade {
+        if trade.sol_amount > max {
+            return rejection(RiskRule::MaxSolPerTrade, max, trade.sol_amount);
+        }
+    }
+    if let Some(max) = limit.max_trades_per_minute {
+        if usage.trades_last_minute >= max as i64 {
+            return rejection(
+                RiskRule::MaxTradesPerMinute,
+                Decimal::from(max),
+                Decimal::from(usage.trades_last_minute + 1),
+            );
+        }
+    }
+    if !is_buy {
+        return None;
+    }
+
+    if let Some(max) = limit.daily_loss_limit_sol {
+        if usage.daily_loss_sol >= max {
+            return rejection(RiskRule::DailyLossLimit, max, usage.daily_loss_sol);
+        }
+    }
+    if let Some(max) = limit.max_token_exposure_sol {
+        let exposure = usage.token_exposure_sol + trade.sol_amount;
+        if exposure > max {
+            return rejection(RiskRule::MaxTokenExposure, max, exposure);
+        }
+    }
+    if let (Some(reserve), Some(balance)) = (limit.min_sol_reserve, usage.sol_balance) {
+        let left = balance - trade.sol_amount - fee_estimate(trade);
+        if left < reserve {
+            return rejection(RiskRule::MinSolReserve, reserve, left);
+        }
+    }
+    None
+}
+
+/// Checks trades against the stored limits
+#[derive(Clone)]
+pub struct RiskManager {
+    pool: PgPool,
+}
+
+impl RiskManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Limit rows covering a trade: the user's, its wallet's and its bot's
+    pub async fn limits_for(&self, trade: &Trade) -> Result<Vec<RiskLimit>> {
+        let limits = sqlx::query_as::<_, RiskLimit>(
+            r#"
+            SELECT * FROM risk_limits
+            WHERE user_id = $1
+              AND (wallet_id IS NULL OR wallet_id = $2)
+              AND (bot_config_id IS NULL OR bot_config_id = $3)
+            "#
+        )
+        .bind(trade.user_id)
+        .bind(trade.wallet_id)
+        .bind(trade.bot_config_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(limits)
+    }
+
+    /// The first limit a trade breaks, if any
+    pub async fn check(&self, trade: &Trade, executor: &dyn TradeExecutor) -> Result<Option<RiskRejection>> {
+        let mut sol_balance = None;
+
+        for limit in self.limits_for(trade).await? {
+            let mut usage = self.usage(&limit, trade).await?;
+            if limit.min_sol_reserve.is_some() && trade.trade_type == "buy" {
+                if sol_balance.is_none() {
+                    sol_balance = Some(executor.sol_balance(trade.wallet_id).await?);
+                }
+                usage.sol_balance = sol_balance;
+            }
+
+            if let Some(rejection) = check_limit(&limit, trade, &usage) {
+                return Ok(Some(rejection));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn usage(&self, limit: &RiskLimit, trade: &Trade) -> Result<Usage> {
+        let mut usage = Usage::default();
+
+        if limit.max_trades_per_minute.is_some() {
+            usage.trades_last_minute = sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT COUNT(*) FROM trades
+                WHERE user_id = $1
+                  AND ($2::uuid IS NULL OR wallet_id = $2)
+                  AND ($3::uuid IS NULL OR bot_config_id = $3)
+                  AND is_paper = $4
+                  AND id <> $5
+                  AND created_at > NOW() - INTERVAL '1 minute'
+                  AND status NOT IN ($6, $7)
+                "#
+            )
+            .bind(limit.user_id)
+            .bind(limit.wallet_id)
+            .bind(limit.bot_config_id)
+            .bind(trade.is_paper)
+            .bind(trade.id)
+            .bind(TradeStatus::Failed.as_str())
+            .bind(TradeStatus::Cancelled.as_str())
+            .fetch_one(&self.pool)
+            .await?;
+        }
+
+        let needs_book = limit.daily_loss_limit_sol.is_some() || limit.max_token_exposure_sol.is_some();
+        if needs_book && trade.trade_type == "buy" {
+            let scope = FillScope {
+                user_id: limit.user_id,
+                paper: trade.is_paper,
+                wallet_id: limit.wallet_id,
+                bot_config_id: limit.bot_config_id,
+                until: None,
+            };
+            let book = build_book(&self.pool, &scope, CostMethod::default()).await?;
+
+            let midnight = Utc::now().date_naive().and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+            let realized_today: Decimal = book.realizations()
+                .iter()
+                .filter(|r| midnight.is_some_and(|midnight| r.executed_at >= midnight))
+                .map(|r| r.realized_pnl)
+                .sum();
+            usage.daily_loss_sol = (-realized_today).max(Decimal::ZERO);
+            usage.token_exposure_sol = book.positions()
+                .filter(|p| p.token_address == trade.token_address)
+                .map(|p| p.cost_basis)
+                .sum();
+        }
+
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::engine::{draft_trade, TradeOptions};
+    use crate::database::models::CreateTradeRequest;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn trade(trade_type: &str, sol_amount: &str) -> Trade {
+        let req = CreateTradeRequest {
+            wallet_id: Uuid::new_v4(),
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            trade_type: trade_type.to_string(),
+            sol_amount: dec(sol_amount),
+            slippage_tolerance: None,
+            priority_fee: Some(dec("0.001")),
+        };
+        draft_trade(Uuid::new_v4(), &req, &TradeOptions::default())
+    }
+
+    #[test]
+    fn test_check_limit() {
+        let limit = RiskLimit {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            wallet_id: Some(Uuid::new_v4()),
+            bot_config_id: None,
+            max_sol_per_trade: Some(dec("2")),
+            daily_loss_limit_sol: Some(dec("5")),
+            max_token_exposure_sol: Some(dec("3")),
+            max_trades_per_minute: Some(10),
+            min_sol_reserve: Some(dec("0.5")),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let usage = Usage {
+            trades_last_minute: 3,
+            daily_loss_sol: dec("1"),
+            token_exposure_sol: dec("1.5"),
+            sol_balance: Some(dec("10")),
+        };
+        let rule = |trade: &Trade, usage: &Usage| check_limit(&limit, trade, usage).map(|r| r.rule);
+
+        assert_eq!(rule(&trade("buy", "1"), &usage), None);
+        assert_eq!(rule(&trade("buy", "2.5"), &usage), Some(RiskRule::MaxSolPerTrade));
+        assert_eq!(rule(&trade("buy", "1.6"), &usage), Some(RiskRule::MaxTokenExposure));
+
+        let rejection = check_limit(&limit, &trade("buy", "1"), &Usage { sol_balance: Some(dec("1.2")), ..usage.clone() });
+        assert_eq!(
+            rejection.unwrap().to_string(),
+            "Risk limit min_sol_reserve (wallet): 0.198995 SOL left in the wallet is under the 0.5 SOL reserve"
+        );
+
+        // Sells can always unwind, but still face the size and rate limits
+        let losing = Usage { daily_loss_sol: dec("6"), trades_last_minute: 10, ..usage.clone() };
+        assert_eq!(rule(&trade("buy", "1"), &losing), Some(RiskRule::MaxTradesPerMinute));
+        assert_eq!(rule(&trade("sell", "1"), &Usage { trades_last_minute: 0, ..losing.clone() }), None);
+        assert_eq!(rule(&trade("buy", "1"), &Usage { trades_last_minute: 0, ..losing }), Some(RiskRule::DailyLossLimit));
+    }
+}
@@ -0,0 +1,45 @@
+//! Pre-flight simulation results and dry-run support
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Base fee charged per transaction signature
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Outcome of simulating a trade's swap transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub success: bool,
+    pub error: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    /// SOL spent (buys) or received (sells)
+    pub sol_amount: Decimal,
+    /// Tokens received (buys) or spent (sells), in UI units
+    pub expected_token_amount: Decimal,
+    /// Minimum tokens received (buys) or maximum tokens spent (sells)
+    /// within the slippage tolerance, in UI units
+    pub worst_case_token_amount: Decimal,
+    pub price_per_token: Option<Decimal>,
+    pub price_impact_pct: Decimal,
+    pub network_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
+    pub compute_units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+impl SimulationReport {
+    /// Short failure summary suitable for `trades.error_message`
+    pub fn failure_message(&self) -> String {
+        let error = self.error.as_deref().unwrap_or("unknown error");
+        match self.logs.last() {
+            Some(last_log) => format!("Simulation failed: {} ({})", error, last_log),
+            None => format!("Simulation failed: {}", error),
+        }
+    }
+}
+
+/// Whether a bot's `config_json` asks for dry-run execution
+pub fn is_dry_run_config(config: &serde_json::Value) -> bool {
+    config.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false)
+}
@@ -0,0 +1,218 @@
+//! Token holdings of a wallet
+//!
+//! Token accounts are read from the chain under both token programs and
+//! summed per mint. `sync_holdings` mirrors those balances, priced in SOL
+//! where a price is known, into `token_holdings`. Wrapped SOL is SOL and is
+//! never counted as a holding.
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::database::models::TokenHolding;
+use crate::market::PriceFeed;
+use crate::trading::jupiter::SOL_MINT;
+use crate::trading::TradeExecutor;
+
+pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// An SPL token account owned by a wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub address: String,
+    pub mint: String,
+    /// Token program the account belongs to
+    pub program: String,
+    pub raw_amount: u64,
+    pub amount: Decimal,
+    pub decimals: u8,
+    /// Rent held by the account
+    pub lamports: u64,
+    /// Frozen accounts can neither move tokens nor be closed
+    pub frozen: bool,
+}
+
+/// A wallet's balance of one mint across its token accounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub mint: String,
+    pub amount: Decimal,
+    pub decimals: u8,
+}
+
+/// Every token account the owner holds under either token program
+pub async fn token_accounts(rpc: &RpcClient, owner: &str) -> Result<Vec<TokenAccount>> {
+    let mut accounts = Vec::new();
+    for program in [TOKEN_PROGRAM, TOKEN_2022_PROGRAM] {
+        let response: Value = rpc
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([owner, {"programId": program}, {"encoding": "jsonParsed", "commitment": "confirmed"}]),
+            )
+            .await?;
+        accounts.extend(parse_token_accounts(&response, program)?);
+    }
+    Ok(accounts)
+}
+
+/// Read the accounts of a `jsonParsed` `getTokenAccountsByOwner` result
+pub fn parse_token_accounts(response: &Value, program: &str) -> Result<Vec<TokenAccount>> {
+    let entries = response
+        .get("value")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Unexpected getTokenAccountsByOwner response"))?;
+
+    entries
+        .iter()
+        .map(|entry| {
+            let info = entry
+                .pointer("/account/data/parsed/info")
+                .ok_or_else(|| anyhow!("Token account is not parsed"))?;
+            let field = |pointer: &str| info.pointer(pointer).ok_or_else(|| anyhow!("Token account has no {}", pointer));
+            let str_field = |pointer: &str| -> Result<String> {
+                field(pointer)?.as_str().map(str::to_string).ok_or_else(|| anyhow!("Token account {} is not a string", pointer))
+            };
+
+            Ok(TokenAccount {
+                address: entry
+                    .get("pubkey")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("Token account has no address"))?
+                    .to_string(),
+                mint: str_field("/mint")?,
+                program: program.to_string(),
+                raw_amount: str_field("/tokenAmount/amount")?.parse()?,
+                amount: Decimal::from_str(&str_field("/tokenAmount/uiAmountString")?)?,
+                decimals: field("/tokenAmount/decimals")?
+                    .as_u64()
+                    .and_then(|d| u8::try_from(d).ok())
+                    .ok_or_else(|| anyhow!("Token account has invalid decimals"))?,
+                lamports: entry.pointer("/account/lamports").and_then(Value::as_u64).unwrap_or_default(),
+                frozen: info.get("state").and_then(Value::as_str) == Some("frozen"),
+            })
+        })
+        .collect()
+}
+
+/// Sum token accounts into non-zero balances per mint, wrapped SOL excluded
+pub fn sum_balances(accounts: &[TokenAccount]) -> Vec<TokenBalance> {
+    let mut balances: BTreeMap<&str, TokenBalance> = BTreeMap::new();
+    for account in accounts.iter().filter(|a| a.raw_amount > 0 && a.mint != SOL_MINT) {
+        balances
+            .entry(&account.mint)
+            .or_insert_with(|| TokenBalance {
+                mint: account.mint.clone(),
+                amount: Decimal::ZERO,
+                decimals: account.decimals,
+            })
+            .amount += account.amount;
+    }
+    balances.into_values().collect()
+}
+
+/// Refresh a wallet's `token_holdings` from the executor's view of its
+/// balances. Mints no longer held are removed; a holding keeps its last
+/// known price when none is available now.
+pub async fn sync_holdings(
+    pool: &PgPool,
+    executor: &dyn TradeExecutor,
+    prices: &dyn PriceFeed,
+    wallet_id: Uuid,
+) -> Result<Vec<TokenHolding>> {
+    let balances = executor.token_balances(wallet_id).await?;
+    let mints: Vec<String> = balances.iter().map(|b| b.mint.clone()).collect();
+
+    // Holdings are still worth recording without prices
+    let prices = if mints.is_empty() {
+        HashMap::new()
+    } else {
+        prices.prices_in_sol(&mints).await.unwrap_or_else(|e| {
+            log::warn!("Failed to price holdings of wallet {}: {}", wallet_id, e);
+            HashMap::new()
+        })
+    };
+
+    let mut tx = pool.begin().await?;
+    for balance in &balances {
+        sqlx::query(
+            r#"
+            INSERT INTO token_holdings (wallet_id, token_address, balance, decimals, last_price_sol, last_updated)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (wallet_id, token_address) DO UPDATE SET
+                balance = EXCLUDED.balance,
+                decimals = EXCLUDED.decimals,
+                last_price_sol = COALESCE(EXCLUDED.last_price_sol, token_holdings.last_price_sol),
+                last_updated = NOW()
+            "#
+        )
+        .bind(wallet_id)
+        .bind(&balance.mint)
+        .bind(balance.amount)
+        .bind(balance.decimals as i32)
+        .bind(prices.get(&balance.mint))
+        .execute(&mut tx)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM token_holdings WHERE wallet_id = $1 AND NOT (token_address = ANY($2))")
+        .bind(wallet_id)
+        .bind(&mints)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+
+    let holdings = sqlx::query_as::<_, TokenHolding>(
+        "SELECT * FROM token_holdings WHERE wallet_id = $1 ORDER BY token_address"
+    )
+    .bind(wallet_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(holdings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(address: &str, mint: &str, amount: &str, ui_amount: &str) -> Value {
+        json!({
+            "pubkey": address,
+            "account": {
+                "lamports": 2_039_280,
+                "data": {"parsed": {"info": {
+                    "mint": mint,
+                    "owner": "Owner",
+                    "tokenAmount": {"amount": amount, "decimals": 6, "uiAmountString": ui_amount},
+                }}},
+            },
+        })
+    }
+
+    #[test]
+    fn test_balances_sum_accounts_per_mint() {
+        let response = json!({"context": {"slot": 1}, "value": [
+            account("A1", "MintA", "1500000", "1.5"),
+            account("A2", "MintA", "2000000", "2"),
+            account("B1", "MintB", "0", "0"),
+            account("W1", SOL_MINT, "1000000", "1"),
+        ]});
+        let accounts = parse_token_accounts(&response, TOKEN_PROGRAM).unwrap();
+        assert_eq!(accounts.len(), 4);
+        assert_eq!((accounts[0].raw_amount, accounts[0].lamports), (1_500_000, 2_039_280));
+
+        // Empty accounts and wrapped SOL hold nothing to sell
+        assert_eq!(sum_balances(&accounts), vec![TokenBalance {
+            mint: "MintA".to_string(),
+            amount: Decimal::from_str("3.5").unwrap(),
+            decimals: 6,
+        }]);
+    }
+}
@@ -0,0 +1,12 @@
+//! Wallet module for Cerberus Chain: Hydra
+//! Handles key custody for the Solana wallets users trade from
+
+pub mod holdings;
+pub mod reclaim;
+pub mod security;
+pub mod settle;
+pub mod spl;
+pub mod transfers;
+pub mod withdrawals;
+
+pub use security::*;
@@ -0,0 +1,268 @@
+//! Rent reclaim for empty token accounts
+//!
+//! Every token a wallet has held leaves a token account behind with about
+//! 0.002 SOL of rent in it. Closing the empty ones returns that rent to the
+//! wallet. Accounts are closed in batches, one transaction per batch and
+//! per token program; a failed batch doesn't stop the others. Bots can have
+//! this run for their wallets once they stop.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::bots::{BotSettings, BotState, StopHook};
+use crate::database::models::{BotConfig, Wallet};
+use crate::trading::executor::lamports_to_sol;
+use crate::trading::simulation::LAMPORTS_PER_SIGNATURE;
+use crate::trading::SolanaExecutor;
+use crate::wallet::holdings::{self, TokenAccount};
+use crate::wallet::spl;
+
+/// Accounts closed per transaction; each close adds one account key
+pub const MAX_CLOSES_PER_TRANSACTION: usize = 20;
+
+/// Whether a bot config asks for its wallets' rent back when it stops
+pub fn is_reclaim_on_stop_config(config: &serde_json::Value) -> bool {
+    config.get("reclaim_rent_on_stop").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Token accounts that can be closed: empty and not frozen
+pub fn closable_accounts(accounts: Vec<TokenAccount>) -> Vec<TokenAccount> {
+    accounts
+        .into_iter()
+        .filter(|account| account.raw_amount == 0 && !account.frozen)
+        .collect()
+}
+
+/// Group accounts into transactions, never mixing token programs
+pub fn batch_accounts(accounts: Vec<TokenAccount>) -> Vec<Vec<TokenAccount>> {
+    let (legacy, token_2022): (Vec<TokenAccount>, Vec<TokenAccount>) = accounts
+        .into_iter()
+        .partition(|account| account.program == holdings::TOKEN_PROGRAM);
+
+    [legacy, token_2022]
+        .iter()
+        .flat_map(|accounts| accounts.chunks(MAX_CLOSES_PER_TRANSACTION).map(<[TokenAccount]>::to_vec))
+        .collect()
+}
+
+/// One transaction of a reclaim
+#[derive(Debug, Clone, Serialize)]
+pub struct ReclaimBatch {
+    pub signature: Option<String>,
+    pub accounts: Vec<String>,
+    pub sol_recovered: Decimal,
+    pub error: Option<String>,
+}
+
+/// Outcome of reclaiming a wallet's rent
+#[derive(Debug, Clone, Serialize)]
+pub struct ReclaimReport {
+    pub wallet_id: Uuid,
+    pub empty_accounts: usize,
+    pub closed_accounts: usize,
+    /// Rent returned by the closed accounts
+    pub sol_recovered: Decimal,
+    pub fees_sol: Decimal,
+    pub batches: Vec<ReclaimBatch>,
+}
+
+impl ReclaimReport {
+    /// SOL gained after fees
+    pub fn net_sol(&self) -> Decimal {
+        self.sol_recovered - self.fees_sol
+    }
+}
+
+/// Closes empty token accounts of wallets
+pub struct Reclaimer {
+    executor: Arc<SolanaExecutor>,
+}
+
+impl Reclaimer {
+    pub fn new(executor: Arc<SolanaExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Close every empty token account of the wallet, rent going back to it
+    pub async fn reclaim(&self, wallet: &Wallet) -> Result<ReclaimReport> {
+        let owner = Pubkey::from_str(&wallet.public_key)?;
+        let accounts = closable_accounts(holdings::token_accounts(self.executor.rpc(), &wallet.public_key).await?);
+
+        let mut report = ReclaimReport {
+            wallet_id: wallet.id,
+            empty_accounts: accounts.len(),
+            closed_accounts: 0,
+            sol_recovered: Decimal::ZERO,
+            fees_sol: Decimal::ZERO,
+            batches: Vec::new(),
+        };
+        if accounts.is_empty() {
+            return Ok(report);
+        }
+
+        let keypair = self.executor.load_keypair(wallet.id).await?;
+        for batch in batch_accounts(accounts) {
+            let lamports: u64 = batch.iter().map(|account| account.lamports).sum();
+            let mut outcome = ReclaimBatch {
+                signature: None,
+                accounts: batch.iter().map(|account| account.address.clone()).collect(),
+                sol_recovered: Decimal::ZERO,
+                error: None,
+            };
+
+            let sent = async {
+                let instructions = batch
+                    .iter()
+                    .map(|account| {
+                        Ok(spl::close_account(
+                            &spl::token_program(&account.program)?,
+                            &Pubkey::from_str(&account.address)?,
+                            &owner,
+                            &owner,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let blockhash = self.executor.rpc().get_latest_blockhash().await?;
+                let transaction =
+                    Transaction::new_signed_with_payer(&instructions, Some(&keypair.pubkey()), &[&keypair], blockhash);
+                outcome.signature = Some(transaction.signatures[0].to_string());
+                self.executor.rpc().send_and_confirm_transaction(&transaction).await?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+
+            match sent {
+                Ok(()) => {
+                    outcome.sol_recovered = lamports_to_sol(lamports);
+                    report.closed_accounts += batch.len();
+                    report.sol_recovered += outcome.sol_recovered;
+                    report.fees_sol += lamports_to_sol(LAMPORTS_PER_SIGNATURE);
+                }
+                Err(e) => {
+                    log::warn!("Failed to close {} token accounts of wallet {}: {}", batch.len(), wallet.id, e);
+                    outcome.error = Some(e.to_string());
+                }
+            }
+            report.batches.push(outcome);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Reclaims rent from a bot's wallets after it stops, for bots that opt in
+/// with `reclaim_rent_on_stop`. Bots stopped only for a server restart are
+/// still active and left alone.
+pub struct ReclaimOnStop {
+    pool: PgPool,
+    reclaimer: Arc<Reclaimer>,
+}
+
+impl ReclaimOnStop {
+    pub fn new(pool: PgPool, reclaimer: Arc<Reclaimer>) -> Self {
+        Self { pool, reclaimer }
+    }
+}
+
+#[async_trait]
+impl StopHook for ReclaimOnStop {
+    async fn bot_stopped(&self, bot: &BotConfig, _state: BotState) {
+        if !is_reclaim_on_stop_config(&bot.config_json) {
+            return;
+        }
+
+        let active = sqlx::query_scalar::<_, bool>("SELECT is_active FROM bot_configs WHERE id = $1")
+            .bind(bot.id)
+            .fetch_optional(&self.pool)
+            .await;
+        match active {
+            Ok(Some(false)) => {}
+            Ok(_) => return,
+            Err(e) => {
+                log::warn!("Skipping rent reclaim for bot {}: {}", bot.id, e);
+                return;
+            }
+        }
+
+        let wallet_ids = match BotSettings::parse(&bot.bot_type, &bot.config_json) {
+            Ok(settings) => settings.wallet_ids().to_vec(),
+            Err(e) => {
+                log::warn!("Skipping rent reclaim for bot {}: {}", bot.id, e);
+                return;
+            }
+        };
+
+        for wallet_id in wallet_ids {
+            let wallet = sqlx::query_as::<_, Wallet>(
+                "SELECT * FROM wallets WHERE id = $1 AND user_id = $2 AND is_active = true"
+            )
+            .bind(wallet_id)
+            .bind(bot.user_id)
+            .fetch_optional(&self.pool)
+            .await;
+
+            let report = match wallet {
+                Ok(Some(wallet)) => self.reclaimer.reclaim(&wallet).await,
+                Ok(None) => continue,
+                Err(e) => Err(e.into()),
+            };
+            match report {
+                Ok(report) => log::info!(
+                    "Bot {} stopped; closed {} of {} empty token accounts in wallet {}, recovering {} SOL",
+                    bot.id,
+                    report.closed_accounts,
+                    report.empty_accounts,
+                    wallet_id,
+                    report.net_sol()
+                ),
+                Err(e) => log::warn!("Rent reclaim for wallet {} of bot {} failed: {}", wallet_id, bot.id, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(address: &str, program: &str, raw_amount: u64, frozen: bool) -> TokenAccount {
+        TokenAccount {
+            address: address.to_string(),
+            mint: "Mint".to_string(),
+            program: program.to_string(),
+            raw_amount,
+            amount: Decimal::from(raw_amount),
+            decimals: 0,
+            lamports: 2_039_280,
+            frozen,
+        }
+    }
+
+    #[test]
+    fn test_empty_accounts_batched_per_program() {
+        let mut accounts: Vec<TokenAccount> = (0..45)
+            .map(|i| account(&format!("A{}", i), holdings::TOKEN_PROGRAM, 0, false))
+            .collect();
+        accounts.push(account("Held", holdings::TOKEN_PROGRAM, 5, false));
+        accounts.push(account("Frozen", holdings::TOKEN_PROGRAM, 0, true));
+        accounts.push(account("B0", holdings::TOKEN_2022_PROGRAM, 0, false));
+
+        let closable = closable_accounts(accounts);
+        assert_eq!(closable.len(), 46);
+
+        let sizes: Vec<(usize, &str)> = batch_accounts(closable)
+            .iter()
+            .map(|batch| (batch.len(), if batch[0].program == holdings::TOKEN_PROGRAM { "token" } else { "2022" }))
+            .collect();
+        assert_eq!(sizes, [(20, "token"), (20, "token"), (5, "token"), (1, "2022")]);
+    }
+}
@@ -0,0 +1,73 @@
+//! Private key encryption for stored wallets using AES-256-GCM
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use solana_sdk::signature::Keypair;
+
+/// Parse the 32 character `ENCRYPTION_KEY` used to encrypt wallet keys
+pub fn parse_master_key(key: &str) -> Result<[u8; 32]> {
+    key.as_bytes()
+        .try_into()
+        .map_err(|_| anyhow!("Encryption key must be exactly 32 characters"))
+}
+
+/// Encrypt a keypair, returning the hex ciphertext and hex nonce for storage
+pub fn encrypt_private_key(keypair: &Keypair, master_key: &[u8; 32]) -> Result<(String, String)> {
+    let cipher = Aes256Gcm::new(&Key::from(*master_key));
+
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), keypair.to_bytes().as_ref())
+        .map_err(|_| anyhow!("Failed to encrypt private key"))?;
+
+    Ok((hex::encode(ciphertext), hex::encode(nonce)))
+}
+
+/// Decrypt a stored private key back into a signing keypair
+pub fn decrypt_private_key(encrypted: &str, nonce: &str, master_key: &[u8; 32]) -> Result<Keypair> {
+    let cipher = Aes256Gcm::new(&Key::from(*master_key));
+
+    let ciphertext = hex::decode(encrypted)
+        .map_err(|e| anyhow!("Encrypted private key is not valid hex: {}", e))?;
+    let nonce: [u8; 12] = hex::decode(nonce)
+        .map_err(|e| anyhow!("Encryption nonce is not valid hex: {}", e))?
+        .try_into()
+        .map_err(|_| anyhow!("Encryption nonce must be 12 bytes"))?;
+
+    let plaintext = cipher
+        .decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow!("Failed to decrypt private key"))?;
+
+    Keypair::from_bytes(&plaintext)
+        .map_err(|e| anyhow!("Decrypted private key is invalid: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn test_private_key_round_trip() {
+        let master_key = [7u8; 32];
+        let keypair = Keypair::new();
+
+        let (encrypted, nonce) = encrypt_private_key(&keypair, &master_key).unwrap();
+        let decrypted = decrypt_private_key(&encrypted, &nonce, &master_key).unwrap();
+        assert_eq!(decrypted.pubkey(), keypair.pubkey());
+
+        // Wrong key must not decrypt
+        assert!(decrypt_private_key(&encrypted, &nonce, &[8u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_master_key_parsing() {
+        assert!(parse_master_key(&"k".repeat(32)).is_ok());
+        assert!(parse_master_key("too short").is_err());
+        assert!(parse_master_key(&"k".repeat(33)).is_err());
+    }
+}
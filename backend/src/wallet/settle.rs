@@ -0,0 +1,168 @@
+//! Settling transactions sent from user wallets
+//!
+//! Transfers and withdrawals are sent and waited on directly. A wait that
+//! ends without an answer says nothing about whether the transaction
+//! landed, so it stays submitted and is settled later from its signature's
+//! history: a transaction that landed is confirmed or failed on its result,
+//! and one that never did fails once its blockhash has expired. Both tables
+//! share the status names of `transfers::status`.
+
+use anyhow::Result;
+use solana_client::client_error::ClientErrorKind;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::trading::executor::{send_error, ChainStatus, NotSent};
+use crate::wallet::transfers::status;
+
+/// Table of transfers between a user's own wallets
+pub const TRANSFERS: &str = "transfers";
+/// Table of withdrawals to external addresses
+pub const WITHDRAWALS: &str = "withdrawals";
+
+/// How a sent transaction ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Settlement {
+    Confirmed,
+    Failed(String),
+    /// Not landed yet but still able to
+    Unsettled,
+}
+
+impl Settlement {
+    /// Settle from the signature's status, given whether its blockhash has
+    /// expired as of a block height read before the status
+    fn from_chain(status: ChainStatus, expired: bool) -> Self {
+        match status {
+            ChainStatus::Confirmed | ChainStatus::Finalized => Settlement::Confirmed,
+            ChainStatus::Failed(error) => Settlement::Failed(error),
+            ChainStatus::Processed => Settlement::Unsettled,
+            ChainStatus::Unknown if expired => Settlement::Failed("Transaction expired without landing".to_string()),
+            ChainStatus::Unknown => Settlement::Unsettled,
+        }
+    }
+}
+
+/// Send a transaction and wait for it to confirm. Only a refusal by the
+/// node or a failed execution fails it outright; an unanswered wait is
+/// settled from the signature's history.
+pub async fn send_and_settle(rpc: &RpcClient, transaction: &Transaction, last_valid_block_height: u64) -> Settlement {
+    let error = match rpc.send_and_confirm_transaction(transaction).await {
+        Ok(_) => return Settlement::Confirmed,
+        Err(e) => e,
+    };
+    if let ClientErrorKind::TransactionError(e) = error.kind() {
+        return Settlement::Failed(e.to_string());
+    }
+    let error = send_error(error);
+    if error.is::<NotSent>() {
+        return Settlement::Failed(error.to_string());
+    }
+
+    let signature = transaction.signatures[0];
+    log::warn!("Confirmation of {} was lost: {}", signature, error);
+    match settle(rpc, &signature, Some(last_valid_block_height)).await {
+        Ok(settlement) => settlement,
+        Err(e) => {
+            log::warn!("Failed to look up {}: {}", signature, e);
+            Settlement::Unsettled
+        }
+    }
+}
+
+/// Settle a sent transaction from its signature's history. Without a last
+/// valid block height its blockhash is taken to have expired.
+pub async fn settle(rpc: &RpcClient, signature: &Signature, last_valid_block_height: Option<u64>) -> Result<Settlement> {
+    // Read the height first: a transaction that lands after the lookup can't
+    // then be taken for one that expired
+    let block_height = rpc.get_block_height().await?;
+    let status = rpc.get_signature_statuses_with_history(&[*signature]).await?.value.pop().flatten();
+    let expired = last_valid_block_height.is_none_or(|height| block_height > height);
+    Ok(Settlement::from_chain(ChainStatus::from_status(status), expired))
+}
+
+/// Settle the submitted rows of `table`, one lookup per signature
+pub async fn resolve_submitted(pool: &PgPool, rpc: &RpcClient, table: &'static str) -> Result<()> {
+    let submitted = sqlx::query_as::<_, (String, Option<i64>)>(&format!(
+        "SELECT DISTINCT signature, last_valid_block_height FROM {} WHERE status = $1 AND signature IS NOT NULL",
+        table
+    ))
+    .bind(status::SUBMITTED)
+    .fetch_all(pool)
+    .await?;
+
+    for (signature, last_valid_block_height) in submitted {
+        let parsed = match Signature::from_str(&signature) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                log::warn!("Unreadable signature {} in {}: {}", signature, table, e);
+                continue;
+            }
+        };
+        let (new_status, error) = match settle(rpc, &parsed, last_valid_block_height.map(|h| h as u64)).await? {
+            Settlement::Unsettled => continue,
+            Settlement::Confirmed => (status::CONFIRMED, None),
+            Settlement::Failed(error) => (status::FAILED, Some(error)),
+        };
+
+        sqlx::query(&format!(
+            "UPDATE {} SET status = $2, error_message = $3 WHERE signature = $1 AND status = $4",
+            table
+        ))
+        .bind(&signature)
+        .bind(new_status)
+        .bind(error)
+        .bind(status::SUBMITTED)
+        .execute(pool)
+        .await?;
+        log::info!("Settled {} of {} as {}", signature, table, new_status);
+    }
+
+    Ok(())
+}
+
+/// Settle the submitted rows of `tables` every `interval` until the handle
+/// is aborted
+pub fn spawn_resolver(
+    pool: PgPool,
+    rpc: Arc<RpcClient>,
+    tables: &'static [&'static str],
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            for table in tables {
+                if let Err(e) = resolve_submitted(&pool, &rpc, table).await {
+                    log::error!("Settling submitted {} failed: {}", table, e);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlanded_transaction_fails_only_once_expired() {
+        assert_eq!(Settlement::from_chain(ChainStatus::Unknown, false), Settlement::Unsettled);
+        assert!(matches!(Settlement::from_chain(ChainStatus::Unknown, true), Settlement::Failed(_)));
+        assert_eq!(Settlement::from_chain(ChainStatus::Processed, true), Settlement::Unsettled);
+        // A landed transaction settles on its result even past its blockhash
+        assert_eq!(Settlement::from_chain(ChainStatus::Confirmed, true), Settlement::Confirmed);
+        assert_eq!(Settlement::from_chain(ChainStatus::Finalized, false), Settlement::Confirmed);
+        assert_eq!(
+            Settlement::from_chain(ChainStatus::Failed("InstructionError".to_string()), false),
+            Settlement::Failed("InstructionError".to_string())
+        );
+    }
+}
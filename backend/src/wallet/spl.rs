@@ -0,0 +1,124 @@
+//! SPL token instructions
+//!
+//! The few token and associated token account instructions the wallets
+//! send themselves, built by hand. They work the same under the token and
+//! token-2022 programs, which take the program as a parameter.
+
+use anyhow::{anyhow, Result};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use std::str::FromStr;
+
+use crate::wallet::holdings::{TOKEN_2022_PROGRAM, TOKEN_PROGRAM};
+
+pub const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+const CLOSE_ACCOUNT: u8 = 9;
+const TRANSFER_CHECKED: u8 = 12;
+const CREATE_IDEMPOTENT: u8 = 1;
+
+fn associated_token_program() -> Pubkey {
+    Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM).expect("valid associated token program id")
+}
+
+/// Associated token account of `owner` for `mint` under `token_program`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let (address, _) = Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token_program(),
+    );
+    address
+}
+
+/// Create `owner`'s associated token account for `mint` unless it exists
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: associated_token_program(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    }
+}
+
+/// Move `amount` base units of `mint` between token accounts
+pub fn transfer_checked(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut data = vec![TRANSFER_CHECKED];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Close an empty token account, sending its rent to `destination`
+pub fn close_account(token_program: &Pubkey, account: &Pubkey, destination: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![CLOSE_ACCOUNT],
+    }
+}
+
+/// Parse a token program id, accepting only the two SPL token programs
+pub fn token_program(program: &str) -> Result<Pubkey> {
+    if program != TOKEN_PROGRAM && program != TOKEN_2022_PROGRAM {
+        return Err(anyhow!("{} is not a token program", program));
+    }
+    Ok(Pubkey::from_str(program)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_checked_layout() {
+        let program = token_program(TOKEN_PROGRAM).unwrap();
+        let (source, mint, destination, authority) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let instruction = transfer_checked(&program, &source, &mint, &destination, &authority, 1_500_000, 6);
+        assert_eq!(instruction.data, [12, 0x60, 0xe3, 0x16, 0, 0, 0, 0, 0, 6]);
+        assert_eq!(instruction.accounts[3], AccountMeta::new_readonly(authority, true));
+
+        // The address depends on the token program as well as owner and mint
+        let token_2022 = token_program(TOKEN_2022_PROGRAM).unwrap();
+        assert_ne!(
+            associated_token_address(&authority, &mint, &program),
+            associated_token_address(&authority, &mint, &token_2022)
+        );
+        assert!(token_program(ASSOCIATED_TOKEN_PROGRAM).is_err());
+    }
+}
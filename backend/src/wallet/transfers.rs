@@ -0,0 +1,423 @@
+//! SOL transfers between a user's own wallets
+//!
+//! A distribution fans SOL out from a funding wallet to trading wallets; a
+//! sweep collects it back into one. Transfers are planned against live
+//! balances so no wallet is left under its rent-exempt minimum, batched
+//! into as few transactions as fit, and recorded in `transfers`. In a
+//! sweep the receiving wallet pays the fees, so swept wallets give up
+//! everything above what they keep.
+
+use anyhow::{anyhow, Result};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::{DistributeRequest, SweepRequest, Transfer, Wallet};
+use crate::trading::executor::{lamports_to_sol, sol_to_lamports};
+use crate::trading::simulation::LAMPORTS_PER_SIGNATURE;
+use crate::trading::SolanaExecutor;
+use crate::wallet::settle::{self, Settlement};
+
+pub mod kind {
+    pub const DISTRIBUTE: &str = "distribute";
+    pub const SWEEP: &str = "sweep";
+}
+
+pub mod status {
+    pub const PENDING: &str = "pending";
+    pub const SUBMITTED: &str = "submitted";
+    pub const CONFIRMED: &str = "confirmed";
+    pub const FAILED: &str = "failed";
+}
+
+/// Transfers per distribution transaction, all signed by the funding wallet
+pub const MAX_DISTRIBUTION_TRANSFERS: usize = 20;
+/// Transfers per sweep transaction; every swept wallet adds a signature
+pub const MAX_SWEEP_TRANSFERS: usize = 8;
+/// Wallets a single distribution or sweep may involve
+pub const MAX_WALLETS: usize = 100;
+
+/// One transfer of a plan
+#[derive(Debug, Clone)]
+pub struct PlannedTransfer {
+    pub from: Wallet,
+    pub to: Wallet,
+    pub lamports: u64,
+}
+
+/// Transfers grouped into the transactions that will carry them
+#[derive(Debug, Clone)]
+pub struct TransferPlan {
+    pub kind: &'static str,
+    pub fee_payer: Wallet,
+    pub batches: Vec<Vec<PlannedTransfer>>,
+}
+
+/// Outcome of an executed plan
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferReport {
+    pub batch_id: Uuid,
+    pub transactions: usize,
+    pub confirmed_sol: Decimal,
+    pub failed_sol: Decimal,
+    pub transfers: Vec<Transfer>,
+}
+
+pub fn validate_distribution(req: &DistributeRequest) -> Result<()> {
+    if req.total_sol <= Decimal::ZERO {
+        return Err(anyhow!("Total SOL must be greater than zero"));
+    }
+    if req.targets.is_empty() {
+        return Err(anyhow!("At least one target wallet is required"));
+    }
+    if req.targets.len() > MAX_WALLETS {
+        return Err(anyhow!("At most {} target wallets are allowed", MAX_WALLETS));
+    }
+    if req.targets.iter().any(|t| t.weight.is_some_and(|w| w <= Decimal::ZERO)) {
+        return Err(anyhow!("Weights must be greater than zero"));
+    }
+
+    let ids: HashSet<Uuid> = req.targets.iter().map(|t| t.wallet_id).collect();
+    if ids.len() != req.targets.len() {
+        return Err(anyhow!("Target wallets must be distinct"));
+    }
+    if ids.contains(&req.from_wallet_id) {
+        return Err(anyhow!("The funding wallet cannot also be a target"));
+    }
+    Ok(())
+}
+
+pub fn validate_sweep(req: &SweepRequest) -> Result<()> {
+    if req.from_wallet_ids.len() > MAX_WALLETS {
+        return Err(anyhow!("At most {} wallets can be swept at once", MAX_WALLETS));
+    }
+    if req.from_wallet_ids.contains(&req.to_wallet_id) {
+        return Err(anyhow!("The receiving wallet cannot also be swept"));
+    }
+    if req.leave_sol.is_some_and(|sol| sol < Decimal::ZERO) {
+        return Err(anyhow!("SOL left behind cannot be negative"));
+    }
+    Ok(())
+}
+
+/// Split lamports in proportion to the weights. Rounding remainders go to
+/// the first wallets, so the parts always add up to the total.
+pub fn split_lamports(total: u64, weights: &[Decimal]) -> Result<Vec<u64>> {
+    let sum: Decimal = weights.iter().sum();
+    if weights.is_empty() || sum <= Decimal::ZERO {
+        return Err(anyhow!("Weights must add up to more than zero"));
+    }
+
+    let mut parts = weights
+        .iter()
+        .map(|weight| {
+            (Decimal::from(total) * weight / sum)
+                .floor()
+                .to_u64()
+                .ok_or_else(|| anyhow!("Invalid transfer amount"))
+        })
+        .collect::<Result<Vec<u64>>>()?;
+
+    let remainder = total - parts.iter().sum::<u64>();
+    for part in parts.iter_mut().take(remainder as usize) {
+        *part += 1;
+    }
+    Ok(parts)
+}
+
+/// Fees for a plan's transactions; every signature costs the base fee
+fn plan_fees(plan_kind: &str, batches: &[Vec<PlannedTransfer>]) -> u64 {
+    batches
+        .iter()
+        .map(|batch| {
+            let signatures = if plan_kind == kind::SWEEP { 1 + batch.len() as u64 } else { 1 };
+            signatures * LAMPORTS_PER_SIGNATURE
+        })
+        .sum()
+}
+
+/// Plans and sends SOL transfers between a user's wallets
+pub struct WalletTransfers {
+    pool: PgPool,
+    executor: Arc<SolanaExecutor>,
+}
+
+impl WalletTransfers {
+    pub fn new(pool: PgPool, executor: Arc<SolanaExecutor>) -> Self {
+        Self { pool, executor }
+    }
+
+    /// Plan sending `total_sol` split by weight to the targets. Fails if a
+    /// target would end up under the rent-exempt minimum or the funding
+    /// wallet can't cover the total, the fees and its own minimum.
+    pub async fn plan_distribution(
+        &self,
+        from: Wallet,
+        targets: Vec<(Wallet, Decimal)>,
+        total_sol: Decimal,
+    ) -> Result<TransferPlan> {
+        let total = sol_to_lamports(total_sol)?;
+        let weights: Vec<Decimal> = targets.iter().map(|(_, weight)| *weight).collect();
+        let amounts = split_lamports(total, &weights)?;
+        if amounts.contains(&0) {
+            return Err(anyhow!("{} SOL is too little to split between {} wallets", total_sol, targets.len()));
+        }
+
+        let rent_minimum = self.rent_minimum().await?;
+        let target_wallets: Vec<Wallet> = targets.iter().map(|(wallet, _)| wallet.clone()).collect();
+        let balances = self.balances(&target_wallets).await?;
+
+        let mut transfers = Vec::with_capacity(targets.len());
+        for ((to, _), (lamports, balance)) in targets.into_iter().zip(amounts.into_iter().zip(balances)) {
+            if balance + lamports < rent_minimum {
+                return Err(anyhow!(
+                    "Wallet {} would hold less than the rent-exempt minimum of {} SOL",
+                    to.name,
+                    lamports_to_sol(rent_minimum)
+                ));
+            }
+            transfers.push(PlannedTransfer { from: from.clone(), to, lamports });
+        }
+
+        let batches: Vec<Vec<PlannedTransfer>> = transfers
+            .chunks(MAX_DISTRIBUTION_TRANSFERS)
+            .map(<[PlannedTransfer]>::to_vec)
+            .collect();
+        let needed = total + plan_fees(kind::DISTRIBUTE, &batches) + rent_minimum;
+        let available = self.balances(std::slice::from_ref(&from)).await?[0];
+        if available < needed {
+            return Err(anyhow!(
+                "Funding wallet holds {} SOL but needs {} SOL including fees and its rent-exempt minimum",
+                lamports_to_sol(available),
+                lamports_to_sol(needed)
+            ));
+        }
+
+        Ok(TransferPlan { kind: kind::DISTRIBUTE, fee_payer: from, batches })
+    }
+
+    /// Plan moving everything above the rent-exempt minimum plus
+    /// `leave_sol` from each wallet into `to`, which pays the fees
+    pub async fn plan_sweep(&self, to: Wallet, from: Vec<Wallet>, leave_sol: Decimal) -> Result<TransferPlan> {
+        let keep = self.rent_minimum().await? + sol_to_lamports(leave_sol)?;
+        let balances = self.balances(&from).await?;
+
+        let transfers: Vec<PlannedTransfer> = from
+            .into_iter()
+            .zip(balances)
+            .filter(|(_, balance)| *balance > keep)
+            .map(|(wallet, balance)| PlannedTransfer {
+                from: wallet,
+                to: to.clone(),
+                lamports: balance - keep,
+            })
+            .collect();
+        if transfers.is_empty() {
+            return Err(anyhow!("No wallet holds more than it keeps; nothing to sweep"));
+        }
+
+        let batches: Vec<Vec<PlannedTransfer>> = transfers
+            .chunks(MAX_SWEEP_TRANSFERS)
+            .map(<[PlannedTransfer]>::to_vec)
+            .collect();
+        let fees = plan_fees(kind::SWEEP, &batches);
+        let available = self.balances(std::slice::from_ref(&to)).await?[0];
+        if available < fees {
+            return Err(anyhow!(
+                "Receiving wallet needs {} SOL to pay the fees but holds {} SOL",
+                lamports_to_sol(fees),
+                lamports_to_sol(available)
+            ));
+        }
+
+        Ok(TransferPlan { kind: kind::SWEEP, fee_payer: to, batches })
+    }
+
+    /// Record and send a plan one transaction at a time. A failed
+    /// transaction fails its own transfers and the rest still go out; one
+    /// whose confirmation is lost leaves its transfers submitted.
+    pub async fn execute(&self, user_id: Uuid, plan: &TransferPlan) -> Result<TransferReport> {
+        let batch_id = Uuid::new_v4();
+
+        for batch in &plan.batches {
+            let ids = self.record(user_id, batch_id, plan.kind, batch).await?;
+
+            let (transaction, last_valid_block_height) = match self.sign(plan, batch).await {
+                Ok(signed) => signed,
+                Err(e) => {
+                    log::warn!("Failed to build {} transaction of batch {}: {}", plan.kind, batch_id, e);
+                    self.set_status(&ids, status::FAILED, None, Some(&e.to_string())).await?;
+                    continue;
+                }
+            };
+
+            // A batch whose outcome is lost stays submitted until the resolver
+            // settles it from this signature
+            let signature = transaction.signatures[0].to_string();
+            self.submitted(&ids, &signature, last_valid_block_height).await?;
+
+            match settle::send_and_settle(self.executor.rpc(), &transaction, last_valid_block_height).await {
+                Settlement::Confirmed => self.set_status(&ids, status::CONFIRMED, None, None).await?,
+                Settlement::Failed(error) => {
+                    log::warn!("{} transaction {} failed: {}", plan.kind, signature, error);
+                    self.set_status(&ids, status::FAILED, None, Some(&error)).await?
+                }
+                Settlement::Unsettled => log::warn!("{} transaction {} is unconfirmed", plan.kind, signature),
+            }
+        }
+
+        let transfers = sqlx::query_as::<_, Transfer>(
+            "SELECT * FROM transfers WHERE batch_id = $1 ORDER BY created_at, id"
+        )
+        .bind(batch_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = |wanted: &str| -> Decimal {
+            transfers.iter().filter(|t| t.status == wanted).map(|t| t.sol_amount).sum()
+        };
+        Ok(TransferReport {
+            batch_id,
+            transactions: plan.batches.len(),
+            confirmed_sol: total(status::CONFIRMED),
+            failed_sol: total(status::FAILED),
+            transfers,
+        })
+    }
+
+    async fn record(&self, user_id: Uuid, batch_id: Uuid, kind: &str, batch: &[PlannedTransfer]) -> Result<Vec<Uuid>> {
+        let mut ids = Vec::with_capacity(batch.len());
+        for transfer in batch {
+            let id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO transfers (user_id, batch_id, kind, from_wallet_id, to_wallet_id,
+                                       from_address, to_address, sol_amount, status)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                RETURNING id
+                "#
+            )
+            .bind(user_id)
+            .bind(batch_id)
+            .bind(kind)
+            .bind(transfer.from.id)
+            .bind(transfer.to.id)
+            .bind(&transfer.from.public_key)
+            .bind(&transfer.to.public_key)
+            .bind(lamports_to_sol(transfer.lamports))
+            .bind(status::PENDING)
+            .fetch_one(&self.pool)
+            .await?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Build and sign one batch; the fee payer signs first, then each
+    /// distinct sending wallet. Returns the block height the transaction's
+    /// blockhash is valid until.
+    async fn sign(&self, plan: &TransferPlan, batch: &[PlannedTransfer]) -> Result<(Transaction, u64)> {
+        let mut instructions: Vec<Instruction> = Vec::with_capacity(batch.len());
+        let mut signers: Vec<Keypair> = vec![self.executor.load_keypair(plan.fee_payer.id).await?];
+        let mut signer_ids = vec![plan.fee_payer.id];
+
+        for transfer in batch {
+            let from = Pubkey::from_str(&transfer.from.public_key)?;
+            let to = Pubkey::from_str(&transfer.to.public_key)?;
+            instructions.push(system_instruction::transfer(&from, &to, transfer.lamports));
+
+            if !signer_ids.contains(&transfer.from.id) {
+                signers.push(self.executor.load_keypair(transfer.from.id).await?);
+                signer_ids.push(transfer.from.id);
+            }
+        }
+
+        let payer = signers[0].pubkey();
+        let rpc = self.executor.rpc();
+        let (blockhash, last_valid_block_height) =
+            rpc.get_latest_blockhash_with_commitment(rpc.commitment()).await?;
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer));
+        let signer_refs: Vec<&Keypair> = signers.iter().collect();
+        transaction
+            .try_sign(&signer_refs, blockhash)
+            .map_err(|e| anyhow!("Failed to sign transfer transaction: {}", e))?;
+        Ok((transaction, last_valid_block_height))
+    }
+
+    async fn submitted(&self, ids: &[Uuid], signature: &str, last_valid_block_height: u64) -> Result<()> {
+        sqlx::query(
+            "UPDATE transfers SET status = $2, signature = $3, last_valid_block_height = $4 WHERE id = ANY($1)"
+        )
+        .bind(ids)
+        .bind(status::SUBMITTED)
+        .bind(signature)
+        .bind(last_valid_block_height as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn set_status(&self, ids: &[Uuid], status: &str, signature: Option<&str>, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE transfers SET status = $2, signature = COALESCE($3, signature), error_message = $4 WHERE id = ANY($1)"
+        )
+        .bind(ids)
+        .bind(status)
+        .bind(signature)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn rent_minimum(&self) -> Result<u64> {
+        Ok(self.executor.rpc().get_minimum_balance_for_rent_exemption(0).await?)
+    }
+
+    /// Lamports held by each wallet, in order; 0 for accounts not on chain yet
+    async fn balances(&self, wallets: &[Wallet]) -> Result<Vec<u64>> {
+        let addresses = wallets
+            .iter()
+            .map(|wallet| Pubkey::from_str(&wallet.public_key))
+            .collect::<Result<Vec<Pubkey>, _>>()?;
+
+        let mut balances = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_WALLETS) {
+            let accounts = self.executor.rpc().get_multiple_accounts(chunk).await?;
+            balances.extend(accounts.into_iter().map(|account| account.map_or(0, |a| a.lamports)));
+        }
+        Ok(balances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_split_lamports() {
+        // Equal weights; the remainder goes to the first wallets
+        assert_eq!(split_lamports(10, &[Decimal::ONE; 3]).unwrap(), [4, 3, 3]);
+        assert_eq!(
+            split_lamports(1_000_000_000, &[dec("1"), dec("3")]).unwrap(),
+            [250_000_000, 750_000_000]
+        );
+        let parts = split_lamports(999_999_999, &[dec("0.2"), dec("0.7"), dec("0.1")]).unwrap();
+        assert_eq!(parts.iter().sum::<u64>(), 999_999_999);
+        assert!(split_lamports(10, &[]).is_err());
+    }
+}
@@ -0,0 +1,402 @@
+//! Withdrawals to external addresses
+//!
+//! Funds only leave for addresses on the user's allowlist. New entries are
+//! time locked, so a hijacked session can't add an address and drain to it
+//! straight away; removing one takes effect at once. A withdrawal is
+//! checked against live balances before anything is recorded, then sent as
+//! a single transaction and tracked in `withdrawals`. Step-up auth and the
+//! audit trail are left to the caller.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use sqlx::PgPool;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::{Wallet, Withdrawal, WithdrawalAddress};
+use crate::trading::executor::{lamports_to_sol, sol_to_lamports};
+use crate::trading::jupiter::SOL_MINT;
+use crate::trading::simulation::LAMPORTS_PER_SIGNATURE;
+use crate::trading::SolanaExecutor;
+use crate::wallet::settle::{self, Settlement};
+use crate::wallet::{holdings, spl};
+
+/// Purpose step-up tokens for withdrawals are issued for
+pub const STEP_UP_PURPOSE: &str = "withdrawal";
+
+/// Size of an SPL token account, whose rent the sender pays when the
+/// receiving account has to be created
+const TOKEN_ACCOUNT_SIZE: usize = 165;
+
+pub mod status {
+    pub const PENDING: &str = "pending";
+    pub const SUBMITTED: &str = "submitted";
+    pub const CONFIRMED: &str = "confirmed";
+    pub const FAILED: &str = "failed";
+}
+
+/// Error for a withdrawal to an address the allowlist doesn't allow yet
+#[derive(Debug, Clone)]
+pub struct AddressNotAllowed {
+    pub address: String,
+    /// When a time-locked entry becomes usable; none if it isn't listed
+    pub active_from: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for AddressNotAllowed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.active_from {
+            Some(at) => write!(f, "Address {} can't be withdrawn to until {}", self.address, at.to_rfc3339()),
+            None => write!(f, "Address {} is not on the withdrawal allowlist", self.address),
+        }
+    }
+}
+
+impl std::error::Error for AddressNotAllowed {}
+
+/// Parse an address a user wants to list or withdraw to
+pub fn validate_address(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address.trim()).map_err(|_| anyhow!("{} is not a valid Solana address", address))
+}
+
+/// Convert a UI token amount to base units, refusing more precision than
+/// the mint has
+pub fn ui_to_raw_amount(amount: Decimal, decimals: u8) -> Result<u64> {
+    let raw = amount * Decimal::from(10u64.pow(decimals as u32));
+    if raw.fract() != Decimal::ZERO {
+        return Err(anyhow!("Amount {} has more than {} decimals", amount, decimals));
+    }
+    raw.to_u64()
+        .filter(|raw| *raw > 0)
+        .ok_or_else(|| anyhow!("Invalid token amount: {}", amount))
+}
+
+/// Allowlist entries of a user, removed ones excluded
+pub async fn list_addresses(pool: &PgPool, user_id: Uuid) -> Result<Vec<WithdrawalAddress>> {
+    let addresses = sqlx::query_as::<_, WithdrawalAddress>(
+        "SELECT * FROM withdrawal_addresses WHERE user_id = $1 AND removed_at IS NULL ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(addresses)
+}
+
+/// List an address, usable once `delay` has passed. Nothing is added if
+/// it is already listed.
+pub async fn add_address(
+    pool: &PgPool,
+    user_id: Uuid,
+    address: &str,
+    label: Option<&str>,
+    delay: Duration,
+) -> Result<Option<WithdrawalAddress>> {
+    let entry = sqlx::query_as::<_, WithdrawalAddress>(
+        r#"
+        INSERT INTO withdrawal_addresses (user_id, address, label, active_from)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(address)
+    .bind(label)
+    .bind(Utc::now() + delay)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+pub async fn remove_address(pool: &PgPool, user_id: Uuid, address_id: Uuid) -> Result<Option<WithdrawalAddress>> {
+    let entry = sqlx::query_as::<_, WithdrawalAddress>(
+        r#"
+        UPDATE withdrawal_addresses SET removed_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND removed_at IS NULL
+        RETURNING *
+        "#
+    )
+    .bind(address_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+/// The allowlist entry for an address, failing with `AddressNotAllowed`
+/// unless it is listed and past its time lock
+pub async fn usable_address(pool: &PgPool, user_id: Uuid, address: &str) -> Result<WithdrawalAddress> {
+    let entry = sqlx::query_as::<_, WithdrawalAddress>(
+        "SELECT * FROM withdrawal_addresses WHERE user_id = $1 AND address = $2 AND removed_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(address)
+    .fetch_optional(pool)
+    .await?;
+
+    match entry {
+        Some(entry) if entry.active_from <= Utc::now() => Ok(entry),
+        entry => Err(AddressNotAllowed {
+            address: address.to_string(),
+            active_from: entry.map(|e| e.active_from),
+        }
+        .into()),
+    }
+}
+
+/// A withdrawal checked against balances and ready to send
+#[derive(Debug, Clone)]
+pub struct PreparedWithdrawal {
+    pub wallet: Wallet,
+    pub address: WithdrawalAddress,
+    pub token_address: Option<String>,
+    pub amount: Decimal,
+    instructions: Vec<Instruction>,
+}
+
+/// Sends withdrawals from user wallets
+pub struct Withdrawals {
+    pool: PgPool,
+    executor: Arc<SolanaExecutor>,
+}
+
+impl Withdrawals {
+    pub fn new(pool: PgPool, executor: Arc<SolanaExecutor>) -> Self {
+        Self { pool, executor }
+    }
+
+    /// Build a withdrawal of SOL, or of `token_address`, from the wallet.
+    /// Fails if the wallet can't cover the amount and fees, or the
+    /// transfer would leave either side under the rent-exempt minimum.
+    pub async fn prepare(
+        &self,
+        wallet: Wallet,
+        address: WithdrawalAddress,
+        token_address: Option<&str>,
+        amount: Decimal,
+    ) -> Result<PreparedWithdrawal> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be greater than zero"));
+        }
+
+        let from = Pubkey::from_str(&wallet.public_key)?;
+        let to = validate_address(&address.address)?;
+        let instructions = match token_address {
+            None | Some(SOL_MINT) => self.sol_instructions(&from, &to, amount).await?,
+            Some(mint) => self.token_instructions(&from, &to, mint, amount).await?,
+        };
+
+        Ok(PreparedWithdrawal {
+            wallet,
+            address,
+            token_address: token_address.filter(|mint| *mint != SOL_MINT).map(str::to_string),
+            amount,
+            instructions,
+        })
+    }
+
+    async fn sol_instructions(&self, from: &Pubkey, to: &Pubkey, amount: Decimal) -> Result<Vec<Instruction>> {
+        let rpc = self.executor.rpc();
+        let lamports = sol_to_lamports(amount)?;
+        let balance = rpc.get_balance(from).await?;
+        let rent_minimum = rpc.get_minimum_balance_for_rent_exemption(0).await?;
+
+        let needed = lamports + LAMPORTS_PER_SIGNATURE;
+        if balance < needed {
+            return Err(anyhow!(
+                "Wallet holds {} SOL but withdrawing {} SOL needs {} SOL including the fee",
+                lamports_to_sol(balance),
+                amount,
+                lamports_to_sol(needed)
+            ));
+        }
+        // An account either keeps its rent-exempt minimum or is emptied
+        let remaining = balance - needed;
+        if remaining > 0 && remaining < rent_minimum {
+            return Err(anyhow!(
+                "Withdrawal would leave {} SOL, under the rent-exempt minimum of {} SOL; withdraw everything but the fee or leave more",
+                lamports_to_sol(remaining),
+                lamports_to_sol(rent_minimum)
+            ));
+        }
+        if rpc.get_balance(to).await? + lamports < rent_minimum {
+            return Err(anyhow!(
+                "The receiving account would hold less than the rent-exempt minimum of {} SOL",
+                lamports_to_sol(rent_minimum)
+            ));
+        }
+
+        Ok(vec![system_instruction::transfer(from, to, lamports)])
+    }
+
+    async fn token_instructions(
+        &self,
+        from: &Pubkey,
+        to: &Pubkey,
+        mint: &str,
+        amount: Decimal,
+    ) -> Result<Vec<Instruction>> {
+        let rpc = self.executor.rpc();
+        let source = holdings::token_accounts(rpc, &from.to_string())
+            .await?
+            .into_iter()
+            .filter(|account| account.mint == mint && !account.frozen)
+            .max_by_key(|account| account.raw_amount)
+            .ok_or_else(|| anyhow!("Wallet holds no {}", mint))?;
+
+        let raw = ui_to_raw_amount(amount, source.decimals)?;
+        if source.raw_amount < raw {
+            return Err(anyhow!("Wallet holds {} of {} but {} was asked for", source.amount, mint, amount));
+        }
+
+        let program = spl::token_program(&source.program)?;
+        let mint = Pubkey::from_str(mint)?;
+        let destination = spl::associated_token_address(to, &mint, &program);
+
+        // The sender pays for the receiving token account if it is missing
+        let mut sol_needed = LAMPORTS_PER_SIGNATURE;
+        if rpc.get_account_with_commitment(&destination, rpc.commitment()).await?.value.is_none() {
+            sol_needed += rpc.get_minimum_balance_for_rent_exemption(TOKEN_ACCOUNT_SIZE).await?;
+        }
+        let balance = rpc.get_balance(from).await?;
+        if balance < sol_needed {
+            return Err(anyhow!(
+                "Wallet needs {} SOL for fees and rent but holds {} SOL",
+                lamports_to_sol(sol_needed),
+                lamports_to_sol(balance)
+            ));
+        }
+
+        Ok(vec![
+            spl::create_associated_token_account_idempotent(from, to, &mint, &program),
+            spl::transfer_checked(
+                &program,
+                &Pubkey::from_str(&source.address)?,
+                &mint,
+                &destination,
+                from,
+                raw,
+                source.decimals,
+            ),
+        ])
+    }
+
+    /// Record and send a prepared withdrawal, waiting for confirmation. A
+    /// transaction that fails is recorded as failed rather than returned
+    /// as an error; one whose confirmation is lost stays submitted.
+    pub async fn execute(&self, user_id: Uuid, prepared: PreparedWithdrawal) -> Result<Withdrawal> {
+        let withdrawal_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO withdrawals (user_id, wallet_id, address_id, from_address, to_address,
+                                     token_address, amount, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+            "#
+        )
+        .bind(user_id)
+        .bind(prepared.wallet.id)
+        .bind(prepared.address.id)
+        .bind(&prepared.wallet.public_key)
+        .bind(&prepared.address.address)
+        .bind(&prepared.token_address)
+        .bind(prepared.amount)
+        .bind(status::PENDING)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rpc = self.executor.rpc();
+        let signed = async {
+            let keypair = self.executor.load_keypair(prepared.wallet.id).await?;
+            let (blockhash, last_valid_block_height) =
+                rpc.get_latest_blockhash_with_commitment(rpc.commitment()).await?;
+            let transaction = Transaction::new_signed_with_payer(
+                &prepared.instructions,
+                Some(&keypair.pubkey()),
+                &[&keypair],
+                blockhash,
+            );
+            Ok::<_, anyhow::Error>((transaction, last_valid_block_height))
+        }
+        .await;
+
+        let (transaction, last_valid_block_height) = match signed {
+            Ok(signed) => signed,
+            Err(e) => return self.set_status(withdrawal_id, status::FAILED, None, Some(&e.to_string())).await,
+        };
+
+        // A withdrawal whose outcome is lost stays submitted until the
+        // resolver settles it from this signature
+        let signature = transaction.signatures[0].to_string();
+        sqlx::query("UPDATE withdrawals SET status = $2, signature = $3, last_valid_block_height = $4 WHERE id = $1")
+            .bind(withdrawal_id)
+            .bind(status::SUBMITTED)
+            .bind(&signature)
+            .bind(last_valid_block_height as i64)
+            .execute(&self.pool)
+            .await?;
+
+        match settle::send_and_settle(rpc, &transaction, last_valid_block_height).await {
+            Settlement::Confirmed => self.set_status(withdrawal_id, status::CONFIRMED, None, None).await,
+            Settlement::Failed(error) => {
+                log::warn!("Withdrawal {} failed: {}", withdrawal_id, error);
+                self.set_status(withdrawal_id, status::FAILED, None, Some(&error)).await
+            }
+            Settlement::Unsettled => {
+                log::warn!("Withdrawal {} is unconfirmed", withdrawal_id);
+                self.set_status(withdrawal_id, status::SUBMITTED, None, None).await
+            }
+        }
+    }
+
+    async fn set_status(
+        &self,
+        withdrawal_id: Uuid,
+        status: &str,
+        signature: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<Withdrawal> {
+        let withdrawal = sqlx::query_as::<_, Withdrawal>(
+            r#"
+            UPDATE withdrawals SET status = $2, signature = COALESCE($3, signature), error_message = $4
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(withdrawal_id)
+        .bind(status)
+        .bind(signature)
+        .bind(error)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(withdrawal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_to_raw_amount_respects_decimals() {
+        assert_eq!(ui_to_raw_amount(Decimal::from_str("1.5").unwrap(), 6).unwrap(), 1_500_000);
+        assert_eq!(ui_to_raw_amount(Decimal::from(42), 0).unwrap(), 42);
+        assert!(ui_to_raw_amount(Decimal::from_str("0.0000001").unwrap(), 6).is_err());
+        assert!(ui_to_raw_amount(Decimal::ZERO, 6).is_err());
+
+        let locked = AddressNotAllowed { address: "Dest".to_string(), active_from: None };
+        assert_eq!(locked.to_string(), "Address Dest is not on the withdrawal allowlist");
+    }
+}
@@ -0,0 +1,38 @@
+-- Cerberus Chain: Hydra - Trade Lifecycle
+-- Replaces free-text trade statuses with an explicit lifecycle and records every transition
+
+-- The old constraint rejects the new statuses, so it goes before the backfill
+ALTER TABLE trades DROP CONSTRAINT trades_status_valid;
+
+-- Map legacy statuses onto the lifecycle
+UPDATE trades SET status = 'created' WHERE status = 'pending';
+UPDATE trades SET status = 'submitted' WHERE status = 'executing';
+
+ALTER TABLE trades ALTER COLUMN status SET DEFAULT 'created';
+ALTER TABLE trades ADD CONSTRAINT trades_status_valid CHECK (
+    status IN ('created', 'simulated', 'submitted', 'confirmed', 'finalized', 'failed', 'expired', 'cancelled')
+);
+
+-- Lifecycle timestamps (executed_at is stamped on submission)
+ALTER TABLE trades ADD COLUMN simulated_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE trades ADD COLUMN finalized_at TIMESTAMP WITH TIME ZONE;
+
+-- Blockhash expiry tracking for resubmission
+ALTER TABLE trades ADD COLUMN last_valid_block_height BIGINT;
+ALTER TABLE trades ADD COLUMN submit_attempts INTEGER NOT NULL DEFAULT 0;
+
+-- Every status transition, including resubmissions
+CREATE TABLE trade_status_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    trade_id UUID NOT NULL REFERENCES trades(id) ON DELETE CASCADE,
+    from_status VARCHAR(20),
+    to_status VARCHAR(20) NOT NULL,
+    signature VARCHAR(88),
+    message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_trade_status_events_trade_id ON trade_status_events(trade_id);
+
+-- The confirmer scans in-flight trades on every poll
+CREATE INDEX idx_trades_in_flight ON trades(executed_at) WHERE status IN ('submitted', 'confirmed');
@@ -0,0 +1,27 @@
+-- Cerberus Chain: Hydra - Paper Trading
+-- Virtual balances and paper-flagged trades for running strategies without spending SOL
+
+-- Per-user paper mode (bots can also opt in through config_json)
+ALTER TABLE users ADD COLUMN paper_trading BOOLEAN NOT NULL DEFAULT false;
+
+-- Paper trades share the trades table so PnL can compare paper and live
+ALTER TABLE trades ADD COLUMN is_paper BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE trades ADD COLUMN fee_sol DECIMAL(20,9);
+
+CREATE INDEX idx_trades_is_paper ON trades(is_paper);
+
+-- Virtual balances, kept apart from wallets.sol_balance
+CREATE TABLE paper_balances (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    token_address VARCHAR(44) NOT NULL,
+    balance DECIMAL(30,9) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    UNIQUE(wallet_id, token_address),
+
+    CONSTRAINT paper_balances_balance_positive CHECK (balance >= 0)
+);
+
+CREATE INDEX idx_paper_balances_wallet_id ON paper_balances(wallet_id);
@@ -0,0 +1,35 @@
+-- Cerberus Chain: Hydra - Conditional Orders
+-- Resting limit, stop-loss, take-profit and trailing-stop orders watched by the order monitor
+
+CREATE TABLE orders (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    token_address VARCHAR(44) NOT NULL,
+    order_type VARCHAR(20) NOT NULL,
+    sol_amount DECIMAL(20,9) NOT NULL,
+    trigger_price DECIMAL(30,18),
+    trail_percent DECIMAL(5,2),
+    peak_price DECIMAL(30,18),
+    slippage_tolerance DECIMAL(5,2),
+    priority_fee DECIMAL(10,9),
+    is_paper BOOLEAN NOT NULL DEFAULT false,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    trade_id UUID REFERENCES trades(id) ON DELETE SET NULL,
+    error_message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    triggered_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT orders_type_valid CHECK (order_type IN ('limit_buy', 'stop_loss', 'take_profit', 'trailing_stop')),
+    CONSTRAINT orders_status_valid CHECK (status IN ('open', 'triggered', 'filled', 'failed', 'cancelled')),
+    CONSTRAINT orders_sol_amount_positive CHECK (sol_amount > 0),
+    CONSTRAINT orders_trail_valid CHECK (trail_percent > 0 AND trail_percent < 100),
+    CONSTRAINT orders_slippage_valid CHECK (slippage_tolerance >= 0 AND slippage_tolerance <= 100)
+);
+
+CREATE INDEX idx_orders_user_id ON orders(user_id);
+CREATE INDEX idx_orders_open ON orders(token_address) WHERE status = 'open';
+
+CREATE TRIGGER update_orders_updated_at BEFORE UPDATE ON orders
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
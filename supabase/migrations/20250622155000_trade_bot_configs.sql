@@ -0,0 +1,4 @@
+-- Cerberus Chain: Hydra - Trade Bot Configs
+-- The bot configuration each trade was placed under, so PnL can be broken down per bot
+
+ALTER TABLE trades ADD COLUMN bot_config_id UUID REFERENCES bot_configs(id) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- Cerberus Chain: Hydra - Trade History Keyset Indexes
+-- Composite indexes backing cursor pagination of trade listings
+
+CREATE INDEX idx_trades_user_created_keyset ON trades(user_id, created_at DESC, id DESC);
+CREATE INDEX idx_trades_user_sol_amount_keyset ON trades(user_id, sol_amount DESC, id DESC);
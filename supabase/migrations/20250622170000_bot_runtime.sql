@@ -0,0 +1,18 @@
+-- Cerberus Chain: Hydra - Bot Runtime State
+-- Supervisor state per bot, kept apart from bot_configs so heartbeats don't touch config timestamps
+
+CREATE TABLE bot_runtime (
+    bot_id UUID PRIMARY KEY REFERENCES bot_configs(id) ON DELETE CASCADE,
+    run_state VARCHAR(20) NOT NULL DEFAULT 'stopped',
+    started_at TIMESTAMP WITH TIME ZONE,
+    last_heartbeat TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    stop_reason TEXT,
+    progress JSONB,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT bot_runtime_state_valid CHECK (run_state IN ('starting', 'running', 'paused', 'backing_off', 'errored', 'stopped'))
+);
+
+CREATE INDEX idx_bot_runtime_state ON bot_runtime(run_state);
@@ -0,0 +1,19 @@
+-- Cerberus Chain: Hydra - Legacy Volume Configs
+-- Volume configs seeded before configs were typed use fields the volume bot no longer takes.
+-- What maps carries over; enabled_hours has no equivalent and is dropped. The old shape never
+-- named a token, wallets or budget, so these bots stay inactive until those are filled in.
+
+UPDATE bot_configs
+SET config_json = jsonb_strip_nulls(jsonb_build_object(
+        'min_sol_amount', config_json->'sol_amount',
+        'max_sol_amount', config_json->'sol_amount',
+        'min_interval_seconds', config_json->'interval_seconds',
+        'max_interval_seconds', config_json->'interval_seconds',
+        'max_sol_per_hour', (config_json->>'sol_amount')::numeric * (config_json->>'max_trades_per_hour')::numeric,
+        'slippage_tolerance', config_json->'slippage_tolerance',
+        'priority_fee', config_json->'priority_fee'
+    )),
+    is_active = false
+WHERE bot_type = 'volume'
+  AND config_json ? 'sol_amount'
+  AND NOT config_json ? 'min_sol_amount';
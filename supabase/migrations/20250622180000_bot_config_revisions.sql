@@ -0,0 +1,27 @@
+-- Cerberus Chain: Hydra - Bot Config Revisions
+-- Immutable history of bot configuration changes, and the revision each bot trade ran under
+
+ALTER TABLE bot_configs ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+
+CREATE TABLE bot_config_revisions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    bot_id UUID NOT NULL REFERENCES bot_configs(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    config_json JSONB NOT NULL,
+    diff JSONB NOT NULL DEFAULT '[]',
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    rolled_back_from INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT bot_config_revisions_unique UNIQUE (bot_id, revision),
+    CONSTRAINT bot_config_revisions_positive CHECK (revision > 0)
+);
+
+-- Existing configurations start their history at revision 1
+INSERT INTO bot_config_revisions (bot_id, revision, name, config_json, changed_by, created_at)
+SELECT id, 1, name, config_json, user_id, created_at FROM bot_configs;
+
+ALTER TABLE trades ADD COLUMN bot_config_revision INTEGER;
+
+CREATE INDEX idx_trades_bot_config ON trades(bot_config_id, bot_config_revision);
@@ -0,0 +1,30 @@
+-- Cerberus Chain: Hydra - Bundles
+-- Multi-wallet trades submitted atomically through a block engine; each leg is a trade linked to its bundle
+
+CREATE TABLE bundles (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    bot_id UUID REFERENCES bot_configs(id) ON DELETE SET NULL,
+    engine_bundle_id VARCHAR(128) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    tip_wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    tip_sol DECIMAL(20,9) NOT NULL,
+    tip_signature VARCHAR(128),
+    landed_slot BIGINT,
+    error_message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT bundles_status_valid CHECK (status IN ('pending', 'landed', 'failed')),
+    CONSTRAINT bundles_tip_positive CHECK (tip_sol > 0)
+);
+
+CREATE INDEX idx_bundles_user_id ON bundles(user_id);
+CREATE INDEX idx_bundles_pending ON bundles(created_at) WHERE status = 'pending';
+
+CREATE TRIGGER update_bundles_updated_at BEFORE UPDATE ON bundles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE trades ADD COLUMN bundle_id UUID REFERENCES bundles(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_trades_bundle_id ON trades(bundle_id) WHERE bundle_id IS NOT NULL;
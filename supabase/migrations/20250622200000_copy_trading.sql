@@ -0,0 +1,10 @@
+-- Cerberus Chain: Hydra - Copy Trading
+-- Copy-trading bot type, and the source transaction each mirrored trade copies
+
+ALTER TABLE bot_configs DROP CONSTRAINT bot_configs_type_valid;
+ALTER TABLE bot_configs ADD CONSTRAINT bot_configs_type_valid
+    CHECK (bot_type IN ('volume', 'bundle', 'bump', 'sniper', 'human', 'copy'));
+
+ALTER TABLE trades ADD COLUMN source_signature VARCHAR(128);
+
+CREATE INDEX idx_trades_source_signature ON trades(source_signature) WHERE source_signature IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Cerberus Chain: Hydra - Grid Bots
+-- Grid trading bot type
+
+ALTER TABLE bot_configs DROP CONSTRAINT bot_configs_type_valid;
+ALTER TABLE bot_configs ADD CONSTRAINT bot_configs_type_valid
+    CHECK (bot_type IN ('volume', 'bundle', 'bump', 'sniper', 'human', 'copy', 'dca', 'grid'));
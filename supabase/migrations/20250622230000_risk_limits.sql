@@ -0,0 +1,31 @@
+-- Cerberus Chain: Hydra - Risk Limits
+-- Per-user, per-wallet and per-bot limits every trade is checked against before it executes
+
+CREATE TABLE risk_limits (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID REFERENCES wallets(id) ON DELETE CASCADE,
+    bot_config_id UUID REFERENCES bot_configs(id) ON DELETE CASCADE,
+    max_sol_per_trade DECIMAL(20,9),
+    daily_loss_limit_sol DECIMAL(20,9),
+    max_token_exposure_sol DECIMAL(20,9),
+    max_trades_per_minute INTEGER,
+    min_sol_reserve DECIMAL(20,9),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT risk_limits_one_scope CHECK (wallet_id IS NULL OR bot_config_id IS NULL),
+    CONSTRAINT risk_limits_max_sol_per_trade_positive CHECK (max_sol_per_trade > 0),
+    CONSTRAINT risk_limits_daily_loss_positive CHECK (daily_loss_limit_sol > 0),
+    CONSTRAINT risk_limits_exposure_positive CHECK (max_token_exposure_sol > 0),
+    CONSTRAINT risk_limits_trades_per_minute_positive CHECK (max_trades_per_minute > 0),
+    CONSTRAINT risk_limits_reserve_valid CHECK (min_sol_reserve >= 0)
+);
+
+-- One row per scope: the user as a whole, one of their wallets, or one of their bots
+CREATE UNIQUE INDEX idx_risk_limits_user ON risk_limits(user_id) WHERE wallet_id IS NULL AND bot_config_id IS NULL;
+CREATE UNIQUE INDEX idx_risk_limits_wallet ON risk_limits(wallet_id) WHERE wallet_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_risk_limits_bot ON risk_limits(bot_config_id) WHERE bot_config_id IS NOT NULL;
+
+CREATE TRIGGER update_risk_limits_updated_at BEFORE UPDATE ON risk_limits
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
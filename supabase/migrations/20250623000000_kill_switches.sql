@@ -0,0 +1,22 @@
+-- Cerberus Chain: Hydra - Kill Switches
+-- Emergency halts of all trading for one user, or for everyone when no user is set
+
+CREATE TABLE kill_switches (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT,
+    engaged_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    engaged_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    released_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    released_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT kill_switches_released_after_engaged CHECK (released_at IS NULL OR released_at >= engaged_at)
+);
+
+-- At most one engaged switch per user and one global switch
+CREATE UNIQUE INDEX idx_kill_switches_engaged_user ON kill_switches(user_id)
+    WHERE released_at IS NULL AND user_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_kill_switches_engaged_global ON kill_switches((user_id IS NULL))
+    WHERE released_at IS NULL AND user_id IS NULL;
+
+CREATE INDEX idx_kill_switches_engaged_at ON kill_switches(engaged_at);
@@ -0,0 +1,35 @@
+-- Cerberus Chain: Hydra - Transfers
+-- SOL moved between a user's own wallets: distributions from a funding wallet and sweeps back into it
+
+CREATE TABLE transfers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Every transfer of one distribution or sweep
+    batch_id UUID NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    from_wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    to_wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    from_address VARCHAR(44) NOT NULL,
+    to_address VARCHAR(44) NOT NULL,
+    sol_amount DECIMAL(20,9) NOT NULL,
+    -- Shared by the transfers batched into the same transaction
+    signature VARCHAR(88),
+    -- Last block height the transaction's blockhash is valid for; a batch
+    -- that hasn't landed by then never will
+    last_valid_block_height BIGINT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    error_message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT transfers_kind_valid CHECK (kind IN ('distribute', 'sweep')),
+    CONSTRAINT transfers_status_valid CHECK (status IN ('pending', 'submitted', 'confirmed', 'failed')),
+    CONSTRAINT transfers_sol_amount_positive CHECK (sol_amount > 0)
+);
+
+CREATE INDEX idx_transfers_user_id ON transfers(user_id, created_at DESC);
+CREATE INDEX idx_transfers_batch_id ON transfers(batch_id);
+CREATE INDEX idx_transfers_signature ON transfers(signature);
+
+CREATE TRIGGER update_transfers_updated_at BEFORE UPDATE ON transfers
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
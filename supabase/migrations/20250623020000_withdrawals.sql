@@ -0,0 +1,48 @@
+-- Cerberus Chain: Hydra - Withdrawals
+-- Allowlisted external addresses and the SOL and SPL token withdrawals sent to them
+
+CREATE TABLE withdrawal_addresses (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    address VARCHAR(44) NOT NULL,
+    label VARCHAR(100),
+    -- New entries are time locked; withdrawals to them are refused until then
+    active_from TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    removed_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT withdrawal_addresses_address_length CHECK (char_length(address) BETWEEN 32 AND 44)
+);
+
+-- An address is on a user's allowlist at most once
+CREATE UNIQUE INDEX idx_withdrawal_addresses_listed ON withdrawal_addresses(user_id, address)
+    WHERE removed_at IS NULL;
+
+CREATE TABLE withdrawals (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    address_id UUID REFERENCES withdrawal_addresses(id) ON DELETE SET NULL,
+    from_address VARCHAR(44) NOT NULL,
+    to_address VARCHAR(44) NOT NULL,
+    -- Mint of the token withdrawn; NULL for SOL
+    token_address VARCHAR(44),
+    amount DECIMAL(30,9) NOT NULL,
+    signature VARCHAR(88),
+    -- Last block height the transaction's blockhash is valid for; a
+    -- withdrawal that hasn't landed by then never will
+    last_valid_block_height BIGINT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    error_message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT withdrawals_status_valid CHECK (status IN ('pending', 'submitted', 'confirmed', 'failed')),
+    CONSTRAINT withdrawals_amount_positive CHECK (amount > 0)
+);
+
+CREATE INDEX idx_withdrawals_user_id ON withdrawals(user_id, created_at DESC);
+CREATE INDEX idx_withdrawals_signature ON withdrawals(signature);
+
+CREATE TRIGGER update_withdrawals_updated_at BEFORE UPDATE ON withdrawals
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,24 @@
+-- Cerberus Chain: Hydra - Panic-sell jobs
+-- Panic-sells run in the background; each run is a job whose report is polled
+
+-- Liquidation sells go through an engaged kill switch
+ALTER TABLE trades ADD COLUMN is_liquidation BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE panic_sell_jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_ids UUID[] NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    report JSONB,
+    error_message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT panic_sell_jobs_status_valid CHECK (status IN ('running', 'completed', 'failed'))
+);
+
+-- One panic-sell at a time per user, so two runs can't sell the same holdings
+CREATE UNIQUE INDEX idx_panic_sell_jobs_running ON panic_sell_jobs(user_id)
+    WHERE status = 'running';
+
+CREATE INDEX idx_panic_sell_jobs_user_id ON panic_sell_jobs(user_id, created_at DESC);